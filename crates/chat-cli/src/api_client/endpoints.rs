@@ -4,8 +4,12 @@ use aws_config::Region;
 use serde_json::Value;
 use tracing::error;
 
-use crate::database::Database;
+use crate::api_client::profile::active_profile;
 use crate::database::settings::Setting;
+use crate::database::{
+    AuthProfile,
+    Database,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Endpoint {
@@ -25,6 +29,11 @@ impl Endpoint {
     };
 
     pub fn configured_value(database: &Database) -> Self {
+        Self::for_profile(database, active_profile(database).as_ref())
+    }
+
+    /// Same as [Self::configured_value], but for `profile` instead of the active profile.
+    pub fn for_profile(database: &Database, profile: Option<&AuthProfile>) -> Self {
         let (endpoint, region) = if let Some(Value::Object(o)) = database.settings.get(Setting::ApiCodeWhispererService)
         {
             // The following branch is evaluated in case the user has set their own endpoint.
@@ -32,7 +41,7 @@ impl Endpoint {
                 o.get("endpoint").and_then(|v| v.as_str()).map(|v| v.to_owned()),
                 o.get("region").and_then(|v| v.as_str()).map(|v| v.to_owned()),
             )
-        } else if let Ok(Some(profile)) = database.get_auth_profile() {
+        } else if let Some(profile) = profile {
             // The following branch is evaluated in the case of user profile being set.
             let region = profile.arn.split(':').nth(3).unwrap_or_default().to_owned();
             match Self::CODEWHISPERER_ENDPOINTS
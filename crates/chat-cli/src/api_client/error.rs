@@ -1,8 +1,11 @@
 use amzn_codewhisperer_client::operation::create_subscription_token::CreateSubscriptionTokenError;
 use amzn_codewhisperer_client::operation::generate_completions::GenerateCompletionsError;
+use amzn_codewhisperer_client::operation::get_usage_limits::GetUsageLimitsError;
 use amzn_codewhisperer_client::operation::list_available_customizations::ListAvailableCustomizationsError;
+use amzn_codewhisperer_client::operation::list_available_models::ListAvailableModelsError;
 use amzn_codewhisperer_client::operation::list_available_profiles::ListAvailableProfilesError;
 use amzn_codewhisperer_client::operation::send_telemetry_event::SendTelemetryEventError;
+use amzn_codewhisperer_client::operation::update_usage_limits::UpdateUsageLimitsError;
 pub use amzn_codewhisperer_streaming_client::operation::generate_assistant_response::GenerateAssistantResponseError;
 use amzn_codewhisperer_streaming_client::types::error::ChatResponseStreamError as CodewhispererChatResponseStreamError;
 use amzn_consolas_client::operation::generate_recommendations::GenerateRecommendationsError;
@@ -64,6 +67,10 @@ pub enum ApiClientError {
 
     #[error("{}", SdkErrorDisplay(.0))]
     CreateSubscriptionToken(#[from] SdkError<CreateSubscriptionTokenError, HttpResponse>),
+    #[error("{}", SdkErrorDisplay(.0))]
+    GetUsageLimits(#[from] SdkError<GetUsageLimitsError, HttpResponse>),
+    #[error("{}", SdkErrorDisplay(.0))]
+    UpdateUsageLimits(#[from] SdkError<UpdateUsageLimitsError, HttpResponse>),
 
     /// Returned from the backend when the user input is too large to fit within the model context
     /// window.
@@ -79,6 +86,9 @@ pub enum ApiClientError {
     #[error(transparent)]
     ListAvailableProfilesError(#[from] SdkError<ListAvailableProfilesError, HttpResponse>),
 
+    #[error("{}", SdkErrorDisplay(.0))]
+    ListAvailableModels(#[from] SdkError<ListAvailableModelsError, HttpResponse>),
+
     #[error(transparent)]
     AuthError(#[from] AuthError),
 
@@ -93,6 +103,19 @@ pub enum ApiClientError {
     // Credential errors
     #[error("failed to load credentials: {}", .0)]
     Credentials(CredentialsError),
+
+    #[error("this request requires a Q Developer profile, run 'q profile' to select one or use the --profile-arn flag")]
+    MissingProfile,
+
+    /// Returned from the backend when the profile the request was made with doesn't exist or the
+    /// user doesn't have access to it.
+    #[error(
+        "the profile '{profile_arn}' was rejected by the service, check the --profile-arn flag, the api.profileArn setting, or run 'q profile' to select another profile"
+    )]
+    ProfileRejected {
+        profile_arn: String,
+        status_code: Option<u16>,
+    },
 }
 
 impl ApiClientError {
@@ -107,8 +130,11 @@ impl ApiClientError {
             Self::CodewhispererChatResponseStream(_) => None,
             Self::QDeveloperChatResponseStream(_) => None,
             Self::ListAvailableProfilesError(e) => sdk_status_code(e),
+            Self::ListAvailableModels(e) => sdk_status_code(e),
             Self::SendTelemetryEvent(e) => sdk_status_code(e),
             Self::CreateSubscriptionToken(e) => sdk_status_code(e),
+            Self::GetUsageLimits(e) => sdk_status_code(e),
+            Self::UpdateUsageLimits(e) => sdk_status_code(e),
             Self::QuotaBreach { status_code, .. } => *status_code,
             Self::ContextWindowOverflow { status_code } => *status_code,
             Self::SmithyBuild(_) => None,
@@ -116,6 +142,18 @@ impl ApiClientError {
             Self::ModelOverloadedError { status_code, .. } => *status_code,
             Self::MonthlyLimitReached { status_code } => *status_code,
             Self::Credentials(_e) => None,
+            Self::MissingProfile => None,
+            Self::ProfileRejected { status_code, .. } => *status_code,
+        }
+    }
+
+    /// Whether the response stream was interrupted by a transport error, e.g. the connection was
+    /// dropped, rather than by an error returned from the service.
+    pub fn is_stream_interrupted(&self) -> bool {
+        match self {
+            Self::CodewhispererChatResponseStream(e) => is_transport_error(e),
+            Self::QDeveloperChatResponseStream(e) => is_transport_error(e),
+            _ => false,
         }
     }
 }
@@ -132,8 +170,11 @@ impl ReasonCode for ApiClientError {
             Self::CodewhispererChatResponseStream(e) => sdk_error_code(e),
             Self::QDeveloperChatResponseStream(e) => sdk_error_code(e),
             Self::ListAvailableProfilesError(e) => sdk_error_code(e),
+            Self::ListAvailableModels(e) => sdk_error_code(e),
             Self::SendTelemetryEvent(e) => sdk_error_code(e),
             Self::CreateSubscriptionToken(e) => sdk_error_code(e),
+            Self::GetUsageLimits(e) => sdk_error_code(e),
+            Self::UpdateUsageLimits(e) => sdk_error_code(e),
             Self::QuotaBreach { .. } => "QuotaBreachError".to_string(),
             Self::ContextWindowOverflow { .. } => "ContextWindowOverflow".to_string(),
             Self::SmithyBuild(_) => "SmithyBuildError".to_string(),
@@ -141,6 +182,8 @@ impl ReasonCode for ApiClientError {
             Self::ModelOverloadedError { .. } => "ModelOverloadedError".to_string(),
             Self::MonthlyLimitReached { .. } => "MonthlyLimitReached".to_string(),
             Self::Credentials(_) => "CredentialsError".to_string(),
+            Self::MissingProfile => "MissingProfile".to_string(),
+            Self::ProfileRejected { .. } => "ProfileRejected".to_string(),
         }
     }
 }
@@ -151,6 +194,13 @@ fn sdk_error_code<T: ProvideErrorMetadata, R>(e: &SdkError<T, R>) -> String {
         .unwrap_or_else(|| e.to_string())
 }
 
+fn is_transport_error<E, R>(e: &SdkError<E, R>) -> bool {
+    matches!(
+        e,
+        SdkError::DispatchFailure(_) | SdkError::ResponseError(_) | SdkError::TimeoutError(_)
+    )
+}
+
 fn sdk_status_code<E>(e: &SdkError<E, Response>) -> Option<u16> {
     e.raw_response().map(|res| res.status().as_u16())
 }
@@ -200,10 +250,22 @@ mod tests {
                 QDeveloperSendMessageError::unhandled("<unhandled>"),
                 response(),
             )),
+            ApiClientError::ListAvailableModels(SdkError::service_error(
+                ListAvailableModelsError::unhandled("<unhandled>"),
+                response(),
+            )),
             ApiClientError::CreateSubscriptionToken(SdkError::service_error(
                 CreateSubscriptionTokenError::unhandled("<unhandled>"),
                 response(),
             )),
+            ApiClientError::GetUsageLimits(SdkError::service_error(
+                GetUsageLimitsError::unhandled("<unhandled>"),
+                response(),
+            )),
+            ApiClientError::UpdateUsageLimits(SdkError::service_error(
+                UpdateUsageLimitsError::unhandled("<unhandled>"),
+                response(),
+            )),
             ApiClientError::CodewhispererChatResponseStream(SdkError::service_error(
                 CodewhispererChatResponseStreamError::unhandled("<unhandled>"),
                 raw_message(),
@@ -213,6 +275,11 @@ mod tests {
                 raw_message(),
             )),
             ApiClientError::SmithyBuild(aws_smithy_types::error::operation::BuildError::other("<other>")),
+            ApiClientError::ProfileRejected {
+                profile_arn: "<arn>".to_string(),
+                status_code: Some(403),
+            },
+            ApiClientError::MissingProfile,
         ]
     }
 
@@ -223,4 +290,22 @@ mod tests {
             println!("{error} {error:?}");
         }
     }
+
+    #[test]
+    fn test_is_stream_interrupted() {
+        let dropped = ApiClientError::CodewhispererChatResponseStream(SdkError::response_error(
+            "connection reset by peer",
+            raw_message(),
+        ));
+        assert!(dropped.is_stream_interrupted());
+        let dropped = ApiClientError::QDeveloperChatResponseStream(SdkError::response_error(
+            "connection reset by peer",
+            raw_message(),
+        ));
+        assert!(dropped.is_stream_interrupted());
+
+        for error in all_errors() {
+            assert!(!error.is_stream_interrupted(), "{error:?}");
+        }
+    }
 }
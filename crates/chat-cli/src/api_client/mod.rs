@@ -12,10 +12,17 @@ use std::time::Duration;
 
 use amzn_codewhisperer_client::Client as CodewhispererClient;
 use amzn_codewhisperer_client::operation::create_subscription_token::CreateSubscriptionTokenOutput;
+use amzn_codewhisperer_client::operation::get_usage_limits::GetUsageLimitsOutput;
+use amzn_codewhisperer_client::operation::update_usage_limits::UpdateUsageLimitsOutput;
+use amzn_codewhisperer_client::operation::update_usage_limits::builders::UpdateUsageLimitsFluentBuilder;
 use amzn_codewhisperer_client::types::{
+    Model,
     OptOutPreference,
     SubscriptionStatus,
     TelemetryEvent,
+    UsageLimitList,
+    UsageLimitType,
+    UsageLimitUpdateRequestStatus,
     UserContext,
 };
 use amzn_codewhisperer_streaming_client::Client as CodewhispererStreamingClient;
@@ -32,10 +39,7 @@ pub use error::ApiClientError;
 use parking_lot::Mutex;
 pub use profile::list_available_profiles;
 use serde_json::Map;
-use tracing::{
-    debug,
-    error,
-};
+use tracing::debug;
 
 use crate::api_client::credentials::CredentialsChain;
 use crate::api_client::model::{
@@ -165,13 +169,7 @@ impl ApiClient {
             },
         }
 
-        let profile = match database.get_auth_profile() {
-            Ok(profile) => profile,
-            Err(err) => {
-                error!("Failed to get auth profile: {err}");
-                None
-            },
-        };
+        let profile = profile::active_profile(database);
 
         Ok(Self {
             client,
@@ -182,6 +180,20 @@ impl ApiClient {
         })
     }
 
+    /// Creates a client that makes requests with `profile` instead of the active profile, e.g.
+    /// for `q chat --profile-arn`.
+    pub async fn with_profile(
+        env: &Env,
+        fs: &Fs,
+        database: &mut Database,
+        profile: AuthProfile,
+    ) -> Result<Self, ApiClientError> {
+        let endpoint = Endpoint::for_profile(database, Some(&profile));
+        let mut client = Self::new(env, fs, database, Some(endpoint)).await?;
+        client.profile = Some(profile);
+        Ok(client)
+    }
+
     pub async fn send_telemetry_event(
         &self,
         telemetry_event: TelemetryEvent,
@@ -232,6 +244,40 @@ impl ApiClient {
         Ok(profiles)
     }
 
+    pub async fn list_available_models(&self) -> Result<Vec<Model>, ApiClientError> {
+        if cfg!(test) {
+            return Ok(vec![
+                Model::builder()
+                    .model_id("CLAUDE_SONNET_4_20250514_V1_0")
+                    .model_name("claude-4-sonnet")
+                    .build()?,
+                Model::builder()
+                    .model_id("CLAUDE_3_7_SONNET_20250219_V1_0")
+                    .model_name("claude-3.7-sonnet")
+                    .build()?,
+            ]);
+        }
+
+        let mut models = vec![];
+        let mut stream = self
+            .client
+            .list_available_models()
+            .origin(amzn_codewhisperer_client::types::Origin::Cli)
+            .set_profile_arn(self.profile.as_ref().map(|p| p.arn.clone()))
+            .into_paginator()
+            .send();
+        while let Some(models_output) = stream.next().await {
+            models.extend(models_output?.models().iter().cloned());
+        }
+
+        Ok(models)
+    }
+
+    /// The ARN of the profile requests are made with, if any.
+    pub fn profile_arn(&self) -> Option<&str> {
+        self.profile.as_ref().map(|p| p.arn.as_str())
+    }
+
     pub async fn create_subscription_token(&self) -> Result<CreateSubscriptionTokenOutput, ApiClientError> {
         if cfg!(test) {
             return Ok(CreateSubscriptionTokenOutput::builder()
@@ -248,6 +294,69 @@ impl ApiClient {
             .map_err(ApiClientError::CreateSubscriptionToken)
     }
 
+    /// Returns the usage limits of the account, along with the current usage and when it resets.
+    pub async fn get_usage_limits(&self) -> Result<GetUsageLimitsOutput, ApiClientError> {
+        if cfg!(test) {
+            return Ok(GetUsageLimitsOutput::builder()
+                .limits(
+                    UsageLimitList::builder()
+                        .r#type(UsageLimitType::AgenticRequest)
+                        .current_usage(10)
+                        .total_usage_limit(50)
+                        .build()?,
+                )
+                .days_until_reset(10)
+                .build());
+        }
+
+        self.client
+            .get_usage_limits()
+            .set_profile_arn(self.profile.as_ref().map(|p| p.arn.clone()))
+            .send()
+            .await
+            .map_err(ApiClientError::GetUsageLimits)
+    }
+
+    /// Requests that the agentic request limit of the account be raised to `requested_limit`.
+    pub async fn update_usage_limits(
+        &self,
+        requested_limit: i64,
+        justification: String,
+    ) -> Result<UpdateUsageLimitsOutput, ApiClientError> {
+        let request = self
+            .update_usage_limits_request(requested_limit, justification)
+            .ok_or(ApiClientError::MissingProfile)?;
+        if cfg!(test) {
+            return Ok(UpdateUsageLimitsOutput::builder()
+                .status(UsageLimitUpdateRequestStatus::PendingReview)
+                .remaining_requests_this_month(2)
+                .build()?);
+        }
+
+        request.send().await.map_err(ApiClientError::UpdateUsageLimits)
+    }
+
+    /// Builds the UpdateUsageLimits request. The service requires the account id, which is taken
+    /// from the ARN of the profile requests are made with, so this returns [None] without one.
+    fn update_usage_limits_request(
+        &self,
+        requested_limit: i64,
+        justification: String,
+    ) -> Option<UpdateUsageLimitsFluentBuilder> {
+        let account_id = self
+            .profile
+            .as_ref()
+            .and_then(|profile| profile::account_id(&profile.arn))?;
+        Some(
+            self.client
+                .update_usage_limits()
+                .account_id(account_id)
+                .feature_type(UsageLimitType::AgenticRequest)
+                .requested_limit(requested_limit)
+                .justification(justification),
+        )
+    }
+
     pub async fn send_message(&self, conversation: ConversationState) -> Result<SendMessageOutput, ApiClientError> {
         debug!("Sending conversation: {:#?}", conversation);
 
@@ -255,6 +364,7 @@ impl ApiClient {
             conversation_id,
             user_input_message,
             history,
+            agent_mode,
         } = conversation;
 
         let model_id_opt: Option<String> = user_input_message.model_id.clone();
@@ -280,6 +390,7 @@ impl ApiClient {
                 .generate_assistant_response()
                 .conversation_state(conversation_state)
                 .set_profile_arn(self.profile.as_ref().map(|p| p.arn.clone()))
+                .set_agent_mode(agent_mode)
                 .send()
                 .await
             {
@@ -350,10 +461,26 @@ impl ApiClient {
                         return Err(ApiClientError::MonthlyLimitReached { status_code });
                     }
 
+                    if let Some(profile) = &self.profile {
+                        if err
+                            .as_service_error()
+                            .is_some_and(|err| is_profile_rejected(err.meta().code(), err.meta().message()))
+                        {
+                            return Err(ApiClientError::ProfileRejected {
+                                profile_arn: profile.arn.clone(),
+                                status_code,
+                            });
+                        }
+                    }
+
                     Err(err.into())
                 },
             }
         } else if let Some(client) = &self.sigv4_streaming_client {
+            if let Some(agent_mode) = agent_mode {
+                debug!(?agent_mode, "agent mode is not supported by the sigv4 client, ignoring");
+            }
+
             let conversation_state = amzn_qdeveloper_streaming_client::types::ConversationState::builder()
                 .set_conversation_id(conversation_id)
                 .current_message(amzn_qdeveloper_streaming_client::types::ChatMessage::UserInputMessage(
@@ -505,6 +632,15 @@ pub fn stalled_stream_protection_config() -> StalledStreamProtectionConfig {
         .build()
 }
 
+/// Whether a service error was caused by the profile the request was made with, e.g. because it
+/// doesn't exist or belongs to an account the user has no access to.
+fn is_profile_rejected(code: Option<&str>, message: Option<&str>) -> bool {
+    matches!(
+        code,
+        Some("AccessDeniedException" | "ResourceNotFoundException" | "ValidationException")
+    ) && message.is_some_and(|message| message.to_lowercase().contains("profile"))
+}
+
 fn split_tool_use_event(value: &Map<String, serde_json::Value>) -> Vec<ChatResponseStream> {
     let tool_use_id = value.get("tool_use_id").unwrap().as_str().unwrap().to_string();
     let name = value.get("name").unwrap().as_str().unwrap().to_string();
@@ -610,6 +746,7 @@ mod tests {
                     model_id: Some("model".to_owned()),
                 },
                 history: None,
+                agent_mode: None,
             })
             .await
             .unwrap();
@@ -620,4 +757,52 @@ mod tests {
         }
         assert_eq!(output_content, "Hello! How can I assist you today?");
     }
+
+    #[test]
+    fn test_is_profile_rejected() {
+        assert!(is_profile_rejected(
+            Some("AccessDeniedException"),
+            Some("User is not authorized to access the Profile")
+        ));
+        assert!(is_profile_rejected(
+            Some("ResourceNotFoundException"),
+            Some("profile not found")
+        ));
+        assert!(!is_profile_rejected(
+            Some("AccessDeniedException"),
+            Some("User is not authorized")
+        ));
+        assert!(!is_profile_rejected(Some("ThrottlingException"), Some("profile")));
+        assert!(!is_profile_rejected(None, None));
+    }
+
+    #[tokio::test]
+    async fn test_update_usage_limits_request() {
+        let env = Env::new();
+        let fs = Fs::new();
+        let mut database = crate::database::Database::new().await.unwrap();
+        let profile =
+            profile::profile_from_arn("arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456").unwrap();
+        let mut client = ApiClient::with_profile(&env, &fs, &mut database, profile)
+            .await
+            .unwrap();
+
+        let input = client
+            .update_usage_limits_request(100, "more requests".to_string())
+            .unwrap()
+            .as_input()
+            .clone()
+            .build()
+            .unwrap();
+        assert_eq!(input.account_id(), Some("123456789012"));
+        assert_eq!(input.feature_type(), Some(&UsageLimitType::AgenticRequest));
+        assert_eq!(input.requested_limit(), Some(100));
+        assert_eq!(input.justification(), Some("more requests"));
+
+        client.profile = None;
+        assert!(matches!(
+            client.update_usage_limits(100, "more requests".to_string()).await,
+            Err(ApiClientError::MissingProfile)
+        ));
+    }
 }
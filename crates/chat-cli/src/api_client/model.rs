@@ -92,14 +92,18 @@ pub struct Recommendation {
 // Streaming
 // =========
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ConversationState {
     pub conversation_id: Option<String>,
     pub user_input_message: UserInputMessage,
     pub history: Option<Vec<ChatMessage>>,
+    /// Selects a server-side agent behavior for the request. Only supported by the
+    /// CodeWhisperer streaming client; the accepted values are defined by the service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_mode: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum ChatMessage {
     AssistantResponseMessage(AssistantResponseMessage),
     UserInputMessage(UserInputMessage),
@@ -193,6 +197,14 @@ impl Serialize for FigDocumentRef<'_> {
     }
 }
 
+/// Serializes an [AwsDocument] field the same way as [FigDocument].
+fn serialize_aws_document<S>(document: &AwsDocument, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    FigDocumentRef(document).serialize(serializer)
+}
+
 impl Serialize for FigDocument {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -424,7 +436,7 @@ impl From<ToolUse> for amzn_qdeveloper_streaming_client::types::ToolUse {
 }
 
 /// A tool result that contains the results for a tool request that was previously made.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ToolResult {
     /// The ID for the tool request.
     pub tool_use_id: String,
@@ -456,10 +468,10 @@ impl From<ToolResult> for amzn_qdeveloper_streaming_client::types::ToolResult {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum ToolResultContentBlock {
     /// A tool result that is JSON format data.
-    Json(AwsDocument),
+    Json(#[serde(serialize_with = "serialize_aws_document")] AwsDocument),
     /// A tool result that is text.
     Text(String),
 }
@@ -507,7 +519,7 @@ impl From<ToolResultStatus> for amzn_qdeveloper_streaming_client::types::ToolRes
 }
 
 /// Markdown text message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AssistantResponseMessage {
     /// Unique identifier for the chat message
     pub message_id: Option<String>,
@@ -542,7 +554,7 @@ impl TryFrom<AssistantResponseMessage> for amzn_qdeveloper_streaming_client::typ
 }
 
 #[non_exhaustive]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum ChatResponseStream {
     AssistantResponseEvent {
         content: String,
@@ -692,6 +704,25 @@ pub struct EnvState {
     pub operating_system: Option<String>,
     pub current_working_directory: Option<String>,
     pub environment_variables: Vec<EnvironmentVariable>,
+    /// The state of the git repository containing the current working directory, only set when
+    /// the user opts in with "q settings chat.sendGitContext true".
+    ///
+    /// The backend's env state has no field for it, so it's sent as the [GitState] of the
+    /// [UserInputMessageContext] instead.
+    ///
+    /// Boxed to keep [EnvState] small, since it's part of every user message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<Box<GitContext>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitContext {
+    /// The checked out branch, [None] for a detached HEAD.
+    pub branch: Option<String>,
+    /// Whether the work tree has uncommitted changes or untracked files.
+    pub dirty: bool,
+    /// The abbreviated hash of the HEAD commit, [None] if there are no commits yet.
+    pub last_commit: Option<String>,
 }
 
 impl From<EnvState> for amzn_codewhisperer_streaming_client::types::EnvState {
@@ -742,11 +773,23 @@ impl From<EnvironmentVariable> for amzn_qdeveloper_streaming_client::types::Envi
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct GitState {
     pub status: String,
 }
 
+impl From<GitContext> for GitState {
+    fn from(value: GitContext) -> Self {
+        let status = format!(
+            "branch: {}\nstatus: {}\nlast commit: {}",
+            value.branch.as_deref().unwrap_or("(detached HEAD)"),
+            if value.dirty { "dirty" } else { "clean" },
+            value.last_commit.as_deref().unwrap_or("(none)")
+        );
+        Self { status }
+    }
+}
+
 impl From<GitState> for amzn_codewhisperer_streaming_client::types::GitState {
     fn from(value: GitState) -> Self {
         Self::builder().status(value.status).build()
@@ -853,7 +896,7 @@ impl From<ImageBlock> for amzn_qdeveloper_streaming_client::types::ImageBlock {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct UserInputMessage {
     pub content: String,
     pub user_input_message_context: Option<UserInputMessageContext>,
@@ -890,7 +933,7 @@ impl From<UserInputMessage> for amzn_qdeveloper_streaming_client::types::UserInp
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct UserInputMessageContext {
     pub env_state: Option<EnvState>,
     pub git_state: Option<GitState>,
@@ -898,11 +941,25 @@ pub struct UserInputMessageContext {
     pub tools: Option<Vec<Tool>>,
 }
 
+impl UserInputMessageContext {
+    /// Takes the [GitContext] out of [Self::env_state], if any.
+    fn take_git_context(&mut self) -> Option<GitContext> {
+        self.env_state
+            .as_mut()
+            .and_then(|env_state| env_state.git.take())
+            .map(|git| *git)
+    }
+}
+
 impl From<UserInputMessageContext> for amzn_codewhisperer_streaming_client::types::UserInputMessageContext {
-    fn from(value: UserInputMessageContext) -> Self {
+    fn from(mut value: UserInputMessageContext) -> Self {
+        let git_state = value
+            .git_state
+            .take()
+            .or_else(|| value.take_git_context().map(Into::into));
         Self::builder()
             .set_env_state(value.env_state.map(Into::into))
-            .set_git_state(value.git_state.map(Into::into))
+            .set_git_state(git_state.map(Into::into))
             .set_tool_results(value.tool_results.map(|t| t.into_iter().map(Into::into).collect()))
             .set_tools(value.tools.map(|t| t.into_iter().map(Into::into).collect()))
             .build()
@@ -910,17 +967,21 @@ impl From<UserInputMessageContext> for amzn_codewhisperer_streaming_client::type
 }
 
 impl From<UserInputMessageContext> for amzn_qdeveloper_streaming_client::types::UserInputMessageContext {
-    fn from(value: UserInputMessageContext) -> Self {
+    fn from(mut value: UserInputMessageContext) -> Self {
+        let git_state = value
+            .git_state
+            .take()
+            .or_else(|| value.take_git_context().map(Into::into));
         Self::builder()
             .set_env_state(value.env_state.map(Into::into))
-            .set_git_state(value.git_state.map(Into::into))
+            .set_git_state(git_state.map(Into::into))
             .set_tool_results(value.tool_results.map(|t| t.into_iter().map(Into::into).collect()))
             .set_tools(value.tools.map(|t| t.into_iter().map(Into::into).collect()))
             .build()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum UserIntent {
     ApplyCommonBestPractices,
 }
@@ -961,6 +1022,7 @@ mod tests {
                         key: "test key".to_string(),
                         value: "test value".to_string(),
                     }],
+                    git: None,
                 }),
                 git_state: Some(GitState {
                     status: "test status".to_string(),
@@ -1002,6 +1064,30 @@ mod tests {
         assert_eq!(format!("{codewhisper_minimal:?}"), format!("{qdeveloper_minimal:?}"));
     }
 
+    #[test]
+    fn build_user_input_message_context_with_git() {
+        let context = UserInputMessageContext {
+            env_state: Some(EnvState {
+                git: Some(Box::new(GitContext {
+                    branch: Some("main".to_string()),
+                    dirty: true,
+                    last_commit: Some("b7b87e5".to_string()),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let codewhisper_context =
+            amzn_codewhisperer_streaming_client::types::UserInputMessageContext::from(context.clone());
+        let qdeveloper_context = amzn_qdeveloper_streaming_client::types::UserInputMessageContext::from(context);
+        assert_eq!(format!("{codewhisper_context:?}"), format!("{qdeveloper_context:?}"));
+        assert_eq!(
+            codewhisper_context.git_state().and_then(|s| s.status()),
+            Some("branch: main\nstatus: dirty\nlast commit: b7b87e5")
+        );
+    }
+
     #[test]
     fn build_assistant_response_message() {
         let message = AssistantResponseMessage {
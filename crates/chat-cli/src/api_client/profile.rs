@@ -1,8 +1,12 @@
+use thiserror::Error;
+use tracing::error;
+
 use crate::api_client::endpoints::Endpoint;
 use crate::api_client::{
     ApiClient,
     ApiClientError,
 };
+use crate::database::settings::Setting;
 use crate::database::{
     AuthProfile,
     Database,
@@ -28,3 +32,103 @@ pub async fn list_available_profiles(
 
     Ok(profiles)
 }
+
+/// A profile ARN that [profile_from_arn] could not build a profile from.
+#[derive(Debug, Error)]
+#[error("invalid profile ARN '{arn}': {reason}")]
+pub struct InvalidProfileArn {
+    pub arn: String,
+    pub reason: String,
+}
+
+/// Builds the profile for a profile ARN given with `--profile-arn` or the `api.profileArn`
+/// setting, e.g. `arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456`.
+///
+/// The region must be one with a Q Developer endpoint.
+pub fn profile_from_arn(arn: &str) -> Result<AuthProfile, InvalidProfileArn> {
+    let invalid = |reason: &str| InvalidProfileArn {
+        arn: arn.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let [prefix, partition, service, region, account_id, resource] = arn.split(':').collect::<Vec<_>>()[..] else {
+        return Err(invalid(
+            "expected arn:aws:codewhisperer:<region>:<account id>:profile/<profile id>",
+        ));
+    };
+    if prefix != "arn" || !partition.starts_with("aws") || service != "codewhisperer" {
+        return Err(invalid("not a Q Developer profile ARN"));
+    }
+    if !Endpoint::CODEWHISPERER_ENDPOINTS
+        .iter()
+        .any(|endpoint| endpoint.region().as_ref() == region)
+    {
+        return Err(invalid(&format!("profiles are not available in the region '{region}'")));
+    }
+    if account_id.len() != 12 || !account_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid("the account id must be 12 digits"));
+    }
+    let profile_id = match resource.strip_prefix("profile/") {
+        Some(id) if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) => id,
+        _ => return Err(invalid("expected the resource to be profile/<profile id>")),
+    };
+
+    Ok(AuthProfile {
+        arn: arn.to_string(),
+        profile_name: profile_id.to_string(),
+    })
+}
+
+/// Returns the account id segment of a profile ARN, e.g. `123456789012` for
+/// `arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456`.
+pub fn account_id(arn: &str) -> Option<&str> {
+    arn.split(':').nth(4).filter(|account_id| !account_id.is_empty())
+}
+
+/// The profile requests are made with: the `api.profileArn` setting if set, otherwise the
+/// profile selected with `q profile`.
+pub fn active_profile(database: &Database) -> Option<AuthProfile> {
+    if let Some(arn) = database.settings.get_string(Setting::ApiProfileArn) {
+        match profile_from_arn(&arn) {
+            Ok(profile) => return Some(profile),
+            Err(err) => error!("Ignoring the {} setting: {err}", Setting::ApiProfileArn),
+        }
+    }
+
+    match database.get_auth_profile() {
+        Ok(profile) => profile,
+        Err(err) => {
+            error!("Failed to get auth profile: {err}");
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_from_arn() {
+        let profile = profile_from_arn("arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456").unwrap();
+        assert_eq!(
+            profile.arn,
+            "arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456"
+        );
+        assert_eq!(profile.profile_name, "ABCDEF123456");
+        assert!(profile_from_arn("arn:aws:codewhisperer:eu-central-1:123456789012:profile/ABC").is_ok());
+
+        for arn in [
+            "",
+            "ABCDEF123456",
+            "arn:aws:codewhisperer:us-east-1:123456789012",
+            "arn:aws:s3:us-east-1:123456789012:profile/ABCDEF123456",
+            "arn:aws:codewhisperer:ap-south-1:123456789012:profile/ABCDEF123456",
+            "arn:aws:codewhisperer:us-east-1:1234:profile/ABCDEF123456",
+            "arn:aws:codewhisperer:us-east-1:123456789012:customization/ABCDEF123456",
+            "arn:aws:codewhisperer:us-east-1:123456789012:profile/",
+        ] {
+            assert_eq!(profile_from_arn(arn).unwrap_err().arn, arn);
+        }
+    }
+}
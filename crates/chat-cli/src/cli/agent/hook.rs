@@ -21,6 +21,10 @@ pub enum HookTrigger {
     AgentSpawn,
     /// Triggered per user message submission
     UserPromptSubmit,
+    /// Triggered before each tool use. A non-zero exit code blocks the tool use
+    PreToolUse,
+    /// Triggered after each tool use. The hook may rewrite the tool's result
+    PostToolUse,
 }
 
 impl Display for HookTrigger {
@@ -28,6 +32,8 @@ impl Display for HookTrigger {
         match self {
             HookTrigger::AgentSpawn => write!(f, "agentSpawn"),
             HookTrigger::UserPromptSubmit => write!(f, "userPromptSubmit"),
+            HookTrigger::PreToolUse => write!(f, "preToolUse"),
+            HookTrigger::PostToolUse => write!(f, "postToolUse"),
         }
     }
 }
@@ -33,7 +33,7 @@ impl McpServerConfig {
 
     pub async fn save_to_file(&self, os: &Os, path: impl AsRef<Path>) -> eyre::Result<()> {
         let json = self.to_non_transparent_json_pretty()?;
-        os.fs.write(path.as_ref(), json).await?;
+        os.fs.write_atomic(path.as_ref(), json).await?;
         Ok(())
     }
 
@@ -4,7 +4,6 @@ mod mcp_config;
 mod root_command_args;
 mod wrapper_types;
 
-use std::borrow::Borrow;
 use std::collections::{
     HashMap,
     HashSet,
@@ -29,6 +28,7 @@ use crossterm::{
     style,
 };
 use eyre::bail;
+use globset::GlobBuilder;
 pub use mcp_config::McpServerConfig;
 pub use root_command_args::*;
 use schemars::{
@@ -145,6 +145,10 @@ pub struct Agent {
     /// List of tools the agent is explicitly allowed to use
     #[serde(default)]
     pub allowed_tools: HashSet<String>,
+    /// List of tools the agent is explicitly denied from using. This takes precedence over
+    /// allowed_tools
+    #[serde(default)]
+    pub denied_tools: HashSet<String>,
     /// Files to include in the agent's context
     #[serde(default)]
     pub resources: Vec<ResourcePath>,
@@ -181,6 +185,7 @@ impl Default for Agent {
                 set.extend(default_approve);
                 set
             },
+            denied_tools: Default::default(),
             resources: vec!["file://AmazonQ.md", "file://README.md", "file://.amazonq/rules/**/*.md"]
                 .into_iter()
                 .map(Into::into)
@@ -241,6 +246,31 @@ impl Agent {
         Ok(())
     }
 
+    /// Returns true if `tool_name` is in [Agent::allowed_tools], either verbatim or by matching one
+    /// of its glob patterns (e.g. `@aws-*` or `@server/*`). Wildcards do not match across the
+    /// server tool delimiter, so `@aws-*` matches the server `@aws-docs` but none of its tools.
+    pub fn is_tool_allowed(&self, tool_name: &str) -> bool {
+        matches_tool_patterns(&self.allowed_tools, tool_name)
+    }
+
+    /// Returns true if the agent allows every tool from the mcp server `server_name`, or the tool
+    /// `tool_name` from it specifically.
+    pub fn is_mcp_tool_allowed(&self, server_name: &str, tool_name: &str) -> bool {
+        self.is_tool_allowed(&format!("@{server_name}"))
+            || self.is_tool_allowed(&format!("@{server_name}{MCP_SERVER_TOOL_DELIMITER}{tool_name}"))
+    }
+
+    /// Same as [Self::is_tool_allowed], but for [Agent::denied_tools].
+    pub fn is_tool_denied(&self, tool_name: &str) -> bool {
+        matches_tool_patterns(&self.denied_tools, tool_name)
+    }
+
+    /// Same as [Self::is_mcp_tool_allowed], but for [Agent::denied_tools].
+    pub fn is_mcp_tool_denied(&self, server_name: &str, tool_name: &str) -> bool {
+        self.is_tool_denied(&format!("@{server_name}"))
+            || self.is_tool_denied(&format!("@{server_name}{MCP_SERVER_TOOL_DELIMITER}{tool_name}"))
+    }
+
     pub fn to_str_pretty(&self) -> eyre::Result<String> {
         let mut agent_clone = self.clone();
         agent_clone.freeze();
@@ -278,7 +308,13 @@ impl Agent {
                 agent.thaw(&config_path, legacy_mcp_config.as_ref())?;
                 Ok((agent, config_path))
             },
-            _ => bail!("Agent {agent_name} does not exist"),
+            _ => {
+                let names = agent_names_on_disk(os).await;
+                match did_you_mean(agent_name, names.iter().map(String::as_str)) {
+                    Some(hint) => bail!("Agent {agent_name} does not exist. {hint}"),
+                    None => bail!("Agent {agent_name} does not exist"),
+                }
+            },
         }
     }
 
@@ -350,7 +386,10 @@ impl Agents {
 
     pub fn switch(&mut self, name: &str) -> eyre::Result<&Agent> {
         if !self.agents.contains_key(name) {
-            eyre::bail!("No agent with name {name} found");
+            match did_you_mean(name, self.agents.keys().map(String::as_str)) {
+                Some(hint) => eyre::bail!("No agent with name {name} found. {hint}"),
+                None => eyre::bail!("No agent with name {name} found"),
+            }
         }
         self.active_idx = name.to_string();
         self.agents
@@ -687,19 +726,17 @@ impl Agents {
 
     /// Returns a label to describe the permission status for a given tool.
     pub fn display_label(&self, tool_name: &str, origin: &ToolOrigin) -> String {
-        let tool_trusted = self.get_active().is_some_and(|a| {
-            a.allowed_tools.iter().any(|name| {
-                // Here the tool names can take the following forms:
-                // - @{server_name}{delimiter}{tool_name}
-                // - native_tool_name
-                name == tool_name && matches!(origin, &ToolOrigin::Native)
-                    || name.strip_prefix("@").is_some_and(|remainder| {
-                        remainder
-                            .split_once(MCP_SERVER_TOOL_DELIMITER)
-                            .is_some_and(|(_left, right)| right == tool_name)
-                            || remainder == <ToolOrigin as Borrow<str>>::borrow(origin)
-                    })
-            })
+        let tool_denied = self.get_active().is_some_and(|a| match origin {
+            ToolOrigin::Native => a.is_tool_denied(tool_name),
+            ToolOrigin::McpServer(server_name) => a.is_mcp_tool_denied(server_name, tool_name),
+        });
+        if tool_denied {
+            return format!("* {}", "denied".dark_red().bold());
+        }
+
+        let tool_trusted = self.get_active().is_some_and(|a| match origin {
+            ToolOrigin::Native => a.is_tool_allowed(tool_name),
+            ToolOrigin::McpServer(server_name) => a.is_mcp_tool_allowed(server_name, tool_name),
         });
 
         if tool_trusted || self.trust_all_tools {
@@ -761,6 +798,50 @@ async fn load_agents_from_entries(
     res
 }
 
+/// Returns the names of the agents in the local and global agent directories.
+async fn agent_names_on_disk(os: &Os) -> HashSet<String> {
+    let mut names = HashSet::<String>::new();
+    for dir in [
+        directories::chat_local_agent_dir(os),
+        directories::chat_global_agent_path(os),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let Ok(mut files) = os.fs.read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(file)) = files.next_entry().await {
+            let file_path = file.path();
+            if let Some(name) = file_path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .filter(|_| file_path.extension().and_then(OsStr::to_str) == Some("json"))
+            {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Returns a hint naming up to two of the `candidates` closest to `agent_name`, closest first.
+/// Candidates that are too different to be a typo are left out.
+fn did_you_mean<'a>(agent_name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (agent_name.chars().count() / 3).max(2);
+    let mut candidates = candidates
+        .into_iter()
+        .map(|name| (strsim::damerau_levenshtein(agent_name, name), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect::<Vec<_>>();
+    candidates.sort();
+    match candidates.as_slice() {
+        [] => None,
+        [(_, name)] => Some(format!("Did you mean '{name}'?")),
+        [(_, first), (_, second), ..] => Some(format!("Did you mean '{first}' or '{second}'?")),
+    }
+}
+
 /// Loads legacy mcp config by combining workspace and global config.
 /// In case of a server naming conflict, the workspace config is prioritized.
 async fn load_legacy_mcp_config(os: &Os) -> eyre::Result<Option<McpServerConfig>> {
@@ -819,6 +900,41 @@ fn validate_agent_name(name: &str) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Returns true if `tool_name` is one of `patterns`, or matches one of them as a glob.
+fn matches_tool_patterns(patterns: &HashSet<String>, tool_name: &str) -> bool {
+    patterns.contains(tool_name)
+        || patterns
+            .iter()
+            // Matching every tool, or every tool of every server, is not supported
+            .filter(|pattern| pattern.contains(['*', '?', '[']) && !is_catch_all_pattern(pattern))
+            .any(|pattern| {
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .is_ok_and(|glob| glob.compile_matcher().is_match(tool_name))
+            })
+}
+
+/// Returns true if `pattern` has no literal characters other than the `@` server prefix and the
+/// server tool delimiter, e.g. `*`, `**`, `?*`, `@*` or `@*/*`, so that it matches arbitrary names.
+fn is_catch_all_pattern(pattern: &str) -> bool {
+    // Character classes and alternatives are not literal, e.g. `[a-z]*` or `{*,fs_read}`
+    let mut depth = 0_usize;
+    !pattern.chars().any(|c| match c {
+        '[' | '{' => {
+            depth += 1;
+            false
+        },
+        ']' | '}' => {
+            depth = depth.saturating_sub(1);
+            false
+        },
+        _ if depth > 0 => false,
+        '*' | '?' | '@' => false,
+        c => !MCP_SERVER_TOOL_DELIMITER.contains(c),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -861,6 +977,132 @@ mod tests {
         assert!(agent.tool_aliases.contains_key("@gits/some_tool"));
     }
 
+    #[test]
+    fn test_is_tool_allowed_with_patterns() {
+        let agent = Agent {
+            allowed_tools: ["*", "fs_read", "@git", "@fetch/*", "@aws-*", "@github/get_*"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            ..Default::default()
+        };
+
+        // exact matches
+        assert!(agent.is_tool_allowed("fs_read"));
+        assert!(!agent.is_tool_allowed("fs_write"));
+        assert!(!agent.is_tool_allowed("execute_bash"));
+        assert!(agent.is_mcp_tool_allowed("git", "git_status"));
+        assert!(!agent.is_mcp_tool_allowed("gitlab", "git_status"));
+
+        // @server/*
+        assert!(agent.is_mcp_tool_allowed("fetch", "fetch_url"));
+        assert!(!agent.is_mcp_tool_allowed("fetcher", "fetch_url"));
+
+        // @aws-*
+        assert!(agent.is_mcp_tool_allowed("aws-docs", "search"));
+        assert!(agent.is_mcp_tool_allowed("aws-", "search"));
+        assert!(!agent.is_mcp_tool_allowed("aws", "search"));
+        assert!(!agent.is_mcp_tool_allowed("my-aws-docs", "search"));
+        assert!(!agent.is_tool_allowed("@aws-docs/nested/search"));
+
+        // tool name patterns
+        assert!(agent.is_mcp_tool_allowed("github", "get_issue"));
+        assert!(!agent.is_mcp_tool_allowed("github", "create_issue"));
+    }
+
+    #[test]
+    fn test_catch_all_patterns_are_ignored() {
+        for pattern in [
+            "*",
+            "**",
+            "?*",
+            "*?",
+            "[a-z]*",
+            "{*,fs_read}",
+            "@*",
+            "@**",
+            "@?*",
+            "@*/*",
+        ] {
+            let agent = Agent {
+                allowed_tools: [pattern.to_string()].into(),
+                denied_tools: [pattern.to_string()].into(),
+                ..Default::default()
+            };
+            assert!(!agent.is_tool_allowed("fs_write"), "{pattern}");
+            assert!(!agent.is_mcp_tool_allowed("server", "tool"), "{pattern}");
+            assert!(!agent.is_tool_denied("fs_write"), "{pattern}");
+        }
+
+        for pattern in ["fs_*", "@aws-*", "@*/read_*", "[f]s_read"] {
+            assert!(!is_catch_all_pattern(pattern), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_denied_tools_override_allowed_tools() {
+        let agent = Agent {
+            allowed_tools: ["fs_write", "@server", "@aws-*"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            denied_tools: ["fs_write", "@server/dangerous_tool", "@aws-*/delete_*"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            ..Default::default()
+        };
+
+        assert!(agent.is_tool_allowed("fs_write") && agent.is_tool_denied("fs_write"));
+        assert!(agent.is_mcp_tool_denied("server", "dangerous_tool"));
+        assert!(!agent.is_mcp_tool_denied("server", "safe_tool"));
+        assert!(agent.is_mcp_tool_allowed("server", "safe_tool"));
+        assert!(agent.is_mcp_tool_denied("aws-docs", "delete_doc"));
+        assert!(!agent.is_mcp_tool_denied("aws-docs", "read_doc"));
+        assert!(!agent.is_tool_denied("fs_read"));
+    }
+
+    #[tokio::test]
+    async fn test_get_agent_by_name_suggests_similar_names() {
+        let os = Os::new().await.unwrap();
+        let global_dir = directories::chat_global_agent_path(&os).unwrap();
+        os.fs.create_dir_all(&global_dir).await.unwrap();
+        for name in ["default", "reviewer", "unrelated"] {
+            os.fs
+                .write(
+                    global_dir.join(format!("{name}.json")),
+                    format!(r#"{{"name": "{name}"}}"#),
+                )
+                .await
+                .unwrap();
+        }
+
+        let err = Agent::get_agent_by_name(&os, "defualt").await.unwrap_err().to_string();
+        assert_eq!(err, "Agent defualt does not exist. Did you mean 'default'?");
+
+        let err = Agent::get_agent_by_name(&os, "something")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "Agent something does not exist");
+
+        assert!(Agent::get_agent_by_name(&os, "default").await.is_ok());
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let names = ["default", "defaults", "reviewer"];
+        assert_eq!(
+            did_you_mean("defualt", names).as_deref(),
+            Some("Did you mean 'default' or 'defaults'?")
+        );
+        assert_eq!(
+            did_you_mean("reveiwer", names).as_deref(),
+            Some("Did you mean 'reviewer'?")
+        );
+        assert_eq!(did_you_mean("planner", names), None);
+    }
+
     #[test]
     fn test_get_active() {
         let mut collection = Agents::default();
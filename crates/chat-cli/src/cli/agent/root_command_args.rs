@@ -23,6 +23,7 @@ use super::{
     McpServerConfig,
     legacy,
 };
+use crate::cli::chat::tools::custom_tool::process_env_vars;
 use crate::database::settings::Setting;
 use crate::os::Os;
 use crate::util::directories;
@@ -63,6 +64,15 @@ pub enum AgentSubcommands {
         #[arg(long, short)]
         name: String,
     },
+    /// Print the config of an agent
+    Show {
+        /// Name of the agent to show
+        name: String,
+        /// Print the config as q chat uses it, i.e. with defaults filled in, environment variables
+        /// substituted and servers from the legacy mcp.json merged in, instead of the file content
+        #[arg(long)]
+        resolved: bool,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Args)]
@@ -250,6 +260,29 @@ impl AgentArgs {
                     },
                 }
             },
+            Some(AgentSubcommands::Show { name, resolved }) => {
+                let agents = Agents::load(os, None, true, &mut stderr).await.0;
+                let Some(agent) = agents.agents.get(&name) else {
+                    bail!("Agent {name} does not exist");
+                };
+
+                let output = match (&agent.path, resolved) {
+                    (Some(path), false) => os.fs.read_to_string(path).await?,
+                    // Agents without a path, such as the built in default, have no file to show
+                    (None, false) => agent.to_str_pretty()?,
+                    (_, true) => {
+                        let mut agent = agent.clone();
+                        for config in agent.mcp_servers.mcp_servers.values_mut() {
+                            if let Some(env) = config.env.as_mut() {
+                                process_env_vars(env, &os.env);
+                            }
+                        }
+                        serde_json::to_string_pretty(&agent)?
+                    },
+                };
+
+                writeln!(std::io::stdout(), "{}", output.trim_end())?;
+            },
             Some(AgentSubcommands::SetDefault { name }) => {
                 let mut agents = Agents::load(os, None, true, &mut stderr).await.0;
                 match agents.switch(&name) {
@@ -356,6 +389,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_agent_subcommand_show() {
+        assert_parse!(
+            ["agent", "show", "some_agent"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Show {
+                    name: "some_agent".to_string(),
+                    resolved: false,
+                })
+            })
+        );
+        assert_parse!(
+            ["agent", "show", "some_agent", "--resolved"],
+            RootSubcommand::Agent(AgentArgs {
+                cmd: Some(AgentSubcommands::Show {
+                    name: "some_agent".to_string(),
+                    resolved: true,
+                })
+            })
+        );
+    }
+
     #[test]
     fn test_agent_subcommand_create() {
         assert_parse!(
@@ -17,10 +17,35 @@ use crate::cli::chat::{
 
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct ClearArgs;
+pub struct ClearArgs {
+    /// Restore the conversation history erased by the last /clear, if no message was sent since
+    #[arg(long)]
+    undo: bool,
+}
 
 impl ClearArgs {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if self.undo {
+            if session.conversation.undo_clear() {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("\nConversation history restored.\n\n"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            } else {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print("\nThere is no cleared conversation history to restore.\n\n"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
         execute!(
             session.stderr,
             style::SetForegroundColor(Color::DarkGrey),
@@ -54,7 +79,13 @@ impl ClearArgs {
             execute!(
                 session.stderr,
                 style::SetForegroundColor(Color::Green),
-                style::Print("\nConversation history cleared.\n\n"),
+                style::Print("\nConversation history cleared.\n"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("Run "),
+                style::SetForegroundColor(Color::DarkGreen),
+                style::Print("/clear --undo"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(" to restore it before sending your next message.\n\n"),
                 style::SetForegroundColor(Color::Reset)
             )?;
         }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::io::Write;
 
 use clap::Subcommand;
 use crossterm::style::{
@@ -7,6 +8,7 @@ use crossterm::style::{
 };
 use crossterm::{
     execute,
+    queue,
     style,
 };
 
@@ -17,6 +19,7 @@ use crate::cli::chat::context::{
 };
 use crate::cli::chat::token_counter::TokenCounter;
 use crate::cli::chat::util::drop_matched_context_files;
+use crate::cli::chat::util::images::is_supported_image_type;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -35,6 +38,7 @@ more accurate and helpful responses.
 
 Notes:
 • You can add specific files or use glob patterns (e.g., \"*.py\", \"src/**/*.js\")
+• Glob patterns skip files ignored by .gitignore unless --no-gitignore is passed
 • Agent rules apply only to the current agent 
 • Context changes are NOT preserved between chat sessions. To make these changes permanent, edit the agent config file."
 )]
@@ -51,6 +55,9 @@ pub enum ContextSubcommand {
         /// Include even if matched files exceed size limits
         #[arg(short, long)]
         force: bool,
+        /// Include files ignored by .gitignore when expanding glob patterns
+        #[arg(long)]
+        no_gitignore: bool,
         #[arg(required = true)]
         paths: Vec<String>,
     },
@@ -86,6 +93,7 @@ impl ContextSubcommand {
                 // the bool signifies if the resources is temporary (i.e. is it session based as
                 // opposed to agent based)
                 let mut profile_context_files = HashSet::<(String, String, bool)>::new();
+                let mut binary_files = Vec::new();
 
                 let (agent_owned_list, session_owned_list) = context_manager
                     .paths
@@ -110,10 +118,11 @@ impl ContextSubcommand {
                 } else {
                     for path in &agent_owned_list {
                         execute!(session.stderr, style::Print(format!("    {} ", path.get_path_as_str())))?;
-                        if let Ok(context_files) = context_manager
+                        if let Ok((context_files, skipped)) = context_manager
                             .get_context_files_by_path(os, path.get_path_as_str())
                             .await
                         {
+                            binary_files.extend(skipped);
                             execute!(
                                 session.stderr,
                                 style::SetForegroundColor(Color::Green),
@@ -150,10 +159,11 @@ impl ContextSubcommand {
                 } else {
                     for path in &session_owned_list {
                         execute!(session.stderr, style::Print(format!("    {} ", path.get_path_as_str())))?;
-                        if let Ok(context_files) = context_manager
+                        if let Ok((context_files, skipped)) = context_manager
                             .get_context_files_by_path(os, path.get_path_as_str())
                             .await
                         {
+                            binary_files.extend(skipped);
                             execute!(
                                 session.stderr,
                                 style::SetForegroundColor(Color::Green),
@@ -172,6 +182,10 @@ impl ContextSubcommand {
                     execute!(session.stderr, style::Print("\n"))?;
                 }
 
+                binary_files.sort();
+                binary_files.dedup();
+                print_binary_files_warning(&mut session.stderr, &binary_files)?;
+
                 if profile_context_files.is_empty() {
                     execute!(
                         session.stderr,
@@ -181,10 +195,13 @@ impl ContextSubcommand {
                     )?;
                 } else {
                     let total = profile_context_files.len();
-                    let total_tokens = profile_context_files
-                        .iter()
-                        .map(|(_, content, _)| TokenCounter::count_tokens(content))
-                        .sum::<usize>();
+                    let file_tokens = TokenCounter::count_tokens_batch(
+                        &profile_context_files
+                            .iter()
+                            .map(|(_, content, _)| content.as_str())
+                            .collect::<Vec<_>>(),
+                    );
+                    let total_tokens = file_tokens.iter().sum::<usize>();
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
@@ -198,8 +215,9 @@ impl ContextSubcommand {
                         style::SetAttribute(Attribute::Reset)
                     )?;
 
-                    for (filename, content, is_temporary) in &profile_context_files {
-                        let est_tokens = TokenCounter::count_tokens(content);
+                    for ((filename, content, is_temporary), est_tokens) in
+                        profile_context_files.iter().zip(&file_tokens)
+                    {
                         let icon = if *is_temporary { "💬" } else { "👤" };
                         execute!(
                             session.stderr,
@@ -294,8 +312,12 @@ impl ContextSubcommand {
                     }
                 }
             },
-            Self::Add { force, paths } => match context_manager.add_paths(os, paths.clone(), force).await {
-                Ok(_) => {
+            Self::Add {
+                force,
+                no_gitignore,
+                paths,
+            } => match context_manager.add_paths(os, paths.clone(), force, no_gitignore).await {
+                Ok(binary_files) => {
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
@@ -303,6 +325,7 @@ impl ContextSubcommand {
                         style::Print("Note: Context modifications via slash command is temporary.\n\n"),
                         style::SetForegroundColor(Color::Reset)
                     )?;
+                    print_binary_files_warning(&mut session.stderr, &binary_files)?;
                 },
                 Err(e) => {
                     execute!(
@@ -372,3 +395,32 @@ impl ContextSubcommand {
         }
     }
 }
+
+/// Warns that the given matched files were left out of context because they are binary.
+fn print_binary_files_warning(output: &mut impl Write, binary_files: &[String]) -> Result<(), ChatError> {
+    if binary_files.is_empty() {
+        return Ok(());
+    }
+
+    queue!(
+        output,
+        style::SetForegroundColor(Color::DarkYellow),
+        style::Print("Skipped binary files that cannot be used as context:\n"),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+    for file in binary_files {
+        queue!(output, style::Print(format!("    {}", file)))?;
+        if is_supported_image_type(file) {
+            queue!(
+                output,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(" (to share an image, include its path in your prompt instead)"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        }
+        queue!(output, style::Print("\n"))?;
+    }
+    execute!(output, style::Print("\n"))?;
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{
+    Subcommand,
+    ValueEnum,
+};
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::protocol_log::ProtocolLog;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+#[command(before_long_help = "Inspect what is sent to the model.
+
+Notes:
+• The delimiters around context entries and prompts can be changed with
+  \"q settings chat.contextEntryStartHeader <header>\", \"q settings chat.contextEntryEndHeader <header>\",
+  \"q settings chat.userMessageStartHeader <header>\" and \"q settings chat.userMessageEndHeader <header>\"
+• To not send the current time with each prompt, run \"q settings chat.injectTimestamp false\"
+• /debug protocol redacts values that look like credentials, but tool results and prompts are
+  otherwise logged as is, so review the log before sharing it")]
+pub enum DebugSubcommand {
+    /// Print the exact content of the last message sent to the model, including all delimiters
+    LastRequest,
+    /// Log each request sent to the backend and each response event received as JSON
+    Protocol {
+        /// Whether to turn logging on or off
+        state: ProtocolLogState,
+        /// Append the log to this file as one line of JSON per entry, instead of printing it
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProtocolLogState {
+    On,
+    Off,
+}
+
+impl DebugSubcommand {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        match self {
+            Self::LastRequest => match session.conversation.last_request_content() {
+                Some(content) => {
+                    let content = content.to_string();
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!("\nLast request ({} bytes):\n\n", content.len())),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(content),
+                        style::Print("\n\n"),
+                    )?;
+                },
+                None => {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("\nNo message has been sent in this conversation yet.\n\n"),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                },
+            },
+            Self::Protocol { state, file } => {
+                session.protocol_log = match state {
+                    ProtocolLogState::On => Some(file.map_or(ProtocolLog::Stderr, ProtocolLog::File)),
+                    ProtocolLogState::Off => None,
+                };
+                let message = match &session.protocol_log {
+                    Some(ProtocolLog::Stderr) => {
+                        "\nRequests and response events will be printed as JSON.\n\n".to_string()
+                    },
+                    Some(ProtocolLog::File(path)) => format!(
+                        "\nRequests and response events will be appended to {} as JSON.\n\n",
+                        path.display()
+                    ),
+                    None => "\nProtocol logging is off.\n\n".to_string(),
+                };
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(message),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+        }
+
+        session.stderr.flush()?;
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LastRequest => "last-request",
+            Self::Protocol { .. } => "protocol",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::chat::cli::SlashCommand;
+
+    #[test]
+    fn test_debug_protocol_parse() {
+        let parse = |args: &[&str]| {
+            SlashCommand::try_parse_from(["slash_command", "debug", "protocol"].iter().chain(args)).map(|command| {
+                match command {
+                    SlashCommand::Debug(sub) => Some(sub),
+                    _ => None,
+                }
+            })
+        };
+        assert_eq!(
+            parse(&["on"]).unwrap(),
+            Some(DebugSubcommand::Protocol {
+                state: ProtocolLogState::On,
+                file: None
+            })
+        );
+        assert_eq!(
+            parse(&["on", "--file", "protocol.jsonl"]).unwrap(),
+            Some(DebugSubcommand::Protocol {
+                state: ProtocolLogState::On,
+                file: Some(PathBuf::from("protocol.jsonl"))
+            })
+        );
+        assert_eq!(
+            parse(&["off"]).unwrap(),
+            Some(DebugSubcommand::Protocol {
+                state: ProtocolLogState::Off,
+                file: None
+            })
+        );
+        assert!(parse(&[]).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use clap::Args;
+use crossterm::style::{
+    Attribute,
+    Color,
+};
+use crossterm::{
+    queue,
+    style,
+};
+
+use crate::cli::chat::line_tracker::FileLineTracker;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "/diffstat shows, for each file the agent wrote to in this session, the net lines changed
+by the agent and the net lines you changed in between its writes. Files are sorted by the most
+lines changed.
+
+Notes:
+• Counts are net line counts, so replacing a line is not counted as a change
+• Changes you made before the agent first wrote to a file are not counted"
+)]
+pub struct DiffstatArgs;
+
+impl DiffstatArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let rows = diffstat(&session.conversation.file_line_tracker);
+        if rows.is_empty() {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("\nThe agent hasn't written to any files in this session.\n\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            session.stderr.flush()?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
+        let total_by_agent: isize = rows.iter().map(|(_, tracker)| tracker.total_lines_by_agent).sum();
+        let total_by_user: isize = rows.iter().map(|(_, tracker)| tracker.total_lines_by_user).sum();
+        let width = rows
+            .iter()
+            .map(|(path, _)| path.len())
+            .max()
+            .unwrap_or(0)
+            .max("Total".len())
+            + 4;
+        let terminal_width = session.terminal_width();
+
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetAttribute(Attribute::Bold),
+            style::Print(format!("{:<width$}{:>8}{:>8}\n", "File", "Agent", "You")),
+            style::SetAttribute(Attribute::Reset),
+            style::Print("▔".repeat(terminal_width)),
+            style::Print("\n"),
+        )?;
+        for (path, tracker) in &rows {
+            queue_row(
+                &mut session.stderr,
+                path,
+                tracker.total_lines_by_agent,
+                tracker.total_lines_by_user,
+                width,
+            )?;
+        }
+        queue!(session.stderr, style::SetAttribute(Attribute::Bold))?;
+        queue_row(&mut session.stderr, "Total", total_by_agent, total_by_user, width)?;
+        queue!(
+            session.stderr,
+            style::SetAttribute(Attribute::Reset),
+            style::Print("\n")
+        )?;
+        session.stderr.flush()?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
+
+fn queue_row(
+    output: &mut impl Write,
+    name: &str,
+    by_agent: isize,
+    by_user: isize,
+    width: usize,
+) -> std::io::Result<()> {
+    let color = |lines: isize| match lines {
+        0 => Color::DarkGrey,
+        l if l > 0 => Color::Green,
+        _ => Color::Red,
+    };
+    queue!(
+        output,
+        style::Print(format!("{name:<width$}")),
+        style::SetForegroundColor(color(by_agent)),
+        style::Print(format!("{:>8}", format!("{by_agent:+}"))),
+        style::SetForegroundColor(color(by_user)),
+        style::Print(format!("{:>8}", format!("{by_user:+}"))),
+        style::SetForegroundColor(Color::Reset),
+        style::Print("\n"),
+    )
+}
+
+/// Returns the tracked files sorted by the most lines changed, then by path.
+fn diffstat(trackers: &HashMap<String, FileLineTracker>) -> Vec<(&str, &FileLineTracker)> {
+    let mut rows = trackers
+        .iter()
+        .map(|(path, tracker)| (path.as_str(), tracker))
+        .collect::<Vec<_>>();
+    rows.sort_by(|(a_path, a), (b_path, b)| {
+        let changed =
+            |t: &FileLineTracker| t.total_lines_by_agent.unsigned_abs() + t.total_lines_by_user.unsigned_abs();
+        changed(b).cmp(&changed(a)).then_with(|| a_path.cmp(b_path))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diffstat_sort() {
+        let tracker = |by_agent, by_user| FileLineTracker {
+            total_lines_by_agent: by_agent,
+            total_lines_by_user: by_user,
+            ..Default::default()
+        };
+        let trackers = HashMap::from([
+            ("/a.rs".to_string(), tracker(3, 0)),
+            ("/b.rs".to_string(), tracker(-20, 1)),
+            ("/c.rs".to_string(), tracker(1, 2)),
+        ]);
+        let paths = diffstat(&trackers)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["/b.rs", "/a.rs", "/c.rs"]);
+    }
+}
@@ -0,0 +1,73 @@
+use std::io::Write;
+
+use clap::Args;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::message::{
+    build_env_state,
+    build_git_context,
+};
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::database::settings::Setting;
+use crate::os::Os;
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "/env prints the environment context sent with each message, as it would be
+serialized. Nothing is sent to the model.
+
+Notes:
+• To not send any environment context, run \"q settings chat.sendEnvContext false\"
+• To also send the git branch, dirty flag, and last commit of the current repository, run
+  \"q settings chat.sendGitContext true\""
+)]
+pub struct EnvArgs;
+
+impl EnvArgs {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let settings = &os.database.settings;
+        if !settings.get_bool(Setting::ChatSendEnvContext).unwrap_or(true) {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "\nNo environment context is sent, since {} is disabled.\n\n",
+                    Setting::ChatSendEnvContext
+                )),
+                style::SetForegroundColor(Color::Reset)
+            )?;
+        } else {
+            let mut env_state = build_env_state();
+            if settings.get_bool(Setting::ChatSendGitContext).unwrap_or(false) {
+                if let Ok(dir) = std::env::current_dir() {
+                    env_state.git = build_git_context(&dir).await.map(Box::new);
+                }
+            }
+            let json = serde_json::to_string_pretty(&env_state).map_err(|e| {
+                ChatError::Custom(format!("Error converting environment context to string: {e}").into())
+            })?;
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("\nEnvironment context sent with each message:\n\n"),
+                style::SetForegroundColor(Color::Reset),
+                style::Print(json),
+                style::Print("\n\n"),
+            )?;
+        }
+
+        session.stderr.flush()?;
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
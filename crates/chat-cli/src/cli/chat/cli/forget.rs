@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use clap::Args;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::token_counter::{
+    CharCount,
+    CharCounter,
+    TokenCount,
+};
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "/forget removes a message from the conversation history, e.g. a large tool result or
+pasted text that is no longer needed, without clearing the rest of the history.
+
+The whole user turn containing the message is removed, i.e. the prompt along with the tool uses,
+tool results and responses that followed it, since a tool result can't be sent without the tool
+use it answers. Use /usage --by-message to see the entry numbers and the size of each message."
+)]
+pub struct ForgetArgs {
+    /// The entry number of the message to remove, as shown by /usage --by-message
+    index: usize,
+}
+
+impl ForgetArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let removed = match self.index.checked_sub(1) {
+            Some(index) => session.conversation.forget(index),
+            None => None,
+        };
+
+        match removed {
+            Some(removed) => {
+                let chars = removed
+                    .iter()
+                    .map(|entry| entry.user().char_count() + entry.assistant().char_count())
+                    .fold(CharCount::from(0), |acc, c| acc + c);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\nRemoved {} {} from the conversation history, freeing ~{} tokens.\n\n",
+                        removed.len(),
+                        if removed.len() == 1 { "entry" } else { "entries" },
+                        TokenCount::from(chars)
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            None => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!(
+                        "\nThere is no entry number {}. Run /usage --by-message to see the entries.\n\n",
+                        self.index
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+
+        session.stderr.flush()?;
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use clap::Subcommand;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+#[command(
+    before_long_help = "The prompt history lets you recall previous prompts with the up arrow, across chat sessions.
+
+Notes:
+• The history is saved to the file set by \"q settings chat.historyFile <path>\"
+• To not save the history at all, run \"q settings chat.historyFile ''\"
+• The number of saved prompts is limited by \"q settings chat.historyMaxEntries <number>\"
+• Press Ctrl(^) + r at the prompt to search the history in place"
+)]
+pub enum HistorySubcommand {
+    /// Search the prompt history and pick a prompt to send again
+    Search {
+        /// Only show prompts containing this text
+        term: Option<String>,
+    },
+    /// Clear the prompt history, including the saved history file
+    Clear,
+}
+
+impl HistorySubcommand {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        match self {
+            Self::Search { term } => {
+                let entries = matching_entries(session.input_source.history_entries(), term.as_deref());
+                if entries.is_empty() {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("\nNo prompts in the history match.\n\n"),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                }
+
+                #[cfg(unix)]
+                {
+                    use crate::cli::chat::skim_integration::launch_skim_selector;
+
+                    // Skim shows one item per line, so multi-line prompts are flattened for display and
+                    // mapped back to the original prompt by position.
+                    let lines = entries.iter().map(|entry| flatten(entry)).collect::<Vec<_>>();
+                    let selected = launch_skim_selector(&lines, "Search history: ", false)
+                        .map_err(|err| ChatError::Custom(format!("Failed to search the prompt history: {err}").into()))?
+                        .and_then(|selections| selections.into_iter().next())
+                        .and_then(|selection| lines.iter().position(|line| *line == selection));
+                    if let Some(i) = selected {
+                        return Ok(ChatState::HandleInput {
+                            input: entries[i].clone(),
+                        });
+                    }
+                }
+
+                #[cfg(not(unix))]
+                {
+                    crossterm::queue!(session.stderr, style::Print("\n"))?;
+                    for entry in &entries {
+                        crossterm::queue!(session.stderr, style::Print(format!("  {}\n", flatten(entry))))?;
+                    }
+                    crossterm::queue!(session.stderr, style::Print("\n"))?;
+                }
+            },
+            Self::Clear => {
+                session
+                    .input_source
+                    .clear_history()
+                    .map_err(|err| ChatError::Custom(format!("Failed to clear the prompt history: {err}").into()))?;
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("\nPrompt history cleared.\n\n"),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            },
+        }
+
+        session.stderr.flush()?;
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Search { .. } => "search",
+            Self::Clear => "clear",
+        }
+    }
+}
+
+/// Returns the history entries containing `term`, ignoring case.
+fn matching_entries(entries: Vec<String>, term: Option<&str>) -> Vec<String> {
+    match term.map(str::to_lowercase) {
+        Some(term) => entries
+            .into_iter()
+            .filter(|entry| entry.to_lowercase().contains(&term))
+            .collect(),
+        None => entries,
+    }
+}
+
+fn flatten(entry: &str) -> String {
+    entry.replace('\n', " ⏎ ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_entries() {
+        let entries = vec![
+            "fix the Build".to_string(),
+            "explain this\nbuild error".to_string(),
+            "hello".to_string(),
+        ];
+        assert_eq!(matching_entries(entries.clone(), None), entries);
+        assert_eq!(matching_entries(entries.clone(), Some("BUILD")), entries[..2].to_vec());
+        assert!(matching_entries(entries, Some("missing")).is_empty());
+    }
+}
@@ -32,13 +32,16 @@ use spinners::{
     Spinner,
     Spinners,
 };
+use tokio::io::AsyncWriteExt;
 
 use crate::cli::agent::hook::{
     Hook,
     HookTrigger,
 };
 use crate::cli::chat::consts::AGENT_FORMAT_HOOKS_DOC_URL;
+use crate::cli::chat::message::ToolUseResultBlock;
 use crate::cli::chat::util::truncate_safe;
+use crate::cli::chat::util::ui::should_animate;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -101,7 +104,7 @@ impl HookExecutor {
         };
 
         if total != 0 {
-            spinner = Some(Spinner::new(Spinners::Dots12, spinner_text(complete, total)));
+            spinner = should_animate().then(|| Spinner::new(Spinners::Dots12, spinner_text(complete, total)));
         }
 
         // Process results as they complete
@@ -161,7 +164,7 @@ impl HookExecutor {
                     style::ResetColor,
                 )?;
             } else {
-                spinner = Some(Spinner::new(Spinners::Dots, spinner_text(complete, total)));
+                spinner = should_animate().then(|| Spinner::new(Spinners::Dots, spinner_text(complete, total)));
             }
         }
         drop(futures);
@@ -172,7 +175,9 @@ impl HookExecutor {
                 output: output.clone(),
                 expiry: match trigger {
                     HookTrigger::AgentSpawn => None,
-                    HookTrigger::UserPromptSubmit => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                    HookTrigger::UserPromptSubmit | HookTrigger::PreToolUse | HookTrigger::PostToolUse => {
+                        Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds))
+                    },
                 },
             });
         }
@@ -189,27 +194,7 @@ impl HookExecutor {
     ) -> ((HookTrigger, Hook), Result<String>, Duration) {
         let start_time = Instant::now();
 
-        let command = &hook.1.command;
-
-        #[cfg(unix)]
-        let mut cmd = tokio::process::Command::new("bash");
-        #[cfg(unix)]
-        let cmd = cmd
-            .arg("-c")
-            .arg(command)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        #[cfg(windows)]
-        let mut cmd = tokio::process::Command::new("cmd");
-        #[cfg(windows)]
-        let cmd = cmd
-            .arg("/C")
-            .arg(command)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+        let mut cmd = hook_command(&hook.1.command);
 
         let timeout = Duration::from_millis(hook.1.timeout_ms);
 
@@ -264,6 +249,138 @@ impl HookExecutor {
     }
 }
 
+/// Runs the given [`HookTrigger::PreToolUse`] hooks in order before a tool is invoked.
+///
+/// Each hook receives a JSON object on stdin of the form:
+///
+/// ```json
+/// { "hook_event_name": "preToolUse", "cwd": "/path/to/cwd", "tool_name": "use_aws", "tool_input": { ... } }
+/// ```
+///
+/// Returns an error describing why the tool use was blocked as soon as a hook exits with a
+/// non-zero status, fails to execute, or times out. Hook output is never cached.
+pub async fn run_pre_tool_use_hooks(hooks: &[Hook], tool_name: &str, tool_input: &serde_json::Value) -> Result<()> {
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "hook_event_name": HookTrigger::PreToolUse.to_string(),
+        "cwd": std::env::current_dir().unwrap_or_default(),
+        "tool_name": tool_name,
+        "tool_input": tool_input,
+    }))?;
+
+    for hook in hooks {
+        run_hook_with_input(hook, &payload).await?;
+    }
+
+    Ok(())
+}
+
+/// Runs a single [`HookTrigger::PostToolUse`] hook against the result of a tool use, returning
+/// the (possibly modified) result.
+///
+/// The hook receives a JSON object on stdin of the form:
+///
+/// ```json
+/// { "hook_event_name": "postToolUse", "cwd": "/path/to/cwd", "tool_name": "execute_bash", "tool_input": { ... }, "tool_response": ... }
+/// ```
+///
+/// where `tool_response` is a JSON string for text output, or any other JSON value for JSON
+/// output. The hook writes the replacement `tool_response` as JSON on stdout; empty output
+/// leaves the result unchanged.
+pub async fn run_post_tool_use_hook(
+    hook: &Hook,
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    result: &ToolUseResultBlock,
+) -> Result<ToolUseResultBlock> {
+    let tool_response = match result {
+        ToolUseResultBlock::Text(text) => serde_json::Value::String(text.clone()),
+        ToolUseResultBlock::Json(json) => json.clone(),
+    };
+    let payload = serde_json::to_vec(&serde_json::json!({
+        "hook_event_name": HookTrigger::PostToolUse.to_string(),
+        "cwd": std::env::current_dir().unwrap_or_default(),
+        "tool_name": tool_name,
+        "tool_input": tool_input,
+        "tool_response": tool_response,
+    }))?;
+
+    let stdout = run_hook_with_input(hook, &payload).await?;
+    if stdout.trim().is_empty() {
+        return Ok(result.clone());
+    }
+
+    match serde_json::from_slice(&stdout) {
+        Ok(serde_json::Value::String(text)) => Ok(ToolUseResultBlock::Text(text)),
+        Ok(json) => Ok(ToolUseResultBlock::Json(json)),
+        Err(err) => Err(eyre!("hook `{}` did not write valid JSON: {}", hook.command, err)),
+    }
+}
+
+/// Runs a hook with `input` written to its stdin, returning its stdout if it exits successfully.
+async fn run_hook_with_input(hook: &Hook, input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = hook_command(&hook.command).kill_on_drop(true).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written from a task so that a hook that never reads its input is still covered by the
+        // timeout. The hook may exit without reading its input, so a broken pipe is not an error.
+        let input = input.to_vec();
+        tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+    }
+
+    let timeout = Duration::from_millis(hook.timeout_ms);
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => return Err(eyre!("hook `{}` failed to execute: {}", hook.command, err)),
+        Err(_) => {
+            return Err(eyre!(
+                "hook `{}` timed out after {} ms",
+                hook.command,
+                timeout.as_millis()
+            ));
+        },
+    };
+
+    if !output.status.success() {
+        let stderr = output.stderr.to_str_lossy();
+        let reason = truncate_safe(stderr.trim(), hook.max_output_size);
+        return Err(match reason.is_empty() {
+            true => eyre!("hook `{}` returned non-zero exit code: {}", hook.command, output.status),
+            false => eyre!(
+                "hook `{}` returned non-zero exit code: {}: {}",
+                hook.command,
+                output.status,
+                reason
+            ),
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+/// Builds the platform shell command used to run a hook, with all stdio piped.
+fn hook_command(command: &str) -> tokio::process::Command {
+    #[cfg(unix)]
+    let mut cmd = tokio::process::Command::new("bash");
+    #[cfg(unix)]
+    cmd.arg("-c");
+
+    #[cfg(windows)]
+    let mut cmd = tokio::process::Command::new("cmd");
+    #[cfg(windows)]
+    cmd.arg("/C");
+
+    cmd.arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
 /// Sanitizes a string value to be used as an environment variable
 fn sanitize_user_prompt(input: &str) -> String {
     // Limit the size of input to first 4096 characters
@@ -329,3 +446,88 @@ impl HooksArgs {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::hook::Source;
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_pre_tool_use_hook_denies_service() {
+        let hooks = vec![Hook::new(
+            r#"if grep -q '"service_name":"ec2"'; then echo "ec2 is off limits" >&2; exit 1; fi"#.to_string(),
+            Source::Agent,
+        )];
+
+        let ec2 = serde_json::json!({ "service_name": "ec2", "operation_name": "describe-instances" });
+        let err = run_pre_tool_use_hooks(&hooks, "use_aws", &ec2).await.unwrap_err();
+        assert!(err.to_string().contains("ec2 is off limits"));
+
+        let s3 = serde_json::json!({ "service_name": "s3", "operation_name": "list-buckets" });
+        assert!(run_pre_tool_use_hooks(&hooks, "use_aws", &s3).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_pre_tool_use_hook_input() {
+        let hooks = vec![Hook::new(
+            r#"input=$(cat); grep -q '"hook_event_name":"preToolUse"' <<< "$input" && grep -q '"tool_name":"fs_read"' <<< "$input" && grep -q '"path":"/tmp"' <<< "$input""#.to_string(),
+            Source::Agent,
+        )];
+
+        let input = serde_json::json!({ "path": "/tmp" });
+        assert!(run_pre_tool_use_hooks(&hooks, "fs_read", &input).await.is_ok());
+        assert!(run_pre_tool_use_hooks(&hooks, "fs_write", &input).await.is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_post_tool_use_hook_transforms_result() {
+        let redact = Hook::new(
+            r#"sed -e 's/.*"tool_response":\(".*"\)}$/\1/' -e 's/AKIA[A-Z0-9]*/[REDACTED]/g'"#.to_string(),
+            Source::Agent,
+        );
+        let input = serde_json::json!({ "command": "env" });
+        let result = ToolUseResultBlock::Text("AWS_ACCESS_KEY_ID=AKIAEXAMPLE123".to_string());
+        let result = run_post_tool_use_hook(&redact, "execute_bash", &input, &result)
+            .await
+            .unwrap();
+        assert!(matches!(&result, ToolUseResultBlock::Text(text) if text == "AWS_ACCESS_KEY_ID=[REDACTED]"));
+
+        let annotate = Hook::new(r#"echo '{"annotated":true}'"#.to_string(), Source::Agent);
+        let result = ToolUseResultBlock::Text("output".to_string());
+        let result = run_post_tool_use_hook(&annotate, "execute_bash", &input, &result)
+            .await
+            .unwrap();
+        assert!(matches!(&result, ToolUseResultBlock::Json(json) if *json == serde_json::json!({ "annotated": true })));
+
+        let silent = Hook::new("cat > /dev/null".to_string(), Source::Agent);
+        let result = run_post_tool_use_hook(&silent, "execute_bash", &input, &result)
+            .await
+            .unwrap();
+        assert!(matches!(&result, ToolUseResultBlock::Json(_)));
+
+        for command in ["exit 1", "echo not json"] {
+            let failing = Hook::new(command.to_string(), Source::Agent);
+            assert!(
+                run_post_tool_use_hook(&failing, "execute_bash", &input, &result)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_hook_timeout_covers_unread_input() {
+        let mut hook = Hook::new("sleep 5".to_string(), Source::Agent);
+        hook.timeout_ms = 100;
+        let input = vec![b'a'; 1024 * 1024];
+        let err = tokio::time::timeout(Duration::from_secs(2), run_hook_with_input(&hook, &input))
+            .await
+            .expect("the hook timeout should cover writing its input")
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+}
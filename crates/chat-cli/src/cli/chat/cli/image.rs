@@ -0,0 +1,155 @@
+use std::io::Write;
+use std::path::Path;
+
+use clap::Subcommand;
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::consts::{
+    MAX_IMAGE_SIZE,
+    MAX_NUMBER_OF_IMAGES_PER_REQUEST,
+};
+use crate::cli::chat::util::images::{
+    RichImageBlock,
+    format_size,
+    load_image,
+    pre_process,
+};
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+#[command(
+    before_long_help = "Images added with /image are attached to your next prompt, then cleared.
+
+Notes:
+• Supported formats are png, jpeg, gif and webp, detected from the file contents
+• Each image must be at most 10MB
+• At most 10 images can be attached to a prompt
+• Use /paste to attach an image from the clipboard"
+)]
+pub enum ImageSubcommand {
+    /// Attach image files to your next prompt
+    Add {
+        /// Paths of the image files
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Show the images that will be attached to your next prompt
+    List,
+    /// Remove all images from your next prompt
+    Clear,
+}
+
+impl ImageSubcommand {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        match self {
+            Self::Add { paths } => {
+                queue!(session.stderr, style::Print("\n"))?;
+                for path in paths {
+                    let path = shellexpand::tilde(&pre_process(&path)).into_owned();
+                    if session
+                        .pending_images
+                        .iter()
+                        .any(|(_, metadata)| metadata.filepath == path)
+                    {
+                        continue;
+                    }
+
+                    match load_image(Path::new(&path)) {
+                        Ok(image) => add_pending_image(session, image)?,
+                        Err(err) => {
+                            queue!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Red),
+                                style::Print(format!("Not adding image: {err}\n")),
+                                style::SetForegroundColor(Color::Reset),
+                            )?;
+                        },
+                    }
+                }
+                queue!(session.stderr, style::Print("\n"))?;
+            },
+            Self::List => {
+                if session.pending_images.is_empty() {
+                    queue!(
+                        session.stderr,
+                        style::Print("\nNo images will be attached to your next prompt.\n\n")
+                    )?;
+                } else {
+                    queue!(session.stderr, style::Print("\nImages attached to your next prompt:\n"))?;
+                    for (_, metadata) in &session.pending_images {
+                        queue!(
+                            session.stderr,
+                            style::Print(format!("  {} ({})\n", metadata.filepath, format_size(metadata.size)))
+                        )?;
+                    }
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!(
+                            "\n{} of {MAX_NUMBER_OF_IMAGES_PER_REQUEST} images, each up to {}MB\n\n",
+                            session.pending_images.len(),
+                            MAX_IMAGE_SIZE / (1024 * 1024)
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+            },
+            Self::Clear => {
+                session.pending_images.clear();
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print("\nRemoved all images from your next prompt.\n\n"),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+
+        session.stderr.flush()?;
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Add { .. } => "add",
+            Self::List => "list",
+            Self::Clear => "clear",
+        }
+    }
+}
+
+/// Adds `image` to the images attached to the next prompt, unless the limit has been reached.
+pub fn add_pending_image(session: &mut ChatSession, image: RichImageBlock) -> Result<(), ChatError> {
+    if session.pending_images.len() >= MAX_NUMBER_OF_IMAGES_PER_REQUEST {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Red),
+            style::Print(format!(
+                "Not adding {}: at most {MAX_NUMBER_OF_IMAGES_PER_REQUEST} images can be attached to a prompt\n",
+                image.1.filename
+            )),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        return Ok(());
+    }
+
+    queue!(
+        session.stderr,
+        style::SetForegroundColor(Color::Green),
+        style::Print(format!("Added {} ({})\n", image.1.filename, format_size(image.1.size))),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+    session.pending_images.push(image);
+    Ok(())
+}
@@ -1,7 +1,11 @@
 use std::io::Write;
 
-use clap::Args;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::{
+    execute,
     queue,
     style,
 };
@@ -12,13 +16,31 @@ use crate::cli::chat::{
     ChatSession,
     ChatState,
 };
+use crate::os::Os;
 
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct McpArgs;
+pub struct McpArgs {
+    #[command(subcommand)]
+    subcommand: Option<McpSubcommand>,
+}
 
 impl McpArgs {
-    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if let Some(McpSubcommand::Reload) = self.subcommand {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(style::Color::DarkGrey),
+                style::Print("\nRestarting MCP servers with the current config...\n"),
+                style::SetForegroundColor(style::Color::Reset),
+            )?;
+            session.reload_mcp_config(os, true).await?;
+
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
         let terminal_width = session.terminal_width();
         let still_loading = session
             .conversation
@@ -67,4 +89,44 @@ impl McpArgs {
             skip_printing_tools: true,
         })
     }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        self.subcommand.as_ref().map(|s| s.name())
+    }
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum McpSubcommand {
+    /// Re-read the MCP config and restart all servers, keeping the conversation
+    Reload,
+}
+
+impl McpSubcommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Reload => "reload",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::chat::cli::SlashCommand;
+
+    #[test]
+    fn test_mcp_reload_parse() {
+        let parse = |args: &[&str]| {
+            SlashCommand::try_parse_from(["slash_command", "mcp"].iter().chain(args)).map(|command| match command {
+                SlashCommand::Mcp(args) => args.subcommand,
+                _ => None,
+            })
+        };
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["reload"]).unwrap(), Some(McpSubcommand::Reload));
+        assert!(parse(&["restart"]).is_err());
+    }
 }
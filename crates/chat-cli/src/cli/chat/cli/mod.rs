@@ -1,15 +1,24 @@
 pub mod clear;
 pub mod compact;
 pub mod context;
+pub mod debug;
+pub mod diffstat;
 pub mod editor;
+pub mod env;
+pub mod forget;
+pub mod history;
 pub mod hooks;
+pub mod image;
 pub mod knowledge;
 pub mod mcp;
 pub mod model;
+pub mod paste;
 pub mod persist;
+pub mod pin;
 pub mod profile;
 pub mod prompts;
 pub mod subscribe;
+pub mod summary;
 pub mod tools;
 pub mod usage;
 
@@ -17,17 +26,26 @@ use clap::Parser;
 use clear::ClearArgs;
 use compact::CompactArgs;
 use context::ContextSubcommand;
+use debug::DebugSubcommand;
+use diffstat::DiffstatArgs;
 use editor::EditorArgs;
+use env::EnvArgs;
+use forget::ForgetArgs;
+use history::HistorySubcommand;
 use hooks::HooksArgs;
+use image::ImageSubcommand;
 use knowledge::KnowledgeSubcommand;
 use mcp::McpArgs;
 use model::ModelArgs;
+use paste::PasteArgs;
 use persist::PersistSubcommand;
+use pin::PinArgs;
 use profile::AgentSubcommand;
 use prompts::PromptsArgs;
 use tools::ToolsArgs;
 
 use crate::cli::chat::cli::subscribe::SubscribeArgs;
+use crate::cli::chat::cli::summary::SummaryArgs;
 use crate::cli::chat::cli::usage::UsageArgs;
 use crate::cli::chat::consts::AGENT_MIGRATION_DOC_URL;
 use crate::cli::chat::{
@@ -63,8 +81,25 @@ pub enum SlashCommand {
     /// Open $EDITOR (defaults to vi) to compose a prompt
     #[command(name = "editor")]
     PromptEditor(EditorArgs),
+    /// Manage the prompt history
+    #[command(subcommand)]
+    History(HistorySubcommand),
+    /// Attach images to your next prompt
+    #[command(subcommand)]
+    Image(ImageSubcommand),
+    /// Attach the image on the clipboard to your next prompt
+    Paste(PasteArgs),
     /// Summarize the conversation to free up context space
     Compact(CompactArgs),
+    /// Keep a prompt in the conversation history when it is trimmed or compacted
+    Pin(PinArgs),
+    /// Remove a message and the rest of its turn from the conversation history
+    Forget(ForgetArgs),
+    /// Inspect what is sent to the model
+    #[command(subcommand)]
+    Debug(DebugSubcommand),
+    /// Show the environment context sent with each message
+    Env(EnvArgs),
     /// View tools and permissions
     Tools(ToolsArgs),
     /// Create a new Github issue or make a feature request
@@ -75,6 +110,10 @@ pub enum SlashCommand {
     Hooks(HooksArgs),
     /// Show current session's context window usage
     Usage(UsageArgs),
+    /// Summarize what happened in this session
+    Summary(SummaryArgs),
+    /// Show the lines changed by the agent and by you in each file the agent wrote to
+    Diffstat(DiffstatArgs),
     /// See mcp server loaded
     Mcp(McpArgs),
     /// Select a model for the current conversation session
@@ -119,8 +158,15 @@ impl SlashCommand {
             Self::Context(args) => args.execute(os, session).await,
             Self::Knowledge(subcommand) => subcommand.execute(os, session).await,
             Self::PromptEditor(args) => args.execute(session).await,
+            Self::History(subcommand) => subcommand.execute(session).await,
+            Self::Image(subcommand) => subcommand.execute(session).await,
+            Self::Paste(args) => args.execute(session).await,
             Self::Compact(args) => args.execute(os, session).await,
-            Self::Tools(args) => args.execute(session).await,
+            Self::Pin(args) => args.execute(session).await,
+            Self::Forget(args) => args.execute(session).await,
+            Self::Debug(subcommand) => subcommand.execute(session).await,
+            Self::Env(args) => args.execute(os, session).await,
+            Self::Tools(args) => args.execute(os, session).await,
             Self::Issue(args) => {
                 if let Err(err) = args.execute(os).await {
                     return Err(ChatError::Custom(err.to_string().into()));
@@ -133,7 +179,9 @@ impl SlashCommand {
             Self::Prompts(args) => args.execute(session).await,
             Self::Hooks(args) => args.execute(session).await,
             Self::Usage(args) => args.execute(os, session).await,
-            Self::Mcp(args) => args.execute(session).await,
+            Self::Summary(args) => args.execute(session).await,
+            Self::Diffstat(args) => args.execute(session).await,
+            Self::Mcp(args) => args.execute(os, session).await,
             Self::Model(args) => args.execute(os, session).await,
             Self::Subscribe(args) => args.execute(os, session).await,
             Self::Persist(subcommand) => subcommand.execute(os, session).await,
@@ -158,18 +206,28 @@ impl SlashCommand {
             Self::Context(_) => "context",
             Self::Knowledge(_) => "knowledge",
             Self::PromptEditor(_) => "editor",
+            Self::History(_) => "history",
+            Self::Image(_) => "image",
+            Self::Paste(_) => "paste",
             Self::Compact(_) => "compact",
+            Self::Pin(_) => "pin",
+            Self::Forget(_) => "forget",
+            Self::Debug(_) => "debug",
+            Self::Env(_) => "env",
             Self::Tools(_) => "tools",
             Self::Issue(_) => "issue",
             Self::Prompts(_) => "prompts",
             Self::Hooks(_) => "hooks",
             Self::Usage(_) => "usage",
+            Self::Summary(_) => "summary",
+            Self::Diffstat(_) => "diffstat",
             Self::Mcp(_) => "mcp",
             Self::Model(_) => "model",
             Self::Subscribe(_) => "subscribe",
             Self::Persist(sub) => match sub {
                 PersistSubcommand::Save { .. } => "save",
                 PersistSubcommand::Load { .. } => "load",
+                PersistSubcommand::Export { .. } => "export",
             },
         }
     }
@@ -179,8 +237,14 @@ impl SlashCommand {
             SlashCommand::Agent(sub) => Some(sub.name()),
             SlashCommand::Context(sub) => Some(sub.name()),
             SlashCommand::Knowledge(sub) => Some(sub.name()),
+            SlashCommand::History(sub) => Some(sub.name()),
+            SlashCommand::Image(sub) => Some(sub.name()),
+            SlashCommand::Debug(sub) => Some(sub.name()),
             SlashCommand::Tools(arg) => arg.subcommand_name(),
             SlashCommand::Prompts(arg) => arg.subcommand_name(),
+            SlashCommand::Model(arg) => arg.subcommand_name(),
+            SlashCommand::Mcp(arg) => arg.subcommand_name(),
+            SlashCommand::Subscribe(arg) => arg.subcommand_name(),
             _ => None,
         }
     }
@@ -1,77 +1,413 @@
-use clap::Args;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{
+    Arc,
+    LazyLock,
+};
+use std::time::Duration;
+
+use amzn_codewhisperer_client::types::Model;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::style::{
     self,
+    Attribute,
     Color,
 };
 use crossterm::{
+    cursor,
     execute,
     queue,
+    terminal,
 };
 use dialoguer::Select;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use spinners::{
+    Spinner,
+    Spinners,
+};
+use tokio::sync::Mutex;
+use tracing::warn;
+use winnow::Partial;
+use winnow::stream::Offset;
 
 use crate::auth::builder_id::{
     BuilderIdToken,
     TokenType,
 };
+use crate::cli::chat::message::AssistantMessage;
+use crate::cli::chat::parse::{
+    ParseState,
+    interpret_markdown,
+};
+use crate::cli::chat::parser::{
+    RequestMetadata,
+    ResponseEvent,
+};
+use crate::cli::chat::util::response_stats;
+use crate::cli::chat::util::ui::should_animate;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::directories::chat_model_cache_path;
 
+/// The context window in tokens of each model returned by [get_model_options], keyed by model
+/// id, so that [context_window_tokens] also knows the models fetched from the backend.
+static FETCHED_CONTEXT_WINDOWS: LazyLock<std::sync::Mutex<HashMap<String, usize>>> = LazyLock::new(Default::default);
+
+/// How long the list of available models is cached for, see [get_model_options].
+const MODEL_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ModelOption {
     /// Display name
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
     /// Actual model id to send in the API
-    pub model_id: &'static str,
+    pub model_id: Cow<'static, str>,
     /// Size of the model's context window, in tokens
     pub context_window_tokens: usize,
 }
 
 const MODEL_OPTIONS: [ModelOption; 2] = [
     ModelOption {
-        name: "claude-4-sonnet",
-        model_id: "CLAUDE_SONNET_4_20250514_V1_0",
+        name: Cow::Borrowed("claude-4-sonnet"),
+        model_id: Cow::Borrowed("CLAUDE_SONNET_4_20250514_V1_0"),
         context_window_tokens: 200_000,
     },
     ModelOption {
-        name: "claude-3.7-sonnet",
-        model_id: "CLAUDE_3_7_SONNET_20250219_V1_0",
+        name: Cow::Borrowed("claude-3.7-sonnet"),
+        model_id: Cow::Borrowed("CLAUDE_3_7_SONNET_20250219_V1_0"),
         context_window_tokens: 200_000,
     },
 ];
 
 const GPT_OSS_120B: ModelOption = ModelOption {
-    name: "openai-gpt-oss-120b-preview",
-    model_id: "OPENAI_GPT_OSS_120B_1_0",
+    name: Cow::Borrowed("openai-gpt-oss-120b-preview"),
+    model_id: Cow::Borrowed("OPENAI_GPT_OSS_120B_1_0"),
     context_window_tokens: 128_000,
 };
 
+impl ModelOption {
+    fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.model_id == name
+    }
+}
+
+impl From<&Model> for ModelOption {
+    fn from(model: &Model) -> Self {
+        let model_id = model.model_id();
+        let context_window_tokens = model
+            .token_limits()
+            .and_then(|limits| limits.max_input_tokens())
+            .and_then(|tokens| usize::try_from(tokens).ok())
+            .unwrap_or_else(|| context_window_tokens(Some(model_id)));
+
+        Self {
+            name: Cow::Owned(model.model_name().unwrap_or(model_id).to_string()),
+            model_id: Cow::Owned(model_id.to_string()),
+            context_window_tokens,
+        }
+    }
+}
+
+/// The list of available models stored at [chat_model_cache_path].
+#[derive(Debug, Serialize, Deserialize)]
+struct ModelCache {
+    /// Unix timestamp in seconds of when the models were fetched.
+    fetched_at: i64,
+    /// The profile the models were fetched for, since each profile can have different models.
+    profile_arn: Option<String>,
+    models: Vec<ModelOption>,
+}
+
+impl ModelCache {
+    fn is_valid(&self, profile_arn: Option<&str>, now: i64) -> bool {
+        self.profile_arn.as_deref() == profile_arn
+            && (0..MODEL_CACHE_TTL.as_secs() as i64).contains(&(now - self.fetched_at))
+            && !self.models.is_empty()
+    }
+}
+
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct ModelArgs;
+pub struct ModelArgs {
+    /// Fetch the list of available models again instead of using the cached list
+    #[arg(long)]
+    refresh: bool,
+    #[command(subcommand)]
+    subcommand: Option<ModelSubcommand>,
+}
 
 impl ModelArgs {
-    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
-        Ok(select_model(os, session).await?.unwrap_or(ChatState::PromptUser {
-            skip_printing_tools: false,
-        }))
+    pub async fn execute(self, os: &mut Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if self.refresh {
+            get_model_options(os, true).await?;
+        }
+
+        match self.subcommand {
+            Some(ModelSubcommand::Compare {
+                first,
+                second,
+                keep,
+                prompt,
+            }) => compare_models(os, session, [first, second], keep, prompt.join(" ")).await,
+            None => Ok(select_model(os, session).await?.unwrap_or(ChatState::PromptUser {
+                skip_printing_tools: false,
+            })),
+        }
+    }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        self.subcommand.as_ref().map(|s| s.name())
+    }
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum ModelSubcommand {
+    /// Send a prompt to two models and show both responses. Neither response is added to the
+    /// conversation unless --keep is given
+    Compare {
+        /// The first model to compare
+        first: String,
+        /// The second model to compare
+        second: String,
+        /// Add the response of this model to the conversation history. Must come before the
+        /// models
+        #[arg(long, value_name = "MODEL")]
+        keep: Option<String>,
+        /// The prompt to send to both models
+        #[arg(required = true, trailing_var_arg = true)]
+        prompt: Vec<String>,
+    },
+}
+
+impl ModelSubcommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Compare { .. } => "compare",
+        }
     }
 }
 
+/// Sends `prompt` to each of the `models` on top of the current conversation history, printing
+/// each response with its token count and latency.
+async fn compare_models(
+    os: &mut Os,
+    session: &mut ChatSession,
+    models: [String; 2],
+    keep: Option<String>,
+    prompt: String,
+) -> Result<ChatState, ChatError> {
+    let model_options = get_model_options(os, false).await?;
+    let mut selected = Vec::new();
+    for name in &models {
+        match model_options.iter().find(|opt| opt.matches(name)) {
+            Some(opt) => selected.push(opt),
+            None => {
+                let available = model_options
+                    .iter()
+                    .map(|opt| opt.name.as_ref())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("\nUnknown model: {name}. Available models: {available}\n\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                return Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                });
+            },
+        }
+    }
+
+    let keep_index = match keep {
+        Some(keep) => match selected.iter().position(|opt| opt.matches(&keep)) {
+            Some(i) => Some(i),
+            None => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("\n--keep must name one of the compared models, not {keep}\n\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                return Ok(ChatState::PromptUser {
+                    skip_printing_tools: true,
+                });
+            },
+        },
+        None => None,
+    };
+
+    let mut kept: Option<(AssistantMessage, RequestMetadata)> = None;
+    for (i, model) in selected.iter().enumerate() {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Cyan),
+            style::SetAttribute(Attribute::Bold),
+            style::Print(format!("\n━━ {} ━━\n", model.name)),
+            style::SetAttribute(Attribute::Reset),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        let state = session
+            .conversation
+            .create_comparison_request(os, &prompt, &model.model_id, &mut session.stderr)
+            .await?;
+
+        if session.interactive {
+            execute!(session.stderr, cursor::Hide)?;
+            session.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+        }
+
+        let result = match session.send_message(os, state, Arc::new(Mutex::new(None)), None).await {
+            Ok(mut response) => loop {
+                match response.recv().await {
+                    Some(Ok(ResponseEvent::EndStream {
+                        message,
+                        request_metadata,
+                    })) => break Ok((message, request_metadata)),
+                    Some(Ok(_)) => (),
+                    Some(Err(err)) => break Err(err.to_string()),
+                    None => break Err("The response stream ended unexpectedly".to_string()),
+                }
+            },
+            Err(err) => Err(err.to_string()),
+        };
+
+        if session.spinner.take().is_some() {
+            queue!(
+                session.stderr,
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                cursor::MoveToColumn(0),
+            )?;
+        }
+        execute!(session.stderr, cursor::Show)?;
+
+        let (message, request_metadata) = match result {
+            Ok(response) => response,
+            Err(err) => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("{err}\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                continue;
+            },
+        };
+
+        print_markdown(os, session, message.content())?;
+        for tool_use in message.tool_uses().unwrap_or_default() {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!("(requested the {} tool, which was not run)\n", tool_use.name)),
+            )?;
+        }
+
+        execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("\n{}\n", response_stats(message.content(), &request_metadata))),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        if keep_index == Some(i) {
+            kept = Some((message, request_metadata));
+        }
+    }
+
+    match (keep_index, kept) {
+        (Some(i), Some((message, request_metadata))) => {
+            // Tool uses can't be kept since they were never run.
+            let message =
+                AssistantMessage::new_response(message.message_id().map(String::from), message.content().to_string());
+            session.conversation.set_next_user_message(prompt).await;
+            session
+                .conversation
+                .push_assistant_message(os, message, Some(request_metadata));
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Green),
+                style::Print(format!(
+                    "\nAdded the response from {} to the conversation history.\n\n",
+                    selected[i].name
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        },
+        (Some(i), None) => {
+            execute!(
+                session.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "\nNo response from {} to add to the conversation history.\n\n",
+                    selected[i].name
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+        },
+        (None, _) => execute!(session.stderr, style::Print("\n"))?,
+    }
+
+    Ok(ChatState::PromptUser {
+        skip_printing_tools: true,
+    })
+}
+
+fn print_markdown(os: &Os, session: &mut ChatSession, text: &str) -> Result<(), ChatError> {
+    let mut state = ParseState::new(
+        Some(session.terminal_width()),
+        os.database.settings.get_bool(Setting::ChatDisableMarkdownRendering),
+    );
+    // The trailing newline lets the parser complete the final line.
+    let buf = format!("{text}\n");
+    let mut offset = 0;
+    while offset < buf.len() {
+        let input = Partial::new(&buf[offset..]);
+        match interpret_markdown(input, &mut session.stdout, &mut state) {
+            Ok(parsed) => {
+                offset += parsed.offset_from(&input);
+                state.newline = state.set_newline;
+                state.set_newline = false;
+            },
+            Err(err) => match err.into_inner() {
+                Some(err) => return Err(ChatError::Custom(err.to_string().into())),
+                None => break,
+            },
+        }
+    }
+    queue!(session.stdout, style::ResetColor, style::SetAttribute(Attribute::Reset))?;
+    session.stdout.flush()?;
+    Ok(())
+}
+
 pub async fn select_model(os: &Os, session: &mut ChatSession) -> Result<Option<ChatState>, ChatError> {
     queue!(session.stderr, style::Print("\n"))?;
     let active_model_id = session.conversation.model.as_deref();
-    let model_options = get_model_options(os).await?;
+    let model_options = get_model_options(os, false).await?;
 
     let labels: Vec<String> = model_options
         .iter()
         .map(|opt| {
-            if (opt.model_id.is_empty() && active_model_id.is_none()) || Some(opt.model_id) == active_model_id {
+            if (opt.model_id.is_empty() && active_model_id.is_none()) || Some(opt.model_id.as_ref()) == active_model_id
+            {
                 format!("{} (active)", opt.name)
             } else {
-                opt.name.to_owned()
+                opt.name.to_string()
             }
         })
         .collect();
@@ -142,23 +478,76 @@ pub async fn default_model_id(os: &Os) -> &'static str {
 }
 
 /// Returns the available models for use.
-#[allow(unused_variables)]
-pub async fn get_model_options(os: &Os) -> Result<Vec<ModelOption>, ChatError> {
-    Ok(MODEL_OPTIONS.into_iter().collect::<Vec<_>>())
-    // TODO: Once we have access to gpt-oss, add back.
-    // let mut model_options = MODEL_OPTIONS.into_iter().collect::<Vec<_>>();
-    //
-    // // GPT OSS is only accessible in IAD.
-    // let endpoint = Endpoint::configured_value(&os.database);
-    // if endpoint.region().as_ref() != "us-east-1" {
-    //     return Ok(model_options);
-    // }
-    //
-    // model_options.push(GPT_OSS_120B);
-    // Ok(model_options)
+///
+/// The list is fetched from the backend and cached at [chat_model_cache_path] for
+/// [MODEL_CACHE_TTL], unless `refresh` is set. A cache that can't be read or was written for
+/// another profile is fetched again. Falls back to the built-in models if fetching fails.
+pub async fn get_model_options(os: &Os, refresh: bool) -> Result<Vec<ModelOption>, ChatError> {
+    let profile_arn = os.client.profile_arn();
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let cache_path = match chat_model_cache_path() {
+        Ok(path) => Some(path),
+        Err(err) => {
+            warn!(?err, "failed to get the model cache path");
+            None
+        },
+    };
+
+    if let (Some(path), false) = (&cache_path, refresh) {
+        if let Ok(content) = os.fs.read_to_string(path).await {
+            match serde_json::from_str::<ModelCache>(&content) {
+                Ok(cache) if cache.is_valid(profile_arn, now) => {
+                    remember_context_windows(&cache.models);
+                    return Ok(cache.models);
+                },
+                Ok(_) => (),
+                Err(err) => warn!(?err, "ignoring the corrupt model cache"),
+            }
+        }
+    }
+
+    let models = match os.client.list_available_models().await {
+        Ok(models) if !models.is_empty() => models.iter().map(ModelOption::from).collect::<Vec<_>>(),
+        Ok(_) => return Ok(MODEL_OPTIONS.into_iter().collect()),
+        Err(err) => {
+            warn!(?err, "failed to fetch the available models");
+            return Ok(MODEL_OPTIONS.into_iter().collect());
+        },
+    };
+
+    let cache = ModelCache {
+        fetched_at: now,
+        profile_arn: profile_arn.map(String::from),
+        models,
+    };
+    if let Some(path) = &cache_path {
+        if let Err(err) = write_model_cache(os, path, &cache).await {
+            warn!(?err, "failed to write the model cache");
+        }
+    }
+
+    remember_context_windows(&cache.models);
+    Ok(cache.models)
+}
+
+fn remember_context_windows(models: &[ModelOption]) {
+    if let Ok(mut context_windows) = FETCHED_CONTEXT_WINDOWS.lock() {
+        for model in models {
+            context_windows.insert(model.model_id.to_string(), model.context_window_tokens);
+        }
+    }
+}
+
+async fn write_model_cache(os: &Os, path: &std::path::Path, cache: &ModelCache) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        os.fs.create_dir_all(parent).await?;
+    }
+    os.fs.write(path, serde_json::to_vec(cache)?).await
 }
 
 /// Returns the context window length in tokens for the given model_id.
+///
+/// Models fetched by [get_model_options] take precedence over the built-in ones.
 pub fn context_window_tokens(model_id: Option<&str>) -> usize {
     const DEFAULT_CONTEXT_WINDOW_LENGTH: usize = 200_000;
 
@@ -166,9 +555,71 @@ pub fn context_window_tokens(model_id: Option<&str>) -> usize {
         return DEFAULT_CONTEXT_WINDOW_LENGTH;
     };
 
-    MODEL_OPTIONS
-        .iter()
-        .chain(std::iter::once(&GPT_OSS_120B))
-        .find(|m| m.model_id == model_id)
-        .map_or(DEFAULT_CONTEXT_WINDOW_LENGTH, |m| m.context_window_tokens)
+    let fetched = FETCHED_CONTEXT_WINDOWS
+        .lock()
+        .ok()
+        .and_then(|context_windows| context_windows.get(model_id).copied());
+    fetched.unwrap_or_else(|| {
+        MODEL_OPTIONS
+            .iter()
+            .chain(std::iter::once(&GPT_OSS_120B))
+            .find(|m| m.model_id == model_id)
+            .map_or(DEFAULT_CONTEXT_WINDOW_LENGTH, |m| m.context_window_tokens)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_cache_is_valid() {
+        let cache = ModelCache {
+            fetched_at: 1_000,
+            profile_arn: Some("arn".to_string()),
+            models: MODEL_OPTIONS.into_iter().collect(),
+        };
+        assert!(cache.is_valid(Some("arn"), 1_000));
+        assert!(cache.is_valid(Some("arn"), 1_000 + 3599));
+        assert!(!cache.is_valid(Some("arn"), 1_000 + 3600));
+        assert!(!cache.is_valid(Some("arn"), 999));
+        assert!(!cache.is_valid(Some("other-arn"), 1_000));
+        assert!(!cache.is_valid(None, 1_000));
+
+        let empty = ModelCache {
+            fetched_at: 1_000,
+            profile_arn: None,
+            models: vec![],
+        };
+        assert!(!empty.is_valid(None, 1_000));
+    }
+
+    #[tokio::test]
+    async fn test_get_model_options_cache() {
+        let os = Os::new().await.unwrap();
+        let path = chat_model_cache_path().unwrap();
+
+        let models = get_model_options(&os, false).await.unwrap();
+        assert_eq!(models[0].name, "claude-4-sonnet");
+        let cache = serde_json::from_str::<ModelCache>(&os.fs.read_to_string(&path).await.unwrap()).unwrap();
+        assert_eq!(cache.models.len(), models.len());
+
+        // A valid cache is used instead of fetching.
+        let cached = ModelCache {
+            models: vec![ModelOption {
+                name: Cow::Borrowed("cached-model"),
+                model_id: Cow::Borrowed("CACHED_MODEL"),
+                context_window_tokens: 100_000,
+            }],
+            ..cache
+        };
+        os.fs.write(&path, serde_json::to_vec(&cached).unwrap()).await.unwrap();
+        assert_eq!(get_model_options(&os, false).await.unwrap()[0].name, "cached-model");
+        assert_eq!(context_window_tokens(Some("CACHED_MODEL")), 100_000);
+
+        // Refreshing or a corrupt cache fetches the models again.
+        assert_eq!(get_model_options(&os, true).await.unwrap()[0].name, "claude-4-sonnet");
+        os.fs.write(&path, "{not json").await.unwrap();
+        assert_eq!(get_model_options(&os, false).await.unwrap()[0].name, "claude-4-sonnet");
+    }
 }
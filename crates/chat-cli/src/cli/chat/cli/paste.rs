@@ -0,0 +1,51 @@
+use std::io::Write;
+
+use clap::Args;
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use super::image::add_pending_image;
+use crate::cli::chat::util::images::read_clipboard_image;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "Attaches the image on the clipboard, such as a screenshot, to your next prompt.
+
+Notes:
+• On Linux, this requires wl-paste (Wayland) or xclip (X11)
+• The image must be at most 10MB
+• Use /image list to see the images attached to your next prompt"
+)]
+pub struct PasteArgs;
+
+impl PasteArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        queue!(session.stderr, style::Print("\n"))?;
+        match read_clipboard_image().await {
+            Ok(image) => add_pending_image(session, image)?,
+            Err(err) => {
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("{err}\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+        queue!(session.stderr, style::Print("\n"))?;
+        session.stderr.flush()?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
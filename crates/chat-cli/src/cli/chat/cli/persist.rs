@@ -1,4 +1,7 @@
-use clap::Subcommand;
+use clap::{
+    Subcommand,
+    ValueEnum,
+};
 use crossterm::execute;
 use crossterm::style::{
     self,
@@ -11,6 +14,7 @@ use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
+    export,
 };
 use crate::os::Os;
 
@@ -25,6 +29,23 @@ pub enum PersistSubcommand {
     },
     /// Load a previous conversation
     Load { path: String },
+    /// Export the conversation to a self-contained HTML or markdown file that can be shared
+    Export {
+        path: String,
+        /// The format to export to. Defaults to markdown for paths ending in .md and to HTML
+        /// otherwise
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Html,
+    #[value(alias = "markdown")]
+    Md,
 }
 
 impl PersistSubcommand {
@@ -75,6 +96,45 @@ impl PersistSubcommand {
                     style::SetAttribute(Attribute::Reset)
                 )?;
             },
+            Self::Export { path, format, force } => {
+                if os.fs.exists(&path) && !force {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nFile at {} already exists. To overwrite, use -f or --force\n\n",
+                            &path
+                        )),
+                        style::SetAttribute(Attribute::Reset)
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                }
+                let format = format.unwrap_or(if path.ends_with(".md") {
+                    ExportFormat::Md
+                } else {
+                    ExportFormat::Html
+                });
+                let conversation_id = session.conversation.conversation_id();
+                let history = session
+                    .conversation
+                    .history()
+                    .iter()
+                    .map(|entry| (entry.user(), entry.assistant()));
+                let contents = match format {
+                    ExportFormat::Html => export::to_html(conversation_id, history),
+                    ExportFormat::Md => export::to_markdown(conversation_id, history),
+                };
+                tri!(os.fs.write(&path, contents).await, "export to", &path);
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Exported conversation to {}\n\n", &path)),
+                    style::SetAttribute(Attribute::Reset)
+                )?;
+            },
             Self::Load { path } => {
                 // Try the original path first
                 let original_result = os.fs.read_to_string(&path).await;
@@ -0,0 +1,136 @@
+use std::io::Write;
+
+use clap::Args;
+use crossterm::style::{
+    self,
+    Color,
+};
+use crossterm::{
+    execute,
+    queue,
+};
+
+use crate::cli::chat::util::truncate_safe;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+/// Maximum number of bytes of each prompt shown by `/pin --list`.
+const PROMPT_PREVIEW_LEN: usize = 80;
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "/pin keeps a prompt in the conversation history when older messages are dropped
+to fit the context window or summarized by /compact.
+
+Pinned prompts are kept together with the tool uses and responses that followed them.
+Use /pin --list to see the prompts in the history and their numbers."
+)]
+pub struct PinArgs {
+    /// The number of the prompt to pin, as shown by --list. Defaults to the most recent prompt
+    #[arg(conflicts_with = "list")]
+    number: Option<usize>,
+    /// Unpin the prompt instead
+    #[arg(long, conflicts_with = "list")]
+    remove: bool,
+    /// List the prompts in the conversation history, marking the pinned ones
+    #[arg(long)]
+    list: bool,
+}
+
+impl PinArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        // Prompts are numbered from 1, skipping entries that only contain tool results.
+        let prompts = session
+            .conversation
+            .history()
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_prompt())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        if self.list {
+            if prompts.is_empty() {
+                queue!(
+                    session.stderr,
+                    style::Print("\nThere are no prompts in the conversation history.\n\n")
+                )?;
+            }
+            for (number, &i) in prompts.iter().enumerate() {
+                let entry = &session.conversation.history()[i];
+                let prompt = entry.user().prompt().unwrap_or_default().replace('\n', " ");
+                let preview = truncate_safe(&prompt, PROMPT_PREVIEW_LEN);
+                let ellipsis = if preview.len() < prompt.len() { "…" } else { "" };
+                if entry.is_pinned() {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format!("{:>4}. 📌 {preview}{ellipsis}\n", number + 1)),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                } else {
+                    queue!(
+                        session.stderr,
+                        style::Print(format!("{:>4}.    {preview}{ellipsis}\n", number + 1))
+                    )?;
+                }
+            }
+            session.stderr.flush()?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
+        let index = match self.number {
+            Some(number) => match number.checked_sub(1).and_then(|n| prompts.get(n)) {
+                Some(&i) => Some(i),
+                None => {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nThere is no prompt number {number}. Run /pin --list to see the prompts.\n\n"
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                },
+            },
+            None => None,
+        };
+
+        match session.conversation.set_pinned(index, !self.remove) {
+            Some(entry) => {
+                let prompt = entry.user().prompt().unwrap_or_default();
+                let preview = truncate_safe(prompt, PROMPT_PREVIEW_LEN);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\n{} prompt: {preview}\n\n",
+                        if self.remove { "Unpinned" } else { "Pinned" }
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            None => {
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print("\nThere are no prompts in the conversation history to pin.\n\n"),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
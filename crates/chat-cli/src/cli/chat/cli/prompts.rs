@@ -2,6 +2,8 @@ use std::collections::{
     HashMap,
     VecDeque,
 };
+use std::ops::Range;
+use std::sync::LazyLock;
 
 use clap::{
     Args,
@@ -16,11 +18,15 @@ use crossterm::{
     execute,
     queue,
 };
+use regex::Regex;
 use thiserror::Error;
 use unicode_width::UnicodeWidthStr;
 
 use crate::cli::chat::error_formatter::format_mcp_error;
-use crate::cli::chat::tool_manager::PromptBundle;
+use crate::cli::chat::tool_manager::{
+    PromptBundle,
+    ToolManager,
+};
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -46,6 +52,127 @@ pub enum GetPromptError {
     General(#[from] eyre::Report),
 }
 
+/// Arguments to a prompt, either in the order of its parameters or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptArguments {
+    Positional(Vec<String>),
+    Named(HashMap<String, String>),
+}
+
+/// Matches inline prompt references, e.g. `#review` or `#review(file=main.rs)`.
+static PROMPT_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)(#([A-Za-z][\w\-/]*)(?:\(([^)]*)\))?)").unwrap());
+
+/// A prompt referenced inline in the user's input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PromptReference<'a> {
+    /// Byte range of the whole reference in the input, including the `#`.
+    range: Range<usize>,
+    name: &'a str,
+    arguments: Option<PromptArguments>,
+}
+
+/// Finds the `#name` and `#name(arg, ...)` prompt references in `input`. Arguments are passed by
+/// name if they all have the form `key=value`, and positionally otherwise.
+fn prompt_references(input: &str) -> Vec<PromptReference<'_>> {
+    PROMPT_REFERENCE
+        .captures_iter(input)
+        .filter_map(|captures| {
+            let reference = captures.get(1)?;
+            let name = captures.get(2)?.as_str();
+            let arguments = captures.get(3).map(|args| {
+                let args = args
+                    .as_str()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|arg| !arg.is_empty())
+                    .collect::<Vec<_>>();
+                match args
+                    .iter()
+                    .map(|arg| {
+                        arg.split_once('=')
+                            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    })
+                    .collect::<Option<HashMap<_, _>>>()
+                {
+                    Some(named) => PromptArguments::Named(named),
+                    None => PromptArguments::Positional(args.into_iter().map(str::to_string).collect()),
+                }
+            });
+            Some(PromptReference {
+                range: reference.range(),
+                name,
+                arguments,
+            })
+        })
+        .collect()
+}
+
+/// Whether `name`, either `prompt` or `server/prompt`, is one of the prompts offered by the mcp
+/// servers.
+fn is_known_prompt(prompts: &HashMap<String, Vec<PromptBundle>>, name: &str) -> bool {
+    match name.split_once('/') {
+        Some((server_name, prompt_name)) => prompts
+            .get(prompt_name)
+            .is_some_and(|bundles| bundles.iter().any(|bundle| bundle.server_name == server_name)),
+        None => prompts.contains_key(name),
+    }
+}
+
+/// Replaces the `#name` prompt references in `input` with the text of the prompts. Only names in
+/// the list of prompts offered by the mcp servers are expanded, so that other uses of `#` such as
+/// hashtags are left alone without asking every server for them. References to known prompts that
+/// can't be expanded are left as they are.
+///
+/// Returns the expanded input, along with a warning for each known prompt that was left as is.
+pub async fn expand_prompt_references(tool_manager: &ToolManager, input: &str) -> (String, Vec<String>) {
+    let references = match tool_manager.prompts.read() {
+        Ok(prompts) => prompt_references(input)
+            .into_iter()
+            .filter(|reference| is_known_prompt(&prompts, reference.name))
+            .collect::<Vec<_>>(),
+        Err(_) => return (input.to_string(), Vec::new()),
+    };
+
+    let mut expanded = String::with_capacity(input.len());
+    let mut warnings = Vec::new();
+    let mut last_end = 0;
+    for reference in references {
+        let text = match tool_manager
+            .get_prompt(reference.name.to_string(), reference.arguments.clone())
+            .await
+        {
+            Ok(resp) => match (resp.error, resp.result) {
+                (Some(err), _) => Err(format_mcp_error(&serde_json::json!(err))),
+                (None, Some(result)) => serde_json::from_value::<PromptGetResult>(result)
+                    .map(|result| {
+                        result
+                            .messages
+                            .iter()
+                            .map(|message| message.content.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n\n")
+                    })
+                    .map_err(|e| format!("failed to deserialize the prompt: {e}")),
+                (None, None) => Err("the prompt has no content".to_string()),
+            },
+            Err(GetPromptError::PromptNotFound(_)) => Err("no such prompt".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match text {
+            Ok(text) => {
+                expanded.push_str(&input[last_end..reference.range.start]);
+                expanded.push_str(&text);
+                last_end = reference.range.end;
+            },
+            Err(reason) => warnings.push(format!("Could not expand #{}: {reason}", reference.name)),
+        }
+    }
+    expanded.push_str(&input[last_end..]);
+    (expanded, warnings)
+}
+
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
 #[command(color = clap::ColorChoice::Always,
@@ -55,7 +182,10 @@ These templates are provided by the mcp servers you have installed and configure
 To actually retrieve a prompt, directly start with the following command (without prepending /prompt get):
   <em>@<<prompt name>> [arg]</em>                             <black!>Retrieve prompt specified</black!>
 Or if you prefer the long way:
-  <em>/prompts get <<prompt name>> [arg]</em>                 <black!>Retrieve prompt specified</black!>"
+  <em>/prompts get <<prompt name>> [arg]</em>                 <black!>Retrieve prompt specified</black!>
+  <em>/prompts run <<prompt name>> [key=value]</em>           <black!>Retrieve prompt with named arguments</black!>
+To use a prompt's text within a message, reference it inline:
+  <em>#<<prompt name>>(arg=value, ...)</em>                   <black!>Expand prompt in place</black!>"
 })]
 pub struct PromptsArgs {
     #[command(subcommand)]
@@ -70,7 +200,10 @@ impl PromptsArgs {
         };
 
         if let Some(subcommand) = self.subcommand {
-            if matches!(subcommand, PromptsSubcommand::Get { .. }) {
+            if matches!(
+                subcommand,
+                PromptsSubcommand::Get { .. } | PromptsSubcommand::Run { .. }
+            ) {
                 return subcommand.execute(session).await;
             }
         }
@@ -217,17 +350,94 @@ pub enum PromptsSubcommand {
         name: String,
         arguments: Option<Vec<String>>,
     },
+    /// Run a prompt with its arguments given by name, and send the result as your message
+    Run {
+        /// Name of the prompt, optionally prefixed by the server name, e.g. server/prompt
+        name: String,
+        /// Arguments of the prompt, e.g. file=main.rs
+        #[arg(value_name = "KEY=VALUE", value_parser = parse_prompt_argument)]
+        arguments: Vec<(String, String)>,
+    },
+}
+
+fn parse_prompt_argument(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{arg}'"))
+}
+
+/// Returns the names of the required arguments of prompt `name` that are missing from
+/// `arguments`. Returns an empty list if the prompt can't be resolved to a single server, in which
+/// case getting the prompt reports the error.
+fn missing_required_arguments(
+    tool_manager: &ToolManager,
+    name: &str,
+    arguments: &HashMap<String, String>,
+) -> Result<Vec<String>, ChatError> {
+    let mut prompts_wl = tool_manager
+        .prompts
+        .write()
+        .map_err(|e| ChatError::Custom(format!("Poison error encountered while retrieving prompts: {}", e).into()))?;
+    let (server_name, prompt_name) = match name.split_once('/') {
+        Some((server_name, prompt_name)) => (Some(server_name), prompt_name),
+        None => (None, name),
+    };
+    if !prompts_wl.contains_key(prompt_name) {
+        tool_manager.refresh_prompts(&mut prompts_wl)?;
+    }
+
+    let bundle = match (prompts_wl.get(prompt_name), server_name) {
+        (Some(bundles), Some(server_name)) => bundles.iter().find(|b| b.server_name == server_name),
+        (Some(bundles), None) if bundles.len() == 1 => bundles.first(),
+        _ => None,
+    };
+    Ok(bundle
+        .and_then(|bundle| bundle.prompt_get.arguments.as_ref())
+        .into_iter()
+        .flatten()
+        .filter(|arg| arg.required == Some(true) && !arguments.contains_key(&arg.name))
+        .map(|arg| arg.name.clone())
+        .collect())
 }
 
 impl PromptsSubcommand {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
-        let PromptsSubcommand::Get {
-            orig_input,
-            name,
-            arguments,
-        } = self
-        else {
-            unreachable!("List has already been parsed out at this point");
+        let (orig_input, name, arguments) = match self {
+            PromptsSubcommand::Get {
+                orig_input,
+                name,
+                arguments,
+            } => (orig_input, name, arguments.map(PromptArguments::Positional)),
+            PromptsSubcommand::Run { name, arguments } => {
+                let arguments = arguments.into_iter().collect::<HashMap<_, _>>();
+                let missing = missing_required_arguments(&session.conversation.tool_manager, &name, &arguments)?;
+                if !missing.is_empty() {
+                    execute!(
+                        session.stderr,
+                        style::Print("\n"),
+                        style::SetForegroundColor(Color::Red),
+                        style::Print("Prompt "),
+                        style::SetForegroundColor(Color::Cyan),
+                        style::Print(&name),
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            " is missing required arguments: {}\n",
+                            missing
+                                .iter()
+                                .map(|arg| format!("{arg}=..."))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print("\n"),
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                }
+                (None, name, Some(PromptArguments::Named(arguments)))
+            },
+            PromptsSubcommand::List { .. } => unreachable!("List has already been parsed out at this point"),
         };
 
         let prompts = match session.conversation.tool_manager.get_prompt(name, arguments).await {
@@ -314,6 +524,131 @@ impl PromptsSubcommand {
         match self {
             PromptsSubcommand::List { .. } => "list",
             PromptsSubcommand::Get { .. } => "get",
+            PromptsSubcommand::Run { .. } => "run",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::chat::cli::SlashCommand;
+
+    #[test]
+    fn test_prompt_references() {
+        let input = "#review(file=main.rs, depth = 2) then #summarize(a, b) and #plain. Not issue#12, # heading or #1";
+        let refs = prompt_references(input);
+        assert_eq!(refs.len(), 3);
+
+        assert_eq!(refs[0].name, "review");
+        assert_eq!(&input[refs[0].range.clone()], "#review(file=main.rs, depth = 2)");
+        assert_eq!(
+            refs[0].arguments,
+            Some(PromptArguments::Named(HashMap::from([
+                ("file".to_string(), "main.rs".to_string()),
+                ("depth".to_string(), "2".to_string()),
+            ])))
+        );
+
+        assert_eq!(refs[1].name, "summarize");
+        assert_eq!(
+            refs[1].arguments,
+            Some(PromptArguments::Positional(vec!["a".to_string(), "b".to_string()]))
+        );
+
+        assert_eq!(refs[2].name, "plain");
+        assert_eq!(&input[refs[2].range.clone()], "#plain");
+        assert_eq!(refs[2].arguments, None);
+    }
+
+    #[test]
+    fn test_prompts_run_parse() {
+        let parse = |args: &[&str]| {
+            SlashCommand::try_parse_from(["slash_command", "prompts", "run"].iter().chain(args)).map(|command| {
+                match command {
+                    SlashCommand::Prompts(args) => args.subcommand,
+                    _ => None,
+                }
+            })
+        };
+        assert_eq!(
+            parse(&["review", "file=main.rs", "note=a=b"]).unwrap(),
+            Some(PromptsSubcommand::Run {
+                name: "review".to_string(),
+                arguments: vec![
+                    ("file".to_string(), "main.rs".to_string()),
+                    ("note".to_string(), "a=b".to_string())
+                ],
+            })
+        );
+        assert!(parse(&["review", "main.rs"]).is_err());
+    }
+
+    #[test]
+    fn test_missing_required_arguments() {
+        let tool_manager = ToolManager::default();
+        let arg = |name: &str, required| crate::mcp_client::PromptGetArg {
+            name: name.to_string(),
+            description: None,
+            required: Some(required),
+        };
+        tool_manager
+            .prompts
+            .write()
+            .unwrap()
+            .insert("review".to_string(), vec![PromptBundle {
+                server_name: "server".to_string(),
+                prompt_get: crate::mcp_client::PromptGet {
+                    name: "review".to_string(),
+                    description: None,
+                    arguments: Some(vec![arg("file", true), arg("focus", true), arg("depth", false)]),
+                },
+            }]);
+
+        let arguments = HashMap::from([("file".to_string(), "main.rs".to_string())]);
+        assert_eq!(
+            missing_required_arguments(&tool_manager, "review", &arguments).unwrap(),
+            vec!["focus".to_string()]
+        );
+        assert_eq!(
+            missing_required_arguments(&tool_manager, "server/review", &arguments).unwrap(),
+            vec!["focus".to_string()]
+        );
+        assert!(
+            missing_required_arguments(&tool_manager, "other/review", &arguments)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expand_prompt_references() {
+        let tool_manager = ToolManager::default();
+        tool_manager
+            .prompts
+            .write()
+            .unwrap()
+            .insert("review".to_string(), vec![PromptBundle {
+                server_name: "git".to_string(),
+                prompt_get: crate::mcp_client::PromptGet {
+                    name: "review".to_string(),
+                    description: None,
+                    arguments: None,
+                },
+            }]);
+
+        // Names that aren't prompts are left alone without a warning
+        let input = "#missing #rust #other/review";
+        let (expanded, warnings) = expand_prompt_references(&tool_manager, input).await;
+        assert_eq!(expanded, input);
+        assert!(warnings.is_empty());
+
+        // Known prompts that can't be fetched are left as they are with a warning
+        let (expanded, warnings) = expand_prompt_references(&tool_manager, "use #git/review here").await;
+        assert_eq!(expanded, "use #git/review here");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Could not expand #git/review"));
+    }
+}
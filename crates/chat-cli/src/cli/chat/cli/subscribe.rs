@@ -1,5 +1,15 @@
-use clap::Args;
+use amzn_codewhisperer_client::types::{
+    SubscriptionType,
+    UsageLimitType,
+    UsageLimitUpdateRequestStatus,
+};
+use aws_smithy_types::date_time::Format;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::style::{
+    Attribute,
     Color,
     Stylize,
 };
@@ -28,16 +38,31 @@ const SUBSCRIBE_TEXT: &str = color_print::cstr! { "During the upgrade, you'll be
 
 Need help? Visit our subscription support page> <blue!>https://docs.aws.amazon.com/console/amazonq/upgrade-builder-id</blue!>" };
 
+/// Width of the bar drawn by `/subscribe --status` for each usage limit.
+const USAGE_BAR_WIDTH: usize = 40;
+
+/// Maximum length of the justification sent with `/subscribe request-limit`.
+const MAX_JUSTIFICATION_LEN: usize = 1000;
+
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
 pub struct SubscribeArgs {
     #[arg(long)]
     manage: bool,
+    /// Show the usage limits of your subscription, how much of them is used, and when they reset
+    #[arg(long, conflicts_with = "manage")]
+    status: bool,
+    #[command(subcommand)]
+    subcommand: Option<SubscribeSubcommand>,
 }
 
 impl SubscribeArgs {
     pub async fn execute(self, os: &mut Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
-        if is_idc_user(&os.database)
+        if let Some(SubscribeSubcommand::RequestLimit { limit, reason }) = self.subcommand {
+            request_limit_increase(os, session, limit, reason).await?;
+        } else if self.status {
+            show_usage_limits(os, session).await?;
+        } else if is_idc_user(&os.database)
             .await
             .map_err(|e| ChatError::Custom(e.to_string().into()))?
         {
@@ -100,6 +125,256 @@ impl SubscribeArgs {
             skip_printing_tools: true,
         })
     }
+
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        self.subcommand.as_ref().map(|s| s.name())
+    }
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum SubscribeSubcommand {
+    /// Request a higher agentic request limit for your account. Only available to IAM Identity
+    /// Center users
+    RequestLimit {
+        /// The limit to request
+        #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+        limit: i64,
+        /// Why you need a higher limit, reviewed along with the request
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+impl SubscribeSubcommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RequestLimit { .. } => "request-limit",
+        }
+    }
+}
+
+async fn request_limit_increase(
+    os: &mut Os,
+    session: &mut ChatSession,
+    limit: i64,
+    reason: String,
+) -> Result<(), ChatError> {
+    // Limit increases are reviewed per organization, so they're only available to enterprise
+    // users.
+    if !is_idc_user(&os.database)
+        .await
+        .map_err(|e| ChatError::Custom(e.to_string().into()))?
+    {
+        execute!(
+            session.stderr,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print("\nLimit increases can only be requested by IAM Identity Center users. "),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("Use "),
+            style::SetForegroundColor(Color::Green),
+            style::Print("/subscribe"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(" to upgrade your subscription for higher limits.\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        return Ok(());
+    }
+
+    let reason = reason.trim().to_string();
+    if reason.is_empty() {
+        return Err(ChatError::Custom("--reason must not be empty".into()));
+    }
+    if reason.chars().count() > MAX_JUSTIFICATION_LEN {
+        return Err(ChatError::Custom(
+            format!("--reason must be at most {MAX_JUSTIFICATION_LEN} characters").into(),
+        ));
+    }
+
+    queue!(session.stderr, style::Print("\n"))?;
+    let output = with_spinner(&mut session.stderr, "Requesting a limit increase...", || async {
+        os.client.update_usage_limits(limit, reason).await
+    })
+    .await?;
+
+    match output.status() {
+        UsageLimitUpdateRequestStatus::Approved => queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Green),
+            style::Print(format!(
+                "Your request was approved. Your new limit is {}.\n",
+                output.approved_limit().unwrap_or(limit)
+            )),
+        )?,
+        UsageLimitUpdateRequestStatus::PendingReview => queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Yellow),
+            style::Print("Your request is pending review.\n"),
+        )?,
+        UsageLimitUpdateRequestStatus::Rejected => queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::Red),
+            style::Print("Your request was rejected.\n"),
+        )?,
+        other => queue!(
+            session.stderr,
+            style::Print(format!("Request status: {}\n", other.as_str())),
+        )?,
+    }
+    if let Some(remaining) = output.remaining_requests_this_month() {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!(
+                "You can make {remaining} more limit {} this month.\n",
+                if remaining == 1 { "request" } else { "requests" }
+            )),
+        )?;
+    }
+    execute!(
+        session.stderr,
+        style::SetForegroundColor(Color::Reset),
+        style::Print("\n")
+    )?;
+
+    Ok(())
+}
+
+async fn show_usage_limits(os: &mut Os, session: &mut ChatSession) -> Result<(), ChatError> {
+    queue!(session.stderr, style::Print("\n"))?;
+    let limits = with_spinner(&mut session.stderr, "Checking usage limits...", || async {
+        os.client.get_usage_limits().await
+    })
+    .await?;
+
+    let subscription_type = limits.subscription_info().map(|info| info.r#type());
+    let is_free_tier = subscription_type.is_none_or(|t| *t == SubscriptionType::QDeveloperStandaloneFree);
+    queue!(
+        session.stderr,
+        style::SetAttribute(Attribute::Bold),
+        style::Print(match subscription_type {
+            Some(t) => subscription_name(t),
+            None => "Q Developer Free".to_string(),
+        }),
+        style::SetAttribute(Attribute::Reset),
+        style::Print("\n\n"),
+    )?;
+
+    if limits.limits().is_empty() {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("No usage limits apply to your account.\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+    }
+
+    let label_width = limits
+        .limits()
+        .iter()
+        .map(|limit| usage_limit_name(limit.r#type()).len())
+        .max()
+        .unwrap_or_default();
+    for limit in limits.limits() {
+        let (used, total) = (limit.current_usage(), limit.total_usage_limit());
+        let label = format!("{:<label_width$}  ", usage_limit_name(limit.r#type()));
+        if total <= 0 {
+            queue!(
+                session.stderr,
+                style::Print(label),
+                style::Print(format!("{used} used, no limit\n")),
+            )?;
+            continue;
+        }
+
+        let filled = usage_bar_filled(used, total, USAGE_BAR_WIDTH);
+        let percent = limit.percent_used().unwrap_or((used as f64 / total as f64) * 100.0);
+        queue!(
+            session.stderr,
+            style::Print(label),
+            style::SetForegroundColor(match percent {
+                p if p >= 90.0 => Color::Red,
+                p if p >= 75.0 => Color::Yellow,
+                _ => Color::Green,
+            }),
+            style::Print("█".repeat(filled)),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("█".repeat(USAGE_BAR_WIDTH - filled)),
+            style::SetForegroundColor(Color::Reset),
+            style::Print(format!(" {used}/{total} ({percent:.0}%)\n")),
+        )?;
+    }
+
+    let reset_date = limits
+        .next_date_reset()
+        .and_then(|date| date.fmt(Format::DateTime).ok())
+        .and_then(|date| date.split('T').next().map(str::to_string));
+    let reset = match (limits.days_until_reset(), reset_date) {
+        (Some(1), date) => Some(format!(
+            "Resets in 1 day{}",
+            date.map(|d| format!(" on {d}")).unwrap_or_default()
+        )),
+        (Some(days), date) => Some(format!(
+            "Resets in {days} days{}",
+            date.map(|d| format!(" on {d}")).unwrap_or_default()
+        )),
+        (None, Some(date)) => Some(format!("Resets on {date}")),
+        (None, None) => None,
+    };
+    if let Some(reset) = reset {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("\n{reset}\n")),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+    }
+
+    if is_free_tier {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("\nUse "),
+            style::SetForegroundColor(Color::Green),
+            style::Print("/subscribe"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(" to upgrade to Q Developer Pro for higher limits.\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+    }
+
+    execute!(session.stderr, style::Print("\n"))?;
+    Ok(())
+}
+
+/// The number of cells of a `width` cell bar to fill for `used` out of `total`.
+fn usage_bar_filled(used: i64, total: i64, width: usize) -> usize {
+    if total <= 0 {
+        return 0;
+    }
+    let ratio = (used.max(0) as f64 / total as f64).min(1.0);
+    (ratio * width as f64).round() as usize
+}
+
+fn subscription_name(subscription_type: &SubscriptionType) -> String {
+    match subscription_type {
+        SubscriptionType::QDeveloperStandaloneFree => "Q Developer Free".to_string(),
+        SubscriptionType::QDeveloperStandalonePro => "Q Developer Pro".to_string(),
+        SubscriptionType::QDeveloperStandaloneProPlus => "Q Developer Pro+".to_string(),
+        SubscriptionType::QDeveloperStandalonePower => "Q Developer Power".to_string(),
+        SubscriptionType::QDeveloperStandalone => "Q Developer".to_string(),
+        other => other.as_str().to_string(),
+    }
+}
+
+fn usage_limit_name(limit_type: &UsageLimitType) -> String {
+    match limit_type {
+        UsageLimitType::AgenticRequest => "Agentic requests".to_string(),
+        UsageLimitType::AiEditor => "AI editor".to_string(),
+        UsageLimitType::CodeCompletions => "Code completions".to_string(),
+        UsageLimitType::Transform => "Transformations".to_string(),
+        other => other.as_str().to_string(),
+    }
 }
 
 async fn upgrade_to_pro(os: &mut Os, session: &mut ChatSession) -> Result<(), ChatError> {
@@ -195,3 +470,43 @@ async fn upgrade_to_pro(os: &mut Os, session: &mut ChatSession) -> Result<(), Ch
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::chat::cli::SlashCommand;
+
+    #[test]
+    fn test_request_limit_parse() {
+        let parse = |args: &[&str]| {
+            SlashCommand::try_parse_from(["slash_command", "subscribe"].iter().chain(args)).map(|command| match command
+            {
+                SlashCommand::Subscribe(args) => args.subcommand,
+                _ => None,
+            })
+        };
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(
+            parse(&["request-limit", "--limit", "500", "--reason", "team rollout"]).unwrap(),
+            Some(SubscribeSubcommand::RequestLimit {
+                limit: 500,
+                reason: "team rollout".to_string()
+            })
+        );
+        assert!(parse(&["request-limit", "--limit", "0", "--reason", "x"]).is_err());
+        assert!(parse(&["request-limit", "--limit", "500"]).is_err());
+    }
+
+    #[test]
+    fn test_usage_bar_filled() {
+        assert_eq!(usage_bar_filled(0, 50, 40), 0);
+        assert_eq!(usage_bar_filled(10, 50, 40), 8);
+        assert_eq!(usage_bar_filled(50, 50, 40), 40);
+        // Overages don't overflow the bar.
+        assert_eq!(usage_bar_filled(75, 50, 40), 40);
+        // No limit.
+        assert_eq!(usage_bar_filled(10, 0, 40), 0);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+use std::io::Write;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use clap::Args;
+use crossterm::style::Attribute;
+use crossterm::{
+    queue,
+    style,
+};
+
+use crate::cli::chat::token_counter::{
+    CharCount,
+    TokenCount,
+};
+use crate::cli::chat::tools::fs_read::FsReadOperation;
+use crate::cli::chat::tools::{
+    QueuedTool,
+    Tool,
+    sanitize_path_tool_arg,
+};
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::os::Os;
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Args)]
+#[command(
+    before_long_help = "/summary shows what happened in this session: the messages exchanged, the tools
+invoked, the AWS services called, the files read and written, the estimated tokens sent and received,
+and the time elapsed.
+
+Unlike /usage, it covers the whole session, including history that was trimmed, compacted, or
+cleared."
+)]
+pub struct SummaryArgs;
+
+impl SummaryArgs {
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let rows = session.stats.rows(session.stats.started_at.elapsed());
+        let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 4;
+        let terminal_width = session.terminal_width();
+
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetAttribute(Attribute::Bold),
+            style::Print(format!("{:<width$}Value\n", "Session summary")),
+            style::SetAttribute(Attribute::Reset),
+            style::Print("▔".repeat(terminal_width)),
+            style::Print("\n"),
+        )?;
+        for (name, value) in rows {
+            let mut lines = value.lines();
+            queue!(
+                session.stderr,
+                style::Print(format!("{name:<width$}{}\n", lines.next().unwrap_or_default()))
+            )?;
+            for line in lines {
+                queue!(session.stderr, style::Print(format!("{:<width$}{line}\n", "")))?;
+            }
+        }
+        queue!(session.stderr, style::Print("\n"))?;
+        session.stderr.flush()?;
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
+
+/// What happened in a chat session, shown with `/summary`.
+///
+/// Unlike the conversation history, this is only ever added to, so it still covers messages that
+/// were trimmed, compacted, or cleared.
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+    pub started_at: Instant,
+    /// Prompts entered by the user.
+    pub prompts: usize,
+    /// Responses received from the model.
+    pub responses: usize,
+    /// Invocations of each tool, by name.
+    pub tool_uses: BTreeMap<String, usize>,
+    /// Calls made with `use_aws`, by service.
+    pub aws_services: BTreeMap<String, usize>,
+    pub files_read: BTreeSet<String>,
+    pub files_written: BTreeSet<String>,
+    /// Characters of text sent to the model, including the history and context sent with every
+    /// request, see `ConversationState::request_char_count`.
+    pub chars_sent: usize,
+    pub chars_received: usize,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            prompts: 0,
+            responses: 0,
+            tool_uses: BTreeMap::new(),
+            aws_services: BTreeMap::new(),
+            files_read: BTreeSet::new(),
+            files_written: BTreeSet::new(),
+            chars_sent: 0,
+            chars_received: 0,
+        }
+    }
+}
+
+impl SessionStats {
+    /// Records an invoked tool. Files are only recorded as read or written if the tool succeeded.
+    pub fn record_tool_use(&mut self, os: &Os, tool: &QueuedTool, succeeded: bool) {
+        *self.tool_uses.entry(tool.name.clone()).or_default() += 1;
+        match &tool.tool {
+            Tool::UseAws(use_aws) => *self.aws_services.entry(use_aws.service_name.clone()).or_default() += 1,
+            Tool::FsRead(fs_read) if succeeded => {
+                for op in &fs_read.operations {
+                    let paths = match op {
+                        FsReadOperation::Line(line) => vec![&line.path],
+                        FsReadOperation::Search(search) => vec![&search.path],
+                        FsReadOperation::Image(image) => image.image_paths.iter().collect(),
+                        FsReadOperation::Directory(_) => vec![],
+                    };
+                    self.files_read.extend(
+                        paths
+                            .into_iter()
+                            .map(|path| sanitize_path_tool_arg(os, path).to_string_lossy().to_string()),
+                    );
+                }
+            },
+            Tool::FsWrite(fs_write) if succeeded => {
+                self.files_written
+                    .insert(fs_write.path(os).to_string_lossy().to_string());
+            },
+            _ => (),
+        }
+    }
+
+    /// Returns the rows of the `/summary` table. Values that list several items have one item per
+    /// line.
+    pub fn rows(&self, elapsed: Duration) -> Vec<(&'static str, String)> {
+        fn counts(counts: &BTreeMap<String, usize>) -> String {
+            if counts.is_empty() {
+                return "none".to_string();
+            }
+            counts
+                .iter()
+                .map(|(name, count)| format!("{name} ({count})"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        fn files(files: &BTreeSet<String>) -> String {
+            if files.is_empty() {
+                return "none".to_string();
+            }
+            files.iter().cloned().collect::<Vec<_>>().join("\n")
+        }
+
+        let tokens_sent: TokenCount = CharCount::from(self.chars_sent).into();
+        let tokens_received: TokenCount = CharCount::from(self.chars_received).into();
+        let secs = elapsed.as_secs();
+        vec![
+            (
+                "Messages",
+                format!("{} prompts, {} responses", self.prompts, self.responses),
+            ),
+            ("Tools invoked", counts(&self.tool_uses)),
+            ("AWS services", counts(&self.aws_services)),
+            ("Files read", files(&self.files_read)),
+            ("Files written", files(&self.files_written)),
+            (
+                "Tokens (estimated)",
+                format!("{tokens_sent} sent, {tokens_received} received"),
+            ),
+            (
+                "Elapsed",
+                format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::chat::tools::fs_write::FsWrite;
+
+    fn queued_tool(name: &str, tool: Tool) -> QueuedTool {
+        QueuedTool {
+            id: "tooluse_1".to_string(),
+            name: name.to_string(),
+            accepted: true,
+            tool,
+            args: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_stats() {
+        let os = Os::new().await.unwrap();
+        let mut stats = SessionStats {
+            prompts: 2,
+            responses: 3,
+            chars_sent: 4000,
+            chars_received: 400,
+            ..Default::default()
+        };
+
+        let use_aws = serde_json::from_value(serde_json::json!({
+            "service_name": "s3",
+            "operation_name": "list-buckets",
+            "region": "us-west-2",
+        }))
+        .unwrap();
+        stats.record_tool_use(&os, &queued_tool("use_aws", Tool::UseAws(use_aws)), true);
+        let fs_read = serde_json::from_value(serde_json::json!({
+            "operations": [
+                { "mode": "Line", "path": "/a.txt" },
+                { "mode": "Directory", "path": "/" },
+            ],
+        }))
+        .unwrap();
+        stats.record_tool_use(&os, &queued_tool("fs_read", Tool::FsRead(fs_read)), true);
+        let fs_write: FsWrite = serde_json::from_value(serde_json::json!({
+            "command": "create",
+            "path": "/b.txt",
+            "file_text": "hello",
+        }))
+        .unwrap();
+        stats.record_tool_use(&os, &queued_tool("fs_write", Tool::FsWrite(fs_write.clone())), true);
+        stats.record_tool_use(&os, &queued_tool("fs_write", Tool::FsWrite(fs_write)), false);
+
+        assert_eq!(stats.tool_uses["fs_write"], 2);
+        assert_eq!(stats.aws_services["s3"], 1);
+        assert_eq!(stats.files_read.len(), 1);
+        assert_eq!(stats.files_written.len(), 1);
+
+        let rows = stats
+            .rows(Duration::from_secs(3725))
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(rows["Messages"], "2 prompts, 3 responses");
+        assert_eq!(rows["Tools invoked"], "fs_read (1)\nfs_write (2)\nuse_aws (1)");
+        assert_eq!(rows["AWS services"], "s3 (1)");
+        assert!(rows["Files read"].ends_with("a.txt"));
+        assert!(rows["Files written"].ends_with("b.txt"));
+        assert_eq!(rows["Tokens (estimated)"], "1000 sent, 100 received");
+        assert_eq!(rows["Elapsed"], "1h 02m 05s");
+    }
+
+    #[test]
+    fn test_empty_rows() {
+        let rows = SessionStats::default()
+            .rows(Duration::ZERO)
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(rows["Tools invoked"], "none");
+        assert_eq!(rows["Files written"], "none");
+        assert_eq!(rows["Elapsed"], "0h 00m 00s");
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::{
+    BTreeMap,
     BTreeSet,
     HashSet,
 };
@@ -16,16 +17,22 @@ use crossterm::{
     queue,
     style,
 };
+use serde::{
+    Deserialize,
+    Serialize,
+};
 
 use crate::api_client::model::Tool as FigTool;
 use crate::cli::agent::{
     Agent,
+    Agents,
     DEFAULT_AGENT_NAME,
 };
 use crate::cli::chat::consts::{
     AGENT_FORMAT_TOOLS_DOC_URL,
     DUMMY_TOOL_NAME,
 };
+use crate::cli::chat::tool_manager::ToolInfo;
 use crate::cli::chat::tools::ToolOrigin;
 use crate::cli::chat::{
     ChatError,
@@ -33,6 +40,7 @@ use crate::cli::chat::{
     ChatState,
     TRUST_ALL_TEXT,
 };
+use crate::os::Os;
 use crate::util::consts::MCP_SERVER_TOOL_DELIMITER;
 
 #[deny(missing_docs)]
@@ -43,9 +51,9 @@ pub struct ToolsArgs {
 }
 
 impl ToolsArgs {
-    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
         if let Some(subcommand) = self.subcommand {
-            return subcommand.execute(session).await;
+            return subcommand.execute(os, session).await;
         }
 
         // No subcommand - print the current tools and their permissions.
@@ -189,6 +197,11 @@ Refer to the documentation for how to configure tools with your agent: https://g
 pub enum ToolsSubcommand {
     /// Show the input schema for all available tools
     Schema,
+    /// Show the description, origin, permission and input schema of a specific tool
+    Describe {
+        #[arg(required = true)]
+        tool_name: String,
+    },
     /// Trust a specific tool or tools for the session
     Trust {
         #[arg(required = true)]
@@ -203,10 +216,209 @@ pub enum ToolsSubcommand {
     TrustAll,
     /// Reset all tools to default permission levels
     Reset,
+    /// Save the tool permissions of this session to a file, or apply ones saved before
+    #[command(subcommand)]
+    Permissions(PermissionsSubcommand),
+}
+
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+#[command(
+    before_long_help = "Exports the permissions changed with /tools trust, untrust, and trust-all, so that the same
+permissions can be imported in a later session. Tools that are denied by the agent stay denied.
+
+Notes:
+• Only the tools currently loaded are exported
+• Unknown tools in an imported file are skipped with a warning"
+)]
+pub enum PermissionsSubcommand {
+    /// Write whether each loaded tool is trusted to a JSON file
+    Export {
+        path: String,
+        /// Overwrite the file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Trust and untrust tools as listed in a file written by export
+    Import { path: String },
+}
+
+/// Whether each tool is trusted, as written by `/tools permissions export`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolPermissions {
+    /// Set by `/tools trust-all`.
+    #[serde(default)]
+    pub trust_all: bool,
+    /// Keyed by the names used by `/tools trust`, with MCP tools as `@server/tool`.
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolPermission>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolPermission {
+    Trusted,
+    Untrusted,
+}
+
+impl ToolPermissions {
+    /// Returns the permissions of the loaded tools, which are the native tools `native_tool_names`
+    /// and the MCP tools `custom_tools`. A tool that is both allowed and denied is untrusted.
+    fn from_agents(agents: &Agents, native_tool_names: &[String], custom_tools: &HashSet<ToolInfo>) -> Self {
+        let permission = |trusted: bool| {
+            if trusted {
+                ToolPermission::Trusted
+            } else {
+                ToolPermission::Untrusted
+            }
+        };
+        let agent = agents.get_active();
+        let native = native_tool_names.iter().map(|name| {
+            let trusted = agent.is_some_and(|a| a.is_tool_allowed(name) && !a.is_tool_denied(name));
+            (name.clone(), permission(trusted))
+        });
+        let custom = custom_tools.iter().map(|info| {
+            let trusted = agent.is_some_and(|a| {
+                a.is_mcp_tool_allowed(&info.server_name, &info.host_tool_name)
+                    && !a.is_mcp_tool_denied(&info.server_name, &info.host_tool_name)
+            });
+            (
+                format!(
+                    "@{}{MCP_SERVER_TOOL_DELIMITER}{}",
+                    info.server_name, info.host_tool_name
+                ),
+                permission(trusted),
+            )
+        });
+        Self {
+            trust_all: agents.trust_all_tools,
+            tools: native.chain(custom).collect(),
+        }
+    }
+
+    /// Applies these permissions to the active agent, returning the names of tools that are not
+    /// loaded, which are skipped.
+    fn apply(
+        &self,
+        agents: &mut Agents,
+        native_tool_names: &[String],
+        custom_tools: &HashSet<ToolInfo>,
+    ) -> Vec<String> {
+        let is_loaded = |name: &str| {
+            native_tool_names.iter().any(|n| n == name)
+                || custom_tools.iter().any(|info| {
+                    name == format!(
+                        "@{}{MCP_SERVER_TOOL_DELIMITER}{}",
+                        info.server_name, info.host_tool_name
+                    )
+                })
+        };
+
+        let mut to_trust = vec![];
+        let mut to_untrust = vec![];
+        let mut unknown = vec![];
+        for (name, permission) in &self.tools {
+            match permission {
+                _ if !is_loaded(name) => unknown.push(name.clone()),
+                ToolPermission::Trusted => to_trust.push(name.clone()),
+                ToolPermission::Untrusted => to_untrust.push(name.clone()),
+            }
+        }
+        agents.untrust_tools(&to_untrust);
+        agents.trust_tools(to_trust);
+        agents.trust_all_tools = self.trust_all;
+        unknown
+    }
+}
+
+impl PermissionsSubcommand {
+    async fn execute(
+        self,
+        os: &Os,
+        session: &mut ChatSession,
+        native_tool_names: &[String],
+        custom_tools: &HashSet<ToolInfo>,
+    ) -> Result<(), ChatError> {
+        match self {
+            Self::Export { path, force } => {
+                if os.fs.exists(&path) && !force {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nFile at {path} already exists. To overwrite, use -f or --force\n"
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                    return Ok(());
+                }
+
+                let permissions =
+                    ToolPermissions::from_agents(&session.conversation.agents, native_tool_names, custom_tools);
+                let contents = serde_json::to_string_pretty(&permissions).map_err(|e| {
+                    ChatError::Custom(format!("Error converting tool permissions to string: {e}").into())
+                })?;
+                os.fs.write(&path, contents).await.map_err(|e| {
+                    ChatError::Custom(format!("Failed to export tool permissions to {path}: {e}").into())
+                })?;
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\n✔ Exported the permissions of {} tools to {path}\n",
+                        permissions.tools.len()
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            Self::Import { path } => {
+                let contents = os
+                    .fs
+                    .read_to_string(&path)
+                    .await
+                    .map_err(|e| ChatError::Custom(format!("Failed to read {path}: {e}").into()))?;
+                let permissions = serde_json::from_str::<ToolPermissions>(&contents).map_err(|e| {
+                    ChatError::Custom(format!("Failed to parse tool permissions in {path}: {e}").into())
+                })?;
+                let unknown = permissions.apply(&mut session.conversation.agents, native_tool_names, custom_tools);
+
+                if !unknown.is_empty() {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(format!(
+                            "\nSkipped '{}', {}\n",
+                            unknown.join("', '"),
+                            if unknown.len() > 1 {
+                                "they are not loaded."
+                            } else {
+                                "it is not loaded."
+                            }
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\n✔ Imported the permissions of {} tools from {path}\n",
+                        permissions.tools.len() - unknown.len()
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                if permissions.trust_all {
+                    queue!(session.stderr, style::Print(TRUST_ALL_TEXT))?;
+                }
+            },
+        }
+        Ok(())
+    }
 }
 
 impl ToolsSubcommand {
-    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
         // Here we need to obtain the list of host tool names
         let existing_custom_tools = session
             .conversation
@@ -239,6 +451,60 @@ impl ToolsSubcommand {
                     .map_err(|e| ChatError::Custom(format!("Error converting tool schema to string: {e}").into()))?;
                 queue!(session.stderr, style::Print(schema_json), style::Print("\n"))?;
             },
+            Self::Describe { tool_name } => {
+                let tool_manager = &session.conversation.tool_manager;
+                // MCP tools are keyed by the name recognized by the model, which may differ from
+                // the name the user sees.
+                let model_tool_name = tool_manager
+                    .tn_map
+                    .iter()
+                    .find(|(_, info)| info.host_tool_name == tool_name)
+                    .map_or(tool_name.as_str(), |(model_tool_name, _)| model_tool_name.as_str());
+
+                match tool_manager
+                    .schema
+                    .get(model_tool_name)
+                    .filter(|spec| spec.name != DUMMY_TOOL_NAME)
+                {
+                    Some(spec) => {
+                        let schema_json = serde_json::to_string_pretty(&spec.input_schema.0).map_err(|e| {
+                            ChatError::Custom(format!("Error converting tool schema to string: {e}").into())
+                        })?;
+                        queue!(
+                            session.stderr,
+                            style::Print("\n"),
+                            style::SetAttribute(Attribute::Bold),
+                            style::Print(&tool_name),
+                            style::SetAttribute(Attribute::Reset),
+                            style::Print(format!("\n{}\n\n", spec.description.trim())),
+                            style::SetAttribute(Attribute::Bold),
+                            style::Print("Origin: "),
+                            style::SetAttribute(Attribute::Reset),
+                            style::Print(format!("{}\n", spec.tool_origin)),
+                            style::SetAttribute(Attribute::Bold),
+                            style::Print("Permission: "),
+                            style::SetAttribute(Attribute::Reset),
+                            style::Print(format!(
+                                "{}\n",
+                                session.conversation.agents.display_label(&tool_name, &spec.tool_origin)
+                            )),
+                            style::SetAttribute(Attribute::Bold),
+                            style::Print("Input schema:\n"),
+                            style::SetAttribute(Attribute::Reset),
+                            style::Print(schema_json),
+                            style::Print("\n"),
+                        )?;
+                    },
+                    None => {
+                        queue!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!("\nCannot describe '{tool_name}', it does not exist.\n")),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    },
+                }
+            },
             Self::Trust { tool_names } => {
                 let (valid_tools, invalid_tools): (Vec<String>, Vec<String>) =
                     tool_names.into_iter().partition(|tool_name| {
@@ -389,6 +655,11 @@ impl ToolsSubcommand {
                     style::SetForegroundColor(Color::Reset),
                 )?;
             },
+            Self::Permissions(subcommand) => {
+                subcommand
+                    .execute(os, session, &native_tool_names, &existing_custom_tools)
+                    .await?;
+            },
         };
 
         session.stderr.flush()?;
@@ -401,10 +672,103 @@ impl ToolsSubcommand {
     pub fn name(&self) -> &'static str {
         match self {
             ToolsSubcommand::Schema => "schema",
+            ToolsSubcommand::Describe { .. } => "describe",
             ToolsSubcommand::Trust { .. } => "trust",
             ToolsSubcommand::Untrust { .. } => "untrust",
             ToolsSubcommand::TrustAll => "trust-all",
             ToolsSubcommand::Reset => "reset",
+            ToolsSubcommand::Permissions(_) => "permissions",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn agents() -> Agents {
+        let agent = Agent {
+            allowed_tools: HashSet::from(["fs_read".to_string(), "@git".to_string()]),
+            ..Default::default()
+        };
+        Agents {
+            agents: HashMap::from([("test".to_string(), agent)]),
+            active_idx: "test".to_string(),
+            trust_all_tools: false,
         }
     }
+
+    fn custom_tools() -> HashSet<ToolInfo> {
+        HashSet::from([
+            ToolInfo {
+                server_name: "git".to_string(),
+                host_tool_name: "git_status".to_string(),
+            },
+            ToolInfo {
+                server_name: "fetch".to_string(),
+                host_tool_name: "fetch".to_string(),
+            },
+        ])
+    }
+
+    #[test]
+    fn test_export_permissions() {
+        let native_tool_names = ["fs_read".to_string(), "execute_bash".to_string()];
+        let permissions = ToolPermissions::from_agents(&agents(), &native_tool_names, &custom_tools());
+        assert!(!permissions.trust_all);
+        assert_eq!(
+            permissions.tools,
+            BTreeMap::from([
+                ("fs_read".to_string(), ToolPermission::Trusted),
+                ("execute_bash".to_string(), ToolPermission::Untrusted),
+                ("@git/git_status".to_string(), ToolPermission::Trusted),
+                ("@fetch/fetch".to_string(), ToolPermission::Untrusted),
+            ])
+        );
+
+        let json = serde_json::to_value(&permissions).unwrap();
+        assert_eq!(json["trustAll"], false);
+        assert_eq!(json["tools"]["fs_read"], "trusted");
+        assert_eq!(serde_json::from_value::<ToolPermissions>(json).unwrap(), permissions);
+    }
+
+    #[test]
+    fn test_export_permissions_of_denied_tools() {
+        let mut agents = agents();
+        let agent = agents.agents.get_mut("test").unwrap();
+        agent.denied_tools = HashSet::from(["fs_read".to_string(), "@git/git_status".to_string()]);
+
+        let native_tool_names = ["fs_read".to_string()];
+        let permissions = ToolPermissions::from_agents(&agents, &native_tool_names, &custom_tools());
+        assert_eq!(permissions.tools["fs_read"], ToolPermission::Untrusted);
+        assert_eq!(permissions.tools["@git/git_status"], ToolPermission::Untrusted);
+    }
+
+    #[test]
+    fn test_import_permissions() {
+        let native_tool_names = ["fs_read".to_string(), "execute_bash".to_string()];
+        let permissions = serde_json::from_value::<ToolPermissions>(serde_json::json!({
+            "tools": {
+                "fs_read": "untrusted",
+                "execute_bash": "trusted",
+                "@fetch/fetch": "trusted",
+                "@missing/tool": "trusted",
+                "use_gcp": "untrusted",
+            }
+        }))
+        .unwrap();
+
+        let mut agents = agents();
+        let unknown = permissions.apply(&mut agents, &native_tool_names, &custom_tools());
+        assert_eq!(unknown, ["@missing/tool", "use_gcp"]);
+
+        let agent = agents.get_active().unwrap();
+        assert!(!agent.is_tool_allowed("fs_read"));
+        assert!(agent.is_tool_allowed("execute_bash"));
+        assert!(agent.is_mcp_tool_allowed("fetch", "fetch"));
+        assert!(!agent.is_tool_allowed("@missing/tool"));
+        assert!(!agents.trust_all_tools);
+    }
 }
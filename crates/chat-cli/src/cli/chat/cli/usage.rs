@@ -10,22 +10,40 @@ use crossterm::{
 };
 
 use super::model::context_window_tokens;
+use crate::cli::chat::message::{
+    AssistantMessage,
+    UserMessage,
+};
 use crate::cli::chat::token_counter::{
     CharCount,
+    CharCounter,
     TokenCount,
 };
+use crate::cli::chat::util::truncate_safe;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
 use crate::os::Os;
+
+/// Maximum number of bytes of each message shown by `/usage --by-message`.
+const MESSAGE_PREVIEW_LEN: usize = 60;
+
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct UsageArgs;
+pub struct UsageArgs {
+    /// List each message in the conversation history with its token count, largest first
+    #[arg(long)]
+    by_message: bool,
+}
 
 impl UsageArgs {
     pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        if self.by_message {
+            return print_usage_by_message(session);
+        }
+
         let state = session
             .conversation
             .backend_conversation_state(os, true, &mut session.stderr)
@@ -205,7 +223,12 @@ impl UsageArgs {
             style::SetForegroundColor(Color::DarkGreen),
             style::Print("/context show"),
             style::SetForegroundColor(Color::DarkGrey),
-            style::Print(" to see tokens per context file\n\n"),
+            style::Print(" to see tokens per context file\n"),
+            style::Print("Run "),
+            style::SetForegroundColor(Color::DarkGreen),
+            style::Print("/usage --by-message"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(" to find the largest messages in the history\n\n"),
             style::SetForegroundColor(Color::Reset),
         )?;
 
@@ -214,3 +237,180 @@ impl UsageArgs {
         })
     }
 }
+
+/// The token count of a single message in the conversation history.
+#[derive(Debug, PartialEq, Eq)]
+struct MessageUsage {
+    /// The 1-based position of the history entry the message belongs to.
+    entry: usize,
+    /// Short description of the message, e.g. the start of the prompt or the tools that were used.
+    label: String,
+    tokens: TokenCount,
+}
+
+/// Returns the token count of each user and assistant message in `history`, largest first.
+fn message_usage<'a>(history: impl Iterator<Item = (&'a UserMessage, &'a AssistantMessage)>) -> Vec<MessageUsage> {
+    let preview = |s: &str| {
+        let s = s.trim().replace('\n', " ");
+        let preview = truncate_safe(&s, MESSAGE_PREVIEW_LEN);
+        let ellipsis = if preview.len() < s.len() { "…" } else { "" };
+        format!("\"{preview}{ellipsis}\"")
+    };
+
+    let mut tool_names = std::collections::HashMap::new();
+    let mut usage = Vec::new();
+    for (i, (user, assistant)) in history.enumerate() {
+        let user_label = match (user.prompt(), user.tool_use_results()) {
+            (_, Some(results)) => {
+                let names = results
+                    .iter()
+                    .map(|result| {
+                        tool_names
+                            .get(result.tool_use_id.as_str())
+                            .copied()
+                            .unwrap_or(result.tool_use_id.as_str())
+                    })
+                    .collect::<Vec<_>>();
+                format!("tool results: {}", names.join(", "))
+            },
+            (Some(prompt), None) => format!("prompt: {}", preview(prompt)),
+            (None, None) => "prompt".to_string(),
+        };
+        usage.push(MessageUsage {
+            entry: i + 1,
+            label: user_label,
+            tokens: user.char_count().into(),
+        });
+
+        let assistant_label = match assistant.tool_uses() {
+            Some(tool_uses) => {
+                for tool_use in tool_uses {
+                    tool_names.insert(tool_use.id.as_str(), tool_use.name.as_str());
+                }
+                let names = tool_uses.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+                format!("tool uses: {}", names.join(", "))
+            },
+            None => format!("response: {}", preview(assistant.content())),
+        };
+        usage.push(MessageUsage {
+            entry: i + 1,
+            label: assistant_label,
+            tokens: assistant.char_count().into(),
+        });
+    }
+
+    // Stable sort, so that messages of the same size stay in history order.
+    usage.sort_by(|a, b| b.tokens.cmp(&a.tokens));
+    usage
+}
+
+fn print_usage_by_message(session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    let usage = message_usage(
+        session
+            .conversation
+            .history()
+            .iter()
+            .map(|entry| (entry.user(), entry.assistant())),
+    );
+
+    if usage.is_empty() {
+        execute!(
+            session.stderr,
+            style::Print("\nThere are no messages in the conversation history.\n\n")
+        )?;
+        return Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        });
+    }
+
+    queue!(
+        session.stderr,
+        style::SetAttribute(Attribute::Bold),
+        style::Print(format!(
+            "\n{:>8} {:>10}  {:>5}  Message\n",
+            "Tokens", "Cumulative", "Entry"
+        )),
+        style::SetAttribute(Attribute::Reset),
+    )?;
+    let mut cumulative = 0;
+    for MessageUsage { entry, label, tokens } in usage {
+        cumulative += tokens.value();
+        queue!(
+            session.stderr,
+            style::Print(format!(
+                "{:>8} {:>10}  ",
+                format!("~{tokens}"),
+                format!("~{cumulative}")
+            )),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("{entry:>5}  ")),
+            style::SetForegroundColor(Color::Reset),
+            style::Print(format!("{label}\n")),
+        )?;
+    }
+
+    execute!(
+        session.stderr,
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print("\nRun "),
+        style::SetForegroundColor(Color::DarkGreen),
+        style::Print("/forget <entry>"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print(" to remove a message along with the rest of its turn, "),
+        style::SetForegroundColor(Color::DarkGreen),
+        style::Print("/pin"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print(" to keep a prompt when the history is trimmed, or "),
+        style::SetForegroundColor(Color::DarkGreen),
+        style::Print("/clear"),
+        style::SetForegroundColor(Color::DarkGrey),
+        style::Print(" to erase the entire chat history\n\n"),
+        style::SetForegroundColor(Color::Reset),
+    )?;
+
+    Ok(ChatState::PromptUser {
+        skip_printing_tools: true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::model::ToolResultStatus;
+    use crate::cli::chat::message::{
+        AssistantToolUse,
+        ToolUseResult,
+        ToolUseResultBlock,
+    };
+
+    #[test]
+    fn test_message_usage() {
+        let history = [
+            (
+                UserMessage::new_prompt("read the log".to_string()),
+                AssistantMessage::new_tool_use(None, "Reading".to_string(), vec![AssistantToolUse {
+                    id: "tool_1".to_string(),
+                    name: "fs_read".to_string(),
+                    ..Default::default()
+                }]),
+            ),
+            (
+                UserMessage::new_tool_use_results(vec![ToolUseResult {
+                    tool_use_id: "tool_1".to_string(),
+                    content: vec![ToolUseResultBlock::Text("x".repeat(4000))],
+                    status: ToolResultStatus::Success,
+                }]),
+                AssistantMessage::new_response(None, "The log is long".to_string()),
+            ),
+        ];
+
+        let usage = message_usage(history.iter().map(|(user, assistant)| (user, assistant)));
+        assert_eq!(usage.len(), 4);
+        assert_eq!(usage[0].entry, 2);
+        assert_eq!(usage[0].label, "tool results: fs_read");
+        assert_eq!(usage[0].tokens.value(), 1000);
+        assert!(usage.windows(2).all(|w| w[0].tokens >= w[1].tokens));
+        assert!(usage.iter().any(|u| u.label == "prompt: \"read the log\""));
+        assert!(usage.iter().any(|u| u.label == "tool uses: fs_read"));
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use notify::{
+    Event,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+};
+use tracing::warn;
+
+use crate::cli::agent::{
+    Agent,
+    McpServerConfig,
+};
+use crate::os::Os;
+use crate::util::directories;
+
+/// How long the watched files need to stay unchanged before a change is reported, so that a burst
+/// of writes (e.g. an editor saving a file in several steps) results in a single reload.
+pub const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches the files that the MCP servers of an agent are configured in.
+///
+/// Changes are not pushed anywhere. Instead the chat loop polls [ConfigWatcher::take_change] in
+/// between turns, which is also where debouncing happens.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    last_change: Arc<Mutex<Option<Instant>>>,
+    debounce: Duration,
+}
+
+impl ConfigWatcher {
+    pub fn new(paths: Vec<PathBuf>, debounce: Duration) -> notify::Result<Self> {
+        let last_change = Arc::new(Mutex::new(None));
+        let last_change_clone = last_change.clone();
+        let watched = paths.iter().cloned().collect::<HashSet<_>>();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) if !event.kind.is_access() && event.paths.iter().any(|path| watched.contains(path)) => {
+                if let Ok(mut last_change) = last_change_clone.lock() {
+                    last_change.replace(Instant::now());
+                }
+            },
+            Ok(_) => {},
+            Err(err) => warn!(?err, "Error watching config files"),
+        })?;
+
+        // The parent directories are watched rather than the files themselves, since files that
+        // are saved by renaming a new file over them would otherwise stop being watched.
+        let dirs = paths
+            .iter()
+            .filter_map(|path| path.parent())
+            .filter(|dir| dir.is_dir())
+            .collect::<HashSet<_>>();
+        for dir in dirs {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            last_change,
+            debounce,
+        })
+    }
+
+    /// Returns true once, after the watched files have changed and then stayed unchanged for the
+    /// debounce period.
+    pub fn take_change(&self) -> bool {
+        let Ok(mut last_change) = self.last_change.lock() else {
+            return false;
+        };
+        match *last_change {
+            Some(time) if time.elapsed() >= self.debounce => {
+                last_change.take();
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+/// The files that the MCP servers of `agent` are loaded from.
+pub fn mcp_config_paths(os: &Os, agent: &Agent) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    match &agent.path {
+        Some(path) => {
+            paths.push(path.clone());
+            if agent.use_legacy_mcp_json {
+                paths.extend(directories::chat_legacy_workspace_mcp_config(os).ok());
+                paths.extend(directories::chat_legacy_global_mcp_config(os).ok());
+            }
+        },
+        // The in-memory default agent only uses the global mcp.json
+        None => paths.extend(directories::chat_legacy_global_mcp_config(os).ok()),
+    }
+    paths
+}
+
+/// Loads the MCP servers of `agent` as they are currently configured on disk.
+pub async fn load_mcp_config(os: &Os, agent: &Agent) -> eyre::Result<McpServerConfig> {
+    match &agent.path {
+        Some(path) => Ok(Agent::load(os, path, &mut None).await?.mcp_servers),
+        None => {
+            let path = directories::chat_legacy_global_mcp_config(os)?;
+            match os.fs.exists(&path) {
+                true => McpServerConfig::load_from_file(os, path).await,
+                false => Ok(McpServerConfig::default()),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_config_watcher_debounces_changes() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("agent.json");
+        std::fs::write(&path, "{}").unwrap();
+        let watcher = ConfigWatcher::new(vec![path.clone()], Duration::from_millis(200)).unwrap();
+        assert!(!watcher.take_change());
+
+        // Changes to other files in the same directory are ignored
+        std::fs::write(tempdir.path().join("other.json"), "{}").unwrap();
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(!watcher.take_change());
+
+        std::fs::write(&path, "{ \"name\": ").unwrap();
+        std::fs::write(&path, "{ \"name\": \"agent\" }").unwrap();
+        assert!(!watcher.take_change(), "changes should be debounced");
+
+        let start = Instant::now();
+        while !watcher.take_change() {
+            assert!(start.elapsed() < Duration::from_secs(5), "change was never reported");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(!watcher.take_change(), "a change should only be reported once");
+    }
+
+    #[tokio::test]
+    async fn test_mcp_config_paths() {
+        let os = Os::new().await.unwrap();
+        let global_mcp_path = directories::chat_legacy_global_mcp_config(&os).unwrap();
+        let workspace_mcp_path = directories::chat_legacy_workspace_mcp_config(&os).unwrap();
+
+        let mut agent = Agent::default();
+        assert_eq!(mcp_config_paths(&os, &agent), vec![global_mcp_path.clone()]);
+
+        agent.path = Some(PathBuf::from("/agents/dev.json"));
+        assert_eq!(mcp_config_paths(&os, &agent), vec![
+            PathBuf::from("/agents/dev.json"),
+            workspace_mcp_path,
+            global_mcp_path
+        ]);
+
+        agent.use_legacy_mcp_json = false;
+        assert_eq!(mcp_config_paths(&os, &agent), vec![PathBuf::from("/agents/dev.json")]);
+    }
+}
@@ -18,6 +18,13 @@ pub const MAX_NUMBER_OF_IMAGES_PER_REQUEST: usize = 10;
 /// In bytes - 10 MB
 pub const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024;
 
+/// Number of identical consecutive tool calls executed before further repeats are refused.
+pub const DEFAULT_MAX_REPEATED_TOOL_CALLS: usize = 3;
+
+/// Default time (in ms) to wait for a still loading MCP server when the model uses one of its
+/// tools.
+pub const DEFAULT_MCP_TOOL_LOAD_TIMEOUT: u64 = 10_000;
+
 pub const AGENT_FORMAT_HOOKS_DOC_URL: &str =
     "https://github.com/aws/amazon-q-developer-cli/blob/main/docs/agent-format.md#hooks-field";
 
@@ -1,6 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::io::Write;
-use std::path::Path;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::Stdio;
 
 use eyre::{
     Result,
@@ -13,6 +20,8 @@ use serde::{
     Serialize,
     Serializer,
 };
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
 
 use super::cli::model::context_window_tokens;
 use super::util::drop_matched_context_files;
@@ -96,6 +105,9 @@ pub struct ContextManager {
     pub current_profile: String,
     /// List of file paths or glob patterns to include in the context.
     pub paths: Vec<ContextFilePath>,
+    /// Glob patterns whose matches should include files ignored by `.gitignore`.
+    #[serde(default)]
+    pub no_gitignore_paths: HashSet<String>,
     /// Map of Hook Name to [`Hook`]. The hook name serves as the hook's ID.
     pub hooks: HashMap<HookTrigger, Vec<Hook>>,
     #[serde(skip)]
@@ -115,6 +127,7 @@ impl ContextManager {
             max_context_files_size,
             current_profile: agent.name.clone(),
             paths,
+            no_gitignore_paths: HashSet::new(),
             hooks: agent.hooks.clone(),
             hook_executor: HookExecutor::new(),
         })
@@ -125,10 +138,19 @@ impl ContextManager {
     /// # Arguments
     /// * `paths` - List of paths to add
     /// * `force` - If true, skip validation that the path exists
+    /// * `no_gitignore` - If true, glob patterns also match files ignored by `.gitignore`
     ///
     /// # Returns
-    /// A Result indicating success or an error
-    pub async fn add_paths(&mut self, os: &Os, paths: Vec<String>, force: bool) -> Result<()> {
+    /// A Result containing the matched files that were skipped for being binary, or an error
+    pub async fn add_paths(
+        &mut self,
+        os: &Os,
+        paths: Vec<String>,
+        force: bool,
+        no_gitignore: bool,
+    ) -> Result<Vec<String>> {
+        let mut binary_files = Vec::new();
+
         // Validate paths exist before adding them
         if !force {
             let mut context_files = Vec::new();
@@ -137,7 +159,7 @@ impl ContextManager {
             for path in &paths {
                 // We're using a temporary context_files vector just for validation
                 // Pass is_validation=true to ensure we error if glob patterns don't match any files
-                match process_path(os, path, &mut context_files, true).await {
+                match process_path(os, path, &mut context_files, &mut binary_files, true, !no_gitignore).await {
                     Ok(_) => {}, // Path is valid
                     Err(e) => return Err(eyre!("Invalid path '{}': {}. Use --force to add anyway.", path, e)),
                 }
@@ -149,12 +171,16 @@ impl ContextManager {
                 return Err(eyre!("Rule '{}' already exists.", path));
             }
 
+            if no_gitignore {
+                self.no_gitignore_paths.insert(path.clone());
+            }
+
             // The assumption here is that we are only calling [add_paths] for adding paths in
             // session
             self.paths.push(ContextFilePath::Session(path));
         }
 
-        Ok(())
+        Ok(binary_files)
     }
 
     /// Remove paths from the context configuration.
@@ -169,6 +195,7 @@ impl ContextManager {
         let old_path_num = self.paths.len();
         self.paths
             .retain(|p| !paths.iter().any(|path| path.as_str() == p.get_path_as_str()));
+        self.no_gitignore_paths.retain(|p| !paths.contains(p));
 
         if old_path_num == self.paths.len() {
             return Err(eyre!("None of the specified paths were found in the context"));
@@ -180,6 +207,7 @@ impl ContextManager {
     /// Clear all paths from the context configuration.
     pub fn clear(&mut self) {
         self.paths.clear();
+        self.no_gitignore_paths.clear();
     }
 
     /// Get all context files (global + profile-specific).
@@ -195,8 +223,13 @@ impl ContextManager {
     /// A Result containing a vector of (filename, content) pairs or an error
     pub async fn get_context_files(&self, os: &Os) -> Result<Vec<(String, String)>> {
         let mut context_files = Vec::new();
+        let mut binary_files = Vec::new();
 
-        self.collect_context_files(os, &self.paths, &mut context_files).await?;
+        self.collect_context_files(os, &self.paths, &mut context_files, &mut binary_files)
+            .await?;
+        if !binary_files.is_empty() {
+            debug!(?binary_files, "skipped binary context files");
+        }
 
         context_files.sort_by(|a, b| a.0.cmp(&b.0));
         context_files.dedup_by(|a, b| a.0 == b.0);
@@ -204,10 +237,21 @@ impl ContextManager {
         Ok(context_files)
     }
 
-    pub async fn get_context_files_by_path(&self, os: &Os, path: &str) -> Result<Vec<(String, String)>> {
+    /// Returns the files matched by a single context rule, along with the matched files that were
+    /// skipped for being binary.
+    pub async fn get_context_files_by_path(&self, os: &Os, path: &str) -> Result<(Vec<(String, String)>, Vec<String>)> {
         let mut context_files = Vec::new();
-        process_path(os, path, &mut context_files, true).await?;
-        Ok(context_files)
+        let mut binary_files = Vec::new();
+        process_path(
+            os,
+            path,
+            &mut context_files,
+            &mut binary_files,
+            true,
+            !self.no_gitignore_paths.contains(path),
+        )
+        .await?;
+        Ok((context_files, binary_files))
     }
 
     /// Collects context files and optionally drops files if the total size exceeds the limit.
@@ -231,10 +275,20 @@ impl ContextManager {
         os: &Os,
         paths: &[ContextFilePath],
         context_files: &mut Vec<(String, String)>,
+        binary_files: &mut Vec<String>,
     ) -> Result<()> {
         for path in paths {
+            let path = path.get_path_as_str();
             // Use is_validation=false to handle non-matching globs gracefully
-            process_path(os, path.get_path_as_str(), context_files, false).await?;
+            process_path(
+                os,
+                path,
+                context_files,
+                binary_files,
+                false,
+                !self.no_gitignore_paths.contains(path),
+            )
+            .await?;
         }
         Ok(())
     }
@@ -264,15 +318,19 @@ pub fn calc_max_context_files_size(model_id: Option<&str>) -> usize {
 ///
 /// This method:
 /// 1. Expands the path (handling ~ for home directory)
-/// 2. If the path contains glob patterns, expands them
-/// 3. For each resulting path, adds the file to the context collection
+/// 2. If the path contains glob patterns, expands them, skipping files ignored by git when
+///    `respect_gitignore` is set
+/// 3. For each resulting path, adds the file to the context collection, or to `binary_files` if its
+///    content is binary
 /// 4. Handles directories by including all files in the directory (non-recursive)
 /// 5. With force=true, includes paths that don't exist yet
 ///
 /// # Arguments
 /// * `path` - The path to process
 /// * `context_files` - The collection to add files to
+/// * `binary_files` - The collection to add skipped binary files to
 /// * `is_validation` - If true, error when glob patterns don't match; if false, silently skip
+/// * `respect_gitignore` - If true, exclude glob matches that git considers ignored
 ///
 /// # Returns
 /// A Result indicating success or an error
@@ -280,50 +338,49 @@ async fn process_path(
     os: &Os,
     path: &str,
     context_files: &mut Vec<(String, String)>,
+    binary_files: &mut Vec<String>,
     is_validation: bool,
+    respect_gitignore: bool,
 ) -> Result<()> {
-    // Expand ~ to home directory
-    let expanded_path = if path.starts_with('~') {
-        if let Some(home_dir) = os.env.home() {
-            home_dir.join(&path[2..]).to_string_lossy().to_string()
-        } else {
-            return Err(eyre!("Could not determine home directory"));
-        }
-    } else {
-        path.to_string()
-    };
-
-    // Handle absolute, relative paths, and glob patterns
-    let full_path = if expanded_path.starts_with('/') {
-        expanded_path
-    } else {
-        os.env.current_dir()?.join(&expanded_path).to_string_lossy().to_string()
-    };
-
-    // Required in chroot testing scenarios so that we can use `Path::exists`.
-    let full_path = os.fs.chroot_path_str(full_path);
+    let full_path = resolve_path(os, path)?;
 
     // Check if the path contains glob patterns
-    if full_path.contains('*') || full_path.contains('?') || full_path.contains('[') {
+    if is_glob(&full_path) {
         // Expand glob pattern
         match glob(&full_path) {
             Ok(entries) => {
-                let mut found_any = false;
-
+                let mut matched = Vec::new();
                 for entry in entries {
                     match entry {
                         Ok(path) => {
                             if path.is_file() {
-                                add_file_to_context(os, &path, context_files).await?;
-                                found_any = true;
+                                matched.push(path);
                             }
                         },
                         Err(e) => return Err(eyre!("Glob error: {}", e)),
                     }
                 }
 
-                if !found_any && is_validation {
+                let matched_count = matched.len();
+                if respect_gitignore {
+                    // Search for ignored files from the deepest directory without glob characters.
+                    let glob_start = full_path.find(['*', '?', '[']).unwrap_or_default();
+                    let base_dir = &full_path[..full_path[..glob_start].rfind('/').map_or(0, |i| i + 1)];
+                    matched = filter_gitignored(Path::new(base_dir), matched).await;
+                }
+
+                for path in &matched {
+                    add_file_to_context(os, path, context_files, binary_files).await?;
+                }
+
+                if matched.is_empty() && is_validation {
                     // When validating paths (e.g., for /context add), error if no files match
+                    if matched_count > 0 {
+                        return Err(eyre!(
+                            "All files matching glob pattern '{}' are ignored by .gitignore. Use --no-gitignore to include them",
+                            full_path
+                        ));
+                    }
                     return Err(eyre!("No files found matching glob pattern '{}'", full_path));
                 }
                 // When just showing expanded files (e.g., for /context show --expand),
@@ -336,14 +393,14 @@ async fn process_path(
         let path = Path::new(&full_path);
         if path.exists() {
             if path.is_file() {
-                add_file_to_context(os, path, context_files).await?;
+                add_file_to_context(os, path, context_files, binary_files).await?;
             } else if path.is_dir() {
                 // For directories, add all files in the directory (non-recursive)
                 let mut read_dir = os.fs.read_dir(path).await?;
                 while let Some(entry) = read_dir.next_entry().await? {
                     let path = entry.path();
                     if path.is_file() {
-                        add_file_to_context(os, &path, context_files).await?;
+                        add_file_to_context(os, &path, context_files, binary_files).await?;
                     }
                 }
             }
@@ -356,25 +413,195 @@ async fn process_path(
     Ok(())
 }
 
+/// Expands `~` to the home directory and makes `path` absolute relative to the current directory.
+fn resolve_path(os: &Os, path: &str) -> Result<String> {
+    // Expand ~ to home directory
+    let expanded_path = if path.starts_with('~') {
+        if let Some(home_dir) = os.env.home() {
+            home_dir.join(&path[2..]).to_string_lossy().to_string()
+        } else {
+            return Err(eyre!("Could not determine home directory"));
+        }
+    } else {
+        path.to_string()
+    };
+
+    // Handle absolute, relative paths, and glob patterns
+    let full_path = if expanded_path.starts_with('/') {
+        expanded_path
+    } else {
+        os.env.current_dir()?.join(&expanded_path).to_string_lossy().to_string()
+    };
+
+    // Required in chroot testing scenarios so that we can use `Path::exists`.
+    Ok(os.fs.chroot_path_str(full_path))
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains('*') || path.contains('?') || path.contains('[')
+}
+
+/// Files referenced inline in a prompt with `@path`.
+#[derive(Debug, Default)]
+pub struct FileReferences {
+    /// (filename, content) pairs of the files to attach to the prompt.
+    pub files: Vec<(String, String)>,
+    /// Referenced files that were skipped for being binary.
+    pub binary_files: Vec<String>,
+    /// Referenced files that were dropped because their total size exceeded the limit.
+    pub dropped_files: Vec<(String, String)>,
+    /// Errors for references that look like paths but could not be read.
+    pub errors: Vec<String>,
+}
+
+/// Collects the files referenced in `input` with `@path` tokens, e.g. `@src/main.rs` or
+/// `@src/*.rs`. Paths are relative to the current directory, and the files are dropped largest
+/// first once their total size exceeds `max_size` tokens.
+///
+/// Tokens that don't match any file are only reported in [FileReferences::errors] if they look
+/// like a path, so that e.g. `@someone` is left alone.
+pub async fn collect_file_references(os: &Os, input: &str, max_size: usize) -> FileReferences {
+    let mut refs = FileReferences::default();
+    for path in file_reference_paths(os, input) {
+        let result = process_path(os, path, &mut refs.files, &mut refs.binary_files, true, false).await;
+        if let Some(err) = result.err().filter(|_| path.contains(['/', '.', '~']) || is_glob(path)) {
+            refs.errors.push(err.to_string());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    refs.files.retain(|(filename, _)| seen.insert(filename.clone()));
+    refs.dropped_files = drop_matched_context_files(&mut refs.files, max_size).unwrap_or_default();
+    refs.files
+        .retain(|file| !refs.dropped_files.iter().any(|dropped| dropped.0 == file.0));
+    refs
+}
+
+/// Returns whether `path`, taken from an `@path` token, matches an existing file or directory.
+pub fn is_file_reference(os: &Os, path: &str) -> bool {
+    let Ok(full_path) = resolve_path(os, path) else {
+        return false;
+    };
+    if is_glob(&full_path) {
+        glob(&full_path).is_ok_and(|mut entries| entries.any(|entry| entry.is_ok_and(|path| path.is_file())))
+    } else {
+        Path::new(&full_path).exists()
+    }
+}
+
+/// Returns the paths of the `@path` tokens in `input`, without trailing punctuation.
+///
+/// A trailing `?` is a glob wildcard, so it is only removed when the path without it is a
+/// [file reference](is_file_reference), e.g. at the end of a question.
+fn file_reference_paths<'a>(os: &'a Os, input: &'a str) -> impl Iterator<Item = &'a str> {
+    const TRAILING_PUNCTUATION: [char; 6] = [',', ';', ':', ')', '.', '!'];
+
+    input
+        .split_whitespace()
+        .filter_map(|token| token.strip_prefix('@'))
+        .map(move |path| {
+            let path = path.trim_end_matches(TRAILING_PUNCTUATION);
+            match path.trim_end_matches('?').trim_end_matches(TRAILING_PUNCTUATION) {
+                unquestioned if unquestioned.len() < path.len() && is_file_reference(os, unquestioned) => unquestioned,
+                _ => path,
+            }
+        })
+        .filter(|path| !path.is_empty())
+}
+
+/// Removes the paths that git considers ignored, as seen from the repository containing `dir`.
+///
+/// The paths are returned unchanged if `dir` is not inside a git work tree or git is unavailable.
+async fn filter_gitignored(dir: &Path, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    if paths.is_empty() {
+        return paths;
+    }
+
+    let child = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["check-ignore", "--stdin", "-z"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return paths;
+    };
+
+    let mut input = Vec::new();
+    for path in &paths {
+        input.extend_from_slice(path.to_string_lossy().as_bytes());
+        input.push(0);
+    }
+    if let Some(mut stdin) = child.stdin.take() {
+        // Write from a separate task so that a large output can't block the write.
+        tokio::spawn(async move { stdin.write_all(&input).await });
+    }
+
+    let output = match child.wait_with_output().await {
+        // check-ignore exits with 1 when none of the paths are ignored
+        Ok(output) if matches!(output.status.code(), Some(0 | 1)) => output,
+        _ => return paths,
+    };
+    let ignored = output
+        .stdout
+        .split(|b| *b == 0)
+        .map(String::from_utf8_lossy)
+        .collect::<HashSet<_>>();
+
+    paths
+        .into_iter()
+        .filter(|path| !ignored.contains(path.to_string_lossy().as_ref()))
+        .collect()
+}
+
 /// Add a file to the context collection.
 ///
 /// This method:
 /// 1. Reads the content of the file
-/// 2. Adds the (filename, content) pair to the context collection
+/// 2. Adds the filename to `binary_files` if the content is binary
+/// 3. Otherwise adds the (filename, content) pair to the context collection
 ///
 /// # Arguments
 /// * `path` - The path to the file
 /// * `context_files` - The collection to add the file to
+/// * `binary_files` - The collection to add the filename to if the file is binary
 ///
 /// # Returns
 /// A Result indicating success or an error
-async fn add_file_to_context(os: &Os, path: &Path, context_files: &mut Vec<(String, String)>) -> Result<()> {
+async fn add_file_to_context(
+    os: &Os,
+    path: &Path,
+    context_files: &mut Vec<(String, String)>,
+    binary_files: &mut Vec<String>,
+) -> Result<()> {
     let filename = path.to_string_lossy().to_string();
-    let content = os.fs.read_to_string(path).await?;
-    context_files.push((filename, content));
+    let content = os.fs.read(path).await?;
+    if is_binary_content(&content) {
+        binary_files.push(filename);
+        return Ok(());
+    }
+
+    context_files.push((filename, String::from_utf8_lossy(&content).into_owned()));
     Ok(())
 }
 
+/// Returns whether `content` looks like binary data rather than text, i.e. the start of it
+/// contains a NUL byte or is mostly not valid UTF-8.
+fn is_binary_content(content: &[u8]) -> bool {
+    const SAMPLE_SIZE: usize = 8 * 1024;
+    const MAX_INVALID_RATIO: f64 = 0.3;
+
+    let sample = &content[..content.len().min(SAMPLE_SIZE)];
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let invalid_bytes = sample.utf8_chunks().map(|chunk| chunk.invalid().len()).sum::<usize>();
+    !sample.is_empty() && invalid_bytes as f64 / sample.len() as f64 > MAX_INVALID_RATIO
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -388,7 +615,9 @@ mod tests {
         os.fs.create_dir_all("test").await?;
         os.fs.write("test/to-include.md", "ha").await?;
         os.fs.write("test/to-drop.md", "long content that exceed limit").await?;
-        manager.add_paths(&os, vec!["test/*.md".to_string()], false).await?;
+        manager
+            .add_paths(&os, vec!["test/*.md".to_string()], false, false)
+            .await?;
 
         let (used, dropped) = manager.collect_context_files_with_limit(&os).await.unwrap();
 
@@ -413,7 +642,9 @@ mod tests {
             "no files should be returned for an empty profile when force is false"
         );
 
-        manager.add_paths(&os, vec!["test/*.md".to_string()], false).await?;
+        manager
+            .add_paths(&os, vec!["test/*.md".to_string()], false, false)
+            .await?;
         let files = manager.get_context_files(&os).await?;
         assert!(files[0].0.ends_with("p1.md"));
         assert_eq!(files[0].1, "p1");
@@ -422,7 +653,7 @@ mod tests {
 
         assert!(
             manager
-                .add_paths(&os, vec!["test/*.txt".to_string()], false)
+                .add_paths(&os, vec!["test/*.txt".to_string()], false, false)
                 .await
                 .is_err(),
             "adding a glob with no matching and without force should fail"
@@ -431,6 +662,115 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_filter_gitignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let git_init = std::process::Command::new("git")
+            .arg("-C")
+            .arg(dir.path())
+            .arg("init")
+            .output();
+        if !git_init.is_ok_and(|output| output.status.success()) {
+            // git is not available
+            return;
+        }
+
+        std::fs::write(dir.path().join(".gitignore"), "node_modules/\n*.log\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("node_modules")).unwrap();
+        let paths = ["main.rs", "debug.log", "node_modules/index.js"]
+            .into_iter()
+            .map(|p| {
+                let path = dir.path().join(p);
+                std::fs::write(&path, "").unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+
+        let filtered = filter_gitignored(dir.path(), paths.clone()).await;
+        assert_eq!(filtered, vec![paths[0].clone()]);
+
+        // Outside of a git repository nothing is filtered
+        let other = tempfile::tempdir().unwrap();
+        let path = other.path().join("debug.log");
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(filter_gitignored(other.path(), vec![path.clone()]).await, vec![path]);
+    }
+
+    #[test]
+    fn test_is_binary_content() {
+        assert!(!is_binary_content(b""));
+        assert!(!is_binary_content("plain text with ünïcödé 👋\n".as_bytes()));
+        assert!(is_binary_content(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(is_binary_content(&[0xff; 64]));
+        // A few stray invalid bytes in otherwise valid text is still text
+        let mut latin1 = b"caf".to_vec();
+        latin1.push(0xe9);
+        latin1.extend_from_slice(b" au lait, s'il vous plait");
+        assert!(!is_binary_content(&latin1));
+    }
+
+    #[tokio::test]
+    async fn test_binary_files_are_skipped() -> Result<()> {
+        let os = Os::new().await.unwrap();
+        let mut manager = create_test_context_manager(None).expect("Failed to create test context manager");
+
+        os.fs.create_dir_all("test").await?;
+        os.fs.write("test/notes.md", "notes").await?;
+        os.fs.write("test/logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").await?;
+
+        let binary_files = manager.add_paths(&os, vec!["test/*".to_string()], false, false).await?;
+        assert_eq!(binary_files.len(), 1);
+        assert!(binary_files[0].ends_with("logo.png"));
+
+        let files = manager.get_context_files(&os).await?;
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with("notes.md"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_file_references() -> Result<()> {
+        let os = Os::new().await.unwrap();
+        os.fs.create_dir_all("src").await?;
+        os.fs.write("src/main.rs", "fn main() {}").await?;
+        os.fs.write("src/lib.rs", "pub mod a;\n".repeat(100)).await?;
+        os.fs.write("src/logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").await?;
+
+        assert_eq!(
+            file_reference_paths(&os, "@src/main.rs, what about @src/*.rs? @ and @x. @README.md!").collect::<Vec<_>>(),
+            vec!["src/main.rs", "src/*.rs", "x", "README.md"]
+        );
+        // A trailing `?` is kept when it is part of a glob
+        assert_eq!(
+            file_reference_paths(&os, "@src/main.rs? @src/main.r? @missing.rs?").collect::<Vec<_>>(),
+            vec!["src/main.rs", "src/main.r?", "missing.rs?"]
+        );
+
+        let refs = collect_file_references(&os, "explain @src/main.rs", 1000).await;
+        assert_eq!(refs.files.len(), 1);
+        assert!(refs.files[0].0.ends_with("src/main.rs"));
+        assert_eq!(refs.files[0].1, "fn main() {}");
+
+        // Globs, duplicates, binary files, and missing files
+        let refs =
+            collect_file_references(&os, "@src/*.rs @src/main.rs @src/logo.png @missing.rs @someone", 1000).await;
+        assert_eq!(refs.files.len(), 2);
+        assert_eq!(refs.binary_files.len(), 1);
+        assert_eq!(refs.errors.len(), 1);
+        assert!(refs.errors[0].contains("missing.rs"));
+
+        // Size limit
+        let refs = collect_file_references(&os, "@src/*.rs", 10).await;
+        assert_eq!(refs.files.len(), 1);
+        assert!(refs.files[0].0.ends_with("src/main.rs"));
+        assert_eq!(refs.dropped_files.len(), 1);
+
+        assert!(is_file_reference(&os, "src/main.rs"));
+        assert!(is_file_reference(&os, "src/*.rs"));
+        assert!(!is_file_reference(&os, "server/prompt"));
+        Ok(())
+    }
+
     #[test]
     fn test_calc_max_context_files_size() {
         assert_eq!(
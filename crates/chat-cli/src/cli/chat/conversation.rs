@@ -33,8 +33,10 @@ use super::context::{
 use super::line_tracker::FileLineTracker;
 use super::message::{
     AssistantMessage,
+    MessageHeaders,
     ToolUseResult,
     UserMessage,
+    build_git_context,
 };
 use super::parser::RequestMetadata;
 use super::token_counter::{
@@ -53,6 +55,7 @@ use super::util::serde_value_to_document;
 use crate::api_client::model::{
     ChatMessage,
     ConversationState as FigConversationState,
+    GitContext,
     ImageBlock,
     Tool,
     ToolInputSchema,
@@ -65,6 +68,7 @@ use crate::cli::agent::hook::{
     HookTrigger,
 };
 use crate::cli::chat::ChatError;
+use crate::database::settings::Setting;
 use crate::mcp_client::Prompt;
 use crate::os::Os;
 
@@ -77,6 +81,49 @@ pub struct HistoryEntry {
     assistant: AssistantMessage,
     #[serde(default)]
     request_metadata: Option<RequestMetadata>,
+    /// Whether this entry was pinned with `/pin`. Pinned entries, along with the tool uses they
+    /// started, are never dropped when the history is trimmed or compacted.
+    #[serde(default)]
+    pinned: bool,
+}
+
+impl HistoryEntry {
+    pub fn user(&self) -> &UserMessage {
+        &self.user
+    }
+
+    pub fn assistant(&self) -> &AssistantMessage {
+        &self.assistant
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /// Whether this entry starts a new user turn, i.e. the user message is a prompt rather than
+    /// tool results.
+    pub fn is_prompt(&self) -> bool {
+        !self.user.has_tool_use_results()
+    }
+}
+
+/// The history and summary erased by [ConversationState::clear].
+#[derive(Debug, Clone)]
+struct ClearedHistory {
+    history: VecDeque<HistoryEntry>,
+    /// [None] if the summary was preserved or there was none.
+    summary: Option<(String, RequestMetadata)>,
+}
+
+/// The git context sent with the requests of a user turn.
+#[derive(Debug, Clone, Default)]
+enum TurnGitContext {
+    /// Not looked up yet this turn.
+    #[default]
+    NotFetched,
+    /// Looked up, but the working directory is not in a git repository.
+    Absent,
+    Present(Box<GitContext>),
 }
 
 /// Tracks state related to an ongoing conversation.
@@ -115,6 +162,18 @@ pub struct ConversationState {
     /// Maps from a file path to [FileLineTracker]
     #[serde(default)]
     pub file_line_tracker: HashMap<String, FileLineTracker>,
+    /// The exact content of the last user message sent to the model, including all delimiters
+    /// and context entries. Shown with `/debug last-request`.
+    #[serde(skip)]
+    last_request_content: Option<String>,
+    /// The history and summary erased by the last [Self::clear], restored with
+    /// [Self::undo_clear] until the next message is sent.
+    #[serde(skip)]
+    cleared: Option<ClearedHistory>,
+    /// The git context sent with every request of the current user turn, looked up on the first
+    /// request of the turn.
+    #[serde(skip)]
+    turn_git_context: TurnGitContext,
 }
 
 impl ConversationState {
@@ -157,6 +216,9 @@ impl ConversationState {
             agents,
             model: current_model_id,
             file_line_tracker: HashMap::new(),
+            last_request_content: None,
+            cleared: None,
+            turn_git_context: TurnGitContext::NotFetched,
         }
     }
 
@@ -168,13 +230,60 @@ impl ConversationState {
         &self.history
     }
 
-    /// Clears the conversation history and optionally the summary.
+    /// Pins or unpins the user prompt at `index` in the history, or the most recent user prompt if
+    /// `index` is [None]. Returns the affected entry, or [None] if there is no such prompt.
+    pub fn set_pinned(&mut self, index: Option<usize>, pinned: bool) -> Option<&HistoryEntry> {
+        let index = match index {
+            Some(index) => Some(index).filter(|i| self.history.get(*i).is_some_and(HistoryEntry::is_prompt)),
+            None => self.history.iter().rposition(HistoryEntry::is_prompt),
+        }?;
+        let entry = &mut self.history[index];
+        entry.pinned = pinned;
+        Some(entry)
+    }
+
+    /// Removes the history entry at `index` together with the entries it is linked to through tool
+    /// uses and their results, since the backend rejects tool results without the tool use they
+    /// answer and vice versa. Returns the removed entries, or [None] if there is no such entry.
+    pub fn forget(&mut self, index: usize) -> Option<Vec<HistoryEntry>> {
+        if index >= self.history.len() {
+            return None;
+        }
+
+        let range = tool_use_chain(&self.history, index);
+        // Tool results waiting to be sent would be orphaned by removing the tool uses they answer.
+        if range.end == self.history.len() && self.next_message.as_ref().is_some_and(|m| m.has_tool_use_results()) {
+            self.next_message = None;
+        }
+        let removed = self.history.drain(range).collect();
+        self.enforce_conversation_invariants();
+        Some(removed)
+    }
+
+    /// Clears the conversation history and optionally the summary. They can be restored with
+    /// [Self::undo_clear] until the next message is sent.
     pub fn clear(&mut self, preserve_summary: bool) {
         self.next_message = None;
-        self.history.clear();
-        if !preserve_summary {
-            self.latest_summary = None;
+        let history = std::mem::take(&mut self.history);
+        let summary = if preserve_summary {
+            None
+        } else {
+            self.latest_summary.take()
+        };
+        self.cleared = Some(ClearedHistory { history, summary });
+    }
+
+    /// Restores the history erased by the last [Self::clear]. Returns false if there is nothing to
+    /// restore, i.e. nothing was cleared or a message was sent since.
+    pub fn undo_clear(&mut self) -> bool {
+        let Some(ClearedHistory { history, summary }) = self.cleared.take() else {
+            return false;
+        };
+        self.history = history;
+        if summary.is_some() {
+            self.latest_summary = summary;
         }
+        true
     }
 
     /// Appends a collection prompts into history and returns the last message in the collection.
@@ -204,6 +313,7 @@ impl ConversationState {
                     user,
                     assistant,
                     request_metadata: None,
+                    pinned: false,
                 });
             }
         }
@@ -233,6 +343,8 @@ impl ConversationState {
 
         let msg = UserMessage::new_prompt(input);
         self.next_message = Some(msg);
+        self.cleared = None;
+        self.turn_git_context = TurnGitContext::NotFetched;
     }
 
     /// Sets the response message according to the currently set [Self::next_message].
@@ -244,12 +356,14 @@ impl ConversationState {
     ) {
         debug_assert!(self.next_message.is_some(), "next_message should exist");
         let next_user_message = self.next_message.take().expect("next user message should exist");
+        self.cleared = None;
 
         self.append_assistant_transcript(&message);
         self.history.push_back(HistoryEntry {
             user: next_user_message,
             assistant: message,
             request_metadata,
+            pinned: false,
         });
 
         if let Ok(cwd) = std::env::current_dir() {
@@ -257,6 +371,21 @@ impl ConversationState {
         }
     }
 
+    /// Adds an exchange of `prompt` and `response` to the history without sending a request. The
+    /// entry is trimmed, compacted and counted towards the context window like any other.
+    ///
+    /// Fails if a user message is already waiting to be sent.
+    pub fn push_synthetic_response(&mut self, os: &mut Os, prompt: String, response: String) -> Result<(), ChatError> {
+        if self.next_message.is_some() {
+            return Err(ChatError::Custom(
+                "can't add a response while a user message is waiting to be sent".into(),
+            ));
+        }
+        self.next_message = Some(UserMessage::new_prompt(prompt));
+        self.push_assistant_message(os, AssistantMessage::new_response(None, response), None);
+        Ok(())
+    }
+
     /// Returns the conversation id.
     pub fn conversation_id(&self) -> &str {
         self.conversation_id.as_ref()
@@ -292,9 +421,16 @@ impl ConversationState {
     ///    are dropped.
     /// 3. If the last message from the assistant contains tool results, and a next user message is
     ///    set without tool results, then the user message will have "cancelled" tool results.
+    /// 4. The history fits within the model's context window, leaving room for the next user
+    ///    message. Oldest messages are dropped.
     pub fn enforce_conversation_invariants(&mut self) {
-        self.valid_history_range =
-            enforce_conversation_invariants(&mut self.history, &mut self.next_message, &self.tools);
+        let max_history_chars = max_history_chars(self.model.as_deref(), self.next_message.as_ref());
+        self.valid_history_range = enforce_conversation_invariants(
+            &mut self.history,
+            &mut self.next_message,
+            &self.tools,
+            max_history_chars,
+        );
     }
 
     /// Here we also need to make sure that the tool result corresponds to one of the tools
@@ -313,6 +449,32 @@ impl ConversationState {
         enforce_tool_use_history_invariants(&mut self.history, &self.tools);
     }
 
+    /// Attaches `images` to the next user message, if any.
+    pub fn attach_images(&mut self, images: Vec<ImageBlock>) {
+        if let Some(next_message) = self.next_message.as_mut().filter(|_| !images.is_empty()) {
+            next_message.images.get_or_insert_with(Vec::new).extend(images);
+        }
+    }
+
+    /// Attaches the contents of the files referenced in the next user message with `@path`.
+    pub fn attach_file_references(&mut self, os: &Os, files: &[(String, String)]) {
+        let Some(next_message) = self.next_message.as_mut() else {
+            return;
+        };
+
+        let headers = MessageHeaders::from_settings(&os.database.settings);
+        let mut file_context = String::new();
+        if !files.is_empty() {
+            file_context.push_str(&headers.context_start);
+            file_context.push_str("These files were referenced in my prompt:\n\n");
+            for (filename, content) in files {
+                file_context.push_str(&format!("[{}]\n{}\n", filename, content));
+            }
+            file_context.push_str(&headers.context_end);
+        }
+        next_message.file_context = file_context;
+    }
+
     pub fn add_tool_results(&mut self, tool_results: Vec<ToolUseResult>) {
         debug_assert!(self.next_message.is_none());
         self.next_message = Some(UserMessage::new_tool_use_results(tool_results));
@@ -361,9 +523,36 @@ impl ConversationState {
             .ok();
         }
 
-        Ok(context
+        let state = context
             .into_fig_conversation_state()
-            .expect("unable to construct conversation state"))
+            .expect("unable to construct conversation state");
+        self.last_request_content = Some(state.user_input_message.content.clone());
+        Ok(state)
+    }
+
+    /// Returns the exact content of the last user message sent with
+    /// [Self::as_sendable_conversation_state], if any.
+    pub fn last_request_content(&self) -> Option<&str> {
+        self.last_request_content.as_deref()
+    }
+
+    /// Returns the git context of the current working directory, looked up once per user turn so
+    /// that tool use follow-ups don't run git again.
+    async fn turn_git_context(&mut self) -> Option<Box<GitContext>> {
+        if matches!(self.turn_git_context, TurnGitContext::NotFetched) {
+            let git_context = match std::env::current_dir() {
+                Ok(dir) => build_git_context(&dir).await,
+                Err(_) => None,
+            };
+            self.turn_git_context = match git_context {
+                Some(git_context) => TurnGitContext::Present(Box::new(git_context)),
+                None => TurnGitContext::Absent,
+            };
+        }
+        match &self.turn_git_context {
+            TurnGitContext::Present(git_context) => Some(git_context.clone()),
+            TurnGitContext::NotFetched | TurnGitContext::Absent => None,
+        }
     }
 
     pub async fn update_state(&mut self, force_update: bool) {
@@ -406,24 +595,30 @@ impl ConversationState {
         self.update_state(false).await;
         self.enforce_conversation_invariants();
 
+        let headers = MessageHeaders::from_settings(&os.database.settings);
+
         // Run hooks and add to conversation start and next user message.
         let mut agent_spawn_context = None;
         if let Some(cm) = self.context_manager.as_mut() {
             let user_prompt = self.next_message.as_ref().and_then(|m| m.prompt());
             let agent_spawn = cm.run_hooks(HookTrigger::AgentSpawn, output, user_prompt).await?;
-            agent_spawn_context = format_hook_context(&agent_spawn, HookTrigger::AgentSpawn);
+            agent_spawn_context = format_hook_context(&agent_spawn, HookTrigger::AgentSpawn, &headers);
 
             if let (true, Some(next_message)) = (run_perprompt_hooks, self.next_message.as_mut()) {
                 let per_prompt = cm
                     .run_hooks(HookTrigger::UserPromptSubmit, output, next_message.prompt())
                     .await?;
-                if let Some(ctx) = format_hook_context(&per_prompt, HookTrigger::UserPromptSubmit) {
+                if let Some(ctx) = format_hook_context(&per_prompt, HookTrigger::UserPromptSubmit, &headers) {
                     next_message.additional_context = ctx;
                 }
             }
         }
 
-        let (context_messages, dropped_context_files) = self.context_messages(os, agent_spawn_context).await;
+        let (context_messages, dropped_context_files) = self.context_messages(os, &headers, agent_spawn_context).await;
+        let git_context = match os.database.settings.get_bool(Setting::ChatSendGitContext) {
+            Some(true) => self.turn_git_context().await,
+            _ => None,
+        };
 
         Ok(BackendConversationState {
             conversation_id: self.conversation_id.as_str(),
@@ -435,9 +630,37 @@ impl ConversationState {
             dropped_context_files,
             tools: &self.tools,
             model_id: self.model.as_deref(),
+            send_env_context: os
+                .database
+                .settings
+                .get_bool(Setting::ChatSendEnvContext)
+                .unwrap_or(true),
+            git_context,
+            headers,
         })
     }
 
+    /// Returns a [FigConversationState] that sends `prompt` to `model_id` on top of the current
+    /// history, without adding the prompt to the conversation.
+    pub async fn create_comparison_request(
+        &mut self,
+        os: &Os,
+        prompt: &str,
+        model_id: &str,
+        stderr: &mut impl Write,
+    ) -> Result<FigConversationState, ChatError> {
+        let model = self.model.replace(model_id.to_string());
+        let next_message = self.next_message.replace(UserMessage::new_prompt(prompt.to_string()));
+        let state = self.backend_conversation_state(os, false, stderr).await.map(|context| {
+            context
+                .into_fig_conversation_state()
+                .expect("unable to construct conversation state")
+        });
+        self.model = model;
+        self.next_message = next_message;
+        state
+    }
+
     /// Returns a [FigConversationState] capable of replacing the history of the current
     /// conversation with a summary generated by the model.
     ///
@@ -491,14 +714,15 @@ impl ConversationState {
                         FILTER OUT CHAT CONVENTIONS (greetings, offers to help, etc).".to_string()
             },
         };
+        let headers = MessageHeaders::from_settings(&os.database.settings);
         if let Some((summary, _)) = &self.latest_summary {
             summary_content.push_str("\n\n");
-            summary_content.push_str(CONTEXT_ENTRY_START_HEADER);
+            summary_content.push_str(&headers.context_start);
             summary_content.push_str("This summary contains ALL relevant information from our previous conversation including tool uses, results, code analysis, and file operations. YOU MUST be sure to include this information when creating your summarization document.\n\n");
             summary_content.push_str("SUMMARY CONTENT:\n");
             summary_content.push_str(summary);
             summary_content.push('\n');
-            summary_content.push_str(CONTEXT_ENTRY_END_HEADER);
+            summary_content.push_str(&headers.context_end);
         }
 
         let conv_state = self.backend_conversation_state(os, false, &mut vec![]).await?;
@@ -526,14 +750,16 @@ impl ConversationState {
             ToolOrigin::McpServer(_) => false,
         });
 
-        enforce_conversation_invariants(&mut history, &mut summary_message, &tools);
+        let max_history_chars = max_history_chars(self.model.as_deref(), summary_message.as_ref());
+        enforce_conversation_invariants(&mut history, &mut summary_message, &tools, max_history_chars);
 
         Ok(FigConversationState {
             conversation_id: Some(self.conversation_id.clone()),
             user_input_message: summary_message
                 .unwrap_or(UserMessage::new_prompt(summary_content)) // should not happen
-                .into_user_input_message(self.model.clone(), &tools),
-            history: Some(flatten_history(history.iter())),
+                .into_user_input_message(self.model.clone(), &tools, &headers),
+            history: Some(flatten_history(history.iter(), &headers)),
+            agent_mode: None,
         })
     }
 
@@ -545,8 +771,16 @@ impl ConversationState {
         strategy: CompactStrategy,
         request_metadata: RequestMetadata,
     ) {
-        self.history
-            .drain(..(self.history.len().saturating_sub(strategy.messages_to_exclude)));
+        let summarized_len = self.history.len().saturating_sub(strategy.messages_to_exclude);
+        let mut in_pinned_turn = false;
+        let mut index = 0;
+        self.history.retain(|entry| {
+            if entry.is_prompt() {
+                in_pinned_turn = entry.pinned;
+            }
+            index += 1;
+            index > summarized_len || in_pinned_turn
+        });
         self.latest_summary = Some((summary, request_metadata));
     }
 
@@ -569,17 +803,18 @@ impl ConversationState {
     async fn context_messages(
         &mut self,
         os: &Os,
+        headers: &MessageHeaders,
         additional_context: Option<String>,
     ) -> (Option<Vec<HistoryEntry>>, Vec<(String, String)>) {
         let mut context_content = String::new();
         let mut dropped_context_files = Vec::new();
         if let Some((summary, _)) = &self.latest_summary {
-            context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+            context_content.push_str(&headers.context_start);
             context_content.push_str("This summary contains ALL relevant information from our previous conversation including tool uses, results, code analysis, and file operations. YOU MUST reference this information when answering questions and explicitly acknowledge specific details from the summary when they're relevant to the current question.\n\n");
             context_content.push_str("SUMMARY CONTENT:\n");
             context_content.push_str(summary);
             context_content.push('\n');
-            context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+            context_content.push_str(&headers.context_end);
         }
 
         // Add context files if available
@@ -591,11 +826,11 @@ impl ConversationState {
                     }
 
                     if !files_to_use.is_empty() {
-                        context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+                        context_content.push_str(&headers.context_start);
                         for (filename, content) in files_to_use {
                             context_content.push_str(&format!("[{}]\n{}\n", filename, content));
                         }
-                        context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+                        context_content.push_str(&headers.context_end);
                     }
                 },
                 Err(e) => {
@@ -621,6 +856,7 @@ impl ConversationState {
                     user,
                     assistant,
                     request_metadata: None,
+                    pinned: false,
                 }]),
                 dropped_context_files,
             )
@@ -634,6 +870,20 @@ impl ConversationState {
         self.context_message_length
     }
 
+    /// The number of characters in the request for the next user message: the sendable history,
+    /// the next message with its tool results, and the context sent with it. Images are not
+    /// counted.
+    pub fn request_char_count(&self) -> CharCount {
+        let history = self
+            .history
+            .range(self.valid_history_range.0..self.valid_history_range.1)
+            .fold(0, |acc, HistoryEntry { user, assistant, .. }| {
+                acc + *user.char_count() + *assistant.char_count()
+            });
+        let next_message = self.next_message.as_ref().map_or(0, |message| *message.char_count());
+        (history + next_message + self.context_message_length.unwrap_or_default()).into()
+    }
+
     /// Calculate the total character count in the conversation
     pub async fn calculate_char_count(&mut self, os: &Os) -> Result<CharCount, ChatError> {
         Ok(self
@@ -692,21 +942,53 @@ pub struct BackendConversationStateImpl<'a, T, U> {
     pub dropped_context_files: Vec<(String, String)>,
     pub tools: &'a HashMap<ToolOrigin, Vec<Tool>>,
     pub model_id: Option<&'a str>,
+    /// Whether the user's operating system and current working directory are sent with each
+    /// message.
+    pub send_env_context: bool,
+    /// The branch, dirty flag, and last commit of the git repository containing the current
+    /// working directory, sent with the next message as part of its env context if enabled.
+    pub git_context: Option<Box<GitContext>>,
+    /// The delimiters used to assemble the content of the user messages.
+    pub headers: MessageHeaders,
 }
 
 impl BackendConversationStateImpl<'_, std::collections::vec_deque::Iter<'_, HistoryEntry>, Option<Vec<HistoryEntry>>> {
     fn into_fig_conversation_state(self) -> eyre::Result<FigConversationState> {
-        let history = flatten_history(self.context_messages.unwrap_or_default().iter().chain(self.history));
-        let user_input_message: UserInputMessage = self
+        let mut history = flatten_history(
+            self.context_messages.unwrap_or_default().iter().chain(self.history),
+            &self.headers,
+        );
+        let mut user_input_message: UserInputMessage = self
             .next_user_message
             .cloned()
-            .map(|msg| msg.into_user_input_message(self.model_id.map(str::to_string), self.tools))
+            .map(|msg| msg.into_user_input_message(self.model_id.map(str::to_string), self.tools, &self.headers))
             .ok_or(eyre::eyre!("next user message is not set"))?;
 
+        if !self.send_env_context {
+            let messages = history.iter_mut().filter_map(|msg| match msg {
+                ChatMessage::UserInputMessage(msg) => Some(msg),
+                ChatMessage::AssistantResponseMessage(_) => None,
+            });
+            for msg in messages.chain(std::iter::once(&mut user_input_message)) {
+                if let Some(ctx) = msg.user_input_message_context.as_mut() {
+                    ctx.env_state = None;
+                }
+            }
+        } else if let Some(git_context) = self.git_context {
+            let env_state = user_input_message
+                .user_input_message_context
+                .as_mut()
+                .and_then(|ctx| ctx.env_state.as_mut());
+            if let Some(env_state) = env_state {
+                env_state.git = Some(git_context);
+            }
+        }
+
         Ok(FigConversationState {
             conversation_id: Some(self.conversation_id.to_string()),
             user_input_message,
             history: Some(history),
+            agent_mode: None,
         })
     }
 
@@ -751,12 +1033,12 @@ pub struct ConversationSize {
 }
 
 /// Converts a list of user/assistant message pairs into a flattened list of ChatMessage.
-fn flatten_history<'a, T>(history: T) -> Vec<ChatMessage>
+fn flatten_history<'a, T>(history: T, headers: &MessageHeaders) -> Vec<ChatMessage>
 where
     T: Iterator<Item = &'a HistoryEntry>,
 {
     history.fold(Vec::new(), |mut acc, HistoryEntry { user, assistant, .. }| {
-        acc.push(ChatMessage::UserInputMessage(user.clone().into_history_entry()));
+        acc.push(ChatMessage::UserInputMessage(user.clone().into_history_entry(headers)));
         acc.push(ChatMessage::AssistantResponseMessage(assistant.clone().into()));
         acc
     })
@@ -785,14 +1067,18 @@ impl From<InputSchema> for ToolInputSchema {
 /// # Returns
 /// [Option::Some] if `hook_results` is not empty and at least one hook has content. Otherwise,
 /// [Option::None]
-fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger: HookTrigger) -> Option<String> {
+fn format_hook_context(
+    hook_results: &[((HookTrigger, Hook), String)],
+    trigger: HookTrigger,
+    headers: &MessageHeaders,
+) -> Option<String> {
     if hook_results.iter().all(|(_, content)| content.is_empty()) {
         return None;
     }
 
     let mut context_content = String::new();
 
-    context_content.push_str(CONTEXT_ENTRY_START_HEADER);
+    context_content.push_str(&headers.context_start);
     context_content.push_str("This section (like others) contains important information that I want you to use in your responses. I have gathered this context from valuable programmatic script hooks. You must follow any requests and consider all of the information in this section");
     if trigger == HookTrigger::AgentSpawn {
         context_content.push_str(" for the entire conversation");
@@ -802,48 +1088,62 @@ fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger:
     for (_, output) in hook_results.iter().filter(|((h_trigger, _), _)| *h_trigger == trigger) {
         context_content.push_str(&format!("{output}\n\n"));
     }
-    context_content.push_str(CONTEXT_ENTRY_END_HEADER);
+    context_content.push_str(&headers.context_end);
     Some(context_content)
 }
 
+/// Returns the number of characters of history that can be sent along with `next_message`
+/// without exceeding the context window of `model_id`.
+///
+/// A quarter of the context window is reserved for context files, tool specifications, and the
+/// response.
+fn max_history_chars(model_id: Option<&str>, next_message: Option<&UserMessage>) -> usize {
+    let window_chars = TokenCounter::token_to_chars(context_window_tokens(model_id));
+    let next_message_chars = next_message.map_or(0, |m| *m.char_count());
+    (window_chars * 3 / 4).saturating_sub(next_message_chars)
+}
+
 fn enforce_conversation_invariants(
     history: &mut VecDeque<HistoryEntry>,
     next_message: &mut Option<UserMessage>,
     tools: &HashMap<ToolOrigin, Vec<Tool>>,
+    max_history_chars: usize,
 ) -> (usize, usize) {
     // First set the valid range as the entire history - this will be truncated as necessary
     // later below.
     let mut valid_history_range = (0, history.len());
 
-    // Trim the conversation history by finding the second oldest message from the user without
-    // tool results - this will be the new oldest message in the history.
+    // Trim the conversation history by dropping the oldest user turn, so that the new oldest
+    // message is from the user without tool results.
     //
     // Note that we reserve extra slots for [ConversationState::context_messages].
-    if (history.len() * 2) > MAX_CONVERSATION_STATE_HISTORY_LEN - 6 {
-        match history
-            .iter()
-            .enumerate()
-            .skip(1)
-            .find(|(_, HistoryEntry { user, .. })| -> bool { !user.has_tool_use_results() })
-            .map(|v| v.0)
-        {
-            Some(i) => {
-                debug!("removing the first {i} user/assistant response pairs in the history");
-                valid_history_range.0 = i;
-            },
-            None => {
-                debug!("no valid starting user message found in the history, clearing");
-                valid_history_range = (0, 0);
-                // Edge case: if the next message contains tool results, then we have to just
-                // abandon them.
-                if next_message.as_ref().is_some_and(|m| m.has_tool_use_results()) {
-                    debug!("abandoning tool results");
-                    *next_message = Some(UserMessage::new_prompt(
-                        "The conversation history has overflowed, clearing state".to_string(),
-                    ));
-                }
-            },
+    if (history.len() * 2) > MAX_CONVERSATION_STATE_HISTORY_LEN - 6
+        && !drop_oldest_turn(history, &mut valid_history_range)
+    {
+        debug!("no valid starting user message found in the history, clearing");
+        valid_history_range = clear_history(history, valid_history_range, next_message);
+    }
+
+    // The message count alone doesn't bound the size of the history, e.g. a few large tool results
+    // can exceed the context window. Keep dropping the oldest turns until the remaining history
+    // fits.
+    loop {
+        let history_chars: usize = history
+            .range(valid_history_range.0..valid_history_range.1)
+            .map(|entry| *entry.user.char_count() + *entry.assistant.char_count())
+            .sum();
+        if history_chars <= max_history_chars {
+            break;
         }
+        if !drop_oldest_turn(history, &mut valid_history_range) {
+            debug!(history_chars, max_history_chars, "history is too large, clearing");
+            valid_history_range = clear_history(history, valid_history_range, next_message);
+            break;
+        }
+        debug!(
+            history_chars,
+            max_history_chars, "history is too large, removed messages before {}", valid_history_range.0
+        );
     }
 
     // If the first message contains tool results, then we add the results to the content field
@@ -902,6 +1202,62 @@ fn enforce_conversation_invariants(
     valid_history_range
 }
 
+/// Returns the index of the oldest user turn in `range` that is not pinned. A turn starts with a
+/// user prompt and includes the tool uses that follow it.
+fn oldest_unpinned_turn(history: &VecDeque<HistoryEntry>, (start, end): (usize, usize)) -> Option<usize> {
+    (start..end).find(|&i| (i == start || history[i].is_prompt()) && !history[i].pinned)
+}
+
+/// Returns the range of entries around `index` that are linked through tool uses: an entry whose
+/// user message contains tool results is linked to the previous entry, whose assistant message made
+/// the tool uses. This always spans the whole user turn that `index` belongs to.
+fn tool_use_chain(history: &VecDeque<HistoryEntry>, index: usize) -> std::ops::Range<usize> {
+    let start = (0..=index).rev().find(|&i| history[i].is_prompt()).unwrap_or(0);
+    let end = (index + 1..history.len())
+        .find(|&i| history[i].is_prompt())
+        .unwrap_or(history.len());
+    start..end
+}
+
+/// Drops the oldest unpinned user turn from the valid history range. Pinned turns before it are
+/// moved after it, so that they stay at the start of the range in their original order.
+///
+/// Returns false if there is no turn that can be dropped while keeping a user prompt as the first
+/// message.
+fn drop_oldest_turn(history: &mut VecDeque<HistoryEntry>, valid_history_range: &mut (usize, usize)) -> bool {
+    let (start, end) = *valid_history_range;
+    let Some(turn_start) = oldest_unpinned_turn(history, *valid_history_range) else {
+        return false;
+    };
+    let Some(turn_end) = (turn_start + 1..end).find(|&i| history[i].is_prompt()) else {
+        return false;
+    };
+
+    let turn_len = turn_end - turn_start;
+    history.make_contiguous()[start..turn_end].rotate_right(turn_len);
+    valid_history_range.0 = start + turn_len;
+    true
+}
+
+/// Drops everything from the valid history range except for the pinned turns at its start. If the
+/// next message contains tool results, they have to be abandoned since the tool uses they refer to
+/// are dropped.
+fn clear_history(
+    history: &VecDeque<HistoryEntry>,
+    valid_history_range: (usize, usize),
+    next_message: &mut Option<UserMessage>,
+) -> (usize, usize) {
+    let (start, end) = valid_history_range;
+    let pinned_end = oldest_unpinned_turn(history, valid_history_range).unwrap_or(end);
+    if pinned_end < end && next_message.as_ref().is_some_and(|m| m.has_tool_use_results()) {
+        debug!("abandoning tool results");
+        *next_message = Some(UserMessage::new_prompt(
+            "The conversation history has overflowed, clearing state".to_string(),
+        ));
+    }
+    (start, pinned_end)
+}
+
 fn enforce_tool_use_history_invariants(history: &mut VecDeque<HistoryEntry>, tools: &HashMap<ToolOrigin, Vec<Tool>>) {
     let tool_names: HashSet<_> = tools
         .values()
@@ -1085,6 +1441,232 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_conversation_state_history_handling_size() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+
+        // A handful of large responses exceeds the context window well before the message count
+        // limit is reached.
+        let large_response = "a".repeat(TokenCounter::token_to_chars(context_window_tokens(None)) / 8);
+        conversation.set_next_user_message("start".to_string()).await;
+        for i in 0..20 {
+            let s = conversation
+                .as_sendable_conversation_state(&os, &mut vec![], true)
+                .await
+                .unwrap();
+            assert_conversation_state_invariants(s, i);
+
+            let (start, end) = conversation.valid_history_range;
+            let history_chars: usize = conversation
+                .history
+                .range(start..end)
+                .map(|entry| *entry.user.char_count() + *entry.assistant.char_count())
+                .sum();
+            let max_chars = max_history_chars(None, conversation.next_message.as_ref());
+            assert!(
+                history_chars <= max_chars,
+                "history of {history_chars} chars exceeds {max_chars} on iteration {i}"
+            );
+
+            conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, large_response.clone()),
+                None,
+            );
+            conversation.set_next_user_message(i.to_string()).await;
+        }
+        assert!(
+            conversation.history.len() < 20,
+            "oldest messages should have been dropped"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conversation_state_history_handling_pinned() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+
+        conversation.set_next_user_message("requirements".to_string()).await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "ok".to_string()), None);
+        conversation.set_next_user_message("other".to_string()).await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "ok".to_string()), None);
+        assert_eq!(
+            conversation.set_pinned(Some(1), true).unwrap().user().prompt(),
+            Some("other")
+        );
+        assert!(conversation.set_pinned(Some(1), false).is_some());
+        assert!(conversation.set_pinned(Some(2), true).is_none());
+        assert!(conversation.set_pinned(Some(0), true).is_some());
+
+        // Large responses push every other message out of the context window.
+        let large_response = "a".repeat(TokenCounter::token_to_chars(context_window_tokens(None)) / 8);
+        conversation.set_next_user_message("start".to_string()).await;
+        for i in 0..20 {
+            let s = conversation
+                .as_sendable_conversation_state(&os, &mut vec![], true)
+                .await
+                .unwrap();
+            assert_conversation_state_invariants(s, i);
+            let front = conversation.history.front().unwrap();
+            assert!(front.is_pinned());
+            assert_eq!(front.user().prompt(), Some("requirements"));
+
+            conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, large_response.clone()),
+                None,
+            );
+            conversation.set_next_user_message(i.to_string()).await;
+        }
+        assert!(
+            conversation
+                .history
+                .iter()
+                .all(|entry| entry.user().prompt() != Some("other"))
+        );
+
+        // Compaction keeps the pinned prompt as well.
+        conversation.replace_history_with_summary(
+            "summary".to_string(),
+            CompactStrategy::default(),
+            RequestMetadata::default(),
+        );
+        assert_eq!(conversation.history.len(), 1);
+        assert_eq!(conversation.history[0].user().prompt(), Some("requirements"));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_state_without_env_context() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+
+        conversation.set_next_user_message("hello".to_string()).await;
+        let s = conversation
+            .as_sendable_conversation_state(&os, &mut vec![], true)
+            .await
+            .unwrap();
+        assert!(format!("{s:?}").contains("current_working_directory: Some"));
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "hi".to_string()), None);
+
+        os.database
+            .settings
+            .set(Setting::ChatSendEnvContext, false)
+            .await
+            .unwrap();
+        conversation.set_next_user_message("again".to_string()).await;
+        let s = conversation
+            .as_sendable_conversation_state(&os, &mut vec![], true)
+            .await
+            .unwrap();
+        assert_eq!(s.history.as_ref().unwrap().len(), 2);
+        assert!(!format!("{s:?}").contains("current_working_directory"));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_state_custom_headers() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+        assert!(conversation.last_request_content().is_none());
+
+        os.database
+            .settings
+            .set(Setting::ChatContextEntryStartHeader, "<context>")
+            .await
+            .unwrap();
+        os.database
+            .settings
+            .set(Setting::ChatUserMessageStartHeader, "<prompt>")
+            .await
+            .unwrap();
+        conversation.set_next_user_message("hello".to_string()).await;
+        conversation.attach_file_references(&os, &[("a.txt".to_string(), "contents".to_string())]);
+        let s = conversation
+            .as_sendable_conversation_state(&os, &mut vec![], true)
+            .await
+            .unwrap();
+
+        let content = conversation.last_request_content().unwrap();
+        assert_eq!(content, s.user_input_message.content);
+        assert!(content.starts_with("<context>\nThese files were referenced in my prompt:"));
+        assert!(content.contains("<context>\nCurrent UTC time: "));
+        assert!(content.contains("<prompt>\nhello"));
+        assert!(!content.contains(CONTEXT_ENTRY_START_HEADER));
+        assert!(content.contains(CONTEXT_ENTRY_END_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_conversation_state_comparison_request() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            Some("model_a".to_string()),
+        )
+        .await;
+
+        conversation.set_next_user_message("hello".to_string()).await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "hi".to_string()), None);
+
+        let state = conversation
+            .create_comparison_request(&os, "which is better?", "model_b", &mut vec![])
+            .await
+            .unwrap();
+        assert!(state.user_input_message.content.contains("which is better?"));
+        assert_eq!(state.user_input_message.model_id.as_deref(), Some("model_b"));
+        assert_eq!(state.history.unwrap().len(), 2);
+
+        // The conversation itself is unchanged.
+        assert_eq!(conversation.model.as_deref(), Some("model_a"));
+        assert!(conversation.next_message.is_none());
+        assert_eq!(conversation.history.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_conversation_state_history_handling_with_tool_results() {
         let mut os = Os::new().await.unwrap();
@@ -1159,6 +1741,146 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_conversation_state_forget() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+
+        // Two turns, each a prompt followed by two tool uses and a final response.
+        for turn in ["first", "second"] {
+            conversation.set_next_user_message(turn.to_string()).await;
+            for i in 0..2 {
+                let id = format!("{turn}_{i}");
+                conversation.push_assistant_message(
+                    &mut os,
+                    AssistantMessage::new_tool_use(None, id.clone(), vec![AssistantToolUse {
+                        id: id.clone(),
+                        name: "tool name".to_string(),
+                        ..Default::default()
+                    }]),
+                    None,
+                );
+                conversation.add_tool_results(vec![ToolUseResult {
+                    tool_use_id: id,
+                    content: vec![],
+                    status: ToolResultStatus::Success,
+                }]);
+            }
+            conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, "done".to_string()),
+                None,
+            );
+        }
+        assert_eq!(conversation.history.len(), 6);
+        assert!(conversation.forget(6).is_none());
+
+        // Forgetting a tool result also forgets the tool use it answers, and the rest of the turn.
+        let removed = conversation.forget(4).unwrap();
+        assert_eq!(removed.len(), 3);
+        assert_eq!(removed[0].user().prompt(), Some("second"));
+        assert!(removed[1].user().has_tool_use_results());
+        assert_eq!(conversation.history.len(), 3);
+
+        // Forgetting a tool use also forgets its results.
+        let removed = conversation.forget(0).unwrap();
+        assert_eq!(removed.len(), 3);
+        assert!(removed[0].assistant().tool_uses().is_some());
+        assert!(removed[1].user().has_tool_use_results());
+        assert!(conversation.history.is_empty());
+
+        conversation.set_next_user_message("third".to_string()).await;
+        let s = conversation
+            .as_sendable_conversation_state(&os, &mut vec![], true)
+            .await
+            .unwrap();
+        assert_conversation_state_invariants(s, 0);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_state_undo_clear() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut vec![]).await.unwrap(),
+            tool_manager,
+            None,
+        )
+        .await;
+        assert!(!conversation.undo_clear());
+
+        conversation.set_next_user_message("hello".to_string()).await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "hi".to_string()), None);
+        conversation.clear(true);
+        assert!(conversation.history.is_empty());
+        assert!(conversation.undo_clear());
+        assert_eq!(conversation.history.len(), 1);
+        assert_eq!(conversation.history[0].user().prompt(), Some("hello"));
+        assert!(!conversation.undo_clear(), "only a single clear can be undone");
+
+        // Sending a message after clearing discards the cleared history.
+        conversation.clear(true);
+        conversation.set_next_user_message("again".to_string()).await;
+        assert!(!conversation.undo_clear());
+        assert!(conversation.history.is_empty());
+    }
+
+    #[test]
+    fn test_tool_use_chain() {
+        let entry = |user: UserMessage, assistant: AssistantMessage| HistoryEntry {
+            user,
+            assistant,
+            request_metadata: None,
+            pinned: false,
+        };
+        let tool_use = || {
+            AssistantMessage::new_tool_use(None, String::new(), vec![AssistantToolUse {
+                id: "id".to_string(),
+                ..Default::default()
+            }])
+        };
+        let tool_result = || {
+            UserMessage::new_tool_use_results(vec![ToolUseResult {
+                tool_use_id: "id".to_string(),
+                content: vec![],
+                status: ToolResultStatus::Success,
+            }])
+        };
+        let history = VecDeque::from([
+            entry(
+                UserMessage::new_prompt("a".to_string()),
+                AssistantMessage::new_response(None, String::new()),
+            ),
+            entry(UserMessage::new_prompt("b".to_string()), tool_use()),
+            entry(tool_result(), tool_use()),
+            entry(tool_result(), AssistantMessage::new_response(None, String::new())),
+            entry(
+                UserMessage::new_prompt("c".to_string()),
+                AssistantMessage::new_response(None, String::new()),
+            ),
+        ]);
+
+        assert_eq!(tool_use_chain(&history, 0), 0..1);
+        for i in 1..=3 {
+            assert_eq!(tool_use_chain(&history, i), 1..4);
+        }
+        assert_eq!(tool_use_chain(&history, 4), 4..5);
+    }
+
     #[tokio::test]
     async fn test_conversation_state_with_context_files() {
         let mut os = Os::new().await.unwrap();
@@ -0,0 +1,384 @@
+//! Rendering of the conversation history into formats that can be shared with people who do not
+//! use q chat.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+
+use chrono::Utc;
+use syntect::highlighting::{
+    Theme,
+    ThemeSet,
+};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use super::message::{
+    AssistantMessage,
+    AssistantToolUse,
+    ToolUseResult,
+    ToolUseResultBlock,
+    UserMessage,
+    UserMessageContent,
+};
+use crate::api_client::model::ToolResultStatus;
+
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME: LazyLock<Theme> = LazyLock::new(|| {
+    let mut themes = ThemeSet::load_defaults().themes;
+    themes.remove("InspiredGitHub").unwrap_or_default()
+});
+
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; max-width: 960px; margin: 2em auto; padding: 0 1em; color: #1f2328; line-height: 1.5; }
+h1 { font-size: 1.5em; margin-bottom: 0; }
+.meta { color: #656d76; font-size: 0.85em; }
+.message { border: 1px solid #d0d7de; border-radius: 6px; margin: 1em 0; padding: 0.5em 1em; }
+.user { background: #f6f8fa; }
+.role { font-weight: bold; }
+.role .time { color: #656d76; font-weight: normal; font-size: 0.85em; margin-left: 0.5em; }
+.text { white-space: pre-wrap; overflow-wrap: anywhere; }
+pre { padding: 0.75em; border-radius: 6px; overflow-x: auto; border: 1px solid #d0d7de; }
+.tool-use, details { margin: 0.5em 0; }
+summary { cursor: pointer; }
+.error { color: #cf222e; }
+"#;
+
+/// Renders a conversation as a self-contained HTML document.
+///
+/// Code blocks in messages are syntax highlighted with inline styles, and tool results are
+/// collapsible. All message content is escaped so it cannot break the document structure.
+pub fn to_html<'a>(
+    conversation_id: &str,
+    history: impl IntoIterator<Item = (&'a UserMessage, &'a AssistantMessage)>,
+) -> String {
+    let mut html = String::new();
+    // Maps tool use ids to tool names so results can be labeled with the tool they came from.
+    let mut tool_names = HashMap::<&str, &str>::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Amazon Q conversation</title>\n\
+         <style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>Amazon Q conversation</h1>\n\
+         <p class=\"meta\">Conversation {} exported {}</p>\n",
+        escape_html(conversation_id),
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    for (user, assistant) in history {
+        let (prompt, tool_use_results) = split_user_content(user);
+        for result in tool_use_results {
+            push_tool_use_result_html(&mut html, result, tool_names.get(result.tool_use_id.as_str()).copied());
+        }
+
+        if let Some(prompt) = prompt {
+            let _ = write!(
+                html,
+                "<div class=\"message user\">\n<div class=\"role\">You<span class=\"time\">{}</span></div>\n",
+                user.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            push_text_html(&mut html, prompt);
+            if let Some(images) = user.images.as_ref().filter(|images| !images.is_empty()) {
+                let _ = writeln!(html, "<p class=\"meta\">[{} image(s) attached]</p>", images.len());
+            }
+            html.push_str("</div>\n");
+        }
+
+        html.push_str("<div class=\"message assistant\">\n<div class=\"role\">Amazon Q</div>\n");
+        push_text_html(&mut html, assistant.content());
+        for tool_use in assistant.tool_uses().unwrap_or_default() {
+            let name = tool_use_name(tool_use);
+            tool_names.insert(tool_use.id.as_str(), name);
+            let _ = writeln!(html, "<div class=\"tool-use\">Using tool <b>{}</b>", escape_html(name));
+            push_code_html(&mut html, &tool_use_args(tool_use), Some("json"));
+            html.push_str("</div>\n");
+        }
+        html.push_str("</div>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders a conversation as markdown, e.g. for pasting into pull request descriptions.
+///
+/// Tool uses and their results are rendered as fenced code blocks. Images are not embedded, a
+/// placeholder noting how many were attached is written instead.
+pub fn to_markdown<'a>(
+    conversation_id: &str,
+    history: impl IntoIterator<Item = (&'a UserMessage, &'a AssistantMessage)>,
+) -> String {
+    let mut md = String::new();
+    let mut tool_names = HashMap::<&str, &str>::new();
+
+    let _ = writeln!(
+        md,
+        "# Amazon Q conversation\n\n_Conversation {conversation_id} exported {}_",
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    for (user, assistant) in history {
+        let (prompt, tool_use_results) = split_user_content(user);
+        for result in tool_use_results {
+            let status = match result.status {
+                ToolResultStatus::Success => "Result",
+                ToolResultStatus::Error => "Error",
+            };
+            let name = tool_names
+                .get(result.tool_use_id.as_str())
+                .copied()
+                .unwrap_or(&result.tool_use_id);
+            let _ = writeln!(md, "\n**{status} of `{name}`:**\n");
+            for block in &result.content {
+                match block {
+                    ToolUseResultBlock::Json(value) => {
+                        push_code_markdown(
+                            &mut md,
+                            &serde_json::to_string_pretty(value).unwrap_or_default(),
+                            "json",
+                        );
+                    },
+                    ToolUseResultBlock::Text(text) => push_code_markdown(&mut md, text, ""),
+                }
+            }
+        }
+
+        if let Some(prompt) = prompt {
+            let _ = writeln!(
+                md,
+                "\n---\n\n## User\n\n_{}_\n\n{}",
+                user.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                prompt.trim()
+            );
+            if let Some(images) = user.images.as_ref().filter(|images| !images.is_empty()) {
+                let _ = writeln!(md, "\n_[{} image(s) attached]_", images.len());
+            }
+            md.push_str("\n---\n\n## Amazon Q\n");
+        }
+
+        let content = assistant.content().trim();
+        if !content.is_empty() {
+            let _ = writeln!(md, "\n{content}");
+        }
+        for tool_use in assistant.tool_uses().unwrap_or_default() {
+            let name = tool_use_name(tool_use);
+            tool_names.insert(tool_use.id.as_str(), name);
+            let _ = writeln!(md, "\n**Using tool `{name}`:**\n");
+            push_code_markdown(&mut md, &tool_use_args(tool_use), "json");
+        }
+    }
+
+    md
+}
+
+/// Returns the prompt the user typed, if any, and the tool results sent with the message.
+fn split_user_content(user: &UserMessage) -> (Option<&str>, &[ToolUseResult]) {
+    match &user.content {
+        UserMessageContent::Prompt { prompt } => (Some(prompt.as_str()), &[]),
+        UserMessageContent::CancelledToolUses {
+            prompt,
+            tool_use_results,
+        } => (prompt.as_deref(), tool_use_results.as_slice()),
+        UserMessageContent::ToolUseResults { tool_use_results } => (None, tool_use_results.as_slice()),
+    }
+}
+
+/// The name of the tool as the user knows it.
+fn tool_use_name(tool_use: &AssistantToolUse) -> &str {
+    if tool_use.orig_name.is_empty() {
+        tool_use.name.as_str()
+    } else {
+        tool_use.orig_name.as_str()
+    }
+}
+
+/// The arguments of the tool use as the user knows them, pretty printed.
+fn tool_use_args(tool_use: &AssistantToolUse) -> String {
+    let args = if tool_use.orig_args.is_null() {
+        &tool_use.args
+    } else {
+        &tool_use.orig_args
+    };
+    serde_json::to_string_pretty(args).unwrap_or_default()
+}
+
+/// Appends a fenced code block, using a fence longer than any backtick run in `code` so the
+/// content cannot terminate the block early.
+fn push_code_markdown(md: &mut String, code: &str, lang: &str) {
+    let longest_backtick_run = code.split(|c| c != '`').map(str::len).max().unwrap_or_default();
+    let fence = "`".repeat(longest_backtick_run.max(2) + 1);
+    let _ = writeln!(md, "{fence}{lang}\n{}\n{fence}", code.trim_end_matches('\n'));
+}
+
+/// Appends a tool result as a collapsed `<details>` element.
+fn push_tool_use_result_html(html: &mut String, result: &ToolUseResult, tool_name: Option<&str>) {
+    let (class, status) = match result.status {
+        ToolResultStatus::Success => ("", "result"),
+        ToolResultStatus::Error => (" class=\"error\"", "error"),
+    };
+    let _ = writeln!(
+        html,
+        "<details>\n<summary{class}>Tool {status}: {}</summary>",
+        escape_html(tool_name.unwrap_or(&result.tool_use_id))
+    );
+    for block in &result.content {
+        match block {
+            ToolUseResultBlock::Json(value) => {
+                push_code_html(
+                    html,
+                    &serde_json::to_string_pretty(value).unwrap_or_default(),
+                    Some("json"),
+                );
+            },
+            ToolUseResultBlock::Text(text) => push_code_html(html, text, None),
+        }
+    }
+    html.push_str("</details>\n");
+}
+
+/// Appends message text, highlighting fenced code blocks and escaping everything else.
+fn push_text_html(html: &mut String, text: &str) {
+    let mut prose = String::new();
+    let mut code: Option<(String, String)> = None;
+
+    for line in text.split_inclusive('\n') {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (None, Some(lang)) => {
+                push_prose_html(html, &prose);
+                prose.clear();
+                code = Some((lang.trim().to_string(), String::new()));
+            },
+            (Some((lang, content)), Some(_)) => {
+                push_code_html(html, content, Some(lang.as_str()).filter(|lang| !lang.is_empty()));
+                code = None;
+            },
+            (Some((_, content)), None) => content.push_str(line),
+            (None, None) => prose.push_str(line),
+        }
+    }
+
+    // An unterminated code block is still rendered as code
+    if let Some((lang, content)) = code {
+        push_code_html(html, &content, Some(lang.as_str()).filter(|lang| !lang.is_empty()));
+    }
+    push_prose_html(html, &prose);
+}
+
+fn push_prose_html(html: &mut String, prose: &str) {
+    let prose = prose.trim();
+    if !prose.is_empty() {
+        let _ = writeln!(html, "<div class=\"text\">{}</div>", escape_html(prose));
+    }
+}
+
+/// Appends a `<pre>` block, syntax highlighted if `lang` is known.
+fn push_code_html(html: &mut String, code: &str, lang: Option<&str>) {
+    let highlighted = lang
+        .and_then(|lang| SYNTAX_SET.find_syntax_by_token(lang))
+        .and_then(|syntax| highlighted_html_for_string(code, &SYNTAX_SET, syntax, &THEME).ok());
+    match highlighted {
+        Some(highlighted) => html.push_str(&highlighted),
+        None => {
+            let _ = writeln!(html, "<pre>{}</pre>", escape_html(code));
+        },
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_html() {
+        let user = UserMessage::new_prompt("Is <script>alert(1)</script> safe?".to_string());
+        let assistant = AssistantMessage::new_tool_use(
+            None,
+            "Let me check.\n```rust\nfn main() {}\n```\nDone".to_string(),
+            vec![AssistantToolUse {
+                id: "tool_1".to_string(),
+                name: "fs_read".to_string(),
+                args: serde_json::json!({ "path": "</pre>" }),
+                ..Default::default()
+            }],
+        );
+        let results = UserMessage::new_tool_use_results(vec![ToolUseResult {
+            tool_use_id: "tool_1".to_string(),
+            content: vec![ToolUseResultBlock::Text("</details><b>bold</b>".to_string())],
+            status: ToolResultStatus::Success,
+        }]);
+        let response = AssistantMessage::new_response(None, "All good".to_string());
+
+        let html = to_html("conv<1>", [(&user, &assistant), (&results, &response)]);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Conversation conv&lt;1&gt;"));
+        assert!(html.contains("Is &lt;script&gt;alert(1)&lt;/script&gt; safe?"));
+        assert!(!html.contains("<script>"));
+        // Highlighted code blocks use inline styles
+        assert!(html.contains("<pre style="));
+        assert!(html.contains("Let me check."));
+        assert!(html.contains("Done"));
+        assert!(html.contains("Using tool <b>fs_read</b>"));
+        assert!(html.contains("<summary>Tool result: fs_read</summary>"));
+        assert!(html.contains("&lt;/details&gt;&lt;b&gt;bold&lt;/b&gt;"));
+        assert_eq!(html.matches("<details>").count(), html.matches("</details>").count());
+        assert!(html.contains("All good"));
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut user = UserMessage::new_prompt("Read the file".to_string());
+        user.images = Some(vec![crate::api_client::model::ImageBlock {
+            format: crate::api_client::model::ImageFormat::Png,
+            source: crate::api_client::model::ImageSource::Bytes(vec![]),
+        }]);
+        let assistant = AssistantMessage::new_tool_use(None, "Sure.".to_string(), vec![AssistantToolUse {
+            id: "tool_1".to_string(),
+            name: "fs_read".to_string(),
+            args: serde_json::json!({ "path": "README.md" }),
+            ..Default::default()
+        }]);
+        let results = UserMessage::new_tool_use_results(vec![ToolUseResult {
+            tool_use_id: "tool_1".to_string(),
+            content: vec![ToolUseResultBlock::Text("```rust\nfn main() {}\n```".to_string())],
+            status: ToolResultStatus::Success,
+        }]);
+        let response = AssistantMessage::new_response(None, "It is a readme.".to_string());
+
+        let md = to_markdown("conv", [(&user, &assistant), (&results, &response)]);
+
+        assert!(md.starts_with("# Amazon Q conversation"));
+        assert!(md.contains("## User"));
+        assert!(md.contains("Read the file"));
+        assert!(md.contains("_[1 image(s) attached]_"));
+        assert!(md.contains("## Amazon Q\n\nSure."));
+        assert!(md.contains("**Using tool `fs_read`:**\n\n```json\n{\n  \"path\": \"README.md\"\n}\n```"));
+        // The fence around the tool output is longer than the one inside of it
+        assert!(md.contains("**Result of `fs_read`:**\n\n````\n```rust\nfn main() {}\n```\n````"));
+        assert!(md.trim_end().ends_with("It is a readme."));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
@@ -1,7 +1,11 @@
 use eyre::Result;
 use rustyline::error::ReadlineError;
+use tracing::debug;
 
-use super::prompt::rl;
+use super::prompt::{
+    history_path,
+    rl,
+};
 #[cfg(unix)]
 use super::skim_integration::SkimCommandSelector;
 use crate::os::Os;
@@ -10,6 +14,8 @@ use crate::os::Os;
 pub struct InputSource(inner::Inner);
 
 mod inner {
+    use std::path::PathBuf;
+
     use rustyline::Editor;
     use rustyline::history::FileHistory;
 
@@ -18,12 +24,10 @@ mod inner {
     #[allow(clippy::large_enum_variant)]
     #[derive(Debug)]
     pub enum Inner {
-        Readline(Editor<ChatHelper, FileHistory>),
+        /// The editor, and the file its history is saved to.
+        Readline(Editor<ChatHelper, FileHistory>, Option<PathBuf>),
         #[allow(dead_code)]
-        Mock {
-            index: usize,
-            lines: Vec<String>,
-        },
+        Mock { index: usize, lines: Vec<String> },
     }
 }
 
@@ -33,7 +37,55 @@ impl InputSource {
         sender: std::sync::mpsc::Sender<Option<String>>,
         receiver: std::sync::mpsc::Receiver<Vec<String>>,
     ) -> Result<Self> {
-        Ok(Self(inner::Inner::Readline(rl(os, sender, receiver)?)))
+        let mut rl = rl(os, sender, receiver)?;
+        let history_path = history_path(os);
+        // The history is appended to after every prompt, which fails if the directory is missing.
+        if let Some(parent) = history_path.as_ref().and_then(|path| path.parent()) {
+            if let Err(err) = std::fs::create_dir_all(parent) {
+                debug!(?err, ?parent, "failed to create the prompt history directory");
+            }
+        }
+        if let Some(path) = history_path.as_ref().filter(|path| path.exists()) {
+            match rl.load_history(path) {
+                Ok(()) => {
+                    let entries = rl.history().iter().cloned().collect::<Vec<_>>();
+                    if let Some(helper) = rl.helper_mut() {
+                        for entry in entries {
+                            helper.update_hinter_history(&entry);
+                        }
+                    }
+                },
+                Err(err) => debug!(?err, ?path, "failed to load the prompt history"),
+            }
+        }
+        Ok(Self(inner::Inner::Readline(rl, history_path)))
+    }
+
+    /// Clears the prompt history, including the file it is saved to.
+    pub fn clear_history(&mut self) -> Result<()> {
+        if let inner::Inner::Readline(rl, history_path) = &mut self.0 {
+            rl.clear_history()?;
+            if let Some(helper) = rl.helper_mut() {
+                helper.clear_hinter_history();
+            }
+            if let Some(path) = history_path.as_ref().filter(|path| path.exists()) {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the prompts in the history, most recent first and without duplicates.
+    pub fn history_entries(&self) -> Vec<String> {
+        let mut entries = Vec::new();
+        if let inner::Inner::Readline(rl, _) = &self.0 {
+            for entry in rl.history().iter().rev() {
+                if !entries.contains(entry) {
+                    entries.push(entry.clone());
+                }
+            }
+        }
+        entries
     }
 
     #[cfg(unix)]
@@ -50,7 +102,7 @@ impl InputSource {
 
         use crate::database::settings::Setting;
 
-        if let inner::Inner::Readline(rl) = &mut self.0 {
+        if let inner::Inner::Readline(rl, _) = &mut self.0 {
             let key_char = match os.database.settings.get_string(Setting::SkimCommandKey) {
                 Some(key) if key.len() == 1 => key.chars().next().unwrap_or('s'),
                 _ => 's', // Default to 's' if setting is missing or invalid
@@ -73,12 +125,18 @@ impl InputSource {
 
     pub fn read_line(&mut self, prompt: Option<&str>) -> Result<Option<String>, ReadlineError> {
         match &mut self.0 {
-            inner::Inner::Readline(rl) => {
+            inner::Inner::Readline(rl, history_path) => {
                 let prompt = prompt.unwrap_or_default();
                 let curr_line = rl.readline(prompt);
                 match curr_line {
                     Ok(line) => {
                         let _ = rl.add_history_entry(line.as_str());
+                        if let Some((path, err)) = history_path
+                            .as_ref()
+                            .and_then(|path| rl.append_history(path).err().map(|err| (path, err)))
+                        {
+                            debug!(?err, ?path, "failed to save the prompt history");
+                        }
 
                         if let Some(helper) = rl.helper_mut() {
                             helper.update_hinter_history(&line);
@@ -100,7 +158,7 @@ impl InputSource {
     // We're keeping this method for potential future use
     #[allow(dead_code)]
     pub fn set_buffer(&mut self, content: &str) {
-        if let inner::Inner::Readline(rl) = &mut self.0 {
+        if let inner::Inner::Readline(rl, _) = &mut self.0 {
             // Add to history so user can access it with up arrow
             let _ = rl.add_history_entry(content);
         }
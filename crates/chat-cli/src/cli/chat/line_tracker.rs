@@ -5,6 +5,10 @@ use serde::{
 
 /// Contains metadata for tracking user and agent contribution metrics for a given file for
 /// `fs_write` tool uses.
+///
+/// Changes are only tracked from the first `fs_write` to a file onward: on the first write,
+/// `prev_fswrite_lines` is the file's line count before it, so lines that already existed are not
+/// attributed to the user. Counts are net line counts, so they are negative when lines are removed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileLineTracker {
     /// Line count at the end of the last `fs_write`
@@ -15,6 +19,12 @@ pub struct FileLineTracker {
     pub after_fswrite_lines: usize,
     /// Whether or not this is the first `fs_write` invocation
     pub is_first_write: bool,
+    /// Net lines changed by the agent across all `fs_write` invocations
+    #[serde(default)]
+    pub total_lines_by_agent: isize,
+    /// Net lines changed by the user between `fs_write` invocations
+    #[serde(default)]
+    pub total_lines_by_user: isize,
 }
 
 impl Default for FileLineTracker {
@@ -24,16 +34,95 @@ impl Default for FileLineTracker {
             before_fswrite_lines: 0,
             after_fswrite_lines: 0,
             is_first_write: true,
+            total_lines_by_agent: 0,
+            total_lines_by_user: 0,
         }
     }
 }
 
 impl FileLineTracker {
     pub fn lines_by_user(&self) -> isize {
-        (self.before_fswrite_lines as isize) - (self.prev_fswrite_lines as isize)
+        line_delta(self.prev_fswrite_lines, self.before_fswrite_lines)
     }
 
     pub fn lines_by_agent(&self) -> isize {
-        (self.after_fswrite_lines as isize) - (self.before_fswrite_lines as isize)
+        line_delta(self.before_fswrite_lines, self.after_fswrite_lines)
+    }
+
+    /// Adds the lines changed by the last `fs_write` to the session totals and starts tracking
+    /// user changes from its result.
+    pub fn finish_write(&mut self) {
+        self.total_lines_by_agent = self.total_lines_by_agent.saturating_add(self.lines_by_agent());
+        self.total_lines_by_user = self.total_lines_by_user.saturating_add(self.lines_by_user());
+        self.prev_fswrite_lines = self.after_fswrite_lines;
+    }
+}
+
+/// Returns the change from `from` to `to` lines, saturating at the bounds of [isize] rather than
+/// wrapping for line counts that don't fit in it.
+fn line_delta(from: usize, to: usize) -> isize {
+    if to >= from {
+        0isize.saturating_add_unsigned(to - from)
+    } else {
+        0isize.saturating_sub_unsigned(from - to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(prev: usize, before: usize, after: usize) -> FileLineTracker {
+        FileLineTracker {
+            prev_fswrite_lines: prev,
+            before_fswrite_lines: before,
+            after_fswrite_lines: after,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_write() {
+        // A new file
+        let t = tracker(0, 0, 10);
+        assert_eq!(t.lines_by_user(), 0);
+        assert_eq!(t.lines_by_agent(), 10);
+
+        // An existing file, whose lines are not attributed to the user
+        let t = tracker(50, 50, 45);
+        assert_eq!(t.lines_by_user(), 0);
+        assert_eq!(t.lines_by_agent(), -5);
+    }
+
+    #[test]
+    fn test_subsequent_writes() {
+        let mut t = tracker(0, 0, 10);
+        t.finish_write();
+        assert_eq!(t.prev_fswrite_lines, 10);
+
+        // The user adds 3 lines, then the agent removes 8
+        t.before_fswrite_lines = 13;
+        t.after_fswrite_lines = 5;
+        assert_eq!(t.lines_by_user(), 3);
+        assert_eq!(t.lines_by_agent(), -8);
+        t.finish_write();
+        assert_eq!(t.total_lines_by_user, 3);
+        assert_eq!(t.total_lines_by_agent, 2);
+    }
+
+    #[test]
+    fn test_huge_line_counts() {
+        let t = tracker(0, 0, usize::MAX);
+        assert_eq!(t.lines_by_agent(), isize::MAX);
+        let t = tracker(usize::MAX, 0, 0);
+        assert_eq!(t.lines_by_user(), isize::MIN);
+        let t = tracker(usize::MAX - 1, usize::MAX, usize::MAX - 3);
+        assert_eq!(t.lines_by_user(), 1);
+        assert_eq!(t.lines_by_agent(), -3);
+
+        let mut t = tracker(0, 0, usize::MAX);
+        t.total_lines_by_agent = isize::MAX - 1;
+        t.finish_write();
+        assert_eq!(t.total_lines_by_agent, isize::MAX);
     }
 }
@@ -1,8 +1,13 @@
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
+use std::process::Stdio;
+use std::str::FromStr;
 
 use chrono::{
     DateTime,
+    Local,
+    SecondsFormat,
     Utc,
 };
 use serde::{
@@ -10,6 +15,7 @@ use serde::{
     Serialize,
 };
 use tracing::{
+    debug,
     error,
     warn,
 };
@@ -30,12 +36,14 @@ use super::tools::{
 use super::util::{
     document_to_serde_value,
     serde_value_to_document,
-    truncate_safe,
+    truncate_path,
+    truncate_safe_graphemes,
     truncate_safe_in_place,
 };
 use crate::api_client::model::{
     AssistantResponseMessage,
     EnvState,
+    GitContext,
     ImageBlock,
     Tool,
     ToolResult,
@@ -45,13 +53,132 @@ use crate::api_client::model::{
     UserInputMessage,
     UserInputMessageContext,
 };
+use crate::database::settings::{
+    Setting,
+    Settings,
+};
 
 const USER_ENTRY_START_HEADER: &str = "--- USER MESSAGE BEGIN ---\n";
 const USER_ENTRY_END_HEADER: &str = "--- USER MESSAGE END ---\n\n";
 
+/// The delimiters that separate the sections of the content sent to the model, i.e. the context
+/// entries (timestamp, referenced files, hook output, ...) and the user's prompt.
+///
+/// Each header can be overridden with a setting, e.g. "q settings chat.userMessageStartHeader
+/// '<prompt>'". Empty values fall back to the defaults, and a newline is appended to headers that
+/// don't end with one so that the sections stay on their own lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHeaders {
+    pub context_start: String,
+    pub context_end: String,
+    pub user_start: String,
+    pub user_end: String,
+    /// Whether the time the prompt was sent is added as a context entry before it. Disabled with
+    /// "q settings chat.injectTimestamp false", e.g. for reproducible evals.
+    pub inject_timestamp: bool,
+    /// The time zone of the injected timestamp, UTC unless set with "q settings
+    /// chat.timestampTimezone".
+    pub timezone: TimestampTimezone,
+}
+
+impl MessageHeaders {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let header = |setting: Setting, default: &str| match settings.get_string(setting) {
+            Some(header) if !header.is_empty() => match header.ends_with('\n') {
+                true => header,
+                false => format!("{header}\n"),
+            },
+            _ => default.to_string(),
+        };
+
+        Self {
+            context_start: header(Setting::ChatContextEntryStartHeader, CONTEXT_ENTRY_START_HEADER),
+            context_end: header(Setting::ChatContextEntryEndHeader, CONTEXT_ENTRY_END_HEADER),
+            user_start: header(Setting::ChatUserMessageStartHeader, USER_ENTRY_START_HEADER),
+            user_end: header(Setting::ChatUserMessageEndHeader, USER_ENTRY_END_HEADER),
+            inject_timestamp: settings.get_bool(Setting::ChatInjectTimestamp).unwrap_or(true),
+            timezone: match settings.get_string(Setting::ChatTimestampTimezone) {
+                // Headers are read for every request, so an invalid value is only logged at debug
+                // level to not flood the log.
+                Some(value) => value.parse().unwrap_or_else(|_| {
+                    debug!(value, "Invalid value for {}, using UTC", Setting::ChatTimestampTimezone);
+                    TimestampTimezone::Utc
+                }),
+                None => TimestampTimezone::Utc,
+            },
+        }
+    }
+}
+
+impl Default for MessageHeaders {
+    fn default() -> Self {
+        Self {
+            context_start: CONTEXT_ENTRY_START_HEADER.to_string(),
+            context_end: CONTEXT_ENTRY_END_HEADER.to_string(),
+            user_start: USER_ENTRY_START_HEADER.to_string(),
+            user_end: USER_ENTRY_END_HEADER.to_string(),
+            inject_timestamp: true,
+            timezone: TimestampTimezone::Utc,
+        }
+    }
+}
+
+/// The time zone of the timestamp sent with each prompt, set with "q settings
+/// chat.timestampTimezone" to `utc`, `local`, or an IANA time zone name such as
+/// `America/New_York`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampTimezone {
+    #[default]
+    Utc,
+    /// The time zone of the system.
+    Local,
+    Named(chrono_tz::Tz),
+}
+
+impl TimestampTimezone {
+    /// Formats `timestamp` as the line sent to the model, e.g.
+    /// `Current UTC time: 2025-08-08T17:43:28.672Z`.
+    fn format(&self, timestamp: &DateTime<Utc>) -> String {
+        // Format the time with iso8601 format, using Z for UTC, e.g. 2025-08-08T17:43:28.672Z
+        match self {
+            Self::Utc => format!(
+                "Current UTC time: {}",
+                timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+            ),
+            Self::Local => format!(
+                "Current local time: {}",
+                timestamp
+                    .with_timezone(&Local)
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+            ),
+            Self::Named(tz) => format!(
+                "Current time in {}: {}",
+                tz.name(),
+                timestamp.with_timezone(tz).to_rfc3339_opts(SecondsFormat::Millis, true)
+            ),
+        }
+    }
+}
+
+impl FromStr for TimestampTimezone {
+    type Err = chrono_tz::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" => Ok(Self::Utc),
+            "local" => Ok(Self::Local),
+            _ => s.parse().map(Self::Named),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserMessage {
     pub additional_context: String,
+    /// Contents of the files referenced in the prompt with `@path`, sent along with
+    /// [Self::additional_context].
+    #[serde(default)]
+    pub file_context: String,
     pub env_context: UserEnvContext,
     pub content: UserMessageContent,
     pub timestamp: DateTime<Utc>,
@@ -80,25 +207,21 @@ impl UserMessageContent {
     fn truncate_safe(&mut self, max_bytes: usize) {
         match self {
             UserMessageContent::Prompt { prompt } => {
-                truncate_safe_in_place(prompt, max_bytes, Self::TRUNCATED_SUFFIX);
+                truncate_annotated(prompt, max_bytes);
             },
             UserMessageContent::CancelledToolUses {
                 prompt,
                 tool_use_results,
             } => {
                 if let Some(prompt) = prompt {
-                    truncate_safe_in_place(prompt, max_bytes / 2, Self::TRUNCATED_SUFFIX);
-                    truncate_safe_tool_use_results(
-                        tool_use_results.as_mut_slice(),
-                        max_bytes / 2,
-                        Self::TRUNCATED_SUFFIX,
-                    );
+                    truncate_annotated(prompt, max_bytes / 2);
+                    truncate_safe_tool_use_results(tool_use_results.as_mut_slice(), max_bytes / 2);
                 } else {
-                    truncate_safe_tool_use_results(tool_use_results.as_mut_slice(), max_bytes, Self::TRUNCATED_SUFFIX);
+                    truncate_safe_tool_use_results(tool_use_results.as_mut_slice(), max_bytes);
                 }
             },
             UserMessageContent::ToolUseResults { tool_use_results } => {
-                truncate_safe_tool_use_results(tool_use_results.as_mut_slice(), max_bytes, Self::TRUNCATED_SUFFIX);
+                truncate_safe_tool_use_results(tool_use_results.as_mut_slice(), max_bytes);
             },
         }
     }
@@ -112,6 +235,7 @@ impl UserMessage {
             images: None,
             timestamp: Utc::now(),
             additional_context: String::new(),
+            file_context: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::Prompt { prompt },
         }
@@ -122,6 +246,7 @@ impl UserMessage {
             images: None,
             timestamp: Utc::now(),
             additional_context: String::new(),
+            file_context: String::new(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::CancelledToolUses {
                 prompt,
@@ -141,6 +266,7 @@ impl UserMessage {
     pub fn new_tool_use_results(results: Vec<ToolUseResult>) -> Self {
         Self {
             additional_context: String::new(),
+            file_context: String::new(),
             timestamp: Utc::now(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::ToolUseResults {
@@ -153,6 +279,7 @@ impl UserMessage {
     pub fn new_tool_use_results_with_images(results: Vec<ToolUseResult>, images: Vec<ImageBlock>) -> Self {
         Self {
             additional_context: String::new(),
+            file_context: String::new(),
             timestamp: Utc::now(),
             env_context: UserEnvContext::generate_new(),
             content: UserMessageContent::ToolUseResults {
@@ -164,8 +291,8 @@ impl UserMessage {
 
     /// Converts this message into a [UserInputMessage] to be stored in the history of
     /// [api_client::model::ConversationState].
-    pub fn into_history_entry(self) -> UserInputMessage {
-        let content = self.content_with_context();
+    pub fn into_history_entry(self, headers: &MessageHeaders) -> UserInputMessage {
+        let content = self.content_with_context(headers);
         UserInputMessage {
             images: self.images.clone(),
             content,
@@ -192,8 +319,9 @@ impl UserMessage {
         self,
         model_id: Option<String>,
         tools: &HashMap<ToolOrigin, Vec<Tool>>,
+        headers: &MessageHeaders,
     ) -> UserInputMessage {
-        let content = self.content_with_context();
+        let content = self.content_with_context(headers);
         UserInputMessage {
             images: self.images,
             content,
@@ -273,33 +401,34 @@ impl UserMessage {
                 // something is set.
                 tool_content.push_str("<tool result redacted>");
             }
-            let prompt = truncate_safe(&tool_content, MAX_USER_MESSAGE_SIZE).to_string();
-            self.content = UserMessageContent::Prompt { prompt };
+            truncate_annotated(&mut tool_content, MAX_USER_MESSAGE_SIZE);
+            self.content = UserMessageContent::Prompt { prompt: tool_content };
         }
     }
 
     /// Returns a formatted [String] containing [Self::additional_context] and [Self::prompt].
-    fn content_with_context(&self) -> String {
-        // Format the time with iso8601 format using Z, e.g. 2025-08-08T17:43:28.672Z
-        let timestamp = self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
-
+    fn content_with_context(&self, headers: &MessageHeaders) -> String {
         let prompt_with_timestamp = self.prompt().map(|p| {
+            let prompt = format!("{}{}{}", headers.user_start, p, headers.user_end);
+            if !headers.inject_timestamp {
+                return prompt;
+            }
+
             format!(
-                "{}Current UTC time: {}{}{}{}{}",
-                CONTEXT_ENTRY_START_HEADER,
-                timestamp,
-                CONTEXT_ENTRY_END_HEADER,
-                USER_ENTRY_START_HEADER,
-                p,
-                USER_ENTRY_END_HEADER
+                "{}{}{}{}",
+                headers.context_start,
+                headers.timezone.format(&self.timestamp),
+                headers.context_end,
+                prompt
             )
         });
 
-        match (self.additional_context.is_empty(), prompt_with_timestamp) {
+        let context = format!("{}{}", self.additional_context, self.file_context);
+        match (context.is_empty(), prompt_with_timestamp) {
             // Only add special delimiters if we have both a prompt and additional context
-            (false, Some(prompt)) => format!("{}\n{}", self.additional_context, prompt),
+            (false, Some(prompt)) => format!("{}\n{}", context, prompt),
             (true, Some(prompt)) => prompt,
-            _ => self.additional_context.clone(),
+            _ => context,
         }
         .trim()
         .to_string()
@@ -336,7 +465,39 @@ impl From<ToolUseResult> for ToolResult {
     }
 }
 
-fn truncate_safe_tool_use_results(tool_use_results: &mut [ToolUseResult], max_bytes: usize, truncated_suffix: &str) {
+/// Truncates `s` to a maximum length of `max_bytes`, ending it with
+/// [UserMessageContent::TRUNCATED_SUFFIX] and a line stating how much of the original was kept,
+/// e.g. `[truncated: showing 400000 of 2300000 bytes]`, so the model can tell how much is missing.
+///
+/// Falls back to only appending the suffix if the annotation doesn't fit within `max_bytes`.
+fn truncate_annotated(s: &mut String, max_bytes: usize) {
+    let original_len = s.len();
+    if original_len <= max_bytes {
+        return;
+    }
+
+    let suffix = |kept: usize| {
+        format!(
+            "{}\n[truncated: showing {kept} of {original_len} bytes]",
+            UserMessageContent::TRUNCATED_SUFFIX
+        )
+    };
+    // The suffix is longest when the kept length has as many digits as the original length.
+    let max_suffix_len = suffix(original_len).len();
+    if max_suffix_len > max_bytes {
+        truncate_safe_in_place(s, max_bytes, UserMessageContent::TRUNCATED_SUFFIX);
+        return;
+    }
+
+    let kept = truncate_safe_graphemes(s, max_bytes - max_suffix_len).len();
+    s.truncate(kept);
+    s.push_str(&suffix(kept));
+}
+
+fn truncate_safe_tool_use_results(tool_use_results: &mut [ToolUseResult], max_bytes: usize) {
+    if tool_use_results.is_empty() {
+        return;
+    }
     let max_bytes = max_bytes / tool_use_results.len();
     for result in tool_use_results {
         for content in &mut result.content {
@@ -344,9 +505,8 @@ fn truncate_safe_tool_use_results(tool_use_results: &mut [ToolUseResult], max_by
                 ToolUseResultBlock::Json(value) => match serde_json::to_string(value) {
                     Ok(mut value_str) => {
                         if value_str.len() > max_bytes {
-                            truncate_safe_in_place(&mut value_str, max_bytes, truncated_suffix);
+                            truncate_annotated(&mut value_str, max_bytes);
                             *content = ToolUseResultBlock::Text(value_str);
-                            return;
                         }
                     },
                     Err(err) => {
@@ -354,7 +514,7 @@ fn truncate_safe_tool_use_results(tool_use_results: &mut [ToolUseResult], max_by
                     },
                 },
                 ToolUseResultBlock::Text(t) => {
-                    truncate_safe_in_place(t, max_bytes, truncated_suffix);
+                    truncate_annotated(t, max_bytes);
                 },
             }
         }
@@ -525,7 +685,7 @@ pub fn build_env_state() -> EnvState {
     match env::current_dir() {
         Ok(current_dir) => {
             env_state.current_working_directory =
-                Some(truncate_safe(&current_dir.to_string_lossy(), MAX_CURRENT_WORKING_DIRECTORY_LEN).into());
+                Some(truncate_path(&current_dir.to_string_lossy(), MAX_CURRENT_WORKING_DIRECTORY_LEN).into());
         },
         Err(err) => {
             error!(?err, "Attempted to fetch the CWD but it did not exist.");
@@ -535,6 +695,38 @@ pub fn build_env_state() -> EnvState {
     env_state
 }
 
+/// Returns the branch, dirty flag, and last commit of the git repository containing `dir`.
+///
+/// Returns [None] if `dir` is not inside a git work tree or git is unavailable.
+pub async fn build_git_context(dir: &Path) -> Option<GitContext> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain=v2", "--branch"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut git = GitContext::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(oid) = line.strip_prefix("# branch.oid ") {
+            git.last_commit = (oid != "(initial)").then(|| oid.chars().take(12).collect());
+        } else if let Some(head) = line.strip_prefix("# branch.head ") {
+            git.branch = (head != "(detached)").then(|| head.to_string());
+        } else if !line.starts_with('#') {
+            // Every other line is a changed or untracked entry
+            git.dirty = true;
+        }
+    }
+
+    Some(git)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -547,13 +739,60 @@ mod tests {
         println!("{env_state:?}");
     }
 
+    #[tokio::test]
+    async fn test_git_context() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(build_git_context(dir.path()).await, None);
+
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .is_ok_and(|output| output.status.success())
+        };
+        if !git(&["init", "-b", "main"]) {
+            // git is not available
+            return;
+        }
+        assert_eq!(
+            build_git_context(dir.path()).await,
+            Some(GitContext {
+                branch: Some("main".to_string()),
+                dirty: false,
+                last_commit: None,
+            })
+        );
+
+        std::fs::write(dir.path().join("file.txt"), "hello").unwrap();
+        let git_context = build_git_context(dir.path()).await.unwrap();
+        assert!(git_context.dirty);
+
+        assert!(git(&[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "init",
+        ]));
+        let git_context = build_git_context(dir.path()).await.unwrap();
+        assert!(git_context.dirty);
+        assert_eq!(git_context.last_commit.map(|c| c.len()), Some(12));
+    }
+
     #[test]
     fn test_user_input_message_timestamp_formatting() {
         let msg = UserMessage::new_prompt("hello world".to_string());
 
         let msgs = [
-            msg.clone().into_user_input_message(None, &HashMap::new()),
-            msg.clone().into_history_entry(),
+            msg.clone()
+                .into_user_input_message(None, &HashMap::new(), &MessageHeaders::default()),
+            msg.clone().into_history_entry(&MessageHeaders::default()),
         ];
 
         for m in msgs {
@@ -565,4 +804,193 @@ mod tests {
             m.content.contains(USER_ENTRY_END_HEADER);
         }
     }
+
+    #[tokio::test]
+    async fn test_message_headers_from_settings() {
+        let mut settings = Settings::default();
+        assert_eq!(MessageHeaders::from_settings(&settings), MessageHeaders::default());
+
+        settings
+            .set(Setting::ChatUserMessageStartHeader, "<prompt>")
+            .await
+            .unwrap();
+        settings
+            .set(Setting::ChatUserMessageEndHeader, "</prompt>\n")
+            .await
+            .unwrap();
+        settings.set(Setting::ChatContextEntryStartHeader, "").await.unwrap();
+        let headers = MessageHeaders::from_settings(&settings);
+        assert_eq!(headers.user_start, "<prompt>\n");
+        assert_eq!(headers.user_end, "</prompt>\n");
+        assert_eq!(headers.context_start, CONTEXT_ENTRY_START_HEADER);
+
+        let msg = UserMessage::new_prompt("hello world".to_string());
+        let content = msg.into_history_entry(&headers).content;
+        assert!(content.starts_with(CONTEXT_ENTRY_START_HEADER));
+        assert!(content.ends_with("<prompt>\nhello world</prompt>"));
+        assert!(!content.contains(USER_ENTRY_START_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_content_without_timestamp() {
+        let mut settings = Settings::default();
+        settings.set(Setting::ChatInjectTimestamp, false).await.unwrap();
+        let headers = MessageHeaders::from_settings(&settings);
+        assert!(!headers.inject_timestamp);
+
+        let msg = UserMessage::new_prompt("hello world".to_string());
+        let content = msg.into_history_entry(&headers).content;
+        assert!(!content.contains("Current UTC time"));
+        assert_eq!(
+            content,
+            format!("{USER_ENTRY_START_HEADER}hello world{USER_ENTRY_END_HEADER}").trim()
+        );
+
+        // Additional context is still delimited from the prompt.
+        let mut msg = UserMessage::new_prompt("hello world".to_string());
+        msg.additional_context = format!("{CONTEXT_ENTRY_START_HEADER}hook output\n{CONTEXT_ENTRY_END_HEADER}");
+        let content = msg.into_user_input_message(None, &HashMap::new(), &headers).content;
+        assert!(!content.contains("Current UTC time"));
+        assert!(content.starts_with(CONTEXT_ENTRY_START_HEADER));
+        assert!(content.ends_with(&format!(
+            "{USER_ENTRY_START_HEADER}hello world{}",
+            USER_ENTRY_END_HEADER.trim()
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_content_timestamp_timezone() {
+        assert_eq!("UTC".parse::<TimestampTimezone>().unwrap(), TimestampTimezone::Utc);
+        assert_eq!("local".parse::<TimestampTimezone>().unwrap(), TimestampTimezone::Local);
+        assert!("Mars/Olympus_Mons".parse::<TimestampTimezone>().is_err());
+
+        let mut msg = UserMessage::new_prompt("hello world".to_string());
+        msg.timestamp = DateTime::parse_from_rfc3339("2025-08-08T17:43:28.672Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut settings = Settings::default();
+
+        settings.set(Setting::ChatTimestampTimezone, "local").await.unwrap();
+        let content = msg
+            .clone()
+            .into_history_entry(&MessageHeaders::from_settings(&settings))
+            .content;
+        let local = msg
+            .timestamp
+            .with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+        assert!(content.contains(&format!("Current local time: {local}")));
+
+        settings
+            .set(Setting::ChatTimestampTimezone, "America/New_York")
+            .await
+            .unwrap();
+        let content = msg
+            .clone()
+            .into_history_entry(&MessageHeaders::from_settings(&settings))
+            .content;
+        assert!(content.contains("Current time in America/New_York: 2025-08-08T13:43:28.672-04:00"));
+        assert!(!content.contains("Current UTC time"));
+
+        // Invalid zones fall back to UTC
+        settings.set(Setting::ChatTimestampTimezone, "nowhere").await.unwrap();
+        let content = msg
+            .into_history_entry(&MessageHeaders::from_settings(&settings))
+            .content;
+        assert!(content.contains("Current UTC time: 2025-08-08T17:43:28.672Z"));
+    }
+
+    #[test]
+    fn test_truncate_annotated() {
+        let mut s = "a".repeat(1000);
+        truncate_annotated(&mut s, 200);
+        assert!(s.len() <= 200);
+        let (kept, suffix) = s.split_at(s.find('.').unwrap());
+        assert_eq!(
+            suffix,
+            format!(
+                "{}\n[truncated: showing {} of 1000 bytes]",
+                UserMessageContent::TRUNCATED_SUFFIX,
+                kept.len()
+            )
+        );
+
+        // Content within the limit is untouched.
+        let mut s = "a".repeat(100);
+        truncate_annotated(&mut s, 100);
+        assert_eq!(s, "a".repeat(100));
+
+        // Falls back to the plain suffix when the annotation doesn't fit.
+        let mut s = "a".repeat(1000);
+        truncate_annotated(&mut s, 40);
+        assert!(s.len() <= 40);
+        assert!(s.ends_with(UserMessageContent::TRUNCATED_SUFFIX));
+    }
+
+    #[test]
+    fn test_truncate_tool_use_results_annotated() {
+        let mut msg = UserMessage::new_tool_use_results(vec![ToolUseResult {
+            tool_use_id: "id".to_string(),
+            content: vec![ToolUseResultBlock::Text("b".repeat(5000))],
+            status: ToolResultStatus::Success,
+        }]);
+        msg.truncate_safe(1000);
+        let Some([result]) = msg.tool_use_results() else {
+            panic!("expected a single tool use result");
+        };
+        let ToolUseResultBlock::Text(text) = &result.content[0] else {
+            panic!("expected text content");
+        };
+        assert!(text.len() <= 1000);
+        let kept = text.chars().take_while(|c| *c == 'b').count();
+        assert!(text.ends_with(&format!("[truncated: showing {kept} of 5000 bytes]")));
+
+        let mut msg = UserMessage::new_tool_use_results(vec![ToolUseResult {
+            tool_use_id: "id".to_string(),
+            content: vec![ToolUseResultBlock::Text("c".repeat(MAX_USER_MESSAGE_SIZE + 10))],
+            status: ToolResultStatus::Success,
+        }]);
+        msg.replace_content_with_tool_use_results();
+        let prompt = msg.prompt().unwrap();
+        assert!(prompt.len() <= MAX_USER_MESSAGE_SIZE);
+        let kept = prompt.chars().take_while(|c| *c == 'c').count();
+        assert!(prompt.ends_with(&format!(
+            "[truncated: showing {kept} of {} bytes]",
+            MAX_USER_MESSAGE_SIZE + 10
+        )));
+    }
+
+    #[test]
+    fn test_truncate_multiple_json_tool_use_results() {
+        let results = (0..3)
+            .map(|i| ToolUseResult {
+                tool_use_id: format!("id{i}"),
+                content: vec![ToolUseResultBlock::Json(
+                    serde_json::json!({ "data": "d".repeat(5000) }),
+                )],
+                status: ToolResultStatus::Success,
+            })
+            .collect();
+        let mut msg = UserMessage::new_tool_use_results(results);
+        msg.truncate_safe(3000);
+
+        let results = msg.tool_use_results().unwrap();
+        assert_eq!(results.len(), 3);
+        for result in results {
+            let ToolUseResultBlock::Text(text) = &result.content[0] else {
+                panic!("expected {} to be truncated to text", result.tool_use_id);
+            };
+            assert!(text.len() <= 1000);
+            assert!(text.contains("[truncated: showing"));
+        }
+    }
+
+    #[test]
+    fn test_truncate_empty_tool_use_results() {
+        truncate_safe_tool_use_results(&mut [], 1000);
+
+        let mut msg = UserMessage::new_tool_use_results(vec![]);
+        msg.truncate_safe(1000);
+        assert!(msg.tool_use_results().unwrap().is_empty());
+    }
 }
@@ -1,8 +1,10 @@
 pub mod cli;
+mod config_watcher;
 mod consts;
 pub mod context;
 mod conversation;
 mod error_formatter;
+mod export;
 mod input_source;
 mod message;
 mod parse;
@@ -11,6 +13,7 @@ mod line_tracker;
 mod parser;
 mod prompt;
 mod prompt_parser;
+mod protocol_log;
 mod server_messenger;
 #[cfg(unix)]
 mod skim_integration;
@@ -47,6 +50,12 @@ use cli::model::{
     get_model_options,
     select_model,
 };
+use cli::summary::SessionStats;
+use config_watcher::{
+    CONFIG_RELOAD_DEBOUNCE,
+    ConfigWatcher,
+};
+use context::calc_max_context_files_size;
 pub use conversation::ConversationState;
 use conversation::TokenWarningLevel;
 use crossterm::style::{
@@ -83,6 +92,7 @@ use parser::{
     RequestMetadata,
     SendMessageStream,
 };
+use protocol_log::ProtocolLog;
 use regex::Regex;
 use spinners::{
     Spinner,
@@ -106,7 +116,9 @@ use tools::{
     OutputKind,
     QueuedTool,
     Tool,
+    ToolCallRepeats,
     ToolSpec,
+    invoke_concurrently,
 };
 use tracing::{
     debug,
@@ -115,30 +127,52 @@ use tracing::{
     trace,
     warn,
 };
-use util::images::RichImageBlock;
-use util::ui::draw_box;
+use util::images::{
+    RichImageBlock,
+    RichImageBlocks,
+};
+use util::ui::{
+    ChatOutput,
+    ElapsedSpinner,
+    disable_animation,
+    draw_box,
+    should_animate,
+};
 use util::{
+    TypewriterSpeed,
     animate_output,
     play_notification_bell,
+    response_stats,
 };
 use winnow::Partial;
 use winnow::stream::Offset;
 
 use super::agent::PermissionEvalResult;
 use crate::api_client::model::ToolResultStatus;
+use crate::api_client::profile::profile_from_arn;
 use crate::api_client::{
     self,
+    ApiClient,
     ApiClientError,
 };
 use crate::auth::AuthError;
 use crate::auth::builder_id::is_idc_user;
 use crate::cli::agent::Agents;
+use crate::cli::agent::hook::HookTrigger;
 use crate::cli::chat::cli::SlashCommand;
+use crate::cli::chat::cli::hooks::{
+    run_post_tool_use_hook,
+    run_pre_tool_use_hooks,
+};
 use crate::cli::chat::cli::model::default_model_id;
 use crate::cli::chat::cli::prompts::{
     GetPromptError,
     PromptsSubcommand,
 };
+use crate::cli::chat::consts::{
+    DEFAULT_MAX_REPEATED_TOOL_CALLS,
+    DEFAULT_MCP_TOOL_LOAD_TIMEOUT,
+};
 use crate::cli::chat::util::sanitize_unicode_tags;
 use crate::database::settings::Setting;
 use crate::mcp_client::Prompt;
@@ -170,6 +204,7 @@ pub const EXTRA_HELP: &str = color_print::cstr! {"
 <em>!{command}</em>          <black!>Quickly execute a command in your current session</black!>
 <em>Ctrl(^) + j</em>         <black!>Insert new-line to provide multi-line prompt</black!>
                     <black!>Alternatively, [Alt(⌥) + Enter(⏎)]</black!>
+<em>Ctrl(^) + r</em>         <black!>Search your prompt history, or use /history search</black!>
 <em>Ctrl(^) + s</em>         <black!>Fuzzy search commands and context files</black!>
                     <black!>Use Tab to select multiple items</black!>
                     <black!>Change the keybind using: q settings chat.skimCommandKey x</black!>
@@ -182,6 +217,9 @@ pub struct ChatArgs {
     /// Resumes the previous conversation from this directory.
     #[arg(short, long)]
     pub resume: bool,
+    /// Resumes the most recent conversation, from any directory.
+    #[arg(short = 'c', long = "continue", conflicts_with = "resume")]
+    pub continue_last: bool,
     /// Context profile to use
     #[arg(long = "agent", alias = "profile")]
     pub agent: Option<String>,
@@ -198,6 +236,34 @@ pub struct ChatArgs {
     /// Whether the command should run without expecting user input
     #[arg(long, alias = "non-interactive")]
     pub no_interactive: bool,
+    /// Print which permission rule decided each tool approval
+    #[arg(long)]
+    pub explain: bool,
+    /// Disable spinners and the typewriter effect. Output is also never animated or styled when
+    /// NO_COLOR or CI is set, or when stdout is not a terminal.
+    #[arg(long)]
+    pub no_animation: bool,
+    /// Run a single prompt without user interaction and exit. Same as passing the prompt as the
+    /// input together with --no-interactive. Tools that would require approval fail the run
+    /// unless they are trusted, e.g. through the agent's allowedTools or --trust-all-tools.
+    #[arg(long, conflicts_with = "input")]
+    pub prompt: Option<String>,
+    /// The AWS CLI profile used by use_aws tool calls that don't specify a profile themselves
+    #[arg(long, value_name = "PROFILE")]
+    pub aws_profile: Option<String>,
+    /// Pause and ask whether to continue after this many assistant turns without user input.
+    /// Defaults to the chat.maxTurns setting
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_turns: Option<u64>,
+    /// Sent with each request to select a server-side agent behavior. The value is passed to the
+    /// service as is; the accepted modes are defined by the service. Ignored when signing in with
+    /// SigV4 credentials
+    #[arg(long, value_name = "MODE", value_parser = clap::builder::NonEmptyStringValueParser::new())]
+    pub agent_mode: Option<String>,
+    /// The Q Developer profile to make requests with for this session instead of the one selected
+    /// with `q profile`. Defaults to the api.profileArn setting
+    #[arg(long, value_name = "ARN", value_parser = parse_profile_arn)]
+    pub profile_arn: Option<String>,
     /// The first question to ask
     pub input: Option<String>,
 }
@@ -205,6 +271,10 @@ pub struct ChatArgs {
 impl ChatArgs {
     pub async fn execute(mut self, os: &mut Os) -> Result<ExitCode> {
         let mut input = self.input;
+        if let Some(prompt) = self.prompt.take() {
+            self.no_interactive = true;
+            input = Some(prompt);
+        }
 
         if self.no_interactive && input.is_none() {
             if !std::io::stdin().is_terminal() {
@@ -226,8 +296,17 @@ impl ChatArgs {
             }
         }
 
-        let stdout = std::io::stdout();
-        let mut stderr = std::io::stderr();
+        if self.no_animation {
+            disable_animation();
+        }
+
+        if let Some(arn) = &self.profile_arn {
+            let profile = profile_from_arn(arn)?;
+            os.client = ApiClient::with_profile(&os.env, &os.fs, &mut os.database, profile).await?;
+        }
+
+        let stdout = ChatOutput::new(std::io::stdout());
+        let mut stderr = ChatOutput::new(std::io::stderr());
 
         let args: Vec<String> = std::env::args().collect();
         if args
@@ -314,13 +393,13 @@ impl ChatArgs {
         };
 
         // If modelId is specified, verify it exists before starting the chat
-        let model_options = get_model_options(os).await?;
+        let model_options = get_model_options(os, false).await?;
         let model_id: Option<String> = if let Some(model_name) = self.model {
             let model_name_lower = model_name.to_lowercase();
             match model_options.iter().find(|opt| opt.name == model_name_lower) {
                 Some(opt) => Some((opt.model_id).to_string()),
                 None => {
-                    let available_names: Vec<&str> = model_options.iter().map(|opt| opt.name).collect();
+                    let available_names: Vec<&str> = model_options.iter().map(|opt| opt.name.as_ref()).collect();
                     bail!(
                         "Model '{}' does not exist. Available models: {}",
                         model_name,
@@ -332,6 +411,17 @@ impl ChatArgs {
             None
         };
 
+        let max_turns = self
+            .max_turns
+            .map(|n| n as usize)
+            .or_else(|| {
+                os.database
+                    .settings
+                    .get_int(Setting::ChatMaxTurns)
+                    .and_then(|n| usize::try_from(n).ok())
+            })
+            .filter(|n| *n > 0);
+
         let (prompt_request_sender, prompt_request_receiver) = std::sync::mpsc::channel::<Option<String>>();
         let (prompt_response_sender, prompt_response_receiver) = std::sync::mpsc::channel::<Vec<String>>();
         let mut tool_manager = ToolManagerBuilder::default()
@@ -343,7 +433,7 @@ impl ChatArgs {
             .await?;
         let tool_config = tool_manager.load_tools(os, &mut stderr).await?;
 
-        ChatSession::new(
+        let mut session = ChatSession::new(
             os,
             stdout,
             stderr,
@@ -351,20 +441,32 @@ impl ChatArgs {
             agents,
             input,
             InputSource::new(os, prompt_request_sender, prompt_response_receiver)?,
-            self.resume,
+            match (self.resume, self.continue_last) {
+                (_, true) => Some(ResumeFrom::MostRecent),
+                (true, false) => Some(ResumeFrom::CurrentDirectory),
+                (false, false) => None,
+            },
             || terminal::window_size().map(|s| s.columns.into()).ok(),
             tool_manager,
             model_id,
             tool_config,
             !self.no_interactive,
+            self.explain,
+            self.aws_profile,
+            max_turns,
         )
-        .await?
-        .spawn(os)
-        .await
-        .map(|_| ExitCode::SUCCESS)
+        .await?;
+        session.agent_mode = self.agent_mode;
+        session.spawn(os).await.map(|_| ExitCode::SUCCESS)
     }
 }
 
+fn parse_profile_arn(arn: &str) -> Result<String, String> {
+    profile_from_arn(arn)
+        .map(|_| arn.to_string())
+        .map_err(|err| err.to_string())
+}
+
 const WELCOME_TEXT: &str = color_print::cstr! {"<cyan!>
     ⢠⣶⣶⣦⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢀⣤⣶⣿⣿⣿⣶⣦⡀⠀
  ⠀⠀⠀⣾⡿⢻⣿⡆⠀⠀⠀⢀⣄⡄⢀⣠⣤⣤⡀⢀⣠⣤⣤⡀⠀⠀⢀⣠⣤⣤⣤⣄⠀⠀⢀⣤⣤⣤⣤⣤⣤⡀⠀⠀⣀⣤⣤⣤⣀⠀⠀⠀⢠⣤⡀⣀⣤⣤⣄⡀⠀⠀⠀⠀⠀⠀⢠⣿⣿⠋⠀⠀⠀⠙⣿⣿⡆
@@ -421,6 +523,11 @@ const SMALL_SCREEN_POPULAR_SHORTCUTS: &str = color_print::cstr! {"<black!><green
 </black!>"};
 
 const RESPONSE_TIMEOUT_CONTENT: &str = "Response timed out - message took too long to generate";
+/// How many times a request is re-sent after its response stream is interrupted, e.g. by a dropped
+/// connection, before giving up.
+const MAX_STREAM_RETRIES: usize = 3;
+/// How many times a throttled request is re-sent before giving up.
+const MAX_THROTTLE_RETRIES: u32 = 3;
 const TRUST_ALL_TEXT: &str = color_print::cstr! {"<green!>All tools are now trusted (<red!>!</red!>). Amazon Q will execute tools <bold>without</bold> asking for confirmation.\
 \nAgents can sometimes do unexpected things so understand the risks.</green!>
 \nLearn more at https://docs.aws.amazon.com/amazonq/latest/qdeveloper-ug/command-line-chat-security.html#command-line-chat-trustall-safety"};
@@ -463,9 +570,11 @@ pub enum ChatError {
     #[error(transparent)]
     GetPromptError(#[from] GetPromptError),
     #[error(
-        "Tool approval required but --no-interactive was specified. Use --trust-all-tools to automatically approve tools."
+        "Tool approval required but --no-interactive was specified. Use --trust-all-tools or the agent's allowedTools to automatically approve tools."
     )]
     NonInteractiveToolApproval,
+    #[error("Reached the limit of {0} turns without user input. Use --max-turns to allow more.")]
+    MaxTurnsReached(usize),
     #[error("The conversation history is too large to compact")]
     CompactHistoryFailure,
 }
@@ -483,6 +592,7 @@ impl ChatError {
             ChatError::Interrupted { .. } => None,
             ChatError::GetPromptError(_) => None,
             ChatError::NonInteractiveToolApproval => None,
+            ChatError::MaxTurnsReached(_) => None,
             ChatError::CompactHistoryFailure => None,
         }
     }
@@ -501,6 +611,7 @@ impl ReasonCode for ChatError {
             ChatError::GetPromptError(_) => "GetPromptError".to_string(),
             ChatError::Auth(_) => "AuthError".to_string(),
             ChatError::NonInteractiveToolApproval => "NonInteractiveToolApproval".to_string(),
+            ChatError::MaxTurnsReached(_) => "MaxTurnsReached".to_string(),
             ChatError::CompactHistoryFailure => "CompactHistoryFailure".to_string(),
         }
     }
@@ -524,11 +635,20 @@ impl From<parser::RecvError> for ChatError {
     }
 }
 
+/// Which previously saved conversation to resume when starting a [ChatSession].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeFrom {
+    /// The conversation last saved in the current directory (`--resume`).
+    CurrentDirectory,
+    /// The conversation last saved in any directory (`--continue`).
+    MostRecent,
+}
+
 pub struct ChatSession {
     /// For output read by humans and machine
-    pub stdout: std::io::Stdout,
+    pub stdout: ChatOutput<std::io::Stdout>,
     /// For display output, only read by humans
-    pub stderr: std::io::Stderr,
+    pub stderr: ChatOutput<std::io::Stderr>,
     initial_input: Option<String>,
     /// Whether we're starting a new conversation or continuing an old one.
     existing_conversation: bool,
@@ -555,31 +675,60 @@ pub struct ChatSession {
     tool_use_status: ToolUseStatus,
     /// Any failed requests that could be useful for error report/debugging
     failed_request_ids: Vec<String>,
+    /// How many times the current request has been re-sent after its response stream was
+    /// interrupted.
+    stream_retries: usize,
+    /// Set with `/debug protocol on` to log each request and response event.
+    protocol_log: Option<ProtocolLog>,
+    /// Set with `--agent-mode` and sent with each request.
+    agent_mode: Option<String>,
     /// Pending prompts to be sent
     pending_prompts: VecDeque<Prompt>,
+    /// Images added with `/image` to attach to the next prompt.
+    pending_images: RichImageBlocks,
     interactive: bool,
+    /// Whether to print the rule that decided each tool approval.
+    explain_permissions: bool,
+    /// Session-wide default AWS CLI profile for `use_aws` calls that don't specify one.
+    aws_profile: Option<String>,
+    /// Maximum number of assistant turns without user input before the user is asked whether to
+    /// continue.
+    max_turns: Option<usize>,
+    /// Number of assistant turns since the user last sent a message or allowed more turns.
+    turn_count: usize,
+    /// Whether tool execution is paused until the user allows more turns.
+    turn_limit_reached: bool,
+    /// Consecutive identical tool calls since the user last sent a message.
+    tool_call_repeats: ToolCallRepeats,
     inner: Option<ChatState>,
     ctrlc_rx: broadcast::Receiver<()>,
+    /// Watches the MCP config of the active agent when `chat.hotReloadConfig` is enabled.
+    config_watcher: Option<ConfigWatcher>,
+    /// What happened in this session, shown with `/summary`.
+    stats: SessionStats,
 }
 
 impl ChatSession {
     #[allow(clippy::too_many_arguments)]
     pub async fn new(
         os: &mut Os,
-        stdout: std::io::Stdout,
-        mut stderr: std::io::Stderr,
+        stdout: ChatOutput<std::io::Stdout>,
+        mut stderr: ChatOutput<std::io::Stderr>,
         conversation_id: &str,
         mut agents: Agents,
         mut input: Option<String>,
         input_source: InputSource,
-        resume_conversation: bool,
+        resume: Option<ResumeFrom>,
         terminal_width_provider: fn() -> Option<usize>,
         tool_manager: ToolManager,
         model_id: Option<String>,
         tool_config: HashMap<String, ToolSpec>,
         interactive: bool,
+        explain_permissions: bool,
+        aws_profile: Option<String>,
+        max_turns: Option<usize>,
     ) -> Result<Self> {
-        let model_options = get_model_options(os).await?;
+        let model_options = get_model_options(os, false).await?;
         let valid_model_id = match model_id {
             Some(id) => id,
             None => {
@@ -591,7 +740,7 @@ impl ChatSession {
                         model_options
                             .iter()
                             .find(|opt| opt.name == model_name)
-                            .map(|opt| opt.model_id.to_owned())
+                            .map(|opt| opt.model_id.to_string())
                     });
 
                 match from_settings {
@@ -603,17 +752,43 @@ impl ChatSession {
 
         // Reload prior conversation
         let mut existing_conversation = false;
-        let previous_conversation = std::env::current_dir()
-            .ok()
-            .and_then(|cwd| os.database.get_conversation_by_path(cwd).ok())
-            .flatten();
+        let previous_conversation = match resume {
+            Some(ResumeFrom::MostRecent) => match os.database.get_last_conversation() {
+                Ok(Some(cs)) => Some(cs),
+                Ok(None) => {
+                    execute!(
+                        stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print("No previous conversation to continue, starting a new one.\n"),
+                        style::ResetColor,
+                    )?;
+                    None
+                },
+                Err(err) => {
+                    warn!(?err, "failed to load the most recent conversation");
+                    execute!(
+                        stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(
+                            "The previous conversation could not be loaded, possibly because it was saved by a different version. Starting a new one.\n"
+                        ),
+                        style::ResetColor,
+                    )?;
+                    None
+                },
+            },
+            Some(ResumeFrom::CurrentDirectory) => std::env::current_dir()
+                .ok()
+                .and_then(|cwd| os.database.get_conversation_by_path(cwd).ok())
+                .flatten(),
+            None => None,
+        };
 
         // Only restore conversations where there were actual messages.
         // Prevents edge case where user clears conversation then exits without chatting.
-        let conversation = match resume_conversation
-            && previous_conversation
-                .as_ref()
-                .is_some_and(|cs| !cs.history().is_empty())
+        let conversation = match previous_conversation
+            .as_ref()
+            .is_some_and(|cs| !cs.history().is_empty())
         {
             true => {
                 let mut cs = previous_conversation.unwrap();
@@ -644,6 +819,17 @@ impl ChatSession {
             },
         };
 
+        let config_watcher =
+            match interactive && os.database.settings.get_bool(Setting::ChatHotReloadConfig) == Some(true) {
+                true => {
+                    let paths = config_watcher::mcp_config_paths(os, &*conversation.tool_manager.agent.lock().await);
+                    ConfigWatcher::new(paths, CONFIG_RELOAD_DEBOUNCE)
+                        .map_err(|err| warn!(?err, "Failed to watch the MCP config for changes"))
+                        .ok()
+                },
+                false => None,
+            };
+
         // Spawn a task for listening and broadcasting sigints.
         let (ctrlc_tx, ctrlc_rx) = tokio::sync::broadcast::channel(4);
         tokio::spawn(async move {
@@ -677,14 +863,37 @@ impl ChatSession {
             tool_use_telemetry_events: HashMap::new(),
             tool_use_status: ToolUseStatus::Idle,
             failed_request_ids: Vec::new(),
+            stream_retries: 0,
+            protocol_log: None,
+            agent_mode: None,
             pending_prompts: VecDeque::new(),
+            pending_images: Vec::new(),
             interactive,
+            explain_permissions,
+            aws_profile,
+            max_turns,
+            turn_count: 0,
+            turn_limit_reached: false,
+            tool_call_repeats: ToolCallRepeats::default(),
             inner: Some(ChatState::default()),
             ctrlc_rx,
+            config_watcher,
+            stats: SessionStats::default(),
         })
     }
 
     pub async fn next(&mut self, os: &mut Os) -> Result<(), ChatError> {
+        // Servers are only swapped out in between tool uses
+        if self.tool_uses.is_empty() && self.config_watcher.as_ref().is_some_and(ConfigWatcher::take_change) {
+            execute!(
+                self.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("\nThe MCP config has changed, reloading...\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            self.reload_mcp_config(os, false).await?;
+        }
+
         // Update conversation state with new tool information
         self.conversation.update_state(false).await;
 
@@ -696,6 +905,9 @@ impl ChatSession {
                         self.inner = Some(ChatState::Exit);
                         return Ok(());
                     },
+                    (false, false) if self.turn_limit_reached => {
+                        return Err(ChatError::MaxTurnsReached(self.turn_count));
+                    },
                     (false, false) => {
                         return Err(ChatError::NonInteractiveToolApproval);
                     },
@@ -890,6 +1102,22 @@ impl ChatSession {
                         style::SetAttribute(Attribute::Reset),
                         style::SetForegroundColor(Color::Reset),
                     )?;
+                    // Higher request limits come with a subscription.
+                    if get_subscription_status(os)
+                        .await
+                        .is_ok_and(|s| s == ActualSubscriptionStatus::None)
+                    {
+                        execute!(
+                            self.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print("Use "),
+                            style::SetForegroundColor(Color::Green),
+                            style::Print("/subscribe"),
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(" to upgrade your subscription for higher limits.\n\n"),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
                     ("Amazon Q is having trouble responding right now", eyre!(err), false)
                 },
                 ApiClientError::ModelOverloadedError { request_id, .. } => {
@@ -1103,31 +1331,88 @@ impl Default for ChatState {
 }
 
 impl ChatSession {
+    /// Seeds the conversation with an assistant `response` to `prompt` without a network call,
+    /// e.g. to prime the session with a prior analysis.
+    #[allow(dead_code)]
+    pub fn inject_assistant_message(
+        &mut self,
+        os: &mut Os,
+        prompt: impl Into<String>,
+        response: impl Into<String>,
+    ) -> Result<(), ChatError> {
+        self.conversation
+            .push_synthetic_response(os, prompt.into(), response.into())
+    }
+
     /// Sends a request to the SendMessage API. Emits error telemetry on failure.
+    ///
+    /// Throttled requests are retried up to [MAX_THROTTLE_RETRIES] times with exponential backoff.
     async fn send_message(
         &mut self,
         os: &mut Os,
-        conversation_state: api_client::model::ConversationState,
+        mut conversation_state: api_client::model::ConversationState,
         request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
         message_meta_tags: Option<Vec<MessageMetaTag>>,
     ) -> Result<SendMessageStream, ChatError> {
-        match SendMessageStream::send_message(&os.client, conversation_state, request_metadata_lock, message_meta_tags)
+        conversation_state.agent_mode = self.agent_mode.clone();
+        self.stats.chars_sent += *self.conversation.request_char_count();
+        let mut attempt = 0;
+        loop {
+            let err = match SendMessageStream::send_message(
+                &os.client,
+                conversation_state.clone(),
+                Arc::clone(&request_metadata_lock),
+                message_meta_tags.clone(),
+                self.protocol_log.clone(),
+            )
             .await
-        {
-            Ok(res) => Ok(res),
-            Err(err) => {
-                let (reason, reason_desc) = get_error_reason(&err);
-                self.send_chat_telemetry(
-                    os,
-                    TelemetryResult::Failed,
-                    Some(reason),
-                    Some(reason_desc),
-                    err.status_code(),
-                    true, // We never retry failed requests, so this always ends the current turn.
-                )
-                .await;
-                Err(err.into())
-            },
+            {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+
+            let is_throttled = matches!(err.source, ApiClientError::QuotaBreach { .. });
+            let will_retry = is_throttled && attempt < MAX_THROTTLE_RETRIES;
+            let (reason, reason_desc) = get_error_reason(&err);
+            self.send_chat_telemetry(
+                os,
+                TelemetryResult::Failed,
+                Some(reason),
+                Some(reason_desc),
+                err.status_code(),
+                !will_retry,
+            )
+            .await;
+            if !will_retry {
+                return Err(err.into());
+            }
+
+            attempt += 1;
+            let backoff = throttle_backoff(attempt);
+            warn!(?err, attempt, ?backoff, "request was throttled, retrying");
+
+            let spinner = self.spinner.take();
+            if spinner.is_some() {
+                drop(spinner);
+                queue!(
+                    self.stderr,
+                    cursor::MoveToColumn(0),
+                    terminal::Clear(terminal::ClearType::CurrentLine),
+                )?;
+            }
+            execute!(
+                self.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "Being rate limited, retrying in {}s ({attempt}/{MAX_THROTTLE_RETRIES})...\n",
+                    backoff.as_secs_f64().round()
+                )),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            tokio::time::sleep(backoff).await;
+            if self.interactive {
+                self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+            }
         }
     }
 
@@ -1197,7 +1482,7 @@ impl ChatSession {
         self.stderr.flush()?;
 
         if let Some(ref id) = self.conversation.model {
-            let model_options = get_model_options(os).await?;
+            let model_options = get_model_options(os, false).await?;
             if let Some(model_option) = model_options.iter().find(|option| option.model_id == *id) {
                 execute!(
                     self.stderr,
@@ -1307,7 +1592,7 @@ impl ChatSession {
 
         if self.interactive {
             execute!(self.stderr, cursor::Hide, style::Print("\n"))?;
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Creating summary...".to_string()));
+            self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Creating summary...".to_string()));
         }
 
         let mut response = match self
@@ -1453,6 +1738,7 @@ impl ChatSession {
                 style::SetForegroundColor(Color::DarkGrey)
             )?;
 
+            let typewriter_speed = TypewriterSpeed::from_settings(os);
             let mut output = Vec::new();
             if let Some(custom_prompt) = &custom_prompt {
                 execute!(
@@ -1460,7 +1746,7 @@ impl ChatSession {
                     style::Print(format!("• Custom prompt applied: {}\n", custom_prompt))
                 )?;
             }
-            animate_output(&mut self.stderr, &output)?;
+            animate_output(&mut self.stderr, &output, typewriter_speed)?;
 
             // Display the summary if the show_summary flag is set
             if show_summary {
@@ -1489,7 +1775,7 @@ impl ChatSession {
                     style::Print("The conversation history has been replaced with this summary.\n"),
                     style::Print("It contains all important details from previous interactions.\n"),
                 )?;
-                animate_output(&mut self.stderr, &output)?;
+                animate_output(&mut self.stderr, &output, typewriter_speed)?;
 
                 execute!(
                     self.stderr,
@@ -1514,6 +1800,39 @@ impl ChatSession {
         }
     }
 
+    /// Re-reads the MCP config of the active agent and applies it to the running servers. See
+    /// [ToolManager::reload_mcp_servers].
+    pub async fn reload_mcp_config(&mut self, os: &Os, restart_all: bool) -> Result<(), ChatError> {
+        let agent = self.conversation.tool_manager.agent.lock().await.clone();
+
+        match config_watcher::load_mcp_config(os, &agent).await {
+            Ok(config) => {
+                if let Err(err) = self
+                    .conversation
+                    .tool_manager
+                    .reload_mcp_servers(os, config, restart_all, &mut self.stderr)
+                    .await
+                {
+                    error!(?err, "Failed to reload the MCP servers");
+                }
+            },
+            Err(err) => {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print("✗ "),
+                    style::ResetColor,
+                    style::Print(format!(
+                        "Failed to reload the MCP config, keeping the current servers: {err}\n"
+                    )),
+                )?;
+            },
+        }
+        execute!(self.stderr, style::Print("\n"))?;
+
+        Ok(())
+    }
+
     /// Read input from the user.
     async fn prompt_user(&mut self, os: &Os, skip_printing_tools: bool) -> Result<ChatState, ChatError> {
         execute!(self.stderr, cursor::Show)?;
@@ -1688,7 +2007,13 @@ impl ChatSession {
             Ok(ChatState::PromptUser {
                 skip_printing_tools: false,
             })
-        } else if let Some(command) = input.strip_prefix("@") {
+        } else if let Some(command) = input.strip_prefix("@").filter(|command| {
+            // `@path` at the start of the input references a file rather than a prompt.
+            !command
+                .split_whitespace()
+                .next()
+                .is_some_and(|path| context::is_file_reference(os, path))
+        }) {
             let input_parts =
                 shlex::split(command).ok_or(ChatError::Custom("Error splitting prompt command".into()))?;
 
@@ -1741,7 +2066,14 @@ impl ChatSession {
             })
         } else {
             // Check for a pending tool approval
-            if let Some(index) = self.pending_tool_index {
+            // Anything but 'y' is handled below like a denied tool use.
+            if self.turn_limit_reached {
+                if ["y", "Y"].contains(&input) {
+                    self.turn_limit_reached = false;
+                    self.turn_count = 0;
+                    return Ok(ChatState::ExecuteTools);
+                }
+            } else if let Some(index) = self.pending_tool_index {
                 let is_trust = ["t", "T"].contains(&input);
                 let tool_use = &mut self.tool_uses[index];
                 if ["y", "Y"].contains(&input) || is_trust {
@@ -1776,6 +2108,53 @@ impl ChatSession {
             // Otherwise continue with normal chat on 'n' or other responses
             self.tool_use_status = ToolUseStatus::Idle;
 
+            if user_input.contains('#') {
+                let (expanded, warnings) =
+                    cli::prompts::expand_prompt_references(&self.conversation.tool_manager, &user_input).await;
+                for warning in warnings {
+                    queue!(
+                        self.stderr,
+                        style::SetForegroundColor(Color::Yellow),
+                        style::Print(format!("{warning}\n")),
+                        style::SetForegroundColor(Color::Reset)
+                    )?;
+                }
+                user_input = expanded;
+            }
+
+            let file_references = context::collect_file_references(
+                os,
+                &user_input,
+                calc_max_context_files_size(self.conversation.model.as_deref()),
+            )
+            .await;
+            for error in &file_references.errors {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!("Not attaching @ reference: {error}\n")),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+            for filename in &file_references.binary_files {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!("Skipping binary file {filename}\n")),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+            for (filename, _) in &file_references.dropped_files {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "Not attaching {filename} as the referenced files are too large\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset)
+                )?;
+            }
+
             if self.pending_tool_index.is_some() {
                 // If the user just enters "n", replace the message we send to the model with
                 // something more substantial.
@@ -1791,8 +2170,19 @@ impl ChatSession {
             } else {
                 self.conversation.set_next_user_message(user_input).await;
             }
+            self.conversation.attach_file_references(os, &file_references.files);
+            self.conversation.attach_images(
+                std::mem::take(&mut self.pending_images)
+                    .into_iter()
+                    .map(|(block, _)| block)
+                    .collect(),
+            );
 
             self.reset_user_turn();
+            self.turn_count = 0;
+            self.stats.prompts += 1;
+            self.turn_limit_reached = false;
+            self.tool_call_repeats.reset();
 
             let conv_state = self
                 .conversation
@@ -1805,7 +2195,7 @@ impl ChatSession {
             queue!(self.stderr, cursor::Hide)?;
 
             if self.interactive {
-                self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
+                self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
             }
 
             Ok(ChatState::HandleResponseStream(conv_state))
@@ -1813,6 +2203,34 @@ impl ChatSession {
     }
 
     async fn tool_use_execute(&mut self, os: &mut Os) -> Result<ChatState, ChatError> {
+        // Pause runaway tool use loops once the turn budget is spent.
+        if let Some(max_turns) = self.max_turns.filter(|max_turns| self.turn_count >= *max_turns) {
+            self.turn_limit_reached = true;
+            self.pending_tool_index = Some(0);
+            execute!(
+                self.stderr,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(format!(
+                    "\nReached the limit of {} turns without user input ({} turns ran).",
+                    max_turns, self.turn_count
+                )),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(format!("\nContinue for another {max_turns} turns? [")),
+                style::SetForegroundColor(Color::Green),
+                style::Print("y"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("/"),
+                style::SetForegroundColor(Color::Green),
+                style::Print("n"),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("]:\n\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            return Ok(ChatState::PromptUser {
+                skip_printing_tools: true,
+            });
+        }
+
         // Verify tools have permissions.
         for i in 0..self.tool_uses.len() {
             let tool = &mut self.tool_uses[i];
@@ -1822,30 +2240,53 @@ impl ChatSession {
                 continue;
             }
 
-            let mut denied = false;
-            let allowed =
-                self.conversation
-                    .agents
-                    .get_active()
-                    .is_some_and(|a| match tool.tool.requires_acceptance(a) {
-                        PermissionEvalResult::Allow => true,
-                        PermissionEvalResult::Ask => false,
-                        PermissionEvalResult::Deny => {
-                            denied = true;
-                            false
-                        },
-                    })
-                    || self.conversation.agents.trust_all_tools;
+            let (eval_result, mut explanation) = match self.conversation.agents.get_active() {
+                Some(a) => tool.tool.explain_acceptance(a),
+                None => (PermissionEvalResult::Ask, "ask: no active agent".to_string()),
+            };
+            let denied = eval_result == PermissionEvalResult::Deny;
+            let allowed = eval_result == PermissionEvalResult::Allow || self.conversation.agents.trust_all_tools;
+            if !denied && eval_result != PermissionEvalResult::Allow && allowed {
+                explanation = "allowed: all tools are trusted".to_string();
+            }
+
+            if self.explain_permissions {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!("{}: {}\n", tool.name, explanation)),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
 
             if denied {
                 return Ok(ChatState::HandleInput {
                     input: format!(
-                        "Tool use with {} was rejected because the arguments supplied were forbidden",
+                        "Tool use with {} was rejected because the arguments supplied were forbidden ({explanation})",
                         tool.name
                     ),
                 });
             }
 
+            let pre_tool_use_hooks = self
+                .conversation
+                .context_manager
+                .as_ref()
+                .and_then(|cm| cm.hooks.get(&HookTrigger::PreToolUse))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if let Err(err) = run_pre_tool_use_hooks(pre_tool_use_hooks, &tool.name, &tool.args).await {
+                queue!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(format!("Tool use with {} was blocked: {err}\n", tool.name)),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                return Ok(ChatState::HandleInput {
+                    input: format!("Tool use with {} was blocked by a preToolUse hook: {err}", tool.name),
+                });
+            }
+
             if os
                 .database
                 .settings
@@ -1878,9 +2319,19 @@ impl ChatSession {
         // Execute the requested tools.
         let mut tool_results = vec![];
         let mut image_blocks: Vec<RichImageBlock> = Vec::new();
-
-        for tool in &self.tool_uses {
-            let tool_start = std::time::Instant::now();
+        let max_parallel_tools = os
+            .database
+            .settings
+            .get_int(Setting::ChatMaxParallelTools)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(1);
+        // Results of tools that were invoked ahead of time, concurrently with the ones after them
+        let mut invoked = VecDeque::new();
+
+        for (i, tool) in self.tool_uses.iter().enumerate() {
+            if invoked.is_empty() {
+                invoked = invoke_concurrently(&self.tool_uses[i..], os, max_parallel_tools).await;
+            }
             let mut tool_telemetry = self.tool_use_telemetry_events.entry(tool.id.clone());
             tool_telemetry = tool_telemetry.and_modify(|ev| {
                 ev.is_accepted = true;
@@ -1898,10 +2349,21 @@ impl ChatSession {
                 }
             }
 
-            let invoke_result = tool
-                .tool
-                .invoke(os, &mut self.stdout, &mut self.conversation.file_line_tracker)
-                .await;
+            let (invoke_result, tool_time) = match invoked.pop_front() {
+                Some((invoke_result, output, tool_time)) => {
+                    self.stdout.write_all(&output)?;
+                    (invoke_result, tool_time)
+                },
+                None => {
+                    let tool_start = std::time::Instant::now();
+                    let invoke_result = tool
+                        .tool
+                        .invoke(os, &mut self.stdout, &mut self.conversation.file_line_tracker)
+                        .await;
+                    (invoke_result, tool_start.elapsed())
+                },
+            };
+            self.stats.record_tool_use(os, tool, invoke_result.is_ok());
 
             if self.spinner.is_some() {
                 queue!(
@@ -1914,7 +2376,6 @@ impl ChatSession {
             execute!(self.stdout, style::Print("\n"))?;
 
             let tool_end_time = Instant::now();
-            let tool_time = tool_end_time.duration_since(tool_start);
             tool_telemetry = tool_telemetry.and_modify(|ev| {
                 ev.execution_duration = Some(tool_time);
                 ev.turn_duration = self.tool_turn_start_time.map(|t| tool_end_time.duration_since(t));
@@ -1986,13 +2447,36 @@ impl ChatSession {
                                 .await
                                 .ok();
 
-                            tracker.prev_fswrite_lines = tracker.after_fswrite_lines;
+                            tracker.finish_write();
+                        }
+                    }
+
+                    let mut content: ToolUseResultBlock = result.into();
+                    let post_tool_use_hooks = self
+                        .conversation
+                        .context_manager
+                        .as_ref()
+                        .and_then(|cm| cm.hooks.get(&HookTrigger::PostToolUse))
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    for hook in post_tool_use_hooks {
+                        match run_post_tool_use_hook(hook, &tool.name, &tool.args, &content).await {
+                            Ok(transformed) => content = transformed,
+                            Err(err) => {
+                                warn!(?err, "postToolUse hook failed");
+                                queue!(
+                                    self.stderr,
+                                    style::SetForegroundColor(Color::Yellow),
+                                    style::Print(format!("Warning: {err}. Keeping the tool output unchanged.\n\n")),
+                                    style::SetForegroundColor(Color::Reset),
+                                )?;
+                            },
                         }
                     }
 
                     tool_results.push(ToolUseResult {
                         tool_use_id: tool.id.clone(),
-                        content: vec![result.into()],
+                        content: vec![content],
                         status: ToolResultStatus::Success,
                     });
                 },
@@ -2051,7 +2535,7 @@ impl ChatSession {
         execute!(self.stderr, cursor::Hide)?;
         execute!(self.stderr, style::Print("\n"), style::SetAttribute(Attribute::Reset))?;
         if self.interactive {
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+            self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_string()));
         }
 
         self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
@@ -2077,6 +2561,7 @@ impl ChatSession {
         state: crate::api_client::model::ConversationState,
         request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
     ) -> Result<ChatState, ChatError> {
+        let request_start = Instant::now();
         let mut rx = self.send_message(os, state, request_metadata_lock, None).await?;
 
         let request_id = rx.request_id().map(String::from);
@@ -2084,6 +2569,9 @@ impl ChatSession {
         let mut buf = String::new();
         let mut offset = 0;
         let mut ended = false;
+        // Only set once this response's EndStream arrives, so that a stream closed early doesn't
+        // show the stats of the previous response.
+        let mut end_stream_metadata = None::<RequestMetadata>;
         let mut state = ParseState::new(
             Some(self.terminal_width()),
             os.database.settings.get_bool(Setting::ChatDisableMarkdownRendering),
@@ -2103,9 +2591,15 @@ impl ChatSession {
                 terminal::Clear(terminal::ClearType::CurrentLine),
             )?;
         }
+        // Keep showing that the request is in flight until the first chunk of the response
+        // arrives.
+        let mut waiting_spinner =
+            (self.interactive && should_animate()).then(|| ElapsedSpinner::new("Thinking...", request_start));
 
         loop {
-            match rx.recv().await {
+            let event = rx.recv().await;
+            drop(waiting_spinner.take());
+            match event {
                 Some(Ok(msg_event)) => {
                     trace!("Consumed: {:?}", msg_event);
                     match msg_event {
@@ -2151,7 +2645,11 @@ impl ChatSession {
                                 error!(?request_id, ?message, "Encountered an unexpected model response");
                             }
                             self.conversation.push_assistant_message(os, message, Some(rm.clone()));
+                            self.stats.responses += 1;
+                            self.stats.chars_received += rm.response_size;
+                            end_stream_metadata = Some(rm.clone());
                             self.user_turn_request_metadata.push(rm);
+                            self.stream_retries = 0;
                             ended = true;
                         },
                     }
@@ -2167,7 +2665,55 @@ impl ChatSession {
                     let status_code = recv_error.status_code();
 
                     match recv_error.source {
+                        RecvErrorKind::Client(source)
+                            if source.is_stream_interrupted() && self.stream_retries < MAX_STREAM_RETRIES =>
+                        {
+                            self.stream_retries += 1;
+                            self.send_chat_telemetry(
+                                os,
+                                TelemetryResult::Failed,
+                                Some(reason),
+                                Some(reason_desc),
+                                status_code,
+                                false, // We retry the request, so don't end the current turn yet.
+                            )
+                            .await;
+
+                            error!(
+                                recv_error.request_metadata.request_id,
+                                ?source,
+                                "The response stream was interrupted, retrying ({}/{})",
+                                self.stream_retries,
+                                MAX_STREAM_RETRIES
+                            );
+
+                            // The partial response isn't added to the conversation, so the same
+                            // request is sent again.
+                            execute!(
+                                self.stderr,
+                                style::SetForegroundColor(Color::Yellow),
+                                style::Print(format!(
+                                    "\n\nConnection dropped, retrying ({}/{})...\n\n",
+                                    self.stream_retries, MAX_STREAM_RETRIES
+                                )),
+                                style::SetForegroundColor(Color::Reset),
+                                cursor::Hide,
+                            )?;
+                            tokio::time::sleep(Duration::from_secs(self.stream_retries as u64)).await;
+                            if self.interactive {
+                                self.spinner =
+                                    should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+                            }
+
+                            return Ok(ChatState::HandleResponseStream(
+                                self.conversation
+                                    .as_sendable_conversation_state(os, &mut self.stderr, false)
+                                    .await?,
+                            ));
+                        },
                         RecvErrorKind::StreamTimeout { source, duration } => {
+                            // A different request is sent, rather than the interrupted one re-sent
+                            self.stream_retries = 0;
                             self.send_chat_telemetry(
                                 os,
                                 TelemetryResult::Failed,
@@ -2186,7 +2732,8 @@ impl ChatSession {
                             );
 
                             execute!(self.stderr, cursor::Hide)?;
-                            self.spinner = Some(Spinner::new(Spinners::Dots, "Dividing up the work...".to_string()));
+                            self.spinner = should_animate()
+                                .then(|| Spinner::new(Spinners::Dots, "Dividing up the work...".to_string()));
 
                             // For stream timeouts, we'll tell the model to try and split its response into
                             // smaller chunks.
@@ -2214,6 +2761,7 @@ impl ChatSession {
                             message,
                             ..
                         } => {
+                            self.stream_retries = 0;
                             self.send_chat_telemetry(
                                 os,
                                 TelemetryResult::Failed,
@@ -2246,6 +2794,7 @@ impl ChatSession {
                             ));
                         },
                         _ => {
+                            self.stream_retries = 0;
                             self.send_chat_telemetry(
                                 os,
                                 TelemetryResult::Failed,
@@ -2308,7 +2857,7 @@ impl ChatSession {
             if tool_name_being_recvd.is_some() {
                 queue!(self.stderr, cursor::Hide)?;
                 if self.interactive {
-                    self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+                    self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_string()));
                 }
             }
 
@@ -2326,6 +2875,22 @@ impl ChatSession {
                 queue!(self.stderr, style::ResetColor, style::SetAttribute(Attribute::Reset))?;
                 execute!(self.stdout, style::Print("\n"))?;
 
+                if let (true, Some(request_metadata), Some(entry)) = (
+                    os.database.settings.get_bool(Setting::ChatShowStats).unwrap_or(false),
+                    end_stream_metadata.as_ref(),
+                    self.conversation.history().back(),
+                ) {
+                    execute!(
+                        self.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!(
+                            "{}\n",
+                            response_stats(entry.assistant().content(), request_metadata)
+                        )),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+
                 for (i, citation) in &state.citations {
                     queue!(
                         self.stdout,
@@ -2342,6 +2907,7 @@ impl ChatSession {
             }
         }
 
+        self.turn_count += 1;
         if !tool_uses.is_empty() {
             Ok(ChatState::ValidateTools { tool_uses })
         } else {
@@ -2363,15 +2929,64 @@ impl ChatSession {
         debug!(?tool_uses, "Validating tool uses");
         let mut queued_tools: Vec<QueuedTool> = Vec::new();
         let mut tool_results: Vec<ToolUseResult> = Vec::new();
+        let max_repeats = os
+            .database
+            .settings
+            .get_int(Setting::ChatMaxRepeatedToolCalls)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(DEFAULT_MAX_REPEATED_TOOL_CALLS);
 
         for tool_use in tool_uses {
             let tool_use_id = tool_use.id.clone();
             let tool_use_name = tool_use.name.clone();
+            let tool_use_args = tool_use.args.clone();
             let mut tool_telemetry =
                 ToolUseEventBuilder::new(conv_id.clone(), tool_use.id.clone(), self.conversation.model.clone())
                     .set_tool_use_id(tool_use_id.clone())
                     .set_tool_name(tool_use.name.clone())
                     .utterance_id(self.conversation.message_id().map(|s| s.to_string()));
+            let repeats = self.tool_call_repeats.record(&tool_use.name, &tool_use.args);
+            if max_repeats > 0 && repeats > max_repeats {
+                tool_telemetry.is_valid = Some(false);
+                tool_results.push(ToolUseResult {
+                    tool_use_id: tool_use_id.clone(),
+                    content: vec![ToolUseResultBlock::Text(format!(
+                        "Refusing to run {}: it was called with identical arguments {} times in a row. Do not repeat this call. Try a different approach, or ask the user how to proceed.",
+                        tool_use.name, repeats
+                    ))],
+                    status: ToolResultStatus::Error,
+                });
+                self.tool_use_telemetry_events.insert(tool_use_id, tool_telemetry);
+                continue;
+            }
+
+            // MCP servers keep loading in the background after the prompt is shown, so the tool may
+            // belong to a server that is not ready yet.
+            if !self.conversation.tool_manager.schema.contains_key(&tool_use.name)
+                && !self.conversation.tool_manager.pending_clients().await.is_empty()
+            {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!("Waiting for MCP servers to load {}...\n", tool_use.name)),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                let timeout = os
+                    .database
+                    .settings
+                    .get_int(Setting::McpToolLoadTimeout)
+                    .and_then(|n| u64::try_from(n).ok())
+                    .unwrap_or(DEFAULT_MCP_TOOL_LOAD_TIMEOUT);
+                if self
+                    .conversation
+                    .tool_manager
+                    .wait_for_tool(&tool_use.name, Duration::from_millis(timeout))
+                    .await
+                {
+                    self.conversation.update_state(false).await;
+                }
+            }
+
             match self.conversation.tool_manager.get_tool_from_tool_use(tool_use) {
                 Ok(mut tool) => {
                     // Apply non-Q-generated context to tools
@@ -2385,6 +3000,7 @@ impl ChatSession {
                                 name: tool_use_name,
                                 tool,
                                 accepted: false,
+                                args: tool_use_args,
                             });
                         },
                         Err(err) => {
@@ -2479,7 +3095,7 @@ impl ChatSession {
         }
 
         if self.interactive {
-            self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
+            self.spinner = should_animate().then(|| Spinner::new(Spinners::Dots, "Thinking...".to_owned()));
         }
 
         Ok(ChatState::HandleResponseStream(
@@ -2494,6 +3110,24 @@ impl ChatSession {
     // output from Amazon Q.
     // TODO: Is there a better way?
     fn contextualize_tool(&self, tool: &mut Tool) {
+        if let Some(agent) = self.conversation.agents.get_active() {
+            match tool {
+                Tool::UseAws(use_aws) => {
+                    use_aws.max_response_size = tools::max_response_size(agent, "use_aws");
+                    use_aws.max_pages = tools::use_aws::auto_paginate_max_pages(agent);
+                },
+                Tool::ExecuteCommand(execute_cmd) => {
+                    let tool_name = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
+                    execute_cmd.max_response_size = tools::max_response_size(agent, tool_name);
+                },
+                _ => {},
+            }
+        }
+
+        if let Tool::UseAws(use_aws) = tool {
+            use_aws.default_profile_name = self.aws_profile.clone();
+        }
+
         if let Tool::GhIssue(gh_issue) = tool {
             let allowed_tools = self
                 .conversation
@@ -2646,6 +3280,7 @@ impl ChatSession {
     fn reset_user_turn(&mut self) {
         info!(?self.user_turn_request_metadata, "Resetting the current user turn");
         self.user_turn_request_metadata.clear();
+        self.stream_retries = 0;
     }
 
     /// Sends an "codewhispererterminal_addChatMessage" telemetry event.
@@ -2779,6 +3414,13 @@ impl ChatSession {
     }
 }
 
+/// How long to wait before the `attempt`th retry of a throttled request: 2s, 4s, 8s, and so on,
+/// plus up to a second of jitter so that concurrent sessions don't retry in lockstep.
+fn throttle_backoff(attempt: u32) -> Duration {
+    let base = Duration::from_secs(1 << attempt.min(6));
+    base + Duration::from_millis(rand::random::<u64>() % 1000)
+}
+
 /// Replaces amzn_codewhisperer_client::types::SubscriptionStatus with a more descriptive type.
 /// See response expectations in [`get_subscription_status`] for reasoning.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -2843,7 +3485,7 @@ where
     Fut: std::future::Future<Output = Result<T, E>>,
 {
     queue!(output, cursor::Hide,).ok();
-    let spinner = Some(Spinner::new(Spinners::Dots, spinner_text.to_owned()));
+    let spinner = should_animate().then(|| Spinner::new(Spinners::Dots, spinner_text.to_owned()));
 
     let result = f().await;
 
@@ -2940,8 +3582,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -2950,12 +3592,15 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
             tool_config,
             true,
+            false,
+            None,
+            None,
         )
         .await
         .unwrap()
@@ -2966,6 +3611,57 @@ mod tests {
         assert_eq!(os.fs.read_to_string("/file.txt").await.unwrap(), "Hello, world!\n");
     }
 
+    #[tokio::test]
+    async fn test_inject_assistant_message() {
+        let mut os = Os::new().await.unwrap();
+        let agents = get_test_agents(&os).await;
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        let mut session = ChatSession::new(
+            &mut os,
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let response = "Here is the prior analysis.".repeat(100);
+        session
+            .inject_assistant_message(&mut os, "analyze this", response.clone())
+            .unwrap();
+
+        let entry = session.conversation.history().back().unwrap();
+        assert_eq!(entry.user().prompt(), Some("analyze this"));
+        assert_eq!(entry.assistant().content(), response);
+
+        let size = session
+            .conversation
+            .backend_conversation_state(&os, false, &mut vec![])
+            .await
+            .unwrap()
+            .calculate_conversation_size();
+        assert!(*size.assistant_messages >= response.len());
+        assert!(*size.user_messages >= "analyze this".len());
+
+        // A user message waiting to be sent would be answered by the injected response
+        session.conversation.set_next_user_message("pending".to_string()).await;
+        assert!(session.inject_assistant_message(&mut os, "again", "response").is_err());
+    }
+
     #[tokio::test]
     async fn test_flow_tool_permissions() {
         let mut os = Os::new().await.unwrap();
@@ -3068,14 +3764,15 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
             InputSource::new_mock(vec![
                 "/tools".to_string(),
                 "/tools help".to_string(),
+                "/tools describe fs_write".to_string(),
                 "create a new file".to_string(),
                 "y".to_string(),
                 "create a new file".to_string(),
@@ -3091,12 +3788,15 @@ mod tests {
                 "n".to_string(),             // cancel
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
             tool_config,
             true,
+            false,
+            None,
+            None,
         )
         .await
         .unwrap()
@@ -3173,8 +3873,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -3187,12 +3887,15 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
             tool_config,
             true,
+            false,
+            None,
+            None,
         )
         .await
         .unwrap()
@@ -3249,8 +3952,8 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
@@ -3261,12 +3964,15 @@ mod tests {
                 "create a new file".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
             tool_config,
             true,
+            false,
+            None,
+            None,
         )
         .await
         .unwrap()
@@ -3278,6 +3984,107 @@ mod tests {
         assert!(!os.fs.exists("/file2.txt"));
     }
 
+    #[tokio::test]
+    async fn test_flow_max_turns() {
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file1.txt",
+                    }
+                }
+            ],
+            [
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file2.txt",
+                    }
+                }
+            ],
+            [
+                "Ok I won't.",
+            ],
+            [
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file3.txt",
+                    }
+                }
+            ],
+            [
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": "/file4.txt",
+                    }
+                }
+            ],
+            [
+                "Done",
+            ],
+        ]));
+
+        let agents = get_test_agents(&os).await;
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![
+                "/tools trust-all".to_string(),
+                "create some files".to_string(),
+                "n".to_string(),
+                "create some more files".to_string(),
+                "y".to_string(),
+                "exit".to_string(),
+            ]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            Some(2),
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        assert!(os.fs.exists("/file1.txt"));
+        assert!(!os.fs.exists("/file2.txt"));
+        assert!(os.fs.exists("/file3.txt"));
+        assert!(os.fs.exists("/file4.txt"));
+    }
+
     #[test]
     fn test_editor_content_processing() {
         // Since we no longer have template replacement, this test is simplified
@@ -3305,18 +4112,21 @@ mod tests {
             .expect("Tools failed to load");
         ChatSession::new(
             &mut os,
-            std::io::stdout(),
-            std::io::stderr(),
+            ChatOutput::new(std::io::stdout()),
+            ChatOutput::new(std::io::stderr()),
             "fake_conv_id",
             agents,
             None,
             InputSource::new_mock(vec!["/subscribe".to_string(), "y".to_string(), "/quit".to_string()]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
             tool_config,
             true,
+            false,
+            None,
+            None,
         )
         .await
         .unwrap()
@@ -3341,4 +4151,16 @@ mod tests {
             assert_eq!(actual, *expected, "expected {} for input {}", expected, input);
         }
     }
+
+    #[test]
+    fn test_throttle_backoff() {
+        for attempt in 1..=MAX_THROTTLE_RETRIES {
+            let backoff = throttle_backoff(attempt);
+            let base = Duration::from_secs(1 << attempt);
+            assert!(
+                backoff >= base && backoff < base + Duration::from_secs(1),
+                "{backoff:?}"
+            );
+        }
+    }
 }
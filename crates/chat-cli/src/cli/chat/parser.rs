@@ -29,6 +29,7 @@ use super::message::{
     AssistantMessage,
     AssistantToolUse,
 };
+use super::protocol_log::ProtocolLog;
 use crate::api_client::model::{
     ChatResponseStream,
     ConversationState,
@@ -184,6 +185,7 @@ impl SendMessageStream {
     /// * `request_metadata_lock` - a mutex that will be updated with metadata about the consumed
     ///   response stream on stream completion (ie, [ResponseEvent::EndStream] is returned) or on
     ///   drop.
+    /// * `protocol_log` - if set, the request and every event received are written to it
     ///
     /// # Details
     ///
@@ -201,7 +203,11 @@ impl SendMessageStream {
         conversation_state: ConversationState,
         request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
         message_meta_tags: Option<Vec<MessageMetaTag>>,
+        protocol_log: Option<ProtocolLog>,
     ) -> Result<Self, SendMessageError> {
+        if let Some(protocol_log) = &protocol_log {
+            protocol_log.request(&conversation_state);
+        }
         let message_id = uuid::Uuid::new_v4().to_string();
         info!(?message_id, "Generated new message id");
         let user_prompt_length = conversation_state.user_input_message.content.len();
@@ -247,6 +253,7 @@ impl SendMessageStream {
                 start_time_sys,
                 cancel_token_clone,
                 request_metadata_lock,
+                protocol_log,
             )
             .try_recv()
             .await;
@@ -312,6 +319,8 @@ struct ResponseParser {
     received_response_size: usize,
     time_to_first_chunk: Option<Duration>,
     time_between_chunks: Vec<Duration>,
+    /// Set with `/debug protocol on` to log each event received.
+    protocol_log: Option<ProtocolLog>,
 }
 
 impl ResponseParser {
@@ -327,6 +336,7 @@ impl ResponseParser {
         request_start_time_sys: SystemTime,
         cancel_token: CancellationToken,
         request_metadata: Arc<Mutex<Option<RequestMetadata>>>,
+        protocol_log: Option<ProtocolLog>,
     ) -> Self {
         Self {
             response,
@@ -347,6 +357,7 @@ impl ResponseParser {
             time_between_chunks: Vec::new(),
             request_metadata,
             cancel_token,
+            protocol_log,
         }
     }
 
@@ -555,6 +566,9 @@ impl ResponseParser {
         match result {
             Ok(ev) => {
                 trace!(?ev, "Received new event");
+                if let (Some(protocol_log), Some(ev)) = (&self.protocol_log, &ev) {
+                    protocol_log.event(ev);
+                }
 
                 // Track metadata about the chunk.
                 self.time_to_first_chunk
@@ -739,6 +753,7 @@ mod tests {
             SystemTime::now(),
             CancellationToken::new(),
             Arc::new(Mutex::new(None)),
+            None,
         );
 
         for _ in 0..5 {
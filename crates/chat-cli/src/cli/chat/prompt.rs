@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::path::PathBuf;
 
 use eyre::Result;
 use rustyline::completion::{
@@ -39,20 +40,36 @@ pub use super::prompt_parser::generate_prompt;
 use super::prompt_parser::parse_prompt_components;
 use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::directories::chat_history_path;
 
 pub const COMMANDS: &[&str] = &[
     "/clear",
+    "/clear --undo",
     "/help",
     "/editor",
+    "/history",
+    "/history search",
+    "/history clear",
+    "/image",
+    "/image add",
+    "/image list",
+    "/image clear",
+    "/paste",
     "/issue",
     "/quit",
     "/tools",
+    "/tools describe",
     "/tools trust",
     "/tools untrust",
     "/tools trust-all",
     "/tools reset",
+    "/tools permissions export",
+    "/tools permissions import",
     "/mcp",
+    "/mcp reload",
     "/model",
+    "/model --refresh",
+    "/model compare",
     "/agent",
     "/agent help",
     "/agent list",
@@ -79,10 +96,24 @@ pub const COMMANDS: &[&str] = &[
     "/hooks disable-all",
     "/compact",
     "/compact help",
+    "/pin",
+    "/pin --list",
+    "/forget",
+    "/debug",
+    "/debug last-request",
+    "/debug protocol on",
+    "/debug protocol off",
+    "/env",
     "/usage",
+    "/usage --by-message",
+    "/summary",
+    "/diffstat",
     "/save",
     "/load",
+    "/export",
     "/subscribe",
+    "/subscribe --status",
+    "/subscribe request-limit",
 ];
 
 /// Complete commands that start with a slash
@@ -228,6 +259,11 @@ impl ChatHinter {
         }
     }
 
+    /// Clears the command history
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
     /// Updates the history with a new command
     pub fn update_history(&mut self, command: &str) {
         let command = command.trim();
@@ -318,6 +354,11 @@ impl ChatHelper {
     pub fn update_hinter_history(&mut self, command: &str) {
         self.hinter.update_history(command);
     }
+
+    /// Clears the history of the ChatHinter
+    pub fn clear_hinter_history(&mut self) {
+        self.hinter.clear_history();
+    }
 }
 
 impl Validator for ChatHelper {
@@ -368,6 +409,16 @@ impl Highlighter for ChatHelper {
     }
 }
 
+/// Returns the file the prompt history is saved to, or [None] if `chat.historyFile` is set to an
+/// empty string to not save the history.
+pub fn history_path(os: &Os) -> Option<PathBuf> {
+    match os.database.settings.get_string(Setting::ChatHistoryFile) {
+        Some(path) if path.is_empty() => None,
+        Some(path) => Some(PathBuf::from(shellexpand::tilde(&path).as_ref())),
+        None => chat_history_path().ok(),
+    }
+}
+
 pub fn rl(
     os: &Os,
     sender: std::sync::mpsc::Sender<Option<String>>,
@@ -377,11 +428,19 @@ pub fn rl(
         Some("vi" | "vim") => EditMode::Vi,
         _ => EditMode::Emacs,
     };
-    let config = Config::builder()
+    let mut config = Config::builder()
         .history_ignore_space(true)
         .completion_type(CompletionType::List)
-        .edit_mode(edit_mode)
-        .build();
+        .edit_mode(edit_mode);
+    if let Some(max_entries) = os
+        .database
+        .settings
+        .get_int(Setting::ChatHistoryMaxEntries)
+        .and_then(|n| usize::try_from(n).ok())
+    {
+        config = config.max_history_size(max_entries)?;
+    }
+    let config = config.build();
 
     // Default to disabled if setting doesn't exist
     let history_hints_enabled = os
@@ -410,6 +469,12 @@ pub fn rl(
         EventHandler::Simple(Cmd::Insert(1, "\n".to_string())),
     );
 
+    // Ctrl+R searches the prompt history in place, in both edit modes
+    rl.bind_sequence(
+        KeyEvent(KeyCode::Char('r'), Modifiers::CTRL),
+        EventHandler::Simple(Cmd::ReverseSearchHistory),
+    );
+
     // Add custom keybinding for Ctrl+F to accept hint (like fish shell)
     rl.bind_sequence(
         KeyEvent(KeyCode::Char('f'), Modifiers::CTRL),
@@ -597,4 +662,20 @@ mod tests {
         let hint = hinter.hint(line, pos, &ctx);
         assert_eq!(hint, None);
     }
+
+    #[tokio::test]
+    async fn test_history_path() {
+        let mut os = Os::new().await.unwrap();
+        assert!(history_path(&os).unwrap().ends_with("chat_history.txt"));
+
+        os.database.settings.set(Setting::ChatHistoryFile, "").await.unwrap();
+        assert_eq!(history_path(&os), None);
+
+        os.database
+            .settings
+            .set(Setting::ChatHistoryFile, "/tmp/q_history")
+            .await
+            .unwrap();
+        assert_eq!(history_path(&os), Some(PathBuf::from("/tmp/q_history")));
+    }
 }
@@ -85,6 +85,14 @@ impl TokenCounter {
         Self::count_tokens_char_count(content.len())
     }
 
+    /// Estimates the number of tokens for each of the inputs, returned in the same order.
+    ///
+    /// Prefer this over calling [Self::count_tokens] in a loop or a sort comparator so that each
+    /// input is only counted once.
+    pub fn count_tokens_batch(inputs: &[&str]) -> Vec<usize> {
+        inputs.iter().map(|content| Self::count_tokens(content)).collect()
+    }
+
     fn count_tokens_char_count(count: usize) -> usize {
         (count / Self::TOKEN_TO_CHAR_RATIO + 5) / 10 * 10
     }
@@ -119,6 +127,7 @@ impl CharCounter for UserMessage {
     fn char_count(&self) -> CharCount {
         let mut total_chars = 0;
         total_chars += self.additional_context().len();
+        total_chars += self.file_context.len();
         match self.content() {
             UserMessageContent::Prompt { prompt } => {
                 total_chars += prompt.len();
@@ -190,6 +199,16 @@ mod tests {
         assert_eq!(count, (text.len() / 3 + 5) / 10 * 10);
     }
 
+    #[test]
+    fn test_token_count_batch() {
+        let inputs = ["", "short", &"a".repeat(1000), &"b".repeat(123)];
+        let counts = TokenCounter::count_tokens_batch(&inputs);
+        assert_eq!(
+            counts,
+            inputs.iter().map(|s| TokenCounter::count_tokens(s)).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_calculate_value_char_count() {
         // Test simple types
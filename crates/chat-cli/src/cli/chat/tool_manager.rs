@@ -62,7 +62,10 @@ use crate::cli::agent::{
     Agent,
     McpServerConfig,
 };
-use crate::cli::chat::cli::prompts::GetPromptError;
+use crate::cli::chat::cli::prompts::{
+    GetPromptError,
+    PromptArguments,
+};
 use crate::cli::chat::consts::DUMMY_TOOL_NAME;
 use crate::cli::chat::message::AssistantToolUse;
 use crate::cli::chat::server_messenger::{
@@ -72,6 +75,7 @@ use crate::cli::chat::server_messenger::{
 use crate::cli::chat::tools::custom_tool::{
     CustomTool,
     CustomToolClient,
+    CustomToolConfig,
 };
 use crate::cli::chat::tools::execute::ExecuteCommand;
 use crate::cli::chat::tools::fs_read::FsRead;
@@ -94,8 +98,10 @@ use crate::mcp_client::{
 };
 use crate::os::Os;
 use crate::telemetry::TelemetryThread;
-use crate::util::MCP_SERVER_TOOL_DELIMITER;
-use crate::util::directories::home_dir;
+use crate::util::{
+    MCP_SERVER_TOOL_DELIMITER,
+    directories,
+};
 
 const NAMESPACE_DELIMITER: &str = "___";
 // This applies for both mcp server and tool name since in the end the tool name as seen by the
@@ -108,7 +114,7 @@ pub fn workspace_mcp_config_path(os: &Os) -> eyre::Result<PathBuf> {
 }
 
 pub fn global_mcp_config_path(os: &Os) -> eyre::Result<PathBuf> {
-    Ok(home_dir(os)?.join(".aws").join("amazonq").join("mcp.json"))
+    Ok(directories::global_config_dir(os)?.join("mcp.json"))
 }
 
 /// Messages used for communication between the tool initialization thread and the loading
@@ -254,6 +260,8 @@ impl ToolManagerBuilder {
         {
             let (tx, mut rx) = tokio::sync::mpsc::channel::<LoadingMsg>(50);
             let disabled_servers_display_clone = disabled_servers_display.clone();
+            let mut loading = loading_servers.keys().cloned().collect::<Vec<_>>();
+            loading.sort();
             (
                 Some(tokio::task::spawn(async move {
                     let mut spinner_logo_idx: usize = 0;
@@ -266,13 +274,14 @@ impl ToolManagerBuilder {
                     }
 
                     if total > 0 {
-                        queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                        queue_init_message(spinner_logo_idx, complete, failed, total, &loading, &mut output)?;
                     }
 
                     loop {
                         match tokio::time::timeout(Duration::from_millis(50), rx.recv()).await {
                             Ok(Some(recv_result)) => match recv_result {
                                 LoadingMsg::Done { name, time } => {
+                                    loading.retain(|server_name| *server_name != name);
                                     complete += 1;
                                     execute!(
                                         output,
@@ -281,9 +290,17 @@ impl ToolManagerBuilder {
                                         terminal::Clear(terminal::ClearType::CurrentLine),
                                     )?;
                                     queue_success_message(&name, &time, &mut output)?;
-                                    queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                    queue_init_message(
+                                        spinner_logo_idx,
+                                        complete,
+                                        failed,
+                                        total,
+                                        &loading,
+                                        &mut output,
+                                    )?;
                                 },
                                 LoadingMsg::Error { name, msg, time } => {
+                                    loading.retain(|server_name| *server_name != name);
                                     failed += 1;
                                     execute!(
                                         output,
@@ -292,9 +309,17 @@ impl ToolManagerBuilder {
                                         terminal::Clear(terminal::ClearType::CurrentLine),
                                     )?;
                                     queue_failure_message(&name, &msg, time.as_str(), &mut output)?;
-                                    queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                    queue_init_message(
+                                        spinner_logo_idx,
+                                        complete,
+                                        failed,
+                                        total,
+                                        &loading,
+                                        &mut output,
+                                    )?;
                                 },
                                 LoadingMsg::Warn { name, msg, time } => {
+                                    loading.retain(|server_name| *server_name != name);
                                     complete += 1;
                                     execute!(
                                         output,
@@ -304,7 +329,14 @@ impl ToolManagerBuilder {
                                     )?;
                                     let msg = eyre::eyre!(msg.to_string());
                                     queue_warn_message(&name, &msg, time.as_str(), &mut output)?;
-                                    queue_init_message(spinner_logo_idx, complete, failed, total, &mut output)?;
+                                    queue_init_message(
+                                        spinner_logo_idx,
+                                        complete,
+                                        failed,
+                                        total,
+                                        &loading,
+                                        &mut output,
+                                    )?;
                                 },
                                 LoadingMsg::Terminate { still_loading } => {
                                     if !still_loading.is_empty() && total > 0 {
@@ -408,7 +440,6 @@ impl ToolManagerBuilder {
                                 let time_taken = (std::time::Instant::now() - init_time).as_secs_f64().abs();
                                 format!("{:.2}", time_taken)
                             });
-                        pending_clone.write().await.remove(&server_name);
                         let (tool_filter, alias_list) = {
                             let agent_lock = agent_clone.lock().await;
 
@@ -573,6 +604,9 @@ impl ToolManagerBuilder {
                                 }
                             },
                         }
+                        // Only mark the server as loaded once its tools are queued, so that
+                        // anything waiting on it can find them.
+                        pending_clone.write().await.remove(&server_name);
                         if let Some(notify) = notify_weak.upgrade() {
                             initialized.insert(server_name);
                             if initialized.len() >= total {
@@ -727,6 +761,7 @@ impl ToolManagerBuilder {
             mcp_load_record: load_record,
             agent,
             disabled_servers: disabled_servers_display,
+            messenger_builder: Some(messenger_builder),
             ..Default::default()
         })
     }
@@ -861,6 +896,10 @@ pub struct ToolManager {
     /// A collection of preferences that pertains to the conversation.
     /// As far as tool manager goes, this is relevant for tool and server filters
     pub agent: Arc<Mutex<Agent>>,
+
+    /// Used to hook servers started after the initial load (i.e. when the config is reloaded) up
+    /// to the same task that processes the tools of the initially loaded servers.
+    messenger_builder: Option<ServerMessengerBuilder>,
 }
 
 impl Clone for ToolManager {
@@ -965,7 +1004,8 @@ impl ToolManager {
                 .database
                 .settings
                 .get_int(Setting::McpInitTimeout)
-                .map_or(5000_u64, |s| s as u64);
+                .and_then(|s| u64::try_from(s).ok())
+                .unwrap_or(5000);
             Box::pin(tokio::time::sleep(std::time::Duration::from_millis(init_timeout)))
         } else {
             // if it is non-interactive we will want to use the "mcp.noInteractiveTimeout"
@@ -1109,6 +1149,7 @@ impl ToolManager {
                     client: client.clone(),
                     method: "tools/call".to_owned(),
                     params: Some(params),
+                    timeout: client.timeout_override(tool_name),
                 };
                 Tool::Custom(custom_tool)
             },
@@ -1189,7 +1230,7 @@ impl ToolManager {
     pub async fn get_prompt(
         &self,
         name: String,
-        arguments: Option<Vec<String>>,
+        arguments: Option<PromptArguments>,
     ) -> Result<JsonRpcResponse, GetPromptError> {
         let (server_name, prompt_name) = match name.split_once('/') {
             None => (None::<String>, Some(name.clone())),
@@ -1283,17 +1324,19 @@ impl ToolManager {
                     // Here we need to convert the positional arguments into key value pair
                     // The assignment order is assumed to be the order of args as they are
                     // presented in PromptGet::arguments
-                    let args = if let (Some(schema), Some(value)) = (&prompt_get.arguments, &arguments) {
-                        let params = schema.iter().zip(value.iter()).fold(
-                            HashMap::<String, String>::new(),
-                            |mut acc, (prompt_get_arg, value)| {
-                                acc.insert(prompt_get_arg.name.clone(), value.clone());
-                                acc
-                            },
-                        );
-                        Some(serde_json::json!(params))
-                    } else {
-                        None
+                    let args = match (&prompt_get.arguments, &arguments) {
+                        (Some(schema), Some(PromptArguments::Positional(value))) => {
+                            let params = schema.iter().zip(value.iter()).fold(
+                                HashMap::<String, String>::new(),
+                                |mut acc, (prompt_get_arg, value)| {
+                                    acc.insert(prompt_get_arg.name.clone(), value.clone());
+                                    acc
+                                },
+                            );
+                            Some(serde_json::json!(params))
+                        },
+                        (_, Some(PromptArguments::Named(params))) => Some(serde_json::json!(params)),
+                        _ => None,
                     };
                     let params = {
                         let mut params = serde_json::Map::new();
@@ -1303,7 +1346,7 @@ impl ToolManager {
                         }
                         Some(serde_json::Value::Object(params))
                     };
-                    let resp = client.request("prompts/get", params).await?;
+                    let resp = client.request("prompts/get", params, None).await?;
                     break 'blk Ok(resp);
                 },
                 // If we have no eligible clients this would mean one of the following:
@@ -1353,6 +1396,173 @@ impl ToolManager {
     pub async fn pending_clients(&self) -> Vec<String> {
         self.pending_clients.read().await.iter().cloned().collect::<Vec<_>>()
     }
+
+    /// Waits for the server providing `tool_name` to finish loading, giving up once no server is
+    /// loading anymore or `timeout` has elapsed. Returns whether the tool has become available.
+    ///
+    /// Newly loaded tools are only usable after [Self::update] has been called.
+    pub async fn wait_for_tool(&self, tool_name: &str, timeout: Duration) -> bool {
+        if self.schema.contains_key(tool_name) {
+            return true;
+        }
+        let wait = async {
+            loop {
+                if self
+                    .new_tool_specs
+                    .lock()
+                    .await
+                    .values()
+                    .any(|(tn_map, _)| tn_map.contains_key(tool_name))
+                {
+                    return true;
+                }
+                if self.pending_clients.read().await.is_empty() {
+                    return false;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.unwrap_or(false)
+    }
+
+    /// Applies a changed MCP server config to the running session. Servers that were removed or
+    /// disabled are stopped, and servers that were added or changed are (re)started, as are all
+    /// other enabled servers if `restart_all` is set. A server that fails to start keeps running
+    /// with its previous config, if it had one, so that a broken edit does not take its tools
+    /// away.
+    pub async fn reload_mcp_servers(
+        &mut self,
+        os: &Os,
+        McpServerConfig { mcp_servers }: McpServerConfig,
+        restart_all: bool,
+        output: &mut impl Write,
+    ) -> eyre::Result<()> {
+        let mut current = self.agent.lock().await.mcp_servers.mcp_servers.clone();
+        let mut names = current.keys().chain(mcp_servers.keys()).cloned().collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let init_timeout = os
+            .database
+            .settings
+            .get_int(Setting::McpInitTimeout)
+            .and_then(|s| u64::try_from(s).ok())
+            .unwrap_or(5000);
+
+        for name in names {
+            let (old_config, new_config) = (current.get(&name), mcp_servers.get(&name));
+            let is_unchanged = old_config == new_config;
+            if is_unchanged && (!restart_all || new_config.is_none_or(|config| config.disabled)) {
+                continue;
+            }
+
+            let Some(config) = new_config.filter(|config| !config.disabled) else {
+                self.remove_server(&name).await;
+                match new_config {
+                    Some(config) => current.insert(name.clone(), config.clone()),
+                    None => current.remove(&name),
+                };
+                queue!(
+                    output,
+                    style::SetForegroundColor(style::Color::Green),
+                    style::Print("✓ "),
+                    style::SetForegroundColor(style::Color::Blue),
+                    style::Print(&name),
+                    style::ResetColor,
+                    style::Print(" was removed from the config and has been stopped\n"),
+                )?;
+                continue;
+            };
+
+            let is_new = old_config.is_none_or(|config| config.disabled);
+            match self.start_server(os, &name, config.clone(), init_timeout).await {
+                Ok(()) => {
+                    current.insert(name.clone(), config.clone());
+                    queue!(
+                        output,
+                        style::SetForegroundColor(style::Color::Green),
+                        style::Print("✓ "),
+                        style::SetForegroundColor(style::Color::Blue),
+                        style::Print(&name),
+                        style::ResetColor,
+                        style::Print(match (is_new, is_unchanged) {
+                            (true, _) => " has been added\n",
+                            (false, true) => " has been restarted\n",
+                            (false, false) => " has been reloaded with the new config\n",
+                        }),
+                    )?;
+                },
+                Err(err) => {
+                    error!("Error reloading mcp server {name}: {:?}", err);
+                    queue!(
+                        output,
+                        style::SetForegroundColor(style::Color::Red),
+                        style::Print("✗ "),
+                        style::SetForegroundColor(style::Color::Blue),
+                        style::Print(&name),
+                        style::ResetColor,
+                        style::Print(" failed to start with the new config"),
+                        style::Print(if is_new { "" } else { ", keeping the previous one" }),
+                        style::Print(format!("\n - {err}\n")),
+                    )?;
+                },
+            }
+        }
+
+        let mut disabled_servers = current
+            .iter()
+            .filter(|(_, config)| config.disabled)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        disabled_servers.sort();
+        self.disabled_servers = disabled_servers;
+        self.agent.lock().await.mcp_servers.mcp_servers = current;
+        self.has_new_stuff.store(true, Ordering::Release);
+        output.flush()?;
+
+        Ok(())
+    }
+
+    /// Starts the server `name`, replacing the running server of the same name once the new one
+    /// has initialized. Its tools are picked up by the next [Self::update].
+    async fn start_server(&mut self, os: &Os, name: &str, config: CustomToolConfig, timeout: u64) -> eyre::Result<()> {
+        if name.contains(MCP_SERVER_TOOL_DELIMITER) || name == "builtin" {
+            eyre::bail!("Server name cannot contain {MCP_SERVER_TOOL_DELIMITER} or be the reserved word builtin");
+        }
+
+        let mut client = CustomToolClient::from_config(name.to_string(), config, os)?;
+        if let Some(messenger_builder) = &self.messenger_builder {
+            client.assign_messenger(Box::new(messenger_builder.build_with_name(name.to_string())));
+        }
+        let client = Arc::new(client);
+        let init_result = match tokio::time::timeout(Duration::from_millis(timeout), client.init()).await {
+            Ok(result) => result,
+            Err(_) => Err(eyre::eyre!("Server did not initialize within {timeout}ms")),
+        };
+
+        if let Err(err) = init_result {
+            // Lets the loading task know that the server is no longer pending. This does not
+            // touch the tools of the server that is already running.
+            if let Some(messenger_builder) = &self.messenger_builder {
+                let messenger = messenger_builder.build_with_name(name.to_string());
+                let _ = messenger
+                    .send_tools_list_result(Err(eyre::eyre!(err.to_string())))
+                    .await;
+            }
+            return Err(err);
+        }
+
+        self.clients.insert(name.to_string(), client);
+        Ok(())
+    }
+
+    /// Stops the server `name` and forgets about its tools.
+    async fn remove_server(&mut self, name: &str) {
+        self.clients.remove(name);
+        self.new_tool_specs.lock().await.remove(name);
+        self.tn_map.retain(|_, tool_info| tool_info.server_name != name);
+        let origin = ToolOrigin::McpServer(name.to_string());
+        self.schema.retain(|_, spec| spec.tool_origin != origin);
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1496,6 +1706,7 @@ fn queue_init_message(
     complete: usize,
     failed: usize,
     total: usize,
+    loading: &[String],
     output: &mut impl Write,
 ) -> eyre::Result<()> {
     if total == complete {
@@ -1526,6 +1737,14 @@ fn queue_init_message(
         style::ResetColor,
         style::Print("mcp servers initialized."),
     )?;
+    if !loading.is_empty() {
+        queue!(
+            output,
+            style::SetForegroundColor(style::Color::DarkGrey),
+            style::Print(format!(" Loading {}.", loading_servers_summary(loading))),
+            style::ResetColor,
+        )?;
+    }
     if total > complete + failed {
         queue!(
             output,
@@ -1538,6 +1757,16 @@ fn queue_init_message(
     Ok(queue!(output, style::Print("\n"))?)
 }
 
+/// Lists the servers still loading, abbreviated after the first few so the line stays short.
+fn loading_servers_summary(loading: &[String]) -> String {
+    const MAX_LISTED: usize = 3;
+    let listed = loading.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join(", ");
+    match loading.len().saturating_sub(MAX_LISTED) {
+        0 => listed,
+        more => format!("{listed} and {more} more"),
+    }
+}
+
 fn queue_failure_message(
     name: &str,
     fail_load_msg: &eyre::Report,
@@ -1627,6 +1856,91 @@ fn queue_incomplete_load_message(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_loading_servers_summary() {
+        let servers = ["a", "b", "c", "d", "e"].map(String::from);
+        assert_eq!(loading_servers_summary(&servers[..1]), "a");
+        assert_eq!(loading_servers_summary(&servers[..3]), "a, b, c");
+        assert_eq!(loading_servers_summary(&servers), "a, b, c and 2 more");
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_tool() {
+        let tool_manager = ToolManager::default();
+        let timeout = Duration::from_secs(5);
+        // Nothing is loading, so an unknown tool is not waited on.
+        assert!(!tool_manager.wait_for_tool("slow_tool", timeout).await);
+
+        tool_manager.pending_clients.write().await.insert("slow".to_string());
+        let new_tool_specs = tool_manager.new_tool_specs.clone();
+        let pending_clients = tool_manager.pending_clients.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let tn_map = HashMap::from([("slow_tool".to_string(), ToolInfo {
+                server_name: "slow".to_string(),
+                host_tool_name: "slow_tool".to_string(),
+            })]);
+            new_tool_specs.lock().await.insert("slow".to_string(), (tn_map, vec![]));
+            pending_clients.write().await.remove("slow");
+        });
+        assert!(tool_manager.wait_for_tool("slow_tool", timeout).await);
+        assert!(!tool_manager.wait_for_tool("missing_tool", timeout).await);
+
+        tool_manager.pending_clients.write().await.insert("stuck".to_string());
+        assert!(
+            !tool_manager
+                .wait_for_tool("stuck_tool", Duration::from_millis(200))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reload_mcp_servers() {
+        let os = Os::new().await.unwrap();
+        let server = |command: &str| -> CustomToolConfig {
+            serde_json::from_value(serde_json::json!({ "command": command })).unwrap()
+        };
+        let mut tool_manager = ToolManager::default();
+        tool_manager.agent.lock().await.mcp_servers.mcp_servers = HashMap::from([
+            ("keep".to_string(), server("keep-mcp")),
+            ("gone".to_string(), server("gone-mcp")),
+        ]);
+        for server_name in ["keep", "gone"] {
+            let tool_name = format!("{server_name}_tool");
+            tool_manager.tn_map.insert(tool_name.clone(), ToolInfo {
+                server_name: server_name.to_string(),
+                host_tool_name: tool_name.clone(),
+            });
+            tool_manager.schema.insert(tool_name.clone(), ToolSpec {
+                name: tool_name,
+                description: String::new(),
+                input_schema: crate::cli::chat::tools::InputSchema(serde_json::json!({})),
+                tool_origin: ToolOrigin::McpServer(server_name.to_string()),
+            });
+        }
+
+        // "keep" is changed to a server that cannot start, and "gone" is removed
+        let config = McpServerConfig {
+            mcp_servers: HashMap::from([("keep".to_string(), server("/does/not/exist/mcp-server"))]),
+        };
+        let mut output = Vec::new();
+        tool_manager
+            .reload_mcp_servers(&os, config, false, &mut output)
+            .await
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("was removed from the config"), "{output}");
+        assert!(output.contains("keeping the previous one"), "{output}");
+        assert!(tool_manager.schema.contains_key("keep_tool"));
+        assert!(tool_manager.tn_map.contains_key("keep_tool"));
+        assert!(!tool_manager.schema.contains_key("gone_tool"));
+        assert!(!tool_manager.tn_map.contains_key("gone_tool"));
+        let agent = tool_manager.agent.lock().await;
+        assert_eq!(agent.mcp_servers.mcp_servers.len(), 1);
+        assert_eq!(agent.mcp_servers.mcp_servers["keep"].command, "keep-mcp");
+    }
+
     #[test]
     fn test_sanitize_server_name() {
         let regex = regex::Regex::new(VALID_TOOL_NAME).unwrap();
@@ -3,6 +3,8 @@ use std::io::Write;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use crossterm::{
     queue,
     style,
@@ -18,15 +20,30 @@ use tokio::sync::RwLock;
 use tracing::warn;
 
 use super::InvokeOutput;
+use crate::api_client::model::{
+    ImageBlock,
+    ImageFormat,
+    ImageSource,
+};
 use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
 use crate::cli::chat::CONTINUATION_LINE;
+use crate::cli::chat::consts::{
+    MAX_IMAGE_SIZE,
+    MAX_NUMBER_OF_IMAGES_PER_REQUEST,
+};
 use crate::cli::chat::token_counter::TokenCounter;
+use crate::cli::chat::util::images::{
+    ImageMetadata,
+    RichImageBlock,
+    RichImageBlocks,
+};
 use crate::mcp_client::{
     Client as McpClient,
     ClientConfig as McpClientConfig,
+    ClientError,
     JsonRpcResponse,
     JsonRpcStdioTransport,
     MessageContent,
@@ -35,8 +52,10 @@ use crate::mcp_client::{
     ServerCapabilities,
     StdioTransport,
     ToolCallResult,
+    Transport,
 };
 use crate::os::Os;
+use crate::util::directories;
 
 // TODO: support http transport type
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, JsonSchema)]
@@ -52,6 +71,24 @@ pub struct CustomToolConfig {
     /// Timeout for each mcp request in ms
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// Timeouts in ms that replace `timeout` for calls to specific tools, keyed by the tool name as
+    /// recognized by the server
+    #[serde(
+        default,
+        rename = "timeoutOverrides",
+        alias = "timeout_overrides",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub timeout_overrides: HashMap<String, u64>,
+    /// Number of times to retry the initial handshake if it fails, waiting 200ms before the first
+    /// retry and doubling the wait after each one
+    #[serde(
+        default = "default_init_retries",
+        rename = "initRetries",
+        alias = "init_retries",
+        skip_serializing_if = "is_default_init_retries"
+    )]
+    pub init_retries: u32,
     /// A boolean flag to denote whether or not to load this mcp server
     #[serde(default)]
     pub disabled: bool,
@@ -64,6 +101,14 @@ pub fn default_timeout() -> u64 {
     120 * 1000
 }
 
+pub fn default_init_retries() -> u32 {
+    3
+}
+
+fn is_default_init_retries(retries: &u32) -> bool {
+    *retries == default_init_retries()
+}
+
 /// Substitutes environment variables in the format ${env:VAR_NAME} with their actual values
 fn substitute_env_vars(input: &str, env: &crate::os::Env) -> String {
     // Create a regex to match ${env:VAR_NAME} pattern
@@ -78,19 +123,25 @@ fn substitute_env_vars(input: &str, env: &crate::os::Env) -> String {
 
 /// Process a HashMap of environment variables, substituting any ${env:VAR_NAME} patterns
 /// with their actual values from the environment
-fn process_env_vars(env_vars: &mut HashMap<String, String>, env: &crate::os::Env) {
+pub fn process_env_vars(env_vars: &mut HashMap<String, String>, env: &crate::os::Env) {
     for (_, value) in env_vars.iter_mut() {
         *value = substitute_env_vars(value, env);
     }
 }
 
+/// The client of an MCP server. The transport is generic so that tests can use a
+/// [crate::mcp_client::MockTransport] instead of spawning a server process.
 #[derive(Debug)]
-pub enum CustomToolClient {
+pub enum CustomToolClient<T: Transport = StdioTransport> {
     Stdio {
         /// This is the server name as recognized by the model (post sanitized)
         server_name: String,
-        client: McpClient<StdioTransport>,
+        client: McpClient<T>,
         server_capabilities: RwLock<Option<ServerCapabilities>>,
+        /// Per tool timeouts in ms, see [CustomToolConfig::timeout_overrides]
+        timeout_overrides: HashMap<String, u64>,
+        /// See [CustomToolConfig::init_retries]
+        init_retries: u32,
     },
 }
 
@@ -102,6 +153,8 @@ impl CustomToolClient {
             args,
             env,
             timeout,
+            timeout_overrides,
+            init_retries,
             disabled: _,
             ..
         } = config;
@@ -122,20 +175,26 @@ impl CustomToolClient {
                "version": "1.0.0"
             }),
             env: processed_env,
+            log_path: directories::mcp_server_log_path(&server_name).ok(),
         };
         let client = McpClient::<JsonRpcStdioTransport>::from_config(mcp_client_config)?;
         Ok(CustomToolClient::Stdio {
             server_name,
             client,
             server_capabilities: RwLock::new(None),
+            timeout_overrides,
+            init_retries,
         })
     }
+}
 
+impl<T: Transport> CustomToolClient<T> {
     pub async fn init(&self) -> Result<()> {
         match self {
             CustomToolClient::Stdio {
                 client,
                 server_capabilities,
+                init_retries,
                 ..
             } => {
                 if let Some(messenger) = &client.messenger {
@@ -143,9 +202,9 @@ impl CustomToolClient {
                 }
                 // We'll need to first initialize. This is the handshake every client and server
                 // needs to do before proceeding to anything else
-                let cap = client.init().await?;
-                // We'll be scrapping this for background server load: https://github.com/aws/amazon-q-developer-cli/issues/1466
-                // So don't worry about the tidiness for now
+                let cap = client.init_with_retries(*init_retries).await?;
+                // Servers are initialized in the background (see ToolManager::load_tools), so this
+                // only blocks the task loading this server.
                 server_capabilities.write().await.replace(cap);
                 Ok(())
             },
@@ -166,9 +225,33 @@ impl CustomToolClient {
         }
     }
 
-    pub async fn request(&self, method: &str, params: Option<serde_json::Value>) -> Result<JsonRpcResponse> {
+    /// Sends a request to the server, waiting for at most `timeout` ms if provided instead of the
+    /// server's configured timeout.
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        timeout: Option<u64>,
+    ) -> Result<JsonRpcResponse> {
         match self {
-            CustomToolClient::Stdio { client, .. } => Ok(client.request(method, params).await?),
+            CustomToolClient::Stdio { client, .. } => match timeout {
+                Some(timeout) => Ok(client.request_with_timeout(method, params, timeout).await?),
+                None => Ok(client.request(method, params).await?),
+            },
+        }
+    }
+
+    /// The time in ms that requests wait for unless a timeout is given.
+    pub fn timeout(&self) -> u64 {
+        match self {
+            CustomToolClient::Stdio { client, .. } => client.timeout(),
+        }
+    }
+
+    /// Returns the configured timeout override for calls to `tool_name`, if any.
+    pub fn timeout_override(&self, tool_name: &str) -> Option<u64> {
+        match self {
+            CustomToolClient::Stdio { timeout_overrides, .. } => timeout_overrides.get(tool_name).copied(),
         }
     }
 
@@ -199,25 +282,51 @@ impl CustomToolClient {
 }
 
 /// Represents a custom tool that can be invoked through the Model Context Protocol (MCP).
-#[derive(Clone, Debug)]
-pub struct CustomTool {
+#[derive(Debug)]
+pub struct CustomTool<T: Transport = StdioTransport> {
     /// Actual tool name as recognized by its MCP server. This differs from the tool names as they
     /// are seen by the model since they are not prefixed by its MCP server name.
     pub name: String,
     /// Reference to the client that manages communication with the tool's server process.
-    pub client: Arc<CustomToolClient>,
+    pub client: Arc<CustomToolClient<T>>,
     /// The method name to call on the tool's server, following the JSON-RPC convention.
     /// This corresponds to a specific functionality provided by the tool.
     pub method: String,
     /// Optional parameters to pass to the tool when invoking the method.
     /// Structured as a JSON value to accommodate various parameter types and structures.
     pub params: Option<serde_json::Value>,
+    /// Timeout in ms for this call. Uses the server's timeout if not set.
+    pub timeout: Option<u64>,
 }
 
-impl CustomTool {
+// Not derived, since that would require the transport to be Clone.
+impl<T: Transport> Clone for CustomTool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            client: Arc::clone(&self.client),
+            method: self.method.clone(),
+            params: self.params.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<T: Transport> CustomTool<T> {
     pub async fn invoke(&self, _os: &Os, _updates: impl Write) -> Result<InvokeOutput> {
         // Assuming a response shape as per https://spec.modelcontextprotocol.io/specification/2024-11-05/server/tools/#calling-tools
-        let resp = self.client.request(self.method.as_str(), self.params.clone()).await?;
+        let timeout = self.timeout.unwrap_or_else(|| self.client.timeout());
+        let resp = self
+            .client
+            .request(self.method.as_str(), self.params.clone(), Some(timeout))
+            .await
+            .map_err(|err| match err.downcast_ref::<ClientError>() {
+                // Surfaced to the model as the tool result, so that it can try something else
+                Some(client_err) if client_err.is_timeout() => {
+                    eyre::eyre!("MCP tool '{}' timed out after {timeout}ms", self.name)
+                },
+                _ => err,
+            })?;
         let result = match resp.result {
             Some(result) => result,
             None => {
@@ -230,14 +339,35 @@ impl CustomTool {
 
         match serde_json::from_value::<ToolCallResult>(result.clone()) {
             Ok(mut de_result) => {
+                let mut images = RichImageBlocks::new();
                 for content in &mut de_result.content {
-                    if let MessageContent::Image { data, .. } = content {
-                        *data = format!("Redacted base64 encoded string of an image of size {}", data.len());
+                    if let MessageContent::Image { data, mime_type } = content {
+                        let image = (images.len() < MAX_NUMBER_OF_IMAGES_PER_REQUEST)
+                            .then(|| image_block_from_content(&self.name, images.len(), data, mime_type))
+                            .flatten();
+                        *data = match image {
+                            Some(image) => {
+                                let placeholder = format!(
+                                    "Base64 encoded image of size {} supplied as image {}",
+                                    data.len(),
+                                    images.len() + 1
+                                );
+                                images.push(image);
+                                placeholder
+                            },
+                            None => format!("Redacted base64 encoded string of an image of size {}", data.len()),
+                        };
                     }
                 }
-                Ok(InvokeOutput {
-                    output: super::OutputKind::Json(serde_json::json!(de_result)),
-                })
+                let output = if images.is_empty() {
+                    super::OutputKind::Json(serde_json::json!(de_result))
+                } else {
+                    super::OutputKind::Mixed {
+                        text: serde_json::json!(de_result).to_string(),
+                        images,
+                    }
+                };
+                Ok(InvokeOutput { output })
             },
             Err(e) => {
                 warn!("Tool call result deserialization failed: {:?}", e);
@@ -283,12 +413,10 @@ impl CustomTool {
     }
 
     pub fn get_input_token_size(&self) -> usize {
-        TokenCounter::count_tokens(self.method.as_str())
-            + TokenCounter::count_tokens(self.params.as_ref().map_or("", |p| p.as_str().unwrap_or_default()))
+        TokenCounter::count_tokens(self.method.as_str()) + params_token_size(self.params.as_ref())
     }
 
     pub fn eval_perm(&self, agent: &Agent) -> PermissionEvalResult {
-        use crate::util::MCP_SERVER_TOOL_DELIMITER;
         let Self {
             name: tool_name,
             client,
@@ -296,11 +424,7 @@ impl CustomTool {
         } = self;
         let server_name = client.get_server_name();
 
-        if agent.allowed_tools.contains(&format!("@{server_name}"))
-            || agent
-                .allowed_tools
-                .contains(&format!("@{server_name}{MCP_SERVER_TOOL_DELIMITER}{tool_name}"))
-        {
+        if agent.is_mcp_tool_allowed(server_name, tool_name) {
             PermissionEvalResult::Allow
         } else {
             PermissionEvalResult::Ask
@@ -308,9 +432,77 @@ impl CustomTool {
     }
 }
 
+/// Counts the tokens of tool call params as they are sent to the server, i.e. serialized as JSON.
+fn params_token_size(params: Option<&serde_json::Value>) -> usize {
+    params.map_or(0, |p| TokenCounter::count_tokens(&p.to_string()))
+}
+
+/// Converts an image returned by an MCP tool into an image block that can be sent to the model.
+///
+/// Returns [None] if the image format is not supported, the data is not valid base64, or the
+/// decoded image exceeds [MAX_IMAGE_SIZE].
+fn image_block_from_content(tool_name: &str, index: usize, data: &str, mime_type: &str) -> Option<RichImageBlock> {
+    let extension = mime_type.strip_prefix("image/")?;
+    let format = extension.parse::<ImageFormat>().ok()?;
+    // Reject oversized images before decoding. Base64 encodes 3 bytes in 4 characters.
+    if data.len() / 4 * 3 > MAX_IMAGE_SIZE + 2 {
+        return None;
+    }
+    let bytes = match STANDARD.decode(data.trim()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to decode image returned by {tool_name}: {e}");
+            return None;
+        },
+    };
+    if bytes.len() > MAX_IMAGE_SIZE {
+        return None;
+    }
+    let filename = format!("{tool_name}-{}.{extension}", index + 1);
+    let size = bytes.len() as u64;
+    Some((
+        ImageBlock {
+            format,
+            source: ImageSource::Bytes(bytes),
+        },
+        ImageMetadata {
+            filepath: filename.clone(),
+            size,
+            filename,
+        },
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cli::chat::tools::OutputKind;
+    use crate::mcp_client::{
+        JsonRpcError,
+        MockTransport,
+    };
+
+    fn mock_tool(transport: MockTransport, timeout: Option<u64>) -> CustomTool<MockTransport> {
+        CustomTool {
+            name: "screenshot".to_string(),
+            client: Arc::new(CustomToolClient::Stdio {
+                server_name: "server".to_string(),
+                client: McpClient::from_transport("server".to_string(), transport, 1000),
+                server_capabilities: RwLock::new(None),
+                timeout_overrides: HashMap::new(),
+                init_retries: 0,
+            }),
+            method: "tools/call".to_string(),
+            params: Some(serde_json::json!({ "name": "screenshot", "arguments": { "full": true } })),
+            timeout,
+        }
+    }
+
+    fn transport(tool: &CustomTool<MockTransport>) -> &MockTransport {
+        match tool.client.as_ref() {
+            CustomToolClient::Stdio { client, .. } => client.transport(),
+        }
+    }
 
     #[tokio::test]
     async fn test_substitute_env_vars() {
@@ -361,4 +553,141 @@ mod tests {
         assert_eq!(env_vars.get("KEY1").unwrap(), "Value is test_value");
         assert_eq!(env_vars.get("KEY2").unwrap(), "No substitution");
     }
+
+    #[test]
+    fn test_params_token_size_counts_structured_params() {
+        assert_eq!(params_token_size(None), 0);
+        let params = serde_json::json!({
+            "name": "tool",
+            "arguments": { "query": { "filters": ["a", "b"], "limit": 10 } }
+        });
+        assert!(params_token_size(Some(&params)) > 0);
+    }
+
+    #[test]
+    fn test_image_block_from_content() {
+        let data = STANDARD.encode(b"not really a png");
+        let (block, metadata) = image_block_from_content("server___screenshot", 0, &data, "image/png").unwrap();
+        assert_eq!(block.format, ImageFormat::Png);
+        assert!(matches!(block.source, ImageSource::Bytes(ref bytes) if bytes == b"not really a png"));
+        assert_eq!(metadata.size, 16);
+        assert_eq!(metadata.filename, "server___screenshot-1.png");
+
+        assert!(image_block_from_content("tool", 0, &data, "image/svg+xml").is_none());
+        assert!(image_block_from_content("tool", 0, &data, "application/pdf").is_none());
+        assert!(image_block_from_content("tool", 0, "not base64!", "image/png").is_none());
+
+        let oversized = STANDARD.encode(vec![0u8; MAX_IMAGE_SIZE + 1]);
+        assert!(image_block_from_content("tool", 0, &oversized, "image/png").is_none());
+    }
+
+    #[test]
+    fn test_config_timeout_overrides() {
+        let config: CustomToolConfig = serde_json::from_value(serde_json::json!({
+            "command": "server",
+            "timeoutOverrides": { "slow_tool": 300000 }
+        }))
+        .unwrap();
+        assert_eq!(config.timeout, default_timeout());
+        assert_eq!(config.timeout_overrides.get("slow_tool"), Some(&300000));
+
+        let config: CustomToolConfig = serde_json::from_value(serde_json::json!({
+            "command": "server",
+            "timeout_overrides": { "slow_tool": 5000 }
+        }))
+        .unwrap();
+        assert_eq!(config.timeout_overrides.get("slow_tool"), Some(&5000));
+
+        let config: CustomToolConfig = serde_json::from_value(serde_json::json!({ "command": "server" })).unwrap();
+        assert!(config.timeout_overrides.is_empty());
+        assert_eq!(config.init_retries, default_init_retries());
+        assert!(
+            !serde_json::to_value(&config)
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .contains_key("timeoutOverrides")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invoke_success() {
+        let os = Os::new().await.unwrap();
+        let tool = mock_tool(
+            MockTransport::new().with_result(
+                "tools/call",
+                serde_json::json!({ "content": [{ "type": "text", "text": "done" }] }),
+            ),
+            None,
+        );
+
+        let output = tool.invoke(&os, std::io::sink()).await.unwrap();
+        match output.output {
+            OutputKind::Json(value) => assert_eq!(value["content"][0]["text"], "done"),
+            other => panic!("unexpected output: {other:?}"),
+        }
+
+        let requests = transport(&tool).requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "tools/call");
+        assert_eq!(requests[0].params, tool.params);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_error_response() {
+        let os = Os::new().await.unwrap();
+        let tool = mock_tool(
+            MockTransport::new().with_error("tools/call", JsonRpcError {
+                code: -32602,
+                message: "Unknown tool: screenshot".to_string(),
+                data: None,
+            }),
+            None,
+        );
+
+        let err = tool.invoke(&os, std::io::sink()).await.unwrap_err();
+        assert!(err.to_string().contains("Unknown tool: screenshot"));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_timeout() {
+        let os = Os::new().await.unwrap();
+        let tool = mock_tool(MockTransport::new(), Some(100));
+
+        let err = tool.invoke(&os, std::io::sink()).await.unwrap_err();
+        assert_eq!(err.to_string(), "MCP tool 'screenshot' timed out after 100ms");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_redacts_images() {
+        let os = Os::new().await.unwrap();
+        let png = STANDARD.encode(b"not really a png");
+        let svg = STANDARD.encode(b"<svg/>");
+        let tool = mock_tool(
+            MockTransport::new().with_result(
+                "tools/call",
+                serde_json::json!({ "content": [
+                    { "type": "image", "data": png, "mimeType": "image/png" },
+                    { "type": "image", "data": svg, "mimeType": "image/svg+xml" },
+                ] }),
+            ),
+            None,
+        );
+
+        let output = tool.invoke(&os, std::io::sink()).await.unwrap();
+        let OutputKind::Mixed { text, images } = output.output else {
+            panic!("expected text and images");
+        };
+        assert_eq!(images.len(), 1);
+        assert!(!text.contains(&png));
+        assert!(!text.contains(&svg));
+        assert!(text.contains(&format!(
+            "Base64 encoded image of size {} supplied as image 1",
+            png.len()
+        )));
+        assert!(text.contains(&format!(
+            "Redacted base64 encoded string of an image of size {}",
+            svg.len()
+        )));
+    }
 }
@@ -43,6 +43,14 @@ pub const READONLY_COMMANDS: &[&str] = &[
 pub struct ExecuteCommand {
     pub command: String,
     pub summary: Option<String>,
+    /// Response size budget, configured through the agent's `toolsSettings`. Stdout and stderr
+    /// are each truncated to a third of it.
+    #[serde(skip, default = "default_max_response_size")]
+    pub max_response_size: usize,
+}
+
+fn default_max_response_size() -> usize {
+    MAX_TOOL_RESPONSE_SIZE
 }
 
 impl ExecuteCommand {
@@ -129,7 +137,7 @@ impl ExecuteCommand {
     }
 
     pub async fn invoke(&self, output: &mut impl Write) -> Result<InvokeOutput> {
-        let output = run_command(&self.command, MAX_TOOL_RESPONSE_SIZE / 3, Some(output)).await?;
+        let output = run_command(&self.command, self.max_response_size / 3, Some(output)).await?;
         let clean_stdout = sanitize_unicode_tags(&output.stdout);
         let clean_stderr = sanitize_unicode_tags(&output.stderr);
 
@@ -193,8 +201,14 @@ impl ExecuteCommand {
 
         let Self { command, .. } = self;
         let tool_name = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
-        let is_in_allowlist = agent.allowed_tools.contains("execute_bash");
-        match agent.tools_settings.get(tool_name) {
+        let is_in_allowlist = agent.is_tool_allowed("execute_bash");
+        // Settings that only tune the tool's output, such as maxResponseBytes, do not restrict it.
+        let settings = agent.tools_settings.get(tool_name).filter(|s| {
+            ["allowedCommands", "deniedCommands", "allowReadOnly"]
+                .iter()
+                .any(|key| s.get(key).is_some())
+        });
+        match settings {
             Some(settings) if is_in_allowlist => {
                 let Settings {
                     allowed_commands,
@@ -384,4 +398,40 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    #[cfg(unix)]
+    async fn test_max_response_size_override() {
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "allowedTools": ["execute_bash"],
+            "toolsSettings": {
+                "execute_bash": {
+                    "maxResponseBytes": 30
+                }
+            }
+        }))
+        .unwrap();
+
+        let mut tool = serde_json::from_value::<ExecuteCommand>(serde_json::json!({
+            "command": "printf 'a%.0s' $(seq 1 20)",
+        }))
+        .unwrap();
+        // An output-only override does not restrict the tool.
+        assert_eq!(tool.eval_perm(&agent), PermissionEvalResult::Allow);
+
+        let output = tool.invoke(&mut std::io::sink()).await.unwrap();
+        let OutputKind::Json(json) = output.output else {
+            panic!("Expected JSON output");
+        };
+        assert_eq!(json["stdout"], "a".repeat(20));
+
+        tool.max_response_size = super::super::max_response_size(&agent, "execute_bash");
+        let output = tool.invoke(&mut std::io::sink()).await.unwrap();
+        let OutputKind::Json(json) = output.output else {
+            panic!("Expected JSON output");
+        };
+        assert!(json["stdout"].as_str().unwrap().starts_with(&"a".repeat(10)));
+        assert!(!json["stdout"].as_str().unwrap().starts_with(&"a".repeat(11)));
+    }
 }
@@ -14,6 +14,7 @@ use super::{
     CommandResult,
     format_output,
 };
+use crate::util::process::TerminateOnDrop;
 
 /// Run a bash command on Unix systems.
 /// # Arguments
@@ -38,6 +39,8 @@ pub async fn run_command<W: Write>(
         .stderr(Stdio::piped())
         .spawn()
         .wrap_err_with(|| format!("Unable to spawn command '{}'", command))?;
+    // Stops the command if the tool use is cancelled before it completes
+    let guard = TerminateOnDrop::new(&child);
 
     let stdout_final: String;
     let stderr_final: String;
@@ -111,6 +114,7 @@ pub async fn run_command<W: Write>(
         stderr_final = String::from_utf8_lossy(&output.stderr).to_string();
     }
 
+    guard.disarm();
     Ok(CommandResult {
         exit_status: exit_status.code(),
         stdout: format_output(&stdout_final, max_result_size),
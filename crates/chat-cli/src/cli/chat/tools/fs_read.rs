@@ -118,7 +118,7 @@ impl FsRead {
             true
         }
 
-        let is_in_allowlist = agent.allowed_tools.contains("fs_read");
+        let is_in_allowlist = agent.is_tool_allowed("fs_read");
         match agent.tools_settings.get("fs_read") {
             Some(settings) if is_in_allowlist => {
                 let Settings {
@@ -212,20 +212,11 @@ impl FsWrite {
         };
 
         let tracker = line_tracker.entry(path.to_string_lossy().to_string()).or_default();
-        match self {
-            FsWrite::Create { .. } => {
-                // For Create, always set prev_lines to 0 since we're creating a new file
-                if tracker.is_first_write {
-                    tracker.prev_fswrite_lines = 0;
-                }
-            },
-            _ => {
-                // For StrReplace, Insert, Append - if it's the first time we're tracking this file,
-                // set prev_lines to curr_lines so we only track changes from this point forward
-                if tracker.is_first_write {
-                    tracker.prev_fswrite_lines = curr_lines;
-                }
-            },
+        // If it's the first time we're tracking this file, set prev_lines to curr_lines so we only
+        // track changes from this point forward. This includes Create, which may overwrite a file
+        // whose existing lines were not written by the user during this session.
+        if tracker.is_first_write {
+            tracker.prev_fswrite_lines = curr_lines;
         }
         tracker.before_fswrite_lines = curr_lines;
 
@@ -425,7 +416,7 @@ impl FsWrite {
             denied_paths: Vec<String>,
         }
 
-        let is_in_allowlist = agent.allowed_tools.contains("fs_write");
+        let is_in_allowlist = agent.is_tool_allowed("fs_write");
         match agent.tools_settings.get("fs_write") {
             Some(settings) if is_in_allowlist => {
                 let Settings {
@@ -1245,6 +1236,30 @@ mod tests {
         assert_eq!(nested_content, "content in nested path\n");
     }
 
+    #[tokio::test]
+    async fn test_line_tracker_create_existing_file() {
+        let os = setup_test_directory().await;
+        let mut stdout = std::io::stdout();
+        let mut line_tracker = HashMap::new();
+
+        let file_path = "/existing_file.txt";
+        os.fs.write(file_path, "1\n2\n3\n4\n").await.unwrap();
+        serde_json::from_value::<FsWrite>(serde_json::json!({
+            "path": file_path,
+            "command": "create",
+            "file_text": "1\n2"
+        }))
+        .unwrap()
+        .invoke(&os, &mut stdout, &mut line_tracker)
+        .await
+        .unwrap();
+
+        let path_key = sanitize_path_tool_arg(&os, file_path).to_string_lossy().to_string();
+        let tracker = line_tracker.get(&path_key).unwrap();
+        assert_eq!(tracker.lines_by_user(), 0);
+        assert_eq!(tracker.lines_by_agent(), -2);
+    }
+
     #[tokio::test]
     async fn test_line_tracker_updates() {
         let os = setup_test_directory().await;
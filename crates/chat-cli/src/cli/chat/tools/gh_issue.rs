@@ -160,10 +160,16 @@ impl GhIssue {
         match os_manager.get_context_files(os).await {
             Ok(context_files) if !context_files.is_empty() => {
                 os_str.push_str("files=\n");
+                let sizes = TokenCounter::count_tokens_batch(
+                    &context_files
+                        .iter()
+                        .map(|(_, content)| content.as_str())
+                        .collect::<Vec<_>>(),
+                );
                 let total_size: usize = context_files
                     .iter()
-                    .map(|(file, content)| {
-                        let size = TokenCounter::count_tokens(content);
+                    .zip(sizes)
+                    .map(|((file, _), size)| {
                         os_str.push_str(&format!("{}, {} tkns\n", file, size));
                         size
                     })
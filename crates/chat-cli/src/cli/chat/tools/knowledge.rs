@@ -478,7 +478,7 @@ impl Knowledge {
 
     pub fn eval_perm(&self, agent: &Agent) -> PermissionEvalResult {
         _ = self;
-        if agent.allowed_tools.contains("knowledge") {
+        if agent.is_tool_allowed("knowledge") {
             PermissionEvalResult::Allow
         } else {
             PermissionEvalResult::Ask
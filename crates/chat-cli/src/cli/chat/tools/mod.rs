@@ -11,12 +11,24 @@ use std::borrow::{
     Borrow,
     Cow,
 };
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::hash::{
+    DefaultHasher,
+    Hash,
+    Hasher,
+};
 use std::io::Write;
 use std::path::{
     Path,
     PathBuf,
 };
+use std::time::{
+    Duration,
+    Instant,
+};
 
 use crossterm::queue;
 use crossterm::style::{
@@ -28,6 +40,10 @@ use execute::ExecuteCommand;
 use eyre::Result;
 use fs_read::FsRead;
 use fs_write::FsWrite;
+use futures::{
+    StreamExt,
+    stream,
+};
 use gh_issue::GhIssue;
 use knowledge::Knowledge;
 use serde::{
@@ -96,6 +112,9 @@ impl Tool {
 
     /// Whether or not the tool should prompt the user to accept before [Self::invoke] is called.
     pub fn requires_acceptance(&self, agent: &Agent) -> PermissionEvalResult {
+        if self.is_denied(agent) {
+            return PermissionEvalResult::Deny;
+        }
         match self {
             Tool::FsRead(fs_read) => fs_read.eval_perm(agent),
             Tool::FsWrite(fs_write) => fs_write.eval_perm(agent),
@@ -108,6 +127,49 @@ impl Tool {
         }
     }
 
+    /// Same as [Self::requires_acceptance], but also returns a short, human readable description
+    /// of the rule that produced the decision.
+    pub fn explain_acceptance(&self, agent: &Agent) -> (PermissionEvalResult, String) {
+        if self.is_denied(agent) {
+            return (
+                PermissionEvalResult::Deny,
+                "denied: forbidden by the agent's denied_tools".to_string(),
+            );
+        }
+        match self {
+            Tool::UseAws(use_aws) => use_aws.explain_perm(agent),
+            Tool::GhIssue(_) | Tool::Thinking(_) => {
+                (PermissionEvalResult::Allow, "allowed: always trusted".to_string())
+            },
+            _ => {
+                let res = self.requires_acceptance(agent);
+                let explanation = match res {
+                    PermissionEvalResult::Allow => "allowed: permitted by the agent's allowed_tools or tools_settings",
+                    PermissionEvalResult::Ask => "ask: no rule matched",
+                    PermissionEvalResult::Deny => "denied: forbidden by the agent's tools_settings",
+                };
+                (res, explanation.to_string())
+            },
+        }
+    }
+
+    /// Whether the tool is listed in the agent's denied_tools. A deny takes precedence over any
+    /// allow rule in allowed_tools or tools_settings.
+    fn is_denied(&self, agent: &Agent) -> bool {
+        match self {
+            Tool::FsRead(_) => agent.is_tool_denied("fs_read"),
+            Tool::FsWrite(_) => agent.is_tool_denied("fs_write"),
+            Tool::ExecuteCommand(_) => agent.is_tool_denied("execute_bash") || agent.is_tool_denied("execute_cmd"),
+            Tool::UseAws(_) => agent.is_tool_denied("use_aws"),
+            Tool::Custom(custom_tool) => {
+                agent.is_mcp_tool_denied(custom_tool.client.get_server_name(), &custom_tool.name)
+            },
+            Tool::GhIssue(_) => agent.is_tool_denied("report_issue"),
+            Tool::Knowledge(_) => agent.is_tool_denied("knowledge"),
+            Tool::Thinking(_) => agent.is_tool_denied("thinking"),
+        }
+    }
+
     /// Invokes the tool asynchronously
     pub async fn invoke(
         &self,
@@ -127,13 +189,26 @@ impl Tool {
         }
     }
 
+    /// Whether the tool only reads state, and thus may run concurrently with other such tools.
+    ///
+    /// MCP tools don't tell whether they are read only, so they are always run one at a time.
+    pub fn is_parallel_safe(&self) -> bool {
+        match self {
+            Tool::FsRead(_) | Tool::Thinking(_) => true,
+            Tool::UseAws(use_aws) => !use_aws.requires_acceptance(),
+            Tool::FsWrite(_) | Tool::ExecuteCommand(_) | Tool::Custom(_) | Tool::GhIssue(_) | Tool::Knowledge(_) => {
+                false
+            },
+        }
+    }
+
     /// Queues up a tool's intention in a human readable format
     pub async fn queue_description(&self, os: &Os, output: &mut impl Write) -> Result<()> {
         match self {
             Tool::FsRead(fs_read) => fs_read.queue_description(os, output).await,
             Tool::FsWrite(fs_write) => fs_write.queue_description(os, output),
             Tool::ExecuteCommand(execute_command) => execute_command.queue_description(output),
-            Tool::UseAws(use_aws) => use_aws.queue_description(output),
+            Tool::UseAws(use_aws) => use_aws.queue_description(os, output),
             Tool::Custom(custom_tool) => custom_tool.queue_description(output),
             Tool::GhIssue(gh_issue) => gh_issue.queue_description(output),
             Tool::Knowledge(knowledge) => knowledge.queue_description(os, output).await,
@@ -241,12 +316,72 @@ fn tool_origin() -> ToolOrigin {
     ToolOrigin::Native
 }
 
+/// Tracks consecutive identical tool calls, so that the model can be stopped from repeating the
+/// same call over and over, e.g. retrying a failing command unchanged.
+#[derive(Debug, Default)]
+pub struct ToolCallRepeats {
+    /// Hash of the last tool call's name and arguments.
+    last: Option<u64>,
+    /// Number of times in a row the last tool call was issued.
+    count: usize,
+}
+
+impl ToolCallRepeats {
+    /// Records a tool call, returning how many times in a row it has now been issued.
+    pub fn record(&mut self, name: &str, args: &serde_json::Value) -> usize {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        args.to_string().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if self.last == Some(hash) {
+            self.count += 1;
+        } else {
+            self.last = Some(hash);
+            self.count = 1;
+        }
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct QueuedTool {
     pub id: String,
     pub name: String,
     pub accepted: bool,
     pub tool: Tool,
+    /// The input supplied by the model for this tool use
+    pub args: serde_json::Value,
+}
+
+/// The result of a tool invoked by [invoke_concurrently], along with the output it printed and
+/// how long it took.
+pub type ConcurrentInvokeResult = (Result<InvokeOutput>, Vec<u8>, Duration);
+
+/// Invokes the parallel safe tools at the start of `tools` concurrently, running at most `limit`
+/// at a time. Their results are returned in the same order as the tools.
+///
+/// Nothing is invoked, and no result is returned, unless there are at least two such tools.
+pub async fn invoke_concurrently(tools: &[QueuedTool], os: &Os, limit: usize) -> VecDeque<ConcurrentInvokeResult> {
+    let count = tools.iter().take_while(|tool| tool.tool.is_parallel_safe()).count();
+    if count < 2 || limit < 2 {
+        return VecDeque::new();
+    }
+
+    stream::iter(tools[..count].iter().map(|tool| async move {
+        let start = Instant::now();
+        let mut output = Vec::new();
+        // Parallel safe tools don't track line changes
+        let result = tool.tool.invoke(os, &mut output, &mut HashMap::new()).await;
+        (result, output, start.elapsed())
+    }))
+    .buffered(limit)
+    .collect()
+    .await
 }
 
 /// The schema specification describing a tool's fields.
@@ -288,6 +423,18 @@ impl Default for OutputKind {
     }
 }
 
+/// Returns the response size budget in bytes for `tool_name`, as configured by the
+/// `maxResponseBytes` key of its entry in the agent's `toolsSettings`. Falls back to
+/// [MAX_TOOL_RESPONSE_SIZE] if no override is set.
+pub fn max_response_size(agent: &Agent, tool_name: &str) -> usize {
+    agent
+        .tools_settings
+        .get(tool_name)
+        .and_then(|settings| settings.get("maxResponseBytes"))
+        .and_then(serde_json::Value::as_u64)
+        .map_or(MAX_TOOL_RESPONSE_SIZE, |size| size as usize)
+}
+
 /// Performs tilde expansion and other required sanitization modifications for handling tool use
 /// path arguments.
 ///
@@ -432,6 +579,121 @@ mod tests {
     use super::*;
     use crate::os::ACTIVE_USER_HOME;
 
+    #[test]
+    fn test_tool_call_repeats() {
+        let mut repeats = ToolCallRepeats::default();
+        let args = serde_json::json!({ "service_name": "s3", "operation_name": "list-buckets" });
+        assert_eq!(repeats.record("use_aws", &args), 1);
+        assert_eq!(repeats.record("use_aws", &args), 2);
+        assert_eq!(repeats.record("use_aws", &args), 3);
+
+        // Any other call breaks the streak.
+        let other_args = serde_json::json!({ "service_name": "s3", "operation_name": "list-objects" });
+        assert_eq!(repeats.record("use_aws", &other_args), 1);
+        assert_eq!(repeats.record("use_aws", &args), 1);
+        assert_eq!(repeats.record("execute_bash", &args), 1);
+
+        repeats.reset();
+        assert_eq!(repeats.record("execute_bash", &args), 1);
+    }
+
+    #[test]
+    fn test_is_parallel_safe() {
+        let fs_read = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [{ "path": "/", "mode": "Directory" }]
+        }))
+        .unwrap();
+        assert!(Tool::FsRead(fs_read).is_parallel_safe());
+
+        let use_aws = |operation_name: &str| {
+            Tool::UseAws(
+                serde_json::from_value(serde_json::json!({
+                    "service_name": "s3",
+                    "operation_name": operation_name,
+                    "region": "us-west-2",
+                }))
+                .unwrap(),
+            )
+        };
+        assert!(use_aws("list-buckets").is_parallel_safe());
+        assert!(!use_aws("delete-bucket").is_parallel_safe());
+
+        let fs_write = serde_json::from_value::<FsWrite>(serde_json::json!({
+            "command": "create", "path": "/file.txt", "file_text": "hello"
+        }))
+        .unwrap();
+        assert!(!Tool::FsWrite(fs_write).is_parallel_safe());
+    }
+
+    #[tokio::test]
+    async fn test_invoke_concurrently() {
+        let os = Os::new().await.unwrap();
+        os.fs.write("/a.txt", "first").await.unwrap();
+        os.fs.write("/b.txt", "second").await.unwrap();
+        let queued = |path: &str, tool| QueuedTool {
+            id: path.to_string(),
+            name: path.to_string(),
+            accepted: true,
+            tool,
+            args: serde_json::Value::Null,
+        };
+        let fs_read = |path: &str| {
+            queued(
+                path,
+                Tool::FsRead(
+                    serde_json::from_value(serde_json::json!({ "operations": [{ "path": path, "mode": "Line" }] }))
+                        .unwrap(),
+                ),
+            )
+        };
+        let fs_write = queued(
+            "/c.txt",
+            Tool::FsWrite(
+                serde_json::from_value(serde_json::json!({
+                    "command": "create", "path": "/c.txt", "file_text": "third"
+                }))
+                .unwrap(),
+            ),
+        );
+
+        let tools = vec![fs_read("/a.txt"), fs_read("/b.txt"), fs_write, fs_read("/a.txt")];
+        let results = invoke_concurrently(&tools, &os, 2).await;
+        let texts = results
+            .into_iter()
+            .map(|(result, _, _)| match result.unwrap().output {
+                OutputKind::Text(text) => text,
+                _ => panic!("expected text output"),
+            })
+            .collect::<Vec<_>>();
+        // Only the leading run of read only tools is invoked, in order.
+        assert_eq!(texts, vec!["first", "second"]);
+        assert!(!os.fs.exists("/c.txt"));
+
+        assert!(invoke_concurrently(&tools, &os, 1).await.is_empty());
+        assert!(invoke_concurrently(&tools[1..], &os, 2).await.is_empty());
+    }
+
+    #[test]
+    fn test_denied_tools_take_precedence() {
+        let tool = Tool::FsRead(
+            serde_json::from_value(serde_json::json!({
+                "operations": [{ "path": "/", "mode": "Directory" }]
+            }))
+            .unwrap(),
+        );
+        let mut agent = Agent {
+            allowed_tools: ["fs_read".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        assert_eq!(tool.requires_acceptance(&agent), PermissionEvalResult::Allow);
+
+        agent.denied_tools.insert("fs_*".to_string());
+        assert_eq!(tool.requires_acceptance(&agent), PermissionEvalResult::Deny);
+        let (res, explanation) = tool.explain_acceptance(&agent);
+        assert_eq!(res, PermissionEvalResult::Deny);
+        assert!(explanation.contains("denied_tools"));
+    }
+
     #[tokio::test]
     async fn test_tilde_path_expansion() {
         let os = Os::new().await.unwrap();
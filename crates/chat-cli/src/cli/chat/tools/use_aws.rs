@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 use std::io::Write;
 use std::process::Stdio;
+use std::sync::{
+    Arc,
+    LazyLock,
+};
 
 use bstr::ByteSlice;
-use convert_case::{
-    Case,
-    Casing,
-};
 use crossterm::{
     queue,
     style,
@@ -15,6 +15,7 @@ use eyre::{
     Result,
     WrapErr,
 };
+use regex::Regex;
 use serde::Deserialize;
 use tracing::error;
 
@@ -27,9 +28,22 @@ use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
+use crate::cli::chat::util::truncate_safe;
+use crate::database::settings::Setting;
 use crate::os::Os;
+use crate::util::process::TerminateOnDrop;
 
 const READONLY_OPS: [&str; 6] = ["get", "describe", "list", "ls", "search", "batch_get"];
+/// Operations that are followed across pages when auto-pagination is enabled.
+const PAGINATED_OPS: [&str; 3] = ["describe", "list", "search"];
+/// Flags that change the region or endpoint a command is sent to, which are not accepted as
+/// parameters when `allowedRegions` is set.
+const REGION_OVERRIDE_FLAGS: [&str; 3] = ["--region", "--endpoint-url", "--profile"];
+/// Default page cap when auto-pagination is enabled without `maxPages`.
+const DEFAULT_MAX_PAGES: usize = 10;
+/// Items requested per page with `--max-items` when auto-pagination is enabled. The AWS CLI
+/// otherwise fetches every page itself and never includes the `NextToken` to continue from.
+const PAGE_SIZE: usize = 100;
 
 /// The environment variable name where we set additional metadata for the AWS CLI user agent.
 const USER_AGENT_ENV_VAR: &str = "AWS_EXECUTION_ENV";
@@ -37,8 +51,73 @@ const USER_AGENT_APP_NAME: &str = "AmazonQ-For-CLI";
 const USER_AGENT_VERSION_KEY: &str = "Version";
 const USER_AGENT_VERSION_VALUE: &str = env!("CARGO_PKG_VERSION");
 
-// TODO: we should perhaps composite this struct with an interface that we can use to mock the
-// actual cli with. That will allow us to more thoroughly test it.
+// The patterns the AWS CLI uses to derive flags from API parameter names, see [cli_flag].
+static PLURAL_ACRONYM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[A-Z]{2,}s$").expect("valid regex"));
+static FIRST_CAP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(.)([A-Z][a-z]+)").expect("valid regex"));
+static END_CAP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([a-z0-9])([A-Z])").expect("valid regex"));
+
+/// The exit status, stdout, and stderr of a command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub status: String,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs the `aws` CLI for [UseAws], so that tests can check the arguments it is run with and how
+/// its output is handled without calling AWS.
+#[async_trait::async_trait]
+pub trait CommandRunner: std::fmt::Debug + Send + Sync {
+    /// Runs `program` with `args` and `envs` added to its environment.
+    async fn run(&self, program: &str, args: &[String], envs: &HashMap<String, String>) -> Result<CommandOutput>;
+}
+
+/// Runs commands as child processes.
+#[derive(Debug)]
+pub struct ProcessRunner;
+
+#[async_trait::async_trait]
+impl CommandRunner for ProcessRunner {
+    async fn run(&self, program: &str, args: &[String], envs: &HashMap<String, String>) -> Result<CommandOutput> {
+        let child = tokio::process::Command::new(program)
+            .envs(envs)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err_with(|| format!("Unable to spawn command '{program} {}'", args.join(" ")))?;
+        // Stops the command if the tool use is cancelled before it completes
+        let guard = TerminateOnDrop::new(&child);
+        let output = child
+            .wait_with_output()
+            .await
+            .wrap_err_with(|| format!("Unable to spawn command '{program} {}'", args.join(" ")))?;
+        guard.disarm();
+        Ok(CommandOutput {
+            status: output.status.code().unwrap_or(0).to_string(),
+            stdout: output.stdout.to_str_lossy().into_owned(),
+            stderr: output.stderr.to_str_lossy().into_owned(),
+        })
+    }
+}
+
+/// The output formats the model can ask the AWS CLI for with `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Text,
+}
+
+impl OutputFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Text => "text",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct UseAws {
     pub service_name: String,
@@ -47,6 +126,32 @@ pub struct UseAws {
     pub region: String,
     pub profile_name: Option<String>,
     pub label: Option<String>,
+    /// Passed as `--output`, so that large responses can be requested as text.
+    pub output_format: Option<OutputFormat>,
+    /// A JMESPath expression passed as `--query`, so that only the fields needed are returned.
+    pub query: Option<String>,
+    /// Response size budget, configured through the agent's `toolsSettings`. Stdout and stderr
+    /// are each truncated to a third of it.
+    #[serde(skip, default = "default_max_response_size")]
+    pub max_response_size: usize,
+    /// Profile to use when `profile_name` is not set, from `q chat --aws-profile`.
+    #[serde(skip)]
+    pub default_profile_name: Option<String>,
+    /// Maximum number of pages to fetch, if auto-pagination is enabled in the agent's
+    /// `toolsSettings`.
+    #[serde(skip)]
+    pub max_pages: Option<usize>,
+    /// Runs the `aws` CLI, replaced in tests.
+    #[serde(skip, default = "default_runner")]
+    pub runner: Arc<dyn CommandRunner>,
+}
+
+fn default_max_response_size() -> usize {
+    MAX_TOOL_RESPONSE_SIZE
+}
+
+fn default_runner() -> Arc<dyn CommandRunner> {
+    Arc::new(ProcessRunner)
 }
 
 impl UseAws {
@@ -55,9 +160,62 @@ impl UseAws {
     }
 
     pub async fn invoke(&self, _os: &Os, _updates: impl Write) -> Result<InvokeOutput> {
-        let mut command = tokio::process::Command::new("aws");
-        command.envs(std::env::vars());
+        let max_bytes = self.max_response_size / 3;
+        let max_pages = self.max_pages.filter(|_| self.is_paginated());
+        let page_size = PAGE_SIZE.to_string();
+        let page_args = match max_pages {
+            Some(_) => vec!["--max-items", page_size.as_str()],
+            None => vec![],
+        };
+        let (status, stdout, stderr) = self.run(&page_args).await?;
+        if status != "0" {
+            return Err(eyre::eyre!(truncate_output(&stderr, max_bytes)));
+        }
+
+        let pagination =
+            max_pages.and_then(|max_pages| Some((max_pages, serde_json::from_str::<serde_json::Value>(&stdout).ok()?)));
+        let Some((max_pages, first_page)) = pagination else {
+            return Ok(InvokeOutput {
+                output: OutputKind::Json(success_output(&status, &stdout, &stderr, max_bytes)),
+            });
+        };
 
+        // Follow NextToken until the page cap or the response size budget is reached. Failures
+        // while fetching further pages are not fatal: the model still gets the pages fetched so far
+        // along with the NextToken to continue from.
+        let mut size = stdout.len();
+        let mut pages = vec![first_page];
+        while pages.len() < max_pages {
+            let Some(token) = pages
+                .last()
+                .and_then(|page| page.get("NextToken"))
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+            else {
+                break;
+            };
+            let Ok((status, stdout, _)) = self.run(&["--max-items", &page_size, "--starting-token", &token]).await
+            else {
+                break;
+            };
+            if status != "0" || size + stdout.len() > max_bytes {
+                break;
+            }
+            let Ok(page) = serde_json::from_str(&stdout) else {
+                break;
+            };
+            size += stdout.len();
+            pages.push(page);
+        }
+
+        let stdout = merge_pages(pages).to_string();
+        Ok(InvokeOutput {
+            output: OutputKind::Json(success_output(&status, &stdout, &stderr, max_bytes)),
+        })
+    }
+
+    /// Runs the command with `page_args` appended, returning its exit status, stdout, and stderr.
+    async fn run(&self, page_args: &[&str]) -> Result<(String, String, String)> {
         // Set up environment variables
         let mut env_vars: std::collections::HashMap<String, String> = std::env::vars().collect();
 
@@ -81,65 +239,54 @@ impl UseAws {
             env_vars.insert(USER_AGENT_ENV_VAR.to_string(), user_agent_metadata_value);
         }
 
-        command.envs(env_vars).arg("--region").arg(&self.region);
-        if let Some(profile_name) = self.profile_name.as_deref() {
-            command.arg("--profile").arg(profile_name);
+        let mut args = self.cli_args();
+        args.extend(page_args.iter().map(|arg| (*arg).to_string()));
+        let CommandOutput { status, stdout, stderr } = self.runner.run("aws", &args, &env_vars).await?;
+        Ok((status, stdout, stderr))
+    }
+
+    /// Returns the arguments the `aws` CLI is run with, other than the pagination flags.
+    fn cli_args(&self) -> Vec<String> {
+        let mut args = vec!["--region".to_string(), self.region.clone()];
+        if let Some(profile_name) = self.effective_profile_name() {
+            args.extend(["--profile".to_string(), profile_name.to_string()]);
         }
-        command.arg(&self.service_name).arg(&self.operation_name);
-        if let Some(parameters) = self.cli_parameters() {
-            for (name, val) in parameters {
-                command.arg(name);
-                if !val.is_empty() {
-                    command.arg(val);
-                }
-            }
+        args.extend([self.service_name.clone(), self.operation_name.clone()]);
+        let parameters = self.cli_parameters().unwrap_or_default();
+        let has_flag = |flag: &str| parameters.iter().any(|(name, _)| name == flag);
+        let query = self.query.as_ref().filter(|_| !has_flag("--query"));
+        let output_format = self.output_format.filter(|_| !has_flag("--output"));
+        for (name, values) in parameters {
+            args.push(name);
+            args.extend(values);
         }
-        let output = command
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))?
-            .wait_with_output()
-            .await
-            .wrap_err_with(|| format!("Unable to spawn command '{:?}'", self))?;
-        let status = output.status.code().unwrap_or(0).to_string();
-        let stdout = output.stdout.to_str_lossy();
-        let stderr = output.stderr.to_str_lossy();
-
-        let stdout = format!(
-            "{}{}",
-            &stdout[0..stdout.len().min(MAX_TOOL_RESPONSE_SIZE / 3)],
-            if stdout.len() > MAX_TOOL_RESPONSE_SIZE / 3 {
-                " ... truncated"
-            } else {
-                ""
-            }
-        );
-
-        let stderr = format!(
-            "{}{}",
-            &stderr[0..stderr.len().min(MAX_TOOL_RESPONSE_SIZE / 3)],
-            if stderr.len() > MAX_TOOL_RESPONSE_SIZE / 3 {
-                " ... truncated"
-            } else {
-                ""
-            }
-        );
+        if let Some(query) = query {
+            args.extend(["--query".to_string(), query.clone()]);
+        }
+        if let Some(output_format) = output_format {
+            args.extend(["--output".to_string(), output_format.as_str().to_string()]);
+        }
+        args
+    }
 
-        if status.eq("0") {
-            Ok(InvokeOutput {
-                output: OutputKind::Json(serde_json::json!({
-                    "exit_status": status,
-                    "stdout": stdout,
-                    "stderr": stderr.clone()
-                })),
+    /// Whether this is a list or describe operation whose pagination is not already controlled by
+    /// its parameters.
+    ///
+    /// Responses reshaped with `--query` or `--output` are not paginated, since they may no longer
+    /// be JSON or contain the `NextToken` to continue from.
+    fn is_paginated(&self) -> bool {
+        PAGINATED_OPS.iter().any(|op| self.operation_name.starts_with(op))
+            && self.query.is_none()
+            && self.output_format.is_none()
+            && !self.cli_parameters().unwrap_or_default().iter().any(|(name, _)| {
+                matches!(
+                    name.as_str(),
+                    "--starting-token" | "--max-items" | "--no-paginate" | "--query" | "--output"
+                )
             })
-        } else {
-            Err(eyre::eyre!(stderr))
-        }
     }
 
-    pub fn queue_description(&self, output: &mut impl Write) -> Result<()> {
+    pub fn queue_description(&self, os: &Os, output: &mut impl Write) -> Result<()> {
         queue!(
             output,
             style::Print("Running aws cli command:\n\n"),
@@ -160,20 +307,85 @@ impl UseAws {
             }
         }
 
+        let (danger_profile, danger_region) = self.matches_danger_lists(os);
+        if danger_profile {
+            queue!(output, style::SetForegroundColor(style::Color::Red))?;
+        }
         if let Some(ref profile_name) = self.profile_name {
             queue!(output, style::Print(format!("Profile name: {}\n", profile_name)))?;
+        } else if let Some(ref profile_name) = self.default_profile_name {
+            queue!(
+                output,
+                style::Print(format!("Profile name: {} (session default)\n", profile_name))
+            )?;
         } else {
             queue!(output, style::Print("Profile name: default\n".to_string()))?;
         }
+        queue!(output, style::ResetColor)?;
 
-        queue!(output, style::Print(format!("Region: {}", self.region)))?;
+        if danger_region {
+            queue!(output, style::SetForegroundColor(style::Color::Red))?;
+        }
+        queue!(
+            output,
+            style::Print(format!("Region: {}", self.region)),
+            style::ResetColor
+        )?;
 
         if let Some(ref label) = self.label {
             queue!(output, style::Print(format!("\nLabel: {}", label)))?;
         }
+
+        if let Some(command) = self.shell_command() {
+            queue!(
+                output,
+                style::Print("\nCommand: "),
+                style::SetForegroundColor(style::Color::Green),
+                style::Print(command),
+                style::ResetColor,
+            )?;
+        }
+
+        if danger_profile || danger_region {
+            queue!(
+                output,
+                style::Print("\n\n"),
+                style::SetForegroundColor(style::Color::Red),
+                style::SetAttribute(style::Attribute::Bold),
+                style::Print("⚠ PRODUCTION"),
+                style::SetAttribute(style::Attribute::Reset),
+                style::SetForegroundColor(style::Color::Red),
+                style::Print(format!(
+                    ": this command targets {}",
+                    match (danger_profile, danger_region) {
+                        (true, true) => "a profile and region marked as dangerous",
+                        (true, false) => "a profile marked as dangerous",
+                        _ => "a region marked as dangerous",
+                    }
+                )),
+                style::ResetColor,
+            )?;
+        }
         Ok(())
     }
 
+    /// Returns whether the resolved profile and region match the user's `chat.awsDangerProfiles`
+    /// and `chat.awsDangerRegions` settings, respectively.
+    fn matches_danger_lists(&self, os: &Os) -> (bool, bool) {
+        let settings = &os.database.settings;
+        let profile_name = match self.effective_profile_name() {
+            Some(profile_name) => profile_name.to_string(),
+            None => os.env.get("AWS_PROFILE").unwrap_or_else(|_| "default".to_string()),
+        };
+        let danger_profile = settings
+            .get_string_list(Setting::ChatAwsDangerProfiles)
+            .is_some_and(|profiles| profiles.contains(&profile_name));
+        let danger_region = settings
+            .get_string_list(Setting::ChatAwsDangerRegions)
+            .is_some_and(|regions| regions.iter().any(|r| r.eq_ignore_ascii_case(&self.region)));
+        (danger_profile, danger_region)
+    }
+
     pub async fn validate(&mut self, _os: &Os) -> Result<()> {
         Ok(())
     }
@@ -185,15 +397,27 @@ impl UseAws {
         })
     }
 
-    /// Returns the CLI arguments properly formatted as kebab case if parameters is
-    /// [Option::Some], otherwise None
-    fn cli_parameters(&self) -> Option<Vec<(String, String)>> {
+    /// Returns the equivalent `aws` CLI invocation, shell-quoted so that it can be pasted into a
+    /// terminal. Returns [None] if an argument can't be quoted, e.g. because it contains a nul
+    /// byte.
+    pub fn shell_command(&self) -> Option<String> {
+        let args = self.cli_args();
+        shlex::try_join(std::iter::once("aws").chain(args.iter().map(String::as_str))).ok()
+    }
+
+    /// Returns the profile the command will run with: the one requested for this call, falling
+    /// back to the session default.
+    fn effective_profile_name(&self) -> Option<&str> {
+        self.profile_name.as_deref().or(self.default_profile_name.as_deref())
+    }
+
+    /// Returns the CLI arguments with their names converted to flags, see [cli_flag], and their
+    /// values, see [cli_values], if parameters is [Option::Some], otherwise None
+    fn cli_parameters(&self) -> Option<Vec<(String, Vec<String>)>> {
         if let Some(parameters) = &self.parameters {
             let mut params = vec![];
             for (param_name, val) in parameters {
-                let param_name = format!("--{}", param_name.trim_start_matches("--").to_case(Case::Kebab));
-                let param_val = val.as_str().map(|s| s.to_string()).unwrap_or(val.to_string());
-                params.push((param_name, param_val));
+                params.push((cli_flag(param_name), cli_values(val)));
             }
             Some(params)
         } else {
@@ -202,6 +426,12 @@ impl UseAws {
     }
 
     pub fn eval_perm(&self, agent: &Agent) -> PermissionEvalResult {
+        self.explain_perm(agent).0
+    }
+
+    /// Evaluates the permission for this command, along with a one line description of the rule
+    /// that decided the outcome.
+    pub fn explain_perm(&self, agent: &Agent) -> (PermissionEvalResult, String) {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct Settings {
@@ -210,36 +440,232 @@ impl UseAws {
         }
 
         let Self { service_name, .. } = self;
-        let is_in_allowlist = agent.allowed_tools.contains("use_aws");
-        match agent.tools_settings.get("use_aws") {
+        // Regions are checked first, since an allowed service must still not be used in a region
+        // the organization has not approved.
+        if let Some(allowed_regions) = allowed_regions(agent).filter(|regions| !regions.is_empty()) {
+            // The AWS CLI uses the last --region it is given, so parameters could otherwise
+            // redirect the call to a region or endpoint outside the allowlist.
+            let overrides = self
+                .cli_parameters()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, _)| name)
+                .filter(|name| REGION_OVERRIDE_FLAGS.contains(&name.as_str()))
+                .collect::<Vec<_>>();
+            if !overrides.is_empty() {
+                return (
+                    PermissionEvalResult::Deny,
+                    format!(
+                        "denied: {} can't be passed as parameters when allowed_regions is set",
+                        overrides.join(", ")
+                    ),
+                );
+            }
+            if !allowed_regions.iter().any(|r| r.eq_ignore_ascii_case(&self.region)) {
+                return (
+                    PermissionEvalResult::Deny,
+                    format!(
+                        "denied: {} is not in allowed_regions=[{}]",
+                        self.region,
+                        allowed_regions.join(", ")
+                    ),
+                );
+            }
+        }
+
+        let is_in_allowlist = agent.is_tool_allowed("use_aws");
+        // Settings that only tune the tool's output, such as maxResponseBytes, do not restrict it.
+        let settings = agent
+            .tools_settings
+            .get("use_aws")
+            .filter(|s| s.get("allowedServices").is_some() || s.get("deniedServices").is_some());
+        match settings {
             Some(settings) if is_in_allowlist => {
                 let settings = match serde_json::from_value::<Settings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
                         error!("Failed to deserialize tool settings for use_aws: {:?}", e);
-                        return PermissionEvalResult::Ask;
+                        return (
+                            PermissionEvalResult::Ask,
+                            "ask: tools_settings for use_aws could not be parsed".to_string(),
+                        );
                     },
                 };
                 if settings.denied_services.contains(service_name) {
-                    return PermissionEvalResult::Deny;
+                    return (
+                        PermissionEvalResult::Deny,
+                        format!(
+                            "denied: matched denied_services=[{}]",
+                            settings.denied_services.join(", ")
+                        ),
+                    );
                 }
                 if settings.allowed_services.contains(service_name) {
-                    return PermissionEvalResult::Allow;
+                    return (
+                        PermissionEvalResult::Allow,
+                        format!(
+                            "allowed: matched allowed_services=[{}]",
+                            settings.allowed_services.join(", ")
+                        ),
+                    );
                 }
-                PermissionEvalResult::Ask
+                (
+                    PermissionEvalResult::Ask,
+                    format!("ask: {service_name} is not in allowed_services"),
+                )
             },
-            None if is_in_allowlist => PermissionEvalResult::Allow,
+            None if is_in_allowlist => (
+                PermissionEvalResult::Allow,
+                "allowed: use_aws is in allowed_tools".to_string(),
+            ),
             _ => {
                 if self.requires_acceptance() {
-                    PermissionEvalResult::Ask
+                    (
+                        PermissionEvalResult::Ask,
+                        "ask: no rule matched, write operation".to_string(),
+                    )
                 } else {
-                    PermissionEvalResult::Allow
+                    (
+                        PermissionEvalResult::Allow,
+                        "allowed: no rule matched, read-only operation".to_string(),
+                    )
                 }
             },
         }
     }
 }
 
+/// Converts an API parameter name to the flag the AWS CLI expects, e.g. `KmsKeyId` to
+/// `--kms-key-id`, `Ipv6CidrBlock` to `--ipv6-cidr-block`, and `DBInstanceIdentifier` to
+/// `--db-instance-identifier`.
+///
+/// This mirrors how the AWS CLI derives its flags (botocore's `xform_name`), which keeps digits
+/// attached to the word before them and treats a run of capitals as one word. Names that are
+/// already in kebab or snake case are only lowercased.
+fn cli_flag(name: &str) -> String {
+    let name = name.trim_start_matches("--");
+    if name.contains(['-', '_']) {
+        return format!("--{}", name.replace('_', "-").to_lowercase());
+    }
+
+    // A trailing plural acronym is one word, e.g. `ResourceARNs` is `--resource-arns`.
+    let name = match PLURAL_ACRONYM.find(name) {
+        Some(m) if m.start() > 0 => format!("{}-{}", &name[..m.start()], m.as_str().to_lowercase()),
+        Some(_) => name.to_lowercase(),
+        None => name.to_string(),
+    };
+    let name = FIRST_CAP.replace_all(&name, "$1-$2");
+    let name = END_CAP.replace_all(&name, "$1-$2");
+    format!("--{}", name.to_lowercase())
+}
+
+/// Converts a parameter value to the arguments that follow its flag:
+/// - an empty string is a flag without a value, e.g. `--no-paginate`
+/// - a list of strings or numbers is passed as separate values, e.g. `--security-group-ids sg-1
+///   sg-2`
+/// - structures and lists of structures are passed as JSON, which the AWS CLI accepts for any
+///   parameter, e.g. `--tags '[{"Key":"env","Value":"dev"}]'`
+fn cli_values(val: &serde_json::Value) -> Vec<String> {
+    fn scalar(val: &serde_json::Value) -> String {
+        val.as_str().map_or_else(|| val.to_string(), str::to_string)
+    }
+
+    match val {
+        serde_json::Value::String(s) if s.is_empty() => vec![],
+        serde_json::Value::Array(items)
+            if !items.is_empty() && items.iter().all(|item| !item.is_array() && !item.is_object()) =>
+        {
+            items.iter().map(scalar).collect()
+        },
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => vec![val.to_string()],
+        val => vec![scalar(val)],
+    }
+}
+
+/// Returns the agent's `allowedRegions` setting for `use_aws`. An empty list allows all regions.
+fn allowed_regions(agent: &Agent) -> Option<Vec<String>> {
+    let regions = agent.tools_settings.get("use_aws")?.get("allowedRegions")?;
+    match serde_json::from_value(regions.clone()) {
+        Ok(regions) => Some(regions),
+        Err(err) => {
+            error!(?err, "Failed to deserialize allowedRegions for use_aws");
+            None
+        },
+    }
+}
+
+/// Returns the page cap for auto-pagination, if `autoPaginate` is enabled in the agent's `use_aws`
+/// settings. The cap defaults to [DEFAULT_MAX_PAGES] and can be changed with `maxPages`.
+pub fn auto_paginate_max_pages(agent: &Agent) -> Option<usize> {
+    let settings = agent.tools_settings.get("use_aws")?;
+    if !settings
+        .get("autoPaginate")
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let max_pages = settings
+        .get("maxPages")
+        .and_then(serde_json::Value::as_u64)
+        .map_or(DEFAULT_MAX_PAGES, |pages| pages as usize);
+    Some(max_pages.max(1))
+}
+
+/// Merges the pages of a paginated response by concatenating their list fields. The `NextToken`
+/// of the last page is kept, so the model can continue from where pagination stopped.
+fn merge_pages(pages: Vec<serde_json::Value>) -> serde_json::Value {
+    let mut pages = pages.into_iter();
+    let mut merged = pages.next().unwrap_or_default();
+    for page in pages {
+        let (serde_json::Value::Object(merged), serde_json::Value::Object(page)) = (&mut merged, page) else {
+            continue;
+        };
+        merged.remove("NextToken");
+        for (key, value) in page {
+            match (merged.get_mut(&key), value) {
+                (Some(serde_json::Value::Array(items)), serde_json::Value::Array(more)) => items.extend(more),
+                (_, value) => {
+                    merged.insert(key, value);
+                },
+            }
+        }
+    }
+    merged
+}
+
+/// Builds the result of a successful command. JSON written to stdout (the AWS CLI's default
+/// output format) is embedded as structured data under `stdout_json` so that the model doesn't
+/// receive it doubly encoded. Any other output, or output too large to be sent in full, is
+/// truncated and sent as a string under `stdout`.
+fn success_output(status: &str, stdout: &str, stderr: &str, max_bytes: usize) -> serde_json::Value {
+    let stdout_json = (stdout.len() <= max_bytes && !stdout.trim().is_empty())
+        .then(|| serde_json::from_str::<serde_json::Value>(stdout).ok())
+        .flatten();
+    let stderr = truncate_output(stderr, max_bytes);
+    match stdout_json {
+        Some(stdout_json) => serde_json::json!({
+            "exit_status": status,
+            "stdout_json": stdout_json,
+            "stderr": stderr
+        }),
+        None => serde_json::json!({
+            "exit_status": status,
+            "stdout": truncate_output(stdout, max_bytes),
+            "stderr": stderr
+        }),
+    }
+}
+
+/// Truncates `output` to at most `max_bytes` bytes, noting when anything was cut off.
+fn truncate_output(output: &str, max_bytes: usize) -> String {
+    format!(
+        "{}{}",
+        truncate_safe(output, max_bytes),
+        if output.len() > max_bytes { " ... truncated" } else { "" }
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,74 +719,656 @@ mod tests {
         }};
         let params = cmd.cli_parameters().unwrap();
         assert!(
-            params.iter().any(|p| p.0 == "--table-name" && p.1 == "table-name"),
+            params.iter().any(|p| p.0 == "--table-name" && p.1 == ["table-name"]),
             "not found in {:?}",
             params
         );
         assert!(
             params
                 .iter()
-                .any(|p| p.0 == "--key-condition-expression" && p.1 == "PartitionKey = :pkValue"),
+                .any(|p| p.0 == "--key-condition-expression" && p.1 == ["PartitionKey = :pkValue"]),
             "not found in {:?}",
             params
         );
     }
 
-    #[tokio::test]
-    #[ignore = "not in ci"]
-    async fn test_aws_read_only() {
-        let os = Os::new().await.unwrap();
+    #[test]
+    fn test_cli_flag() {
+        for (name, flag) in [
+            ("MaxItems", "--max-items"),
+            ("KmsKeyId", "--kms-key-id"),
+            ("KMSKeyId", "--kms-key-id"),
+            ("VpcId", "--vpc-id"),
+            ("VPCId", "--vpc-id"),
+            ("Ipv6CidrBlock", "--ipv6-cidr-block"),
+            ("AssignIpv6AddressOnCreation", "--assign-ipv6-address-on-creation"),
+            ("S3Key", "--s3-key"),
+            ("S3BucketName", "--s3-bucket-name"),
+            ("DBInstanceIdentifier", "--db-instance-identifier"),
+            ("ResourceARNs", "--resource-arns"),
+            ("VPCs", "--vpcs"),
+            ("TableName", "--table-name"),
+            ("bucket", "--bucket"),
+            ("ipv6", "--ipv6"),
+            ("max-items", "--max-items"),
+            ("--max-items", "--max-items"),
+            ("max_items", "--max-items"),
+            ("no-paginate", "--no-paginate"),
+        ] {
+            assert_eq!(cli_flag(name), flag, "{name}");
+            // Converting a flag again doesn't change it.
+            assert_eq!(cli_flag(flag), flag, "{flag}");
+            assert!(
+                !flag[2..].contains("--") && !flag.ends_with('-') && flag == flag.to_lowercase(),
+                "{flag}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cli_values() {
+        use serde_json::json;
+
+        assert_eq!(cli_values(&json!("my-bucket")), ["my-bucket"]);
+        assert!(cli_values(&json!("")).is_empty());
+        assert_eq!(cli_values(&json!(10)), ["10"]);
+        assert_eq!(cli_values(&json!(true)), ["true"]);
+        assert_eq!(cli_values(&json!(["sg-1", "sg-2"])), ["sg-1", "sg-2"]);
+        assert_eq!(cli_values(&json!([1, 2])), ["1", "2"]);
+        assert_eq!(cli_values(&json!([])), ["[]"]);
+        assert_eq!(cli_values(&json!({ "Enabled": true })), [r#"{"Enabled":true}"#]);
+        assert_eq!(cli_values(&json!([{ "Key": "env", "Value": "dev" }])), [
+            r#"[{"Key":"env","Value":"dev"}]"#
+        ]);
+    }
+
+    #[test]
+    fn test_list_and_structure_parameters() {
+        let cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "run-instances",
+            "parameters": {
+                "SecurityGroupIds": ["sg-1", "sg-2"],
+                "TagSpecifications": [{
+                    "ResourceType": "instance",
+                    "Tags": [{ "Key": "env", "Value": "dev" }]
+                }],
+                "dry-run": ""
+            },
+            "region": "us-west-2",
+            "label": ""
+        }};
+        let args = cmd.cli_args();
+        let position = |flag: &str| args.iter().position(|arg| arg == flag).unwrap();
+
+        let i = position("--security-group-ids");
+        assert_eq!(args[i + 1..i + 3], ["sg-1", "sg-2"]);
+        let i = position("--tag-specifications");
+        let tags = serde_json::from_str::<serde_json::Value>(&args[i + 1]).unwrap();
+        assert_eq!(tags[0]["Tags"][0]["Key"], "env");
+        let i = position("--dry-run");
+        assert!(args.get(i + 1).is_none_or(|arg| arg.starts_with("--")));
+
+        let command = cmd.shell_command().unwrap();
+        assert!(command.contains("--security-group-ids sg-1 sg-2"));
+    }
 
-        let v = serde_json::json!({
+    #[test]
+    fn test_explain_perm_denied_service() {
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "allowedTools": ["use_aws"],
+            "toolsSettings": {
+                "use_aws": {
+                    "allowedServices": ["ec2"],
+                    "deniedServices": ["s3"]
+                }
+            }
+        }))
+        .unwrap();
+
+        let cmd = use_aws! {{
             "service_name": "s3",
-            "operation_name": "put-object",
-            // technically this wouldn't be a valid request with an empty parameter set but it's
-            // okay for this test
-            "parameters": {},
+            "operation_name": "list-buckets",
             "region": "us-west-2",
             "profile_name": "default",
             "label": ""
-        });
+        }};
+        let (res, explanation) = cmd.explain_perm(&agent);
+        assert_eq!(res, PermissionEvalResult::Deny);
+        assert_eq!(explanation, "denied: matched denied_services=[s3]");
+        assert_eq!(cmd.eval_perm(&agent), res);
 
-        assert!(
-            serde_json::from_value::<UseAws>(v)
-                .unwrap()
-                .invoke(&os, &mut std::io::stdout())
-                .await
-                .is_err()
+        let cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "terminate-instances",
+            "region": "us-west-2",
+            "profile_name": "default",
+            "label": ""
+        }};
+        let (res, explanation) = cmd.explain_perm(&agent);
+        assert_eq!(res, PermissionEvalResult::Allow);
+        assert_eq!(explanation, "allowed: matched allowed_services=[ec2]");
+    }
+
+    #[test]
+    fn test_explain_perm_allowed_regions() {
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "allowedTools": ["use_aws"],
+            "toolsSettings": {
+                "use_aws": {
+                    "allowedRegions": ["us-east-1", "eu-west-1"]
+                }
+            }
+        }))
+        .unwrap();
+
+        let cmd = use_aws! {{
+            "service_name": "s3",
+            "operation_name": "list-buckets",
+            "region": "ap-south-1",
+            "label": ""
+        }};
+        let (res, explanation) = cmd.explain_perm(&agent);
+        assert_eq!(res, PermissionEvalResult::Deny);
+        assert_eq!(
+            explanation,
+            "denied: ap-south-1 is not in allowed_regions=[us-east-1, eu-west-1]"
         );
+
+        let cmd = use_aws! {{
+            "service_name": "s3",
+            "operation_name": "list-buckets",
+            "region": "EU-WEST-1",
+            "label": ""
+        }};
+        assert_eq!(cmd.eval_perm(&agent), PermissionEvalResult::Allow);
+
+        // Allowed services don't override the region allowlist
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "allowedTools": ["use_aws"],
+            "toolsSettings": {
+                "use_aws": {
+                    "allowedServices": ["s3"],
+                    "deniedServices": [],
+                    "allowedRegions": ["us-east-1"]
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(cmd.eval_perm(&agent), PermissionEvalResult::Deny);
+
+        // Parameters can't override the region, endpoint, or profile
+        for parameter in ["region", "endpoint-url", "EndpointUrl", "profile"] {
+            let cmd = use_aws! {{
+                "service_name": "s3",
+                "operation_name": "list-buckets",
+                "parameters": { parameter: "ap-south-1" },
+                "region": "us-east-1",
+                "label": ""
+            }};
+            let (res, explanation) = cmd.explain_perm(&agent);
+            assert_eq!(res, PermissionEvalResult::Deny, "{parameter} should be denied");
+            assert!(explanation.contains("can't be passed as parameters"), "{explanation}");
+        }
+
+        // An empty list allows all regions
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "toolsSettings": {
+                "use_aws": {
+                    "allowedRegions": []
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(cmd.eval_perm(&agent), PermissionEvalResult::Allow);
     }
 
     #[tokio::test]
-    #[ignore = "not in ci"]
-    async fn test_aws_output() {
+    async fn test_session_default_profile() {
         let os = Os::new().await.unwrap();
-
-        let v = serde_json::json!({
+        let mut cmd = use_aws! {{
             "service_name": "s3",
             "operation_name": "ls",
-            "parameters": {},
             "region": "us-west-2",
-            "profile_name": "default",
             "label": ""
-        });
-        let out = serde_json::from_value::<UseAws>(v)
+        }};
+        assert_eq!(cmd.effective_profile_name(), None);
+
+        cmd.default_profile_name = Some("staging".to_string());
+        assert_eq!(cmd.effective_profile_name(), Some("staging"));
+        let mut out = vec![];
+        cmd.queue_description(&os, &mut out).unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .contains("Profile name: staging (session default)\n")
+        );
+
+        cmd.profile_name = Some("prod".to_string());
+        assert_eq!(cmd.effective_profile_name(), Some("prod"));
+        let mut out = vec![];
+        cmd.queue_description(&os, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("Profile name: prod\n"));
+    }
+
+    #[test]
+    fn test_success_output_json() {
+        let stdout = r#"{"Buckets": [{"Name": "my-bucket"}], "Owner": {"ID": "123"}}"#;
+        let output = success_output("0", stdout, "", 1000);
+        assert_eq!(output["stdout_json"]["Buckets"][0]["Name"], "my-bucket");
+        assert!(output.get("stdout").is_none());
+        assert_eq!(output["stderr"], "");
+
+        // Output that would be truncated is sent as a string instead.
+        let output = success_output("0", stdout, "", 20);
+        assert!(output.get("stdout_json").is_none());
+        assert!(output["stdout"].as_str().unwrap().ends_with(" ... truncated"));
+    }
+
+    #[test]
+    fn test_success_output_text() {
+        let stdout = "BUCKETS\t2024-01-01T00:00:00+00:00\tmy-bucket\n";
+        let output = success_output("0", stdout, "", 1000);
+        assert_eq!(output["stdout"], stdout);
+        assert!(output.get("stdout_json").is_none());
+
+        let output = success_output("0", "", "", 1000);
+        assert_eq!(output["stdout"], "");
+    }
+
+    #[test]
+    fn test_auto_paginate_settings() {
+        let agent = |settings: serde_json::Value| {
+            serde_json::from_value::<Agent>(serde_json::json!({
+                "name": "test_agent",
+                "toolsSettings": { "use_aws": settings }
+            }))
             .unwrap()
-            .invoke(&os, &mut std::io::stdout())
+        };
+        assert_eq!(auto_paginate_max_pages(&agent(serde_json::json!({}))), None);
+        assert_eq!(
+            auto_paginate_max_pages(&agent(serde_json::json!({ "autoPaginate": true }))),
+            Some(DEFAULT_MAX_PAGES)
+        );
+        assert_eq!(
+            auto_paginate_max_pages(&agent(serde_json::json!({ "autoPaginate": true, "maxPages": 3 }))),
+            Some(3)
+        );
+
+        let mut cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "describe-instances",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert!(cmd.is_paginated());
+        cmd.parameters = Some(HashMap::from([("max_items".to_string(), serde_json::json!(5))]));
+        assert!(!cmd.is_paginated());
+        cmd.operation_name = "terminate-instances".to_string();
+        cmd.parameters = None;
+        assert!(!cmd.is_paginated());
+    }
+
+    #[test]
+    fn test_merge_pages() {
+        let merged = merge_pages(vec![
+            serde_json::json!({ "Functions": [{ "FunctionName": "a" }], "NextToken": "t1" }),
+            serde_json::json!({ "Functions": [{ "FunctionName": "b" }], "NextToken": "t2" }),
+            serde_json::json!({ "Functions": [{ "FunctionName": "c" }] }),
+        ]);
+        assert_eq!(
+            merged,
+            serde_json::json!({ "Functions": [{ "FunctionName": "a" }, { "FunctionName": "b" }, { "FunctionName": "c" }] })
+        );
+
+        // Stopping early keeps the token to continue from.
+        let merged = merge_pages(vec![
+            serde_json::json!({ "Functions": [{ "FunctionName": "a" }], "NextToken": "t1" }),
+            serde_json::json!({ "Functions": [{ "FunctionName": "b" }], "NextToken": "t2" }),
+        ]);
+        assert_eq!(merged["NextToken"], "t2");
+        assert_eq!(merged["Functions"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_shell_command() {
+        let os = Os::new().await.unwrap();
+        let mut cmd = use_aws! {{
+            "service_name": "s3api",
+            "operation_name": "put-object",
+            "parameters": {
+                "bucket": "my-bucket",
+                "key": "my file.txt",
+                "no-paginate": ""
+            },
+            "region": "us-west-2",
+            "label": ""
+        }};
+        let command = cmd.shell_command().unwrap();
+        assert!(command.starts_with("aws --region us-west-2 s3api put-object "));
+        assert!(command.contains("--bucket my-bucket"));
+        assert!(command.contains("--key 'my file.txt'"));
+        assert!(command.contains("--no-paginate"));
+        assert_eq!(shlex::split(&command).unwrap().len(), 10);
+
+        cmd.default_profile_name = Some("staging".to_string());
+        assert!(
+            cmd.shell_command()
+                .unwrap()
+                .starts_with("aws --region us-west-2 --profile staging s3api put-object ")
+        );
+
+        let mut out = vec![];
+        cmd.queue_description(&os, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("Command: "));
+    }
+
+    #[tokio::test]
+    async fn test_danger_banner() {
+        let mut os = Os::new().await.unwrap();
+        let mut cmd = use_aws! {{
+            "service_name": "ec2",
+            "operation_name": "terminate-instances",
+            "region": "us-east-1",
+            "profile_name": "staging",
+            "label": ""
+        }};
+        let description = |cmd: &UseAws, os: &Os| {
+            let mut out = vec![];
+            cmd.queue_description(os, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(cmd.matches_danger_lists(&os), (false, false));
+        assert!(!description(&cmd, &os).contains("PRODUCTION"));
+
+        os.database
+            .settings
+            .set(Setting::ChatAwsDangerProfiles, serde_json::json!(["prod"]))
+            .await
+            .unwrap();
+        os.database
+            .settings
+            .set(Setting::ChatAwsDangerRegions, "us-east-1")
             .await
             .unwrap();
+        assert_eq!(cmd.matches_danger_lists(&os), (false, true));
+        assert!(description(&cmd, &os).contains("⚠ PRODUCTION"));
 
-        if let OutputKind::Json(json) = out.output {
-            // depending on where the test is ran we might get different outcome here but it does
-            // not mean the tool is not working
-            let exit_status = json.get("exit_status").unwrap();
-            if exit_status == 0 {
-                assert_eq!(json.get("stderr").unwrap(), "");
-            } else {
-                assert_ne!(json.get("stderr").unwrap(), "");
+        cmd.profile_name = None;
+        cmd.default_profile_name = Some("prod".to_string());
+        cmd.region = "eu-west-1".to_string();
+        assert_eq!(cmd.matches_danger_lists(&os), (true, false));
+        assert!(description(&cmd, &os).contains("a profile marked as dangerous"));
+    }
+
+    #[test]
+    fn test_max_response_size_override() {
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "toolsSettings": {
+                "use_aws": {
+                    "maxResponseBytes": 30
+                }
             }
-        } else {
-            panic!("Expected JSON output");
+        }))
+        .unwrap();
+
+        let mut cmd = use_aws! {{
+            "service_name": "logs",
+            "operation_name": "get-log-events",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        assert_eq!(cmd.max_response_size, MAX_TOOL_RESPONSE_SIZE);
+        assert_eq!(
+            super::super::max_response_size(&agent, "execute_bash"),
+            MAX_TOOL_RESPONSE_SIZE
+        );
+
+        cmd.max_response_size = super::super::max_response_size(&agent, "use_aws");
+        assert_eq!(cmd.max_response_size, 30);
+
+        let output = "a".repeat(20);
+        assert_eq!(
+            truncate_output(&output, cmd.max_response_size / 3),
+            "aaaaaaaaaa ... truncated"
+        );
+        assert_eq!(truncate_output(&output, MAX_TOOL_RESPONSE_SIZE / 3), output);
+        // Never splits a multibyte character.
+        assert_eq!(truncate_output("ééé", 3), "é ... truncated");
+    }
+
+    #[test]
+    fn test_max_response_size_does_not_restrict() {
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "allowedTools": ["use_aws"],
+            "toolsSettings": {
+                "use_aws": {
+                    "maxResponseBytes": 1000000
+                }
+            }
+        }))
+        .unwrap();
+
+        let cmd = use_aws! {{
+            "service_name": "s3",
+            "operation_name": "put-object",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        let (res, explanation) = cmd.explain_perm(&agent);
+        assert_eq!(res, PermissionEvalResult::Allow);
+        assert_eq!(explanation, "allowed: use_aws is in allowed_tools");
+    }
+
+    /// The arguments and environment of a single run.
+    type Call = (Vec<String>, HashMap<String, String>);
+
+    /// Answers each run with the next of its outputs, recording the arguments and environment
+    /// of each run.
+    #[derive(Debug, Default)]
+    struct MockRunner {
+        outputs: std::sync::Mutex<std::collections::VecDeque<CommandOutput>>,
+        calls: std::sync::Mutex<Vec<Call>>,
+    }
+
+    impl MockRunner {
+        fn new(outputs: impl IntoIterator<Item = CommandOutput>) -> Arc<Self> {
+            Arc::new(Self {
+                outputs: std::sync::Mutex::new(outputs.into_iter().collect()),
+                calls: Default::default(),
+            })
         }
+
+        fn args(&self) -> Vec<Vec<String>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(args, _)| args.clone())
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CommandRunner for MockRunner {
+        async fn run(&self, program: &str, args: &[String], envs: &HashMap<String, String>) -> Result<CommandOutput> {
+            assert_eq!(program, "aws");
+            self.calls.lock().unwrap().push((args.to_vec(), envs.clone()));
+            self.outputs
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| eyre::eyre!("no more outputs"))
+        }
+    }
+
+    fn success(stdout: &str) -> CommandOutput {
+        CommandOutput {
+            status: "0".to_string(),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invoke_failure() {
+        let os = Os::new().await.unwrap();
+        let runner = MockRunner::new([CommandOutput {
+            status: "254".to_string(),
+            stdout: String::new(),
+            stderr: "An error occurred (AccessDenied) when calling the PutObject operation".to_string(),
+        }]);
+        let mut cmd = use_aws! {{
+            "service_name": "s3api",
+            "operation_name": "put-object",
+            "parameters": {},
+            "region": "us-west-2",
+            "profile_name": "default",
+            "label": ""
+        }};
+        cmd.runner = runner.clone();
+
+        let err = cmd.invoke(&os, &mut std::io::sink()).await.unwrap_err();
+        assert!(err.to_string().contains("AccessDenied"));
+
+        let calls = runner.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, [
+            "--region",
+            "us-west-2",
+            "--profile",
+            "default",
+            "s3api",
+            "put-object"
+        ]);
+        assert!(calls[0].1[USER_AGENT_ENV_VAR].contains(&format!("{USER_AGENT_APP_NAME} {USER_AGENT_VERSION_KEY}/")));
+    }
+
+    #[tokio::test]
+    async fn test_invoke_output() {
+        let os = Os::new().await.unwrap();
+        let runner = MockRunner::new([success(r#"{"Buckets": [{"Name": "my-bucket"}]}"#)]);
+        let mut cmd = use_aws! {{
+            "service_name": "s3api",
+            "operation_name": "list-buckets",
+            "parameters": { "BucketRegion": "us-west-2" },
+            "region": "us-west-2",
+            "label": ""
+        }};
+        cmd.runner = runner.clone();
+
+        let out = cmd.invoke(&os, &mut std::io::sink()).await.unwrap();
+        let OutputKind::Json(json) = out.output else {
+            panic!("Expected JSON output");
+        };
+        assert_eq!(json["exit_status"], "0");
+        assert_eq!(json["stdout_json"]["Buckets"][0]["Name"], "my-bucket");
+        assert_eq!(json["stderr"], "");
+        assert_eq!(runner.args(), [[
+            "--region",
+            "us-west-2",
+            "s3api",
+            "list-buckets",
+            "--bucket-region",
+            "us-west-2"
+        ]]);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_paginates() {
+        let os = Os::new().await.unwrap();
+        let runner = MockRunner::new([
+            success(r#"{"Functions": [{"FunctionName": "a"}], "NextToken": "t1"}"#),
+            success(r#"{"Functions": [{"FunctionName": "b"}]}"#),
+        ]);
+        let mut cmd = use_aws! {{
+            "service_name": "lambda",
+            "operation_name": "list-functions",
+            "region": "us-west-2",
+            "label": ""
+        }};
+        cmd.runner = runner.clone();
+        cmd.max_pages = Some(3);
+
+        let out = cmd.invoke(&os, &mut std::io::sink()).await.unwrap();
+        let OutputKind::Json(json) = out.output else {
+            panic!("Expected JSON output");
+        };
+        let functions = json["stdout_json"]["Functions"].as_array().unwrap();
+        assert_eq!(functions.len(), 2);
+        assert!(json["stdout_json"].get("NextToken").is_none());
+
+        let args = runner.args();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[0][args[0].len() - 2..], ["--max-items", "100"]);
+        assert_eq!(args[1][args[1].len() - 4..], [
+            "--max-items",
+            "100",
+            "--starting-token",
+            "t1"
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_invoke_query_and_output_format() {
+        let os = Os::new().await.unwrap();
+        let runner = MockRunner::new([success("a\tb\n")]);
+        let mut cmd = use_aws! {{
+            "service_name": "lambda",
+            "operation_name": "list-functions",
+            "region": "us-west-2",
+            "label": "",
+            "query": "Functions[].FunctionName",
+            "output_format": "text"
+        }};
+        cmd.runner = runner.clone();
+        cmd.max_pages = Some(3);
+
+        let out = cmd.invoke(&os, &mut std::io::sink()).await.unwrap();
+        let OutputKind::Json(json) = out.output else {
+            panic!("Expected JSON output");
+        };
+        assert_eq!(json["stdout"], "a\tb\n");
+
+        // The reshaped response is not paginated
+        let args = runner.args();
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0][args[0].len() - 4..], [
+            "--query",
+            "Functions[].FunctionName",
+            "--output",
+            "text"
+        ]);
+
+        // Flags already given as parameters are not repeated
+        let cmd = use_aws! {{
+            "service_name": "lambda",
+            "operation_name": "list-functions",
+            "parameters": { "query": "Functions[0]" },
+            "region": "us-west-2",
+            "label": "",
+            "query": "Functions[].FunctionName"
+        }};
+        let args = cmd.cli_args();
+        assert_eq!(args.iter().filter(|arg| *arg == "--query").count(), 1);
+        assert!(args.contains(&"Functions[0]".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_output_format() {
+        let cmd = serde_json::from_value::<UseAws>(serde_json::json!({
+            "service_name": "lambda",
+            "operation_name": "list-functions",
+            "region": "us-west-2",
+            "output_format": "yaml-stream"
+        }));
+        assert!(cmd.is_err());
     }
 }
@@ -114,17 +114,10 @@ pub fn handle_images_from_paths(output: &mut impl Write, paths: &[String]) -> Ri
         )
         .ok();
         for (_, metadata) in &images_exceeding_size_limit {
-            let image_size_str = if metadata.size > 1024 * 1024 {
-                format!("{:.2} MB", metadata.size as f64 / (1024.0 * 1024.0))
-            } else if metadata.size > 1024 {
-                format!("{:.2} KB", metadata.size as f64 / 1024.0)
-            } else {
-                format!("{} bytes", metadata.size)
-            };
             execute!(
                 &mut *output,
                 style::SetForegroundColor(Color::DarkYellow),
-                style::Print(format!("  - {} ({})\n", metadata.filename, image_size_str)),
+                style::Print(format!("  - {} ({})\n", metadata.filename, format_size(metadata.size))),
                 style::SetForegroundColor(Color::Reset)
             )
             .ok();
@@ -133,6 +126,141 @@ pub fn handle_images_from_paths(output: &mut impl Write, paths: &[String]) -> Ri
     valid_images
 }
 
+/// Reads the image at `path` to attach to a prompt, detecting its format from its contents rather
+/// than its extension. The error describes why the image can't be attached.
+pub fn load_image(path: &Path) -> eyre::Result<RichImageBlock> {
+    let size = fs::metadata(path)
+        .map_err(|err| eyre::eyre!("{}: {err}", path.display()))?
+        .len();
+    if size as usize > MAX_IMAGE_SIZE {
+        eyre::bail!(
+            "{} is {}, over the {}MB limit",
+            path.display(),
+            format_size(size),
+            MAX_IMAGE_SIZE / (1024 * 1024)
+        );
+    }
+
+    let bytes = fs::read(path).map_err(|err| eyre::eyre!("{}: {err}", path.display()))?;
+    let Some(format) = detect_image_format(&bytes) else {
+        eyre::bail!("{} is not a png, jpeg, gif or webp image", path.display());
+    };
+
+    Ok((
+        ImageBlock {
+            format,
+            source: ImageSource::Bytes(bytes),
+        },
+        ImageMetadata {
+            filepath: path.to_string_lossy().to_string(),
+            size,
+            filename: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        },
+    ))
+}
+
+/// Reads an image from the system clipboard using the platform's clipboard tools, since the
+/// `arboard` build used for text does not include image support.
+pub async fn read_clipboard_image() -> eyre::Result<RichImageBlock> {
+    let bytes = clipboard_image_bytes().await?;
+    if bytes.is_empty() {
+        eyre::bail!("There is no image on the clipboard");
+    }
+    let size = bytes.len() as u64;
+    if bytes.len() > MAX_IMAGE_SIZE {
+        eyre::bail!(
+            "The clipboard image is {}, over the {}MB limit",
+            format_size(size),
+            MAX_IMAGE_SIZE / (1024 * 1024)
+        );
+    }
+    let Some(format) = detect_image_format(&bytes) else {
+        eyre::bail!("There is no png, jpeg, gif or webp image on the clipboard");
+    };
+
+    Ok((
+        ImageBlock {
+            format,
+            source: ImageSource::Bytes(bytes),
+        },
+        ImageMetadata {
+            filepath: "clipboard".to_string(),
+            size,
+            filename: "clipboard image".to_string(),
+        },
+    ))
+}
+
+#[cfg(target_os = "macos")]
+async fn clipboard_image_bytes() -> eyre::Result<Vec<u8>> {
+    let output = run_clipboard_command("osascript", &["-e", "the clipboard as «class PNGf»"]).await?;
+    parse_osascript_data(&String::from_utf8_lossy(&output))
+        .ok_or_else(|| eyre::eyre!("There is no image on the clipboard"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn clipboard_image_bytes() -> eyre::Result<Vec<u8>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run_clipboard_command("wl-paste", &["--no-newline", "--type", "image/png"]).await
+    } else {
+        run_clipboard_command("xclip", &["-selection", "clipboard", "-target", "image/png", "-out"]).await
+    }
+}
+
+#[cfg(not(unix))]
+async fn clipboard_image_bytes() -> eyre::Result<Vec<u8>> {
+    eyre::bail!(
+        "Pasting images from the clipboard is not supported on this platform. Save the image and use /image add instead"
+    )
+}
+
+#[cfg(unix)]
+async fn run_clipboard_command(program: &str, args: &[&str]) -> eyre::Result<Vec<u8>> {
+    let output = match tokio::process::Command::new(program).args(args).output().await {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            eyre::bail!("Reading images from the clipboard requires {program}, which was not found")
+        },
+        Err(err) => eyre::bail!("Failed to run {program}: {err}"),
+    };
+    if !output.status.success() {
+        eyre::bail!("There is no image on the clipboard");
+    }
+    Ok(output.stdout)
+}
+
+/// Parses the `«data PNGf89504E47...»` output of reading the clipboard with osascript.
+#[cfg(any(target_os = "macos", test))]
+fn parse_osascript_data(output: &str) -> Option<Vec<u8>> {
+    let data = output.trim().strip_prefix("«data ")?.strip_suffix('»')?;
+    hex::decode(data.get(4..)?).ok()
+}
+
+/// Detects the format of an image from the magic bytes at the start of its contents.
+pub fn detect_image_format(bytes: &[u8]) -> Option<ImageFormat> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(ImageFormat::Jpeg)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some(ImageFormat::Webp)
+    } else {
+        None
+    }
+}
+
+pub fn format_size(size: u64) -> String {
+    if size > 1024 * 1024 {
+        format!("{:.2} MB", size as f64 / (1024.0 * 1024.0))
+    } else if size > 1024 {
+        format!("{:.2} KB", size as f64 / 1024.0)
+    } else {
+        format!("{} bytes", size)
+    }
+}
+
 /// This function checks if the file path has a supported image type
 /// and returns true if it does, otherwise false.
 /// Supported image types are: jpg, jpeg, png, gif, webp
@@ -280,4 +408,57 @@ mod tests {
 
         assert_eq!(images.len(), MAX_NUMBER_OF_IMAGES_PER_REQUEST);
     }
+
+    #[test]
+    fn test_detect_image_format() {
+        assert_eq!(
+            detect_image_format(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(detect_image_format(&[0xff, 0xd8, 0xff, 0xe0]), Some(ImageFormat::Jpeg));
+        assert_eq!(detect_image_format(b"GIF89a\x01\0"), Some(ImageFormat::Gif));
+        assert_eq!(detect_image_format(b"RIFF\x24\0\0\0WEBPVP8 "), Some(ImageFormat::Webp));
+        assert_eq!(detect_image_format(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(detect_image_format(b"fake_image_data"), None);
+    }
+
+    #[test]
+    fn test_load_image() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // The format comes from the contents, not the extension.
+        let path = temp_dir.path().join("image.jpg");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\nrest").unwrap();
+        let (block, metadata) = load_image(&path).unwrap();
+        assert_eq!(block.format, ImageFormat::Png);
+        assert_eq!(metadata.filename, "image.jpg");
+        assert_eq!(metadata.size, 12);
+
+        let path = temp_dir.path().join("notes.png");
+        std::fs::write(&path, b"not an image").unwrap();
+        assert!(load_image(&path).unwrap_err().to_string().contains("is not a png"));
+
+        let path = temp_dir.path().join("large.png");
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        bytes.resize(MAX_IMAGE_SIZE + 1, 0);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(
+            load_image(&path)
+                .unwrap_err()
+                .to_string()
+                .contains("over the 10MB limit")
+        );
+
+        assert!(load_image(&temp_dir.path().join("missing.png")).is_err());
+    }
+
+    #[test]
+    fn test_parse_osascript_data() {
+        assert_eq!(
+            parse_osascript_data("«data PNGf89504E470D0A1A0A»\n"),
+            Some(b"\x89PNG\r\n\x1a\n".to_vec())
+        );
+        assert_eq!(parse_osascript_data("some text"), None);
+        assert_eq!(parse_osascript_data("«data PNGfZZ»"), None);
+    }
 }
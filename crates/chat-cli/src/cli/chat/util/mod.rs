@@ -4,7 +4,9 @@ pub mod issue;
 pub mod test;
 pub mod ui;
 
+use std::borrow::Cow;
 use std::io::Write;
+use std::str::FromStr;
 use std::time::Duration;
 
 use aws_smithy_types::{
@@ -12,9 +14,13 @@ use aws_smithy_types::{
     Number as SmithyNumber,
 };
 use eyre::Result;
+use unicode_segmentation::UnicodeSegmentation;
 
 use super::ChatError;
+use super::parser::RequestMetadata;
 use super::token_counter::TokenCounter;
+use crate::database::settings::Setting;
+use crate::os::Os;
 
 pub fn truncate_safe(s: &str, max_bytes: usize) -> &str {
     if s.len() <= max_bytes {
@@ -34,6 +40,61 @@ pub fn truncate_safe(s: &str, max_bytes: usize) -> &str {
     &s[..byte_count]
 }
 
+/// Truncates the path `path` to a maximum length of `max_bytes` by replacing its middle
+/// components with `...`, keeping as many leading components as fit along with the final one, e.g.
+/// `/very/long/.../my-project`.
+///
+/// Falls back to keeping the end of `path` if even its final component doesn't fit.
+pub fn truncate_path(path: &str, max_bytes: usize) -> Cow<'_, str> {
+    const ELLIPSIS: &str = "...";
+
+    if path.len() <= max_bytes {
+        return Cow::Borrowed(path);
+    }
+
+    let is_separator = |c: char| c == '/' || c == '\\';
+    let tail_start = path.rfind(is_separator).unwrap_or(0);
+    let tail = &path[tail_start..];
+    // Index of the last separator that can end the kept leading components.
+    let head_end = path[..tail_start]
+        .match_indices(is_separator)
+        .map(|(i, _)| i)
+        .take_while(|i| i + 1 + ELLIPSIS.len() + tail.len() <= max_bytes)
+        .last();
+
+    match head_end {
+        Some(i) => Cow::Owned(format!("{}{ELLIPSIS}{tail}", &path[..=i])),
+        None if max_bytes >= ELLIPSIS.len() => {
+            let mut start = path.len() - (max_bytes - ELLIPSIS.len());
+            while !path.is_char_boundary(start) {
+                start += 1;
+            }
+            Cow::Owned(format!("{ELLIPSIS}{}", &path[start..]))
+        },
+        None => Cow::Borrowed(truncate_safe(path, max_bytes)),
+    }
+}
+
+/// Like [truncate_safe], but only cuts on grapheme cluster boundaries so that the result never ends
+/// in the middle of a user-perceived character such as a flag or a ZWJ emoji sequence.
+///
+/// The result is at most `max_bytes` long, and may be empty if the first grapheme cluster alone
+/// exceeds `max_bytes`.
+pub fn truncate_safe_graphemes(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+
+    let end = s
+        .grapheme_indices(true)
+        .map(|(i, grapheme)| i + grapheme.len())
+        .take_while(|end| *end <= max_bytes)
+        .last()
+        .unwrap_or(0);
+
+    &s[..end]
+}
+
 /// Truncates `s` to a maximum length of `max_bytes`, appending `suffix` if `s` was truncated. The
 /// result is always guaranteed to be at least less than `max_bytes`.
 ///
@@ -46,13 +107,72 @@ pub fn truncate_safe_in_place(s: &mut String, max_bytes: usize, suffix: &str) {
         return;
     }
 
-    let end = truncate_safe(s, max_bytes - suffix.len()).len();
+    let end = truncate_safe_graphemes(s, max_bytes - suffix.len()).len();
     s.replace_range(end..s.len(), suffix);
     s.truncate(max_bytes);
 }
 
-pub fn animate_output(output: &mut impl Write, bytes: &[u8]) -> Result<(), ChatError> {
-    for b in bytes.chunks(12) {
+/// How quickly [animate_output] types out its output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypewriterSpeed {
+    /// Write everything at once
+    Off,
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl TypewriterSpeed {
+    /// Reads the speed from the `chat.typewriterSpeed` setting. Animation is always off when
+    /// [ui::should_animate] is false.
+    pub fn from_settings(os: &Os) -> Self {
+        if !ui::should_animate() {
+            return Self::Off;
+        }
+
+        os.database
+            .settings
+            .get_string(Setting::ChatTypewriterSpeed)
+            .and_then(|speed| speed.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Number of bytes written every frame, or [None] if the output is not animated.
+    fn bytes_per_frame(self) -> Option<usize> {
+        match self {
+            Self::Off => None,
+            Self::Slow => Some(6),
+            Self::Normal => Some(12),
+            Self::Fast => Some(48),
+        }
+    }
+}
+
+impl FromStr for TypewriterSpeed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "slow" => Ok(Self::Slow),
+            "normal" => Ok(Self::Normal),
+            "fast" => Ok(Self::Fast),
+            other => Err(format!(
+                "invalid typewriter speed '{other}', expected one of: off, slow, normal, fast"
+            )),
+        }
+    }
+}
+
+pub fn animate_output(output: &mut impl Write, bytes: &[u8], speed: TypewriterSpeed) -> Result<(), ChatError> {
+    let Some(bytes_per_frame) = speed.bytes_per_frame() else {
+        output.write_all(bytes)?;
+        output.flush()?;
+        return Ok(());
+    };
+
+    for b in bytes.chunks(bytes_per_frame) {
         output.write_all(b)?;
         std::thread::sleep(Duration::from_millis(16));
     }
@@ -155,6 +275,21 @@ fn should_play_bell() -> bool {
     false
 }
 
+/// Formats a one-line summary of a response's latency and approximate output tokens.
+pub fn response_stats(content: &str, request_metadata: &RequestMetadata) -> String {
+    let first_chunk = request_metadata
+        .time_to_first_chunk
+        .map_or_else(|| "-".to_string(), |d| format!("{:.1}s", d.as_secs_f64()));
+    let total = request_metadata
+        .stream_end_timestamp_ms
+        .saturating_sub(request_metadata.request_start_timestamp_ms);
+    format!(
+        "~{} tokens · first chunk {first_chunk} · total {:.1}s",
+        TokenCounter::count_tokens(content),
+        Duration::from_millis(total).as_secs_f64()
+    )
+}
+
 /// This is a simple greedy algorithm that drops the largest files first
 /// until the total size is below the limit
 ///
@@ -164,17 +299,24 @@ fn should_play_bell() -> bool {
 ///
 /// Returns the dropped files
 pub fn drop_matched_context_files(files: &mut [(String, String)], limit: usize) -> Result<Vec<(String, String)>> {
-    files.sort_by(|a, b| TokenCounter::count_tokens(&b.1).cmp(&TokenCounter::count_tokens(&a.1)));
+    let sizes =
+        TokenCounter::count_tokens_batch(&files.iter().map(|(_, content)| content.as_str()).collect::<Vec<_>>());
+    let mut sized_files = sizes
+        .into_iter()
+        .zip(files.iter_mut().map(std::mem::take))
+        .collect::<Vec<_>>();
+    sized_files.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+
     let mut total_size = 0;
     let mut dropped_files = Vec::new();
 
-    for (filename, content) in files.iter() {
-        let size = TokenCounter::count_tokens(content);
+    for (slot, (size, file)) in files.iter_mut().zip(sized_files) {
         if total_size + size > limit {
-            dropped_files.push((filename.clone(), content.clone()));
+            dropped_files.push(file.clone());
         } else {
             total_size += size;
         }
+        *slot = file;
     }
     Ok(dropped_files)
 }
@@ -235,6 +377,39 @@ pub fn document_to_serde_value(value: Document) -> serde_json::Value {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_typewriter_speed_from_str() {
+        assert_eq!("off".parse::<TypewriterSpeed>(), Ok(TypewriterSpeed::Off));
+        assert_eq!(" Fast ".parse::<TypewriterSpeed>(), Ok(TypewriterSpeed::Fast));
+        assert!("ludicrous".parse::<TypewriterSpeed>().is_err());
+    }
+
+    #[test]
+    fn test_animate_output_off_writes_everything() {
+        let bytes = "a".repeat(10_000);
+        let mut output = Vec::new();
+        animate_output(&mut output, bytes.as_bytes(), TypewriterSpeed::Off).unwrap();
+        assert_eq!(output, bytes.as_bytes());
+    }
+
+    #[test]
+    fn test_response_stats() {
+        let request_metadata = RequestMetadata {
+            request_start_timestamp_ms: 1_000,
+            stream_end_timestamp_ms: 3_500,
+            time_to_first_chunk: Some(Duration::from_millis(800)),
+            ..Default::default()
+        };
+        assert_eq!(
+            response_stats(&"a".repeat(40), &request_metadata),
+            "~10 tokens · first chunk 0.8s · total 2.5s"
+        );
+        assert_eq!(
+            response_stats("", &RequestMetadata::default()),
+            "~0 tokens · first chunk - · total 0.0s"
+        );
+    }
+
     #[test]
     fn test_truncate_safe() {
         assert_eq!(truncate_safe("Hello World", 5), "Hello");
@@ -243,6 +418,59 @@ mod tests {
         assert_eq!(truncate_safe("Hello World", 15), "Hello World");
     }
 
+    #[test]
+    fn test_truncate_path() {
+        let deep = format!(
+            "/very/long/prefix/{}/my-project",
+            "nested/".repeat(60).trim_end_matches('/')
+        );
+        assert!(deep.len() > 256);
+        let truncated = truncate_path(&deep, 256);
+        assert!(truncated.len() <= 256);
+        assert!(truncated.starts_with("/very/long/prefix/"));
+        assert!(truncated.ends_with("/.../my-project"));
+
+        assert_eq!(truncate_path("/a/b/c/d/my-project", 100), "/a/b/c/d/my-project");
+        assert_eq!(truncate_path("/a/b/c/d/my-project", 18), "/a/.../my-project");
+        assert_eq!(truncate_path("/a/b/c/d/my-project", 15), "/.../my-project");
+        assert_eq!(
+            truncate_path(r"C:\Users\me\src\my-project", 23),
+            r"C:\Users\...\my-project"
+        );
+
+        // The final component alone is too long, so only its end is kept
+        assert_eq!(truncate_path("/a/b/my-project", 8), "...oject");
+        assert_eq!(truncate_path("/αα/αααα", 6), "...α");
+        assert_eq!(truncate_path("/a/b/my-project", 2), "/a");
+    }
+
+    #[test]
+    fn test_truncate_safe_graphemes() {
+        let family = "👨‍👩‍👧‍👦";
+        assert_eq!(family.len(), 25);
+
+        let s = format!("ab{family}cd");
+        // truncate_safe would cut inside the ZWJ sequence
+        assert_ne!(truncate_safe(&s, 10), "ab");
+        assert_eq!(truncate_safe_graphemes(&s, 10), "ab");
+        assert_eq!(truncate_safe_graphemes(&s, 26), "ab");
+        assert_eq!(truncate_safe_graphemes(&s, 27), format!("ab{family}"));
+        assert_eq!(truncate_safe_graphemes(&s, 100), s);
+        assert_eq!(truncate_safe_graphemes(family, 24), "");
+
+        // Flags and combining characters
+        assert_eq!(truncate_safe_graphemes("🇺🇸🇯🇵", 12), "🇺🇸");
+        assert_eq!(truncate_safe_graphemes("cafe\u{301}!", 5), "caf");
+    }
+
+    #[test]
+    fn test_truncate_safe_in_place_keeps_graphemes_whole() {
+        let family = "👨‍👩‍👧‍👦";
+        let mut s = format!("ab{family}cd");
+        truncate_safe_in_place(&mut s, 20, "...");
+        assert_eq!(s, "ab...");
+    }
+
     #[test]
     fn test_truncate_safe_in_place() {
         let suffix = "suffix";
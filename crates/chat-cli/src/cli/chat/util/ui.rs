@@ -1,4 +1,20 @@
-use std::io::Write;
+use std::io::{
+    IsTerminal,
+    Write,
+};
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::sync::{
+    LazyLock,
+    mpsc,
+};
+use std::thread;
+use std::time::{
+    Duration,
+    Instant,
+};
 
 use crossterm::style::{
     Color,
@@ -15,56 +31,162 @@ use crossterm::{
 };
 use eyre::Result;
 use strip_ansi_escapes::strip_str;
+use unicode_width::{
+    UnicodeWidthChar,
+    UnicodeWidthStr,
+};
 
-pub fn draw_box(
-    output: &mut impl Write,
-    title: &str,
-    content: &str,
-    box_width: usize,
-    border_color: Color,
-) -> Result<()> {
-    let inner_width = box_width - 4; // account for │ and padding
+/// Set by `--no-animation`, see [should_animate].
+static ANIMATION_DISABLED: AtomicBool = AtomicBool::new(false);
 
-    // wrap the single line into multiple lines respecting inner width
-    // Manually wrap the text by splitting at word boundaries
-    let mut wrapped_lines = Vec::new();
-    let mut line = String::new();
+/// Disables the typewriter effect and spinners for the rest of the process.
+pub fn disable_animation() {
+    ANIMATION_DISABLED.store(true, Ordering::Relaxed);
+}
 
-    for word in content.split_whitespace() {
-        if line.len() + word.len() < inner_width {
-            if !line.is_empty() {
-                line.push(' ');
-            }
-            line.push_str(word);
-        } else {
-            // Here we need to account for words that are too long as well
-            if word.len() >= inner_width {
-                let mut start = 0_usize;
-                for (i, _) in word.chars().enumerate() {
-                    if i - start >= inner_width {
-                        wrapped_lines.push(word[start..i].to_string());
-                        start = i;
-                    }
+/// Whether chat output should contain ANSI styling. Styling is dropped when `NO_COLOR` or `CI` is
+/// set, or when stdout is not a terminal.
+pub fn should_style() -> bool {
+    static SHOULD_STYLE: LazyLock<bool> = LazyLock::new(|| {
+        let env_set = |key: &str| std::env::var(key).is_ok_and(|v| !v.is_empty() && v != "0" && v != "false");
+        !env_set("NO_COLOR") && !env_set("CI") && std::io::stdout().is_terminal()
+    });
+    *SHOULD_STYLE
+}
+
+/// Whether chat output should be animated with spinners and the typewriter effect. This is never
+/// the case when [should_style] is false.
+pub fn should_animate() -> bool {
+    !ANIMATION_DISABLED.load(Ordering::Relaxed) && should_style()
+}
+
+/// A spinner that also shows how long it has been running, used while waiting on the first chunk
+/// of a response since that can take a while on slow connections.
+///
+/// Like [spinners::Spinner], it draws to stderr from a separate thread until dropped, at which
+/// point the line is cleared.
+pub struct ElapsedSpinner {
+    stop_tx: mpsc::Sender<()>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl ElapsedSpinner {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    const INTERVAL: Duration = Duration::from_millis(80);
+
+    /// Starts drawing `message` with the time elapsed since `start`.
+    pub fn new(message: impl Into<String>, start: Instant) -> Self {
+        let message = message.into();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut stderr = std::io::stderr();
+            let _ = execute!(stderr, cursor::Hide);
+            for frame in Self::FRAMES.iter().cycle() {
+                let _ = execute!(
+                    stderr,
+                    cursor::MoveToColumn(0),
+                    terminal::Clear(ClearType::CurrentLine),
+                    style::Print(elapsed_spinner_line(*frame, &message, start.elapsed())),
+                );
+                // Stop when asked to, or when the sender was dropped.
+                if !matches!(
+                    stop_rx.recv_timeout(Self::INTERVAL),
+                    Err(mpsc::RecvTimeoutError::Timeout)
+                ) {
+                    break;
                 }
-                wrapped_lines.push(word[start..].to_string());
-                line = String::new();
-            } else {
-                wrapped_lines.push(line);
-                line = word.to_string();
             }
+            let _ = execute!(
+                stderr,
+                cursor::MoveToColumn(0),
+                terminal::Clear(ClearType::CurrentLine),
+                cursor::Show
+            );
+        });
+
+        Self {
+            stop_tx,
+            handle: Some(handle),
         }
     }
+}
 
-    if !line.is_empty() {
-        wrapped_lines.push(line);
+impl Drop for ElapsedSpinner {
+    fn drop(&mut self) {
+        let _ = self.stop_tx.send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The line drawn by [ElapsedSpinner], e.g. `⠋ Thinking... 3s`. The elapsed time is only shown
+/// after the first second.
+fn elapsed_spinner_line(frame: char, message: &str, elapsed: Duration) -> String {
+    match elapsed.as_secs() {
+        0 => format!("{frame} {message}"),
+        secs => format!("{frame} {message} {}", format!("{secs}s").dark_grey()),
+    }
+}
+
+/// An output stream for the chat UI that strips ANSI escape sequences from everything written to
+/// it when [should_style] is false.
+pub enum ChatOutput<W: Write> {
+    Styled(W),
+    Plain(Box<strip_ansi_escapes::Writer<W>>),
+}
+
+impl<W: Write> ChatOutput<W> {
+    pub fn new(inner: W) -> Self {
+        match should_style() {
+            true => Self::Styled(inner),
+            false => Self::Plain(Box::new(strip_ansi_escapes::Writer::new(inner))),
+        }
+    }
+}
+
+impl<W: Write> Write for ChatOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Styled(w) => w.write(buf),
+            Self::Plain(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Styled(w) => w.flush(),
+            Self::Plain(w) => w.flush(),
+        }
     }
+}
+
+/// Draws `content` centered in a rounded box titled `title`.
+///
+/// The box is `box_width` columns wide, or one column narrower than the terminal if that is
+/// smaller, so that it never wraps.
+pub fn draw_box(
+    output: &mut impl Write,
+    title: &str,
+    content: &str,
+    box_width: usize,
+    border_color: Color,
+) -> Result<()> {
+    let box_width = match terminal::size() {
+        Ok((cols, _)) => box_width.min((cols as usize).saturating_sub(1)),
+        Err(_) => box_width,
+    }
+    .max(title.width() + 4);
+    let inner_width = box_width - 4; // account for │ and padding
+    let wrapped_lines = wrap_to_width(content, inner_width);
 
-    let side_len = (box_width.saturating_sub(title.len())) / 2;
+    let title_width = title.width();
+    let side_len = (box_width.saturating_sub(title_width)) / 2;
     let top_border = format!(
         "{} {} {}",
         style::style(format!("╭{}", "─".repeat(side_len - 2))).with(border_color),
         title,
-        style::style(format!("{}╮", "─".repeat(box_width - side_len - title.len() - 2))).with(border_color)
+        style::style(format!("{}╮", "─".repeat(box_width - side_len - title_width - 2))).with(border_color)
     );
 
     execute!(
@@ -83,7 +205,7 @@ pub fn draw_box(
 
     // Centered wrapped content
     for line in wrapped_lines {
-        let visible_line_len = strip_str(&line).len();
+        let visible_line_len = strip_str(&line).width();
         let left_pad = box_width.saturating_sub(4).saturating_sub(visible_line_len) / 2;
 
         let content = format!(
@@ -114,6 +236,51 @@ pub fn draw_box(
     Ok(())
 }
 
+/// Wraps `content` at word boundaries into lines no wider than `width` columns, splitting words
+/// that don't fit on a line of their own.
+fn wrap_to_width(content: &str, width: usize) -> Vec<String> {
+    let mut wrapped_lines = Vec::new();
+    let mut line = String::new();
+    let mut line_width = 0;
+
+    for word in content.split_whitespace() {
+        let word_width = word.width();
+        if line.is_empty() && word_width <= width {
+            line.push_str(word);
+            line_width = word_width;
+        } else if !line.is_empty() && line_width + 1 + word_width <= width {
+            line.push(' ');
+            line.push_str(word);
+            line_width += 1 + word_width;
+        } else {
+            if !line.is_empty() {
+                wrapped_lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if word_width <= width {
+                line.push_str(word);
+                line_width = word_width;
+            } else {
+                // Split the word by character, since wide characters take up two columns.
+                for c in word.chars() {
+                    let c_width = c.width().unwrap_or(0);
+                    if line_width + c_width > width {
+                        wrapped_lines.push(std::mem::take(&mut line));
+                        line_width = 0;
+                    }
+                    line.push(c);
+                    line_width += c_width;
+                }
+            }
+        }
+    }
+
+    if !line.is_empty() {
+        wrapped_lines.push(line);
+    }
+    wrapped_lines
+}
+
 #[cfg(test)]
 mod tests {
     use bstr::ByteSlice;
@@ -122,6 +289,25 @@ mod tests {
     use super::*;
     use crate::cli::chat::GREETING_BREAK_POINT;
 
+    #[test]
+    fn test_plain_chat_output_strips_styling() {
+        let mut buf = Vec::new();
+        {
+            let mut output = ChatOutput::Plain(Box::new(strip_ansi_escapes::Writer::new(&mut buf)));
+            execute!(
+                output,
+                style::SetForegroundColor(Color::Green),
+                style::SetAttribute(style::Attribute::Bold),
+                style::Print("hello"),
+                style::SetAttribute(style::Attribute::Reset),
+                cursor::Hide,
+                style::Print(" world\n"),
+            )
+            .unwrap();
+        }
+        assert_eq!(buf, b"hello world\n");
+    }
+
     #[tokio::test]
     async fn test_draw_tip_box() {
         let mut output = vec![];
@@ -199,4 +385,45 @@ mod tests {
             assert!(output_str.contains(part), "Output should contain parts of the long tip");
         }
     }
+
+    #[test]
+    fn test_wrap_to_width_cjk() {
+        // Each of these characters is two columns wide.
+        let lines = wrap_to_width("你好世界 こんにちは世界 안녕하세요", 10);
+        assert_eq!(lines, vec!["你好世界", "こんにちは", "世界", "안녕하세요"]);
+
+        let lines = wrap_to_width(&"界".repeat(7), 5);
+        assert_eq!(lines, vec!["界界", "界界", "界界", "界"]);
+
+        let lines = wrap_to_width("ascii 你好 ok", 9);
+        assert_eq!(lines, vec!["ascii", "你好 ok"]);
+    }
+
+    #[test]
+    fn test_draw_box_cjk_width() {
+        let mut output = vec![];
+        draw_box(&mut output, "提示", &"你好世界 ".repeat(20), 40, Color::DarkGrey).unwrap();
+
+        let output = strip_str(output.to_str_lossy());
+        let widths = output
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.width())
+            .collect::<Vec<_>>();
+        assert!(widths.len() > 4);
+        assert!(widths.iter().all(|w| *w == widths[0]), "uneven box: {output}");
+        assert!(widths[0] <= 40);
+    }
+
+    #[test]
+    fn test_elapsed_spinner_line() {
+        assert_eq!(
+            elapsed_spinner_line('⠋', "Thinking...", Duration::from_millis(900)),
+            "⠋ Thinking..."
+        );
+        assert_eq!(
+            strip_str(elapsed_spinner_line('⠋', "Thinking...", Duration::from_millis(3500))),
+            "⠋ Thinking... 3s"
+        );
+    }
 }
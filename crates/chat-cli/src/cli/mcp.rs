@@ -2,9 +2,14 @@ use std::collections::{
     BTreeMap,
     HashMap,
 };
-use std::io::Write;
+use std::io::{
+    IsTerminal,
+    SeekFrom,
+    Write,
+};
 use std::path::PathBuf;
 use std::process::ExitCode;
+use std::time::Duration;
 
 use clap::{
     ArgAction,
@@ -20,6 +25,10 @@ use eyre::{
     Result,
     bail,
 };
+use tokio::io::{
+    AsyncReadExt as _,
+    AsyncSeekExt as _,
+};
 
 use super::agent::{
     Agent,
@@ -59,6 +68,8 @@ impl std::fmt::Display for Scope {
 pub enum McpSubcommand {
     /// Add or replace a configured server
     Add(AddArgs),
+    /// Edit individual fields of a configured server
+    Edit(EditArgs),
     /// Remove a server from the MCP configuration
     #[command(alias = "rm")]
     Remove(RemoveArgs),
@@ -68,16 +79,23 @@ pub enum McpSubcommand {
     Import(ImportArgs),
     /// Get the status of a configured server
     Status(StatusArgs),
+    /// Show the output of a server from the chat sessions running it
+    Logs(LogsArgs),
+    /// Move the servers of the legacy mcp.json files into agent configs
+    Migrate(MigrateArgs),
 }
 
 impl McpSubcommand {
     pub async fn execute(self, os: &mut Os, output: &mut impl Write) -> Result<ExitCode> {
         match self {
             Self::Add(args) => args.execute(os, output).await?,
+            Self::Edit(args) => args.execute(os, output).await?,
             Self::Remove(args) => args.execute(os, output).await?,
             Self::List(args) => args.execute(os, output).await?,
             Self::Import(args) => args.execute(os, output).await?,
             Self::Status(args) => args.execute(os, output).await?,
+            Self::Logs(args) => args.execute(os, output).await?,
+            Self::Migrate(args) => args.execute(os, output).await?,
         }
 
         output.flush()?;
@@ -89,13 +107,13 @@ impl McpSubcommand {
 pub struct AddArgs {
     /// Name for the server
     #[arg(long)]
-    pub name: String,
+    pub name: Option<String>,
     /// Scope. This parameter is only meaningful in the absence of agent name.
     #[arg(long)]
     pub scope: Option<Scope>,
     /// The command used to launch the server
     #[arg(long)]
-    pub command: String,
+    pub command: Option<String>,
     /// Arguments to pass to the command
     #[arg(long, action = ArgAction::Append, allow_hyphen_values = true, value_delimiter = ',')]
     pub args: Vec<String>,
@@ -115,37 +133,44 @@ pub struct AddArgs {
     /// Overwrite an existing server with the same name
     #[arg(long, default_value_t = false)]
     pub force: bool,
+    /// Prompt for the server configuration step by step. This is the default when neither --name
+    /// nor --command is supplied.
+    #[arg(long, short = 'i', default_value_t = false)]
+    pub interactive: bool,
 }
 
 impl AddArgs {
-    pub async fn execute(self, os: &Os, output: &mut impl Write) -> Result<()> {
+    pub async fn execute(mut self, os: &Os, output: &mut impl Write) -> Result<()> {
+        if self.interactive || (self.name.is_none() && self.command.is_none()) {
+            if !std::io::stdin().is_terminal() {
+                bail!("--name and --command are required when not running in a terminal");
+            }
+            self = self.prompt()?;
+        }
+
+        let (Some(name), Some(_)) = (self.name.clone(), self.command.as_ref()) else {
+            bail!("Both --name and --command are required. Use --interactive to be prompted for them.");
+        };
+        let tool = self.tool_config()?;
+
         match self.agent.as_deref() {
             Some(agent_name) => {
                 let (mut agent, config_path) = Agent::get_agent_by_name(os, agent_name).await?;
                 let mcp_servers = &mut agent.mcp_servers.mcp_servers;
 
-                if mcp_servers.contains_key(&self.name) && !self.force {
+                if mcp_servers.contains_key(&name) && !self.force {
                     bail!(
                         "\nMCP server '{}' already exists in agent {} (path {}). Use --force to overwrite.",
-                        self.name,
+                        name,
                         agent_name,
                         config_path.display(),
                     );
                 }
 
-                let merged_env = self.env.into_iter().flatten().collect::<HashMap<_, _>>();
-                let tool: CustomToolConfig = serde_json::from_value(serde_json::json!({
-                    "command": self.command,
-                    "args": self.args,
-                    "env": merged_env,
-                    "timeout": self.timeout.unwrap_or(default_timeout()),
-                    "disabled": self.disabled,
-                }))?;
-
-                mcp_servers.insert(self.name.clone(), tool);
+                mcp_servers.insert(name.clone(), tool);
                 let json = agent.to_str_pretty()?;
-                os.fs.write(config_path, json).await?;
-                writeln!(output, "✓ Added MCP server '{}' to agent {}\n", self.name, agent_name)?;
+                os.fs.write_atomic(config_path, json).await?;
+                writeln!(output, "✓ Added MCP server '{}' to agent {}\n", name, agent_name)?;
             },
             None => {
                 let legacy_mcp_config_path = match self.scope {
@@ -154,29 +179,20 @@ impl AddArgs {
                 };
                 let mut mcp_servers = McpServerConfig::load_from_file(os, &legacy_mcp_config_path).await?;
 
-                if mcp_servers.mcp_servers.contains_key(&self.name) && !self.force {
+                if mcp_servers.mcp_servers.contains_key(&name) && !self.force {
                     bail!(
                         "\nMCP server '{}' already exists in global config (path {}). Use --force to overwrite.",
-                        self.name,
+                        name,
                         &legacy_mcp_config_path.display(),
                     );
                 }
 
-                let merged_env = self.env.into_iter().flatten().collect::<HashMap<_, _>>();
-                let tool: CustomToolConfig = serde_json::from_value(serde_json::json!({
-                    "command": self.command,
-                    "args": self.args,
-                    "env": merged_env,
-                    "timeout": self.timeout.unwrap_or(default_timeout()),
-                    "disabled": self.disabled,
-                }))?;
-
-                mcp_servers.mcp_servers.insert(self.name.clone(), tool);
+                mcp_servers.mcp_servers.insert(name.clone(), tool);
                 mcp_servers.save_to_file(os, &legacy_mcp_config_path).await?;
                 writeln!(
                     output,
                     "✓ Added MCP server '{}' to global config in {}\n",
-                    self.name,
+                    name,
                     legacy_mcp_config_path.display()
                 )?;
             },
@@ -184,6 +200,220 @@ impl AddArgs {
 
         Ok(())
     }
+
+    /// Builds the [CustomToolConfig] described by these args.
+    fn tool_config(&self) -> Result<CustomToolConfig> {
+        let merged_env = self.env.iter().flatten().collect::<HashMap<_, _>>();
+        Ok(serde_json::from_value(serde_json::json!({
+            "command": self.command,
+            "args": self.args,
+            "env": merged_env,
+            "timeout": self.timeout.unwrap_or(default_timeout()),
+            "disabled": self.disabled,
+        }))?)
+    }
+
+    /// Walks the user through each field of the server config, using any values already supplied
+    /// as flags as the initial text.
+    fn prompt(mut self) -> Result<Self> {
+        let name = prompt_text("Server name", self.name.as_deref(), false, |s: &String| {
+            match s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                true => Ok(()),
+                false => Err("Name can only contain alphanumeric characters, hyphens, and underscores"),
+            }
+        })?;
+        self.name = Some(name);
+
+        let command = prompt_text("Command", self.command.as_deref(), false, |_: &String| Ok(()))?;
+        self.command = Some(command);
+
+        let args = prompt_text(
+            "Arguments (space separated, quote arguments containing spaces)",
+            Some(&shlex::try_join(self.args.iter().map(String::as_str)).unwrap_or_default()),
+            true,
+            |s: &String| match shlex::split(s) {
+                Some(_) => Ok(()),
+                None => Err("Arguments contain an unterminated quote"),
+            },
+        )?;
+        self.args = shlex::split(&args).unwrap_or_default();
+
+        let env = prompt_text(
+            "Environment variables (KEY=VALUE, comma separated)",
+            Some(
+                &self
+                    .env
+                    .iter()
+                    .flatten()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            true,
+            |s: &String| match s.trim().is_empty() || parse_env_vars(s).is_ok() {
+                true => Ok(()),
+                false => Err("Expected a comma separated list of KEY=VALUE pairs"),
+            },
+        )?;
+        self.env = match env.trim().is_empty() {
+            true => vec![],
+            false => vec![parse_env_vars(&env)?],
+        };
+
+        let timeout = prompt_text(
+            "Launch timeout in milliseconds",
+            Some(&self.timeout.unwrap_or(default_timeout()).to_string()),
+            false,
+            |s: &String| match s.trim().parse::<u64>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err("Timeout must be a whole number of milliseconds"),
+            },
+        )?;
+        self.timeout = Some(timeout.trim().parse()?);
+
+        if self.agent.is_none() {
+            let options = ["Global mcp.json", "Workspace mcp.json", "An agent"];
+            let default = match self.scope {
+                Some(Scope::Workspace) => 1,
+                _ => 0,
+            };
+            let selection = dialoguer::Select::with_theme(&crate::util::dialoguer_theme())
+                .with_prompt("Where should the server be added?")
+                .items(&options)
+                .default(default)
+                .interact_opt()?;
+            match selection {
+                Some(0) => self.scope = Some(Scope::Global),
+                Some(1) => self.scope = Some(Scope::Workspace),
+                Some(_) => {
+                    let agent = prompt_text("Agent name", None, false, |_: &String| Ok(()))?;
+                    self.agent = Some(agent);
+                },
+                None => bail!("Cancelled"),
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Prompts for a single line of text, re-prompting until `validator` accepts the input.
+fn prompt_text(
+    prompt: &str,
+    initial_text: Option<&str>,
+    allow_empty: bool,
+    validator: impl FnMut(&String) -> Result<(), &'static str>,
+) -> Result<String> {
+    let theme = crate::util::dialoguer_theme();
+    let mut input = dialoguer::Input::<String>::with_theme(&theme)
+        .with_prompt(prompt)
+        .allow_empty(allow_empty)
+        .validate_with(validator);
+    if let Some(initial_text) = initial_text.filter(|s| !s.is_empty()) {
+        input = input.with_initial_text(initial_text);
+    }
+
+    Ok(input.interact_text()?.trim().to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct EditArgs {
+    /// Name of the server to edit
+    #[arg(long)]
+    pub name: String,
+    /// Scope. This parameter is only meaningful in the absence of agent name.
+    #[arg(long)]
+    pub scope: Option<Scope>,
+    /// The agent whose server should be edited. If an agent name is not supplied, the changes
+    /// shall be made to the global mcp.json
+    #[arg(long)]
+    pub agent: Option<String>,
+    /// Arguments to append to the existing arguments
+    #[arg(long, action = ArgAction::Append, allow_hyphen_values = true, value_delimiter = ',')]
+    pub append_args: Vec<String>,
+    /// Arguments to remove from the existing arguments
+    #[arg(long, action = ArgAction::Append, allow_hyphen_values = true, value_delimiter = ',')]
+    pub remove_args: Vec<String>,
+    /// New server launch timeout, in milliseconds
+    #[arg(long)]
+    pub set_timeout: Option<u64>,
+    /// Environment variables to add or overwrite
+    #[arg(long, value_parser = parse_env_vars)]
+    pub set_env: Vec<HashMap<String, String>>,
+    /// Environment variables to remove
+    #[arg(long, action = ArgAction::Append, value_delimiter = ',')]
+    pub unset_env: Vec<String>,
+}
+
+impl EditArgs {
+    pub async fn execute(self, os: &Os, output: &mut impl Write) -> Result<()> {
+        if self.append_args.is_empty()
+            && self.remove_args.is_empty()
+            && self.set_timeout.is_none()
+            && self.set_env.is_empty()
+            && self.unset_env.is_empty()
+        {
+            bail!("Nothing to edit. Use --append-args, --remove-args, --set-timeout, --set-env, or --unset-env.");
+        }
+
+        match self.agent.as_deref() {
+            Some(agent_name) => {
+                let (mut agent, config_path) = Agent::get_agent_by_name(os, agent_name).await?;
+                let Some(tool) = agent.mcp_servers.mcp_servers.get_mut(&self.name) else {
+                    bail!("No MCP server named '{}' found in agent {}", self.name, agent_name);
+                };
+
+                self.apply(tool);
+                let json = agent.to_str_pretty()?;
+                os.fs.write_atomic(config_path, json).await?;
+                writeln!(output, "✓ Updated MCP server '{}' in agent {}\n", self.name, agent_name)?;
+            },
+            None => {
+                let legacy_mcp_config_path = match self.scope {
+                    Some(Scope::Workspace) => directories::chat_legacy_workspace_mcp_config(os)?,
+                    _ => directories::chat_legacy_global_mcp_config(os)?,
+                };
+                let mut config = McpServerConfig::load_from_file(os, &legacy_mcp_config_path).await?;
+                let Some(tool) = config.mcp_servers.get_mut(&self.name) else {
+                    bail!(
+                        "No MCP server named '{}' found in global config (path {})",
+                        self.name,
+                        legacy_mcp_config_path.display()
+                    );
+                };
+
+                self.apply(tool);
+                config.save_to_file(os, &legacy_mcp_config_path).await?;
+                writeln!(
+                    output,
+                    "✓ Updated MCP server '{}' in global config in {}\n",
+                    self.name,
+                    legacy_mcp_config_path.display()
+                )?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Mutates only the fields of `tool` that were specified, leaving everything else untouched.
+    fn apply(&self, tool: &mut CustomToolConfig) {
+        tool.args.retain(|arg| !self.remove_args.contains(arg));
+        tool.args.extend(self.append_args.iter().cloned());
+
+        if let Some(timeout) = self.set_timeout {
+            tool.timeout = timeout;
+        }
+
+        if !self.set_env.is_empty() || !self.unset_env.is_empty() {
+            let env = tool.env.get_or_insert_with(HashMap::new);
+            env.extend(self.set_env.iter().flatten().map(|(k, v)| (k.clone(), v.clone())));
+            env.retain(|k, _| !self.unset_env.contains(k));
+            if env.is_empty() {
+                tool.env = None;
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Args)]
@@ -213,7 +443,7 @@ impl RemoveArgs {
                 match config.remove(&self.name) {
                     Some(_) => {
                         let json = agent.to_str_pretty()?;
-                        os.fs.write(config_path, json).await?;
+                        os.fs.write_atomic(config_path, json).await?;
                         writeln!(
                             output,
                             "\n✓ Removed MCP server '{}' from agent {}\n",
@@ -408,6 +638,254 @@ impl StatusArgs {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct LogsArgs {
+    /// Name of the server
+    #[arg(long)]
+    pub name: String,
+    /// Keep printing new output as the server writes it, until interrupted
+    #[arg(long, short)]
+    pub follow: bool,
+}
+
+impl LogsArgs {
+    pub async fn execute(self, os: &mut Os, output: &mut impl Write) -> Result<()> {
+        let path = directories::mcp_server_log_path(&self.name)?;
+        let mut file = match os.fs.open(&path).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => bail!(
+                "No logs found for MCP server '{}'. Logs are written while the server runs in a chat session.\n",
+                self.name
+            ),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut offset = 0;
+        let mut buf = Vec::new();
+        loop {
+            // Start over if the file was truncated, e.g. by the user
+            if file.metadata().await?.len() < offset {
+                file.seek(SeekFrom::Start(0)).await?;
+                offset = 0;
+            }
+            buf.clear();
+            offset += file.read_to_end(&mut buf).await? as u64;
+            output.write_all(&buf)?;
+            output.flush()?;
+
+            if !self.follow {
+                return Ok(());
+            }
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+                _ = tokio::time::sleep(Duration::from_millis(250)) => {},
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct MigrateArgs {
+    /// The agent to move the servers into. Defaults to every agent that uses the legacy mcp.json
+    #[arg(long)]
+    pub agent: Option<String>,
+    /// Only migrate the legacy mcp.json of this scope. Both workspace and global are migrated by
+    /// default. Refused while the mcp.json of the other scope exists, since migrated agents stop
+    /// loading both.
+    #[arg(long)]
+    pub scope: Option<Scope>,
+    /// Rename the migrated mcp.json files to mcp.json.bak afterwards
+    #[arg(long, default_value_t = false)]
+    pub archive: bool,
+    /// Replace servers that already exist in an agent without prompting
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+/// What happened to a single legacy server when migrating it into an agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MigrateOutcome {
+    Moved,
+    Replaced,
+    AlreadyPresent,
+    KeptExisting,
+}
+
+impl MigrateArgs {
+    pub async fn execute(self, os: &mut Os, output: &mut impl Write) -> Result<()> {
+        // Workspace servers come first since they take priority over global ones, as they do when
+        // the legacy mcp.json files are loaded for an agent.
+        let mut legacy_paths = Vec::new();
+        let mut skipped_paths = Vec::new();
+        let workspace_path = directories::chat_legacy_workspace_mcp_config(os)?;
+        match self.scope {
+            Some(Scope::Global | Scope::Default) => skipped_paths.push(workspace_path),
+            _ => legacy_paths.push(workspace_path),
+        }
+        let global_path = directories::chat_legacy_global_mcp_config(os)?;
+        match self.scope {
+            Some(Scope::Workspace | Scope::Default) => skipped_paths.push(global_path),
+            _ => legacy_paths.push(global_path),
+        }
+        legacy_paths.retain(|path| os.fs.exists(path));
+
+        // Migrated agents stop loading both legacy mcp.json files, so the servers of one that is
+        // not migrated would be silently dropped from them.
+        if let Some(skipped_path) = skipped_paths.iter().find(|path| os.fs.exists(path)) {
+            bail!(
+                "Migrated agents stop loading the legacy mcp.json, which would drop the servers in {}. Leave out --scope to migrate it too.",
+                skipped_path.display()
+            );
+        }
+
+        let mut legacy_servers = BTreeMap::<String, (CustomToolConfig, PathBuf)>::new();
+        for path in &legacy_paths {
+            let config = McpServerConfig::load_from_file(os, path).await?;
+            for (name, server) in config.mcp_servers {
+                legacy_servers.entry(name).or_insert((server, path.clone()));
+            }
+        }
+        if legacy_paths.is_empty() {
+            writeln!(output, "No legacy mcp.json found. Nothing to migrate.\n")?;
+            return Ok(());
+        }
+
+        let mut stderr = std::io::stderr();
+        let all_agents = Agents::load(os, None, true, &mut stderr).await.0.agents;
+        let mut agents = match self.agent.as_deref() {
+            Some(agent_name) => vec![Agent::get_agent_by_name(os, agent_name).await?.0],
+            None => {
+                let mut agents = all_agents
+                    .values()
+                    .filter(|agent| agent.use_legacy_mcp_json && agent.path.is_some())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                agents.sort_by(|a, b| a.name.cmp(&b.name));
+                agents
+            },
+        };
+        if agents.is_empty() {
+            bail!("No agent config uses the legacy mcp.json. Use --agent to choose one to migrate into.");
+        }
+
+        // Archiving the legacy mcp.json would silently drop its servers from any agent that is not
+        // migrated here but still loads it, including the built-in default agent, which has no
+        // config file to migrate into.
+        if self.archive {
+            let mut still_loading = all_agents
+                .values()
+                .filter(|agent| {
+                    agent.use_legacy_mcp_json
+                        && !(agent.path.is_some() && agents.iter().any(|migrated| migrated.name == agent.name))
+                })
+                .map(|agent| agent.name.as_str())
+                .collect::<Vec<_>>();
+            still_loading.sort();
+            if !still_loading.is_empty() {
+                let hint = match still_loading.contains(&DEFAULT_AGENT_NAME) {
+                    true => {
+                        " Set chat.defaultAgent to a migrated agent to stop the built-in default agent from loading it."
+                    },
+                    false => "",
+                };
+                bail!(
+                    "Not archiving the legacy mcp.json since these agents still load it: {}.{hint}",
+                    still_loading.join(", ")
+                );
+            }
+        }
+
+        let interactive = std::io::stdin().is_terminal();
+        for agent in &mut agents {
+            let Some(config_path) = agent.path.clone() else {
+                continue;
+            };
+            let agent_name = agent.name.clone();
+            let outcomes = migrate_servers(agent, &legacy_servers, |name| {
+                if self.force {
+                    return Ok(true);
+                }
+                if !interactive {
+                    return Ok(false);
+                }
+                Ok(dialoguer::Confirm::with_theme(&crate::util::dialoguer_theme())
+                    .with_prompt(format!(
+                        "MCP server '{name}' already exists in agent {agent_name} with a different config. Replace it with the one from {}?",
+                        legacy_servers[name].1.display()
+                    ))
+                    .default(false)
+                    .interact()?)
+            })?;
+
+            os.fs.write_atomic(&config_path, agent.to_str_pretty()?).await?;
+            writeln!(
+                output,
+                "\nAgent {} ({}):",
+                agent.name.as_str().bold(),
+                config_path.display()
+            )?;
+            for (name, outcome) in outcomes {
+                let source = legacy_servers[&name].1.display();
+                match outcome {
+                    MigrateOutcome::Moved => writeln!(output, "  ✓ Moved '{name}' from {source}")?,
+                    MigrateOutcome::Replaced => writeln!(output, "  ✓ Replaced '{name}' with the one from {source}")?,
+                    MigrateOutcome::AlreadyPresent => writeln!(output, "  • '{name}' was already configured")?,
+                    MigrateOutcome::KeptExisting => {
+                        writeln!(output, "  • Kept the existing '{name}', skipping the one from {source}")?;
+                    },
+                }
+            }
+        }
+        writeln!(output)?;
+
+        for path in &legacy_paths {
+            if self.archive {
+                let archive_path = path.with_extension("json.bak");
+                os.fs.rename(path, &archive_path).await?;
+                writeln!(output, "✓ Archived {} to {}", path.display(), archive_path.display())?;
+            } else {
+                writeln!(
+                    output,
+                    "{} was left in place. Use --archive to move it aside.",
+                    path.display()
+                )?;
+            }
+        }
+        writeln!(output)?;
+
+        Ok(())
+    }
+}
+
+/// Merges `legacy_servers` into the servers configured in `agent` and stops the agent from
+/// loading the legacy mcp.json. `replace` is asked whether a server that the agent already
+/// configures differently should be replaced by the legacy one.
+fn migrate_servers(
+    agent: &mut Agent,
+    legacy_servers: &BTreeMap<String, (CustomToolConfig, PathBuf)>,
+    mut replace: impl FnMut(&str) -> Result<bool>,
+) -> Result<Vec<(String, MigrateOutcome)>> {
+    let servers = &mut agent.mcp_servers.mcp_servers;
+    servers.retain(|_, server| !server.is_from_legacy_mcp_json);
+
+    let mut outcomes = Vec::new();
+    for (name, (legacy_server, _)) in legacy_servers {
+        let outcome = match servers.get(name) {
+            None => MigrateOutcome::Moved,
+            Some(server) if server == legacy_server => MigrateOutcome::AlreadyPresent,
+            Some(_) if replace(name)? => MigrateOutcome::Replaced,
+            Some(_) => MigrateOutcome::KeptExisting,
+        };
+        if matches!(outcome, MigrateOutcome::Moved | MigrateOutcome::Replaced) {
+            servers.insert(name.clone(), legacy_server.clone());
+        }
+        outcomes.push((name.clone(), outcome));
+    }
+
+    agent.use_legacy_mcp_json = false;
+    Ok(outcomes)
+}
+
 /// Returns a [BTreeMap] for consistent key iteration.
 async fn get_mcp_server_configs(os: &mut Os) -> Result<BTreeMap<Scope, Vec<(String, Option<McpServerConfig>, bool)>>> {
     let mut results = BTreeMap::new();
@@ -508,6 +986,7 @@ async fn load_cfg(os: &Os, p: &PathBuf) -> Result<McpServerConfig> {
 mod tests {
     use super::*;
     use crate::cli::RootSubcommand;
+    use crate::database::settings::Setting;
     use crate::util::test::assert_parse;
 
     #[tokio::test]
@@ -551,9 +1030,9 @@ mod tests {
 
         // 1. add
         AddArgs {
-            name: "local".into(),
+            name: Some("local".into()),
             scope: None,
-            command: "echo hi".into(),
+            command: Some("echo hi".into()),
             args: vec![
                 "awslabs.eks-mcp-server".to_string(),
                 "--allow-write".to_string(),
@@ -564,6 +1043,7 @@ mod tests {
             agent: None,
             disabled: false,
             force: false,
+            interactive: false,
         }
         .execute(&os, &mut vec![])
         .await
@@ -604,9 +1084,9 @@ mod tests {
                 "key1=value1,key2=value2"
             ],
             RootSubcommand::Mcp(McpSubcommand::Add(AddArgs {
-                name: "test_server".to_string(),
+                name: Some("test_server".to_string()),
                 scope: None,
-                command: "test_command".to_string(),
+                command: Some("test_command".to_string()),
                 args: vec![
                     "awslabs.eks-mcp-server".to_string(),
                     "--allow-write".to_string(),
@@ -624,10 +1104,93 @@ mod tests {
                 timeout: None,
                 disabled: false,
                 force: false,
+                interactive: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_mcp_subcommand_add_interactive() {
+        assert_parse!(
+            ["mcp", "add", "--interactive", "--name", "test_server"],
+            RootSubcommand::Mcp(McpSubcommand::Add(AddArgs {
+                name: Some("test_server".to_string()),
+                scope: None,
+                command: None,
+                args: vec![],
+                agent: None,
+                env: vec![],
+                timeout: None,
+                disabled: false,
+                force: false,
+                interactive: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_mcp_subcommand_edit() {
+        assert_parse!(
+            [
+                "mcp",
+                "edit",
+                "--name",
+                "test_server",
+                "--append-args",
+                "--verbose",
+                "--set-timeout",
+                "5000",
+                "--set-env",
+                "A=1",
+                "--unset-env",
+                "B"
+            ],
+            RootSubcommand::Mcp(McpSubcommand::Edit(EditArgs {
+                name: "test_server".to_string(),
+                scope: None,
+                agent: None,
+                append_args: vec!["--verbose".to_string()],
+                remove_args: vec![],
+                set_timeout: Some(5000),
+                set_env: vec![[("A".to_string(), "1".to_string())].into_iter().collect()],
+                unset_env: vec!["B".to_string()],
             }))
         );
     }
 
+    #[test]
+    fn test_edit_args_apply_preserves_unmentioned_fields() {
+        let mut tool: CustomToolConfig = serde_json::from_value(serde_json::json!({
+            "command": "node",
+            "args": ["server.js", "--debug"],
+            "env": { "A": "old", "B": "2" },
+            "timeout": 1000,
+            "disabled": true,
+        }))
+        .unwrap();
+
+        EditArgs {
+            name: "test_server".to_string(),
+            scope: None,
+            agent: None,
+            append_args: vec!["--port".to_string(), "8080".to_string()],
+            remove_args: vec!["--debug".to_string()],
+            set_timeout: None,
+            set_env: vec![[("A".to_string(), "new".to_string())].into_iter().collect()],
+            unset_env: vec!["B".to_string()],
+        }
+        .apply(&mut tool);
+
+        assert_eq!(tool.command, "node");
+        assert_eq!(tool.args, vec!["server.js", "--port", "8080"]);
+        assert_eq!(
+            tool.env,
+            Some([("A".to_string(), "new".to_string())].into_iter().collect())
+        );
+        assert_eq!(tool.timeout, 1000);
+        assert!(tool.disabled);
+    }
+
     #[test]
     fn test_mcp_subcomman_remove_workspace() {
         assert_parse!(
@@ -660,6 +1223,200 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mcp_subcommand_logs() {
+        assert_parse!(
+            ["mcp", "logs", "--name", "aws", "-f"],
+            RootSubcommand::Mcp(McpSubcommand::Logs(LogsArgs {
+                name: "aws".into(),
+                follow: true,
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logs_prints_server_output() {
+        let mut os = Os::new().await.unwrap();
+        let args = LogsArgs {
+            name: "test-server".into(),
+            follow: false,
+        };
+        assert!(args.clone().execute(&mut os, &mut Vec::new()).await.is_err());
+
+        let path = directories::mcp_server_log_path("test-server").unwrap();
+        os.fs.create_dir_all(path.parent().unwrap()).await.unwrap();
+        os.fs.write(&path, "starting\nready\n").await.unwrap();
+        let mut output = Vec::new();
+        args.execute(&mut os, &mut output).await.unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "starting\nready\n");
+    }
+
+    #[test]
+    fn test_mcp_subcommand_migrate() {
+        assert_parse!(
+            ["mcp", "migrate", "--agent", "dev", "--scope", "global", "--archive"],
+            RootSubcommand::Mcp(McpSubcommand::Migrate(MigrateArgs {
+                agent: Some("dev".into()),
+                scope: Some(Scope::Global),
+                archive: true,
+                force: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_migrate_servers() {
+        let server = |command: &str| -> CustomToolConfig {
+            serde_json::from_value(serde_json::json!({ "command": command })).unwrap()
+        };
+        let mut agent = Agent::default();
+        agent.mcp_servers.mcp_servers.extend([
+            ("same".to_string(), server("same")),
+            ("keep".to_string(), server("mine")),
+            ("replace".to_string(), server("mine")),
+        ]);
+        let legacy_servers = ["new", "same", "keep", "replace"]
+            .into_iter()
+            .map(|name| (name.to_string(), (server(name), PathBuf::from("mcp.json"))))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut asked = Vec::new();
+        let outcomes = migrate_servers(&mut agent, &legacy_servers, |name| {
+            asked.push(name.to_string());
+            Ok(name == "replace")
+        })
+        .unwrap();
+
+        assert_eq!(asked, vec!["keep", "replace"]);
+        assert_eq!(outcomes, vec![
+            ("keep".to_string(), MigrateOutcome::KeptExisting),
+            ("new".to_string(), MigrateOutcome::Moved),
+            ("replace".to_string(), MigrateOutcome::Replaced),
+            ("same".to_string(), MigrateOutcome::AlreadyPresent),
+        ]);
+        let servers = &agent.mcp_servers.mcp_servers;
+        assert_eq!(servers["keep"].command, "mine");
+        assert_eq!(servers["new"].command, "new");
+        assert_eq!(servers["replace"].command, "replace");
+        assert!(!agent.use_legacy_mcp_json);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_moves_legacy_servers_into_agents() {
+        let mut os = Os::new().await.unwrap();
+        let global_mcp_path = directories::chat_legacy_global_mcp_config(&os).unwrap();
+        let agent_path = directories::chat_global_agent_path(&os).unwrap().join("dev.json");
+        os.fs.create_dir_all(agent_path.parent().unwrap()).await.unwrap();
+        os.fs
+            .write(
+                &global_mcp_path,
+                r#"{ "mcpServers": { "aws": { "command": "aws-mcp" }, "git": { "command": "git-mcp" } } }"#,
+            )
+            .await
+            .unwrap();
+        os.fs
+            .write(
+                &agent_path,
+                r#"{ "name": "dev", "mcpServers": { "git": { "command": "my-git-mcp" } }, "useLegacyMcpJson": true }"#,
+            )
+            .await
+            .unwrap();
+
+        // The built-in default agent also loads the legacy mcp.json, so it can't be archived until
+        // another agent is the default.
+        let err = MigrateArgs {
+            agent: None,
+            scope: None,
+            archive: true,
+            force: true,
+        }
+        .execute(&mut os, &mut Vec::new())
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains(DEFAULT_AGENT_NAME), "{err}");
+        assert!(os.fs.exists(&global_mcp_path));
+        assert!(os.fs.read_to_string(&agent_path).await.unwrap().contains("my-git-mcp"));
+
+        os.database
+            .settings
+            .set(Setting::ChatDefaultAgent, "dev")
+            .await
+            .unwrap();
+        let mut output = Vec::new();
+        MigrateArgs {
+            agent: None,
+            scope: None,
+            archive: true,
+            force: true,
+        }
+        .execute(&mut os, &mut output)
+        .await
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Moved 'aws'"), "{output}");
+        assert!(output.contains("Replaced 'git'"), "{output}");
+        let agent: Agent = serde_json::from_str(&os.fs.read_to_string(&agent_path).await.unwrap()).unwrap();
+        assert!(!agent.use_legacy_mcp_json);
+        assert_eq!(agent.mcp_servers.mcp_servers["aws"].command, "aws-mcp");
+        assert_eq!(agent.mcp_servers.mcp_servers["git"].command, "git-mcp");
+        assert!(!os.fs.exists(&global_mcp_path));
+        assert!(os.fs.exists(global_mcp_path.with_extension("json.bak")));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_scope_keeps_other_legacy_servers() {
+        let mut os = Os::new().await.unwrap();
+        let global_mcp_path = directories::chat_legacy_global_mcp_config(&os).unwrap();
+        let workspace_mcp_path = directories::chat_legacy_workspace_mcp_config(&os).unwrap();
+        let agent_path = directories::chat_global_agent_path(&os).unwrap().join("dev.json");
+        os.fs.create_dir_all(agent_path.parent().unwrap()).await.unwrap();
+        os.fs
+            .create_dir_all(workspace_mcp_path.parent().unwrap())
+            .await
+            .unwrap();
+        os.fs
+            .write(
+                &global_mcp_path,
+                r#"{ "mcpServers": { "aws": { "command": "aws-mcp" } } }"#,
+            )
+            .await
+            .unwrap();
+        os.fs
+            .write(&agent_path, r#"{ "name": "dev", "useLegacyMcpJson": true }"#)
+            .await
+            .unwrap();
+        let migrate_global = || MigrateArgs {
+            agent: Some("dev".into()),
+            scope: Some(Scope::Global),
+            archive: false,
+            force: true,
+        };
+
+        // The workspace servers would no longer be loaded by the agent
+        os.fs
+            .write(
+                &workspace_mcp_path,
+                r#"{ "mcpServers": { "git": { "command": "git-mcp" } } }"#,
+            )
+            .await
+            .unwrap();
+        let err = migrate_global().execute(&mut os, &mut Vec::new()).await.unwrap_err();
+        assert!(
+            err.to_string().contains(&workspace_mcp_path.display().to_string()),
+            "{err}"
+        );
+        let agent: Agent = serde_json::from_str(&os.fs.read_to_string(&agent_path).await.unwrap()).unwrap();
+        assert!(agent.use_legacy_mcp_json);
+        assert!(agent.mcp_servers.mcp_servers.is_empty());
+
+        os.fs.remove_file(&workspace_mcp_path).await.unwrap();
+        migrate_global().execute(&mut os, &mut Vec::new()).await.unwrap();
+        let agent: Agent = serde_json::from_str(&os.fs.read_to_string(&agent_path).await.unwrap()).unwrap();
+        assert!(!agent.use_legacy_mcp_json);
+        assert_eq!(agent.mcp_servers.mcp_servers["aws"].command, "aws-mcp");
+    }
+
     #[test]
     fn test_mcp_subcommand_list() {
         assert_parse!(
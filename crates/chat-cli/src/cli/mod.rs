@@ -359,12 +359,20 @@ mod test {
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "chat", "-vv"]), Cli {
             subcommand: Some(RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })),
             verbose: 2,
             help_all: false,
@@ -398,28 +406,169 @@ mod test {
             ["chat", "--profile", "my-profile"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
 
+    #[test]
+    fn test_chat_with_agent_and_prompt() {
+        assert_parse!(
+            ["chat", "--agent", "ci", "--prompt", "Hello", "--trust-all-tools"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: false,
+                input: None,
+                agent: Some("ci".to_string()),
+                model: None,
+                trust_all_tools: true,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: Some("Hello".to_string()),
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
+            })
+        );
+        assert!(Cli::try_parse_from(["q", "chat", "--prompt", "Hello", "Hi"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_aws_profile() {
+        assert_parse!(
+            ["chat", "--aws-profile", "staging"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: Some("staging".to_string()),
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_max_turns() {
+        assert_parse!(
+            ["chat", "--max-turns", "25"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: Some(25),
+                agent_mode: None,
+                profile_arn: None,
+            })
+        );
+        assert!(Cli::try_parse_from(["q", "chat", "--max-turns", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_agent_mode() {
+        assert_parse!(
+            ["chat", "--agent-mode", "my-mode"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: Some("my-mode".to_string()),
+                profile_arn: None,
+            })
+        );
+        assert!(Cli::try_parse_from(["q", "chat", "--agent-mode", ""]).is_err());
+    }
+
+    #[test]
+    fn test_chat_with_profile_arn() {
+        let arn = "arn:aws:codewhisperer:us-east-1:123456789012:profile/ABCDEF123456";
+        assert_parse!(
+            ["chat", "--profile-arn", arn],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: false,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: Some(arn.to_string()),
+            })
+        );
+        assert!(Cli::try_parse_from(["q", "chat", "--profile-arn", "my-profile"]).is_err());
+    }
+
     #[test]
     fn test_chat_with_context_profile_and_input() {
         assert_parse!(
             ["chat", "--profile", "my-profile", "Hello"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: Some("Hello".to_string()),
                 agent: Some("my-profile".to_string()),
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
@@ -430,14 +579,67 @@ mod test {
             ["chat", "--profile", "my-profile", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
                 trust_all_tools: true,
                 trust_tools: None,
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_continue() {
+        assert_parse!(
+            ["chat", "--continue"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: true,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
+            })
+        );
+        assert_parse!(
+            ["chat", "-c"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: false,
+                continue_last: true,
+                input: None,
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--continue", "--resume"]).is_err());
     }
 
     #[test]
@@ -446,24 +648,40 @@ mod test {
             ["chat", "--no-interactive", "--resume"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
                 no_interactive: true,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
         assert_parse!(
             ["chat", "--non-interactive", "-r"],
             RootSubcommand::Chat(ChatArgs {
                 resume: true,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: None,
                 no_interactive: true,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
@@ -474,12 +692,20 @@ mod test {
             ["chat", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: true,
                 trust_tools: None,
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
@@ -490,12 +716,20 @@ mod test {
             ["chat", "--trust-tools="],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["".to_string()]),
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
@@ -506,12 +740,20 @@ mod test {
             ["chat", "--trust-tools=fs_read,fs_write"],
             RootSubcommand::Chat(ChatArgs {
                 resume: false,
+                continue_last: false,
                 input: None,
                 agent: None,
                 model: None,
                 trust_all_tools: false,
                 trust_tools: Some(vec!["fs_read".to_string(), "fs_write".to_string()]),
                 no_interactive: false,
+                explain: false,
+                no_animation: false,
+                prompt: None,
+                aws_profile: None,
+                max_turns: None,
+                agent_mode: None,
+                profile_arn: None,
             })
         );
     }
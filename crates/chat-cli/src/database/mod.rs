@@ -61,6 +61,7 @@ const IDC_REGION_KEY: &str = "auth.idc.region";
 // We include this key to remove for backwards compatibility
 const CUSTOMIZATION_STATE_KEY: &str = "api.selectedCustomization";
 const PROFILE_MIGRATION_KEY: &str = "profile.Migrated";
+const LAST_CONVERSATION_PATH_KEY: &str = "chat.lastConversationPath";
 
 const MIGRATIONS: &[Migration] = migrations![
     "000_migration_table",
@@ -352,7 +353,17 @@ impl Database {
             None => return Ok(0),
         };
 
-        self.set_json_entry(Table::Conversations, path, state)
+        let rows = self.set_json_entry(Table::Conversations, path, state)?;
+        self.set_entry(Table::State, LAST_CONVERSATION_PATH_KEY, path)?;
+        Ok(rows)
+    }
+
+    /// Get the most recently saved chat conversation, regardless of its path.
+    pub fn get_last_conversation(&mut self) -> Result<Option<ConversationState>, DatabaseError> {
+        match self.get_entry::<String>(Table::State, LAST_CONVERSATION_PATH_KEY)? {
+            Some(path) => self.get_json_entry(Table::Conversations, path),
+            None => Ok(None),
+        }
     }
 
     pub async fn get_secret(&self, key: &str) -> Result<Option<Secret>, DatabaseError> {
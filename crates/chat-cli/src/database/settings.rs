@@ -1,5 +1,4 @@
 use std::fmt::Display;
-use std::io::SeekFrom;
 
 use fd_lock::RwLock;
 use serde_json::{
@@ -9,7 +8,6 @@ use serde_json::{
 use tokio::fs::File;
 use tokio::io::{
     AsyncReadExt,
-    AsyncSeekExt,
     AsyncWriteExt,
 };
 
@@ -18,6 +16,7 @@ use super::DatabaseError;
 #[derive(Clone, Copy, Debug)]
 pub enum Setting {
     TelemetryEnabled,
+    TelemetryLocalSink,
     OldClientId,
     ShareCodeWhispererContent,
     EnabledThinking,
@@ -29,6 +28,7 @@ pub enum Setting {
     ChatEnableNotifications,
     ApiCodeWhispererService,
     ApiQService,
+    ApiProfileArn,
     McpInitTimeout,
     McpNoInteractiveTimeout,
     McpLoadedBefore,
@@ -37,12 +37,32 @@ pub enum Setting {
     ChatDefaultAgent,
     ChatDisableAutoCompaction,
     ChatEnableHistoryHints,
+    ChatHistoryFile,
+    ChatHistoryMaxEntries,
+    ChatTypewriterSpeed,
+    ChatAwsDangerRegions,
+    ChatAwsDangerProfiles,
+    ChatMaxTurns,
+    ChatMaxRepeatedToolCalls,
+    ChatShowStats,
+    ChatSendEnvContext,
+    ChatSendGitContext,
+    McpToolLoadTimeout,
+    ChatMaxParallelTools,
+    ChatHotReloadConfig,
+    ChatContextEntryStartHeader,
+    ChatContextEntryEndHeader,
+    ChatUserMessageStartHeader,
+    ChatUserMessageEndHeader,
+    ChatInjectTimestamp,
+    ChatTimestampTimezone,
 }
 
 impl AsRef<str> for Setting {
     fn as_ref(&self) -> &'static str {
         match self {
             Self::TelemetryEnabled => "telemetry.enabled",
+            Self::TelemetryLocalSink => "telemetry.localSink",
             Self::OldClientId => "telemetryClientId",
             Self::ShareCodeWhispererContent => "codeWhisperer.shareCodeWhispererContentWithAWS",
             Self::EnabledThinking => "chat.enableThinking",
@@ -54,6 +74,7 @@ impl AsRef<str> for Setting {
             Self::ChatEnableNotifications => "chat.enableNotifications",
             Self::ApiCodeWhispererService => "api.codewhisperer.service",
             Self::ApiQService => "api.q.service",
+            Self::ApiProfileArn => "api.profileArn",
             Self::McpInitTimeout => "mcp.initTimeout",
             Self::McpNoInteractiveTimeout => "mcp.noInteractiveTimeout",
             Self::McpLoadedBefore => "mcp.loadedBefore",
@@ -62,6 +83,25 @@ impl AsRef<str> for Setting {
             Self::ChatDefaultAgent => "chat.defaultAgent",
             Self::ChatDisableAutoCompaction => "chat.disableAutoCompaction",
             Self::ChatEnableHistoryHints => "chat.enableHistoryHints",
+            Self::ChatHistoryFile => "chat.historyFile",
+            Self::ChatHistoryMaxEntries => "chat.historyMaxEntries",
+            Self::ChatShowStats => "chat.showStats",
+            Self::ChatSendEnvContext => "chat.sendEnvContext",
+            Self::ChatSendGitContext => "chat.sendGitContext",
+            Self::McpToolLoadTimeout => "mcp.toolLoadTimeout",
+            Self::ChatMaxParallelTools => "chat.maxParallelTools",
+            Self::ChatHotReloadConfig => "chat.hotReloadConfig",
+            Self::ChatContextEntryStartHeader => "chat.contextEntryStartHeader",
+            Self::ChatContextEntryEndHeader => "chat.contextEntryEndHeader",
+            Self::ChatUserMessageStartHeader => "chat.userMessageStartHeader",
+            Self::ChatUserMessageEndHeader => "chat.userMessageEndHeader",
+            Self::ChatInjectTimestamp => "chat.injectTimestamp",
+            Self::ChatTimestampTimezone => "chat.timestampTimezone",
+            Self::ChatTypewriterSpeed => "chat.typewriterSpeed",
+            Self::ChatAwsDangerRegions => "chat.awsDangerRegions",
+            Self::ChatAwsDangerProfiles => "chat.awsDangerProfiles",
+            Self::ChatMaxTurns => "chat.maxTurns",
+            Self::ChatMaxRepeatedToolCalls => "chat.maxRepeatedToolCalls",
         }
     }
 }
@@ -78,6 +118,7 @@ impl TryFrom<&str> for Setting {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "telemetry.enabled" => Ok(Self::TelemetryEnabled),
+            "telemetry.localSink" => Ok(Self::TelemetryLocalSink),
             "telemetryClientId" => Ok(Self::OldClientId),
             "codeWhisperer.shareCodeWhispererContentWithAWS" => Ok(Self::ShareCodeWhispererContent),
             "chat.enableThinking" => Ok(Self::EnabledThinking),
@@ -89,6 +130,7 @@ impl TryFrom<&str> for Setting {
             "chat.enableNotifications" => Ok(Self::ChatEnableNotifications),
             "api.codewhisperer.service" => Ok(Self::ApiCodeWhispererService),
             "api.q.service" => Ok(Self::ApiQService),
+            "api.profileArn" => Ok(Self::ApiProfileArn),
             "mcp.initTimeout" => Ok(Self::McpInitTimeout),
             "mcp.noInteractiveTimeout" => Ok(Self::McpNoInteractiveTimeout),
             "mcp.loadedBefore" => Ok(Self::McpLoadedBefore),
@@ -97,6 +139,25 @@ impl TryFrom<&str> for Setting {
             "chat.defaultAgent" => Ok(Self::ChatDefaultAgent),
             "chat.disableAutoCompaction" => Ok(Self::ChatDisableAutoCompaction),
             "chat.enableHistoryHints" => Ok(Self::ChatEnableHistoryHints),
+            "chat.historyFile" => Ok(Self::ChatHistoryFile),
+            "chat.historyMaxEntries" => Ok(Self::ChatHistoryMaxEntries),
+            "chat.showStats" => Ok(Self::ChatShowStats),
+            "chat.sendEnvContext" => Ok(Self::ChatSendEnvContext),
+            "chat.sendGitContext" => Ok(Self::ChatSendGitContext),
+            "mcp.toolLoadTimeout" => Ok(Self::McpToolLoadTimeout),
+            "chat.maxParallelTools" => Ok(Self::ChatMaxParallelTools),
+            "chat.hotReloadConfig" => Ok(Self::ChatHotReloadConfig),
+            "chat.contextEntryStartHeader" => Ok(Self::ChatContextEntryStartHeader),
+            "chat.contextEntryEndHeader" => Ok(Self::ChatContextEntryEndHeader),
+            "chat.userMessageStartHeader" => Ok(Self::ChatUserMessageStartHeader),
+            "chat.userMessageEndHeader" => Ok(Self::ChatUserMessageEndHeader),
+            "chat.injectTimestamp" => Ok(Self::ChatInjectTimestamp),
+            "chat.timestampTimezone" => Ok(Self::ChatTimestampTimezone),
+            "chat.typewriterSpeed" => Ok(Self::ChatTypewriterSpeed),
+            "chat.awsDangerRegions" => Ok(Self::ChatAwsDangerRegions),
+            "chat.awsDangerProfiles" => Ok(Self::ChatAwsDangerProfiles),
+            "chat.maxTurns" => Ok(Self::ChatMaxTurns),
+            "chat.maxRepeatedToolCalls" => Ok(Self::ChatMaxRepeatedToolCalls),
             _ => Err(DatabaseError::InvalidSetting(value.to_string())),
         }
     }
@@ -166,6 +227,15 @@ impl Settings {
         self.get(key).and_then(|value| value.as_i64())
     }
 
+    /// Returns a list of strings, treating a single string value as a list of one.
+    pub fn get_string_list(&self, key: Setting) -> Option<Vec<String>> {
+        match self.get(key)? {
+            Value::String(s) => Some(vec![s.clone()]),
+            Value::Array(values) => Some(values.iter().filter_map(|v| v.as_str().map(String::from)).collect()),
+            _ => None,
+        }
+    }
+
     pub async fn save_to_file(&self) -> Result<(), DatabaseError> {
         if cfg!(test) {
             return Ok(());
@@ -180,23 +250,8 @@ impl Settings {
             }
         }
 
-        let mut file_opts = File::options();
-        file_opts.create(true).write(true).truncate(true);
-
-        #[cfg(unix)]
-        file_opts.mode(0o600);
-        let mut file = RwLock::new(file_opts.open(&path).await?);
-        let mut lock = file.write()?;
-
-        match serde_json::to_string_pretty(&self.0) {
-            Ok(json) => lock.write_all(json.as_bytes()).await?,
-            Err(_err) => {
-                lock.seek(SeekFrom::Start(0)).await?;
-                lock.set_len(0).await?;
-                lock.write_all(b"{}").await?;
-            },
-        }
-        lock.flush().await?;
+        let json = serde_json::to_string_pretty(&self.0).unwrap_or_else(|_| "{}".into());
+        crate::os::write_atomic(&path, json, 0o600).await?;
 
         Ok(())
     }
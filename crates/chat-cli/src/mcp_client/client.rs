@@ -1,4 +1,8 @@
 use std::collections::HashMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
 use std::process::Stdio;
 use std::sync::atomic::{
     AtomicBool,
@@ -16,6 +20,7 @@ use serde::{
     Serialize,
 };
 use thiserror::Error;
+use tokio::io::AsyncWriteExt as _;
 use tokio::time;
 use tokio::time::error::Elapsed;
 
@@ -46,7 +51,8 @@ use super::{
 };
 use crate::util::process::{
     Pid,
-    terminate_process,
+    TERMINATE_GRACE_PERIOD,
+    terminate_gracefully,
 };
 
 pub type ClientInfo = serde_json::Value;
@@ -74,6 +80,20 @@ impl From<ClientInfo> for ClientCapabilities {
     }
 }
 
+/// Time in ms to wait before the first retry of a failed init, see [Client::init_with_retries].
+pub const INIT_RETRY_BASE_DELAY_MS: u64 = 200;
+/// Longest time in ms to wait between retries of a failed init.
+pub const INIT_RETRY_MAX_DELAY_MS: u64 = 5_000;
+
+/// Returns the time in ms to wait before retrying a failed init for the `attempt`th time,
+/// starting at 0.
+fn init_retry_delay_ms(attempt: u32) -> u64 {
+    1_u64
+        .checked_shl(attempt)
+        .map_or(u64::MAX, |factor| INIT_RETRY_BASE_DELAY_MS.saturating_mul(factor))
+        .min(INIT_RETRY_MAX_DELAY_MS)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ClientConfig {
     pub server_name: String,
@@ -82,6 +102,8 @@ pub struct ClientConfig {
     pub timeout: u64,
     pub client_info: serde_json::Value,
     pub env: Option<HashMap<String, String>>,
+    /// File that the server's output is written to, see [Client::init_with_retries]
+    pub log_path: Option<PathBuf>,
 }
 
 #[allow(dead_code)]
@@ -113,6 +135,13 @@ pub enum ClientError {
     PoisonError(String),
 }
 
+impl ClientError {
+    /// Whether the server did not answer in time.
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, ClientError::RuntimeError { .. })
+    }
+}
+
 impl From<(tokio::time::error::Elapsed, String)> for ClientError {
     fn from((error, context): (tokio::time::error::Elapsed, String)) -> Self {
         ClientError::RuntimeError { source: error, context }
@@ -127,6 +156,7 @@ pub struct Client<T: Transport> {
     server_process_id: Option<Pid>,
     client_info: serde_json::Value,
     current_id: Arc<AtomicU64>,
+    log_path: Option<PathBuf>,
     pub messenger: Option<Box<dyn Messenger>>,
     // TODO: move this to tool manager that way all the assets are treated equally
     pub prompt_gets: Arc<SyncRwLock<HashMap<String, PromptGet>>>,
@@ -144,6 +174,7 @@ impl<T: Transport> Clone for Client<T> {
             server_process_id: None,
             client_info: self.client_info.clone(),
             current_id: self.current_id.clone(),
+            log_path: self.log_path.clone(),
             messenger: None,
             prompt_gets: self.prompt_gets.clone(),
             is_prompts_out_of_date: self.is_prompts_out_of_date.clone(),
@@ -160,6 +191,7 @@ impl Client<StdioTransport> {
             timeout,
             client_info,
             env,
+            log_path,
         } = config;
         let child = {
             let expanded_bin_path = shellexpand::tilde(&bin_path);
@@ -206,6 +238,7 @@ impl Client<StdioTransport> {
             server_process_id,
             client_info,
             current_id: Arc::new(AtomicU64::new(0)),
+            log_path,
             messenger: None,
             prompt_gets: Arc::new(SyncRwLock::new(HashMap::new())),
             is_prompts_out_of_date: Arc::new(AtomicBool::new(false)),
@@ -274,7 +307,9 @@ where
     // This drop trait is here as a fail safe to ensure we don't leave behind any orphans.
     fn drop(&mut self) {
         if let Some(process_id) = self.server_process_id {
-            let _ = terminate_process(process_id);
+            // Servers are spawned in their own process group, so that anything they spawned in
+            // turn is stopped too
+            terminate_gracefully(process_id, cfg!(not(windows)), TERMINATE_GRACE_PERIOD);
         }
     }
 }
@@ -283,24 +318,64 @@ impl<T> Client<T>
 where
     T: Transport,
 {
+    /// Creates a client that talks to a server through `transport` instead of spawning a server
+    /// process, e.g. a [transport::MockTransport].
+    #[cfg(test)]
+    pub fn from_transport(server_name: String, transport: T, timeout: u64) -> Self {
+        Self {
+            server_name,
+            transport: Arc::new(transport),
+            timeout,
+            server_process_id: None,
+            client_info: serde_json::json!({}),
+            current_id: Arc::new(AtomicU64::new(0)),
+            log_path: None,
+            messenger: None,
+            prompt_gets: Arc::new(SyncRwLock::new(HashMap::new())),
+            is_prompts_out_of_date: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
     /// Exchange of information specified as per https://spec.modelcontextprotocol.io/specification/2024-11-05/basic/lifecycle/#initialization
     ///
     /// Also done are the following:
     /// - Spawns task for listening to server driven workflows
     /// - Spawns tasks to ask for relevant info such as tools and prompts in accordance to server
     ///   capabilities received
-    pub async fn init(&self) -> Result<ServerCapabilities, ClientError> {
+    ///
+    /// A failed handshake is retried up to `retries` times, waiting
+    /// [INIT_RETRY_BASE_DELAY_MS] before the first retry and doubling the wait after each one, up
+    /// to [INIT_RETRY_MAX_DELAY_MS].
+    /// Some servers need a moment to be ready after they are spawned.
+    ///
+    /// The server's output is appended to the configured log file, so that sessions running the
+    /// same server don't overwrite each other's output.
+    pub async fn init_with_retries(&self, retries: u32) -> Result<ServerCapabilities, ClientError> {
         let transport_ref = self.transport.clone();
         let server_name = self.server_name.clone();
+        let log_path = self.log_path.clone();
 
         // Spawning a task to listen and log stderr output
         tokio::spawn(async move {
             let mut log_listener = transport_ref.get_log_listener();
+            let mut log_file = match log_path {
+                Some(path) => open_log_file(&path)
+                    .await
+                    .map_err(|e| tracing::warn!("Failed to open log file for {server_name}: {:?}", e))
+                    .ok(),
+                None => None,
+            };
             loop {
                 match log_listener.recv().await {
                     Ok(msg) => {
                         tracing::trace!(target: "mcp", "{server_name} logged {}", msg);
+                        if let Some(file) = log_file.as_mut() {
+                            // Stop writing to the file after the first failure
+                            let written = file.write_all(format!("{msg}\n").as_bytes()).await;
+                            log_file = log_file.filter(|_| written.is_ok());
+                        }
                     },
+                    Err(TransportError::RecvError(tokio::sync::broadcast::error::RecvError::Lagged(_))) => {},
                     Err(e) => {
                         tracing::error!(
                             "Error encountered while reading from stderr for {server_name}: {:?}\nEnding stderr listening task.",
@@ -312,32 +387,23 @@ where
             }
         });
 
-        let init_params = Some({
-            let client_cap = ClientCapabilities::from(self.client_info.clone());
-            serde_json::json!(client_cap)
-        });
-        let init_resp = self.request("initialize", init_params).await?;
-        if let Err(e) = examine_server_capabilities(&init_resp) {
-            return Err(ClientError::NegotiationError(format!(
-                "Client {} has failed to negotiate server capabilities with server: {:?}",
-                self.server_name, e
-            )));
-        }
-        let cap = {
-            let result = init_resp.result.ok_or(ClientError::NegotiationError(format!(
-                "Server {} init resp is missing result",
-                self.server_name
-            )))?;
-            let cap = result
-                .get("capabilities")
-                .ok_or(ClientError::NegotiationError(format!(
-                    "Server {} init resp result is missing capabilities",
-                    self.server_name
-                )))?
-                .clone();
-            serde_json::from_value::<ServerCapabilities>(cap)?
+        let mut attempt = 0;
+        let cap = loop {
+            match self.handshake().await {
+                Ok(cap) => break cap,
+                Err(e) if attempt < retries => {
+                    let delay = init_retry_delay_ms(attempt);
+                    attempt += 1;
+                    tracing::debug!(
+                        "Init attempt {attempt} for {} failed, retrying in {delay}ms: {:?}",
+                        self.server_name,
+                        e
+                    );
+                    time::sleep(Duration::from_millis(delay)).await;
+                },
+                Err(e) => return Err(e),
+            }
         };
-        self.notify("initialized", None).await?;
 
         // TODO: group this into examine_server_capabilities
         // Prefetch prompts in the background. We should only do this after the server has been
@@ -448,12 +514,55 @@ where
         Ok(cap)
     }
 
+    /// Sends the initialize request and, once the server's capabilities are accepted, the
+    /// initialized notification.
+    async fn handshake(&self) -> Result<ServerCapabilities, ClientError> {
+        let init_params = Some({
+            let client_cap = ClientCapabilities::from(self.client_info.clone());
+            serde_json::json!(client_cap)
+        });
+        let init_resp = self.request("initialize", init_params).await?;
+        if let Err(e) = examine_server_capabilities(&init_resp) {
+            return Err(ClientError::NegotiationError(format!(
+                "Client {} has failed to negotiate server capabilities with server: {:?}",
+                self.server_name, e
+            )));
+        }
+        let cap = {
+            let result = init_resp.result.ok_or(ClientError::NegotiationError(format!(
+                "Server {} init resp is missing result",
+                self.server_name
+            )))?;
+            let cap = result
+                .get("capabilities")
+                .ok_or(ClientError::NegotiationError(format!(
+                    "Server {} init resp result is missing capabilities",
+                    self.server_name
+                )))?
+                .clone();
+            serde_json::from_value::<ServerCapabilities>(cap)?
+        };
+        self.notify("initialized", None).await?;
+        Ok(cap)
+    }
+
     /// Sends a request to the server associated.
     /// This call will yield until a response is received.
     pub async fn request(
         &self,
         method: &str,
         params: Option<serde_json::Value>,
+    ) -> Result<JsonRpcResponse, ClientError> {
+        self.request_with_timeout(method, params, self.timeout).await
+    }
+
+    /// Like [Self::request], but each send and receive waits for at most `timeout` milliseconds
+    /// instead of the server's configured timeout.
+    pub async fn request_with_timeout(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        timeout: u64,
     ) -> Result<JsonRpcResponse, ClientError> {
         let send_map_err = |e: Elapsed| (e, method.to_string());
         let recv_map_err = |e: Elapsed| (e, format!("recv for {method}"));
@@ -466,11 +575,11 @@ where
         };
         tracing::trace!(target: "mcp", "To {}:\n{:#?}", self.server_name, request);
         let msg = JsonRpcMessage::Request(request);
-        time::timeout(Duration::from_millis(self.timeout), self.transport.send(&msg))
+        time::timeout(Duration::from_millis(timeout), self.transport.send(&msg))
             .await
             .map_err(send_map_err)??;
         let mut listener = self.transport.get_listener();
-        let mut resp = time::timeout(Duration::from_millis(self.timeout), async {
+        let mut resp = time::timeout(Duration::from_millis(timeout), async {
             // we want to ignore all other messages sent by the server at this point and let the
             // background loop handle them
             // We also want to ignore all messages emitted by the server to its stdout that does
@@ -539,10 +648,10 @@ where
                         })),
                     };
                     let msg = JsonRpcMessage::Request(next_request);
-                    time::timeout(Duration::from_millis(self.timeout), self.transport.send(&msg))
+                    time::timeout(Duration::from_millis(timeout), self.transport.send(&msg))
                         .await
                         .map_err(send_map_err)??;
-                    let resp = time::timeout(Duration::from_millis(self.timeout), async {
+                    let resp = time::timeout(Duration::from_millis(timeout), async {
                         loop {
                             if let Ok(JsonRpcMessage::Response(resp)) = listener.recv().await {
                                 if resp.id == id {
@@ -584,11 +693,28 @@ where
         )
     }
 
+    /// The time in ms that requests wait for by default.
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    #[cfg(test)]
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
     fn get_id(&self) -> u64 {
         self.current_id.fetch_add(1, Ordering::SeqCst)
     }
 }
 
+async fn open_log_file(path: &Path) -> std::io::Result<tokio::fs::File> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::OpenOptions::new().create(true).append(true).open(path).await
+}
+
 fn examine_server_capabilities(ser_cap: &JsonRpcResponse) -> Result<(), ClientError> {
     // Check the jrpc version.
     // Currently we are only proceeding if the versions are EXACTLY the same.
@@ -689,6 +815,7 @@ mod tests {
     use serde_json::Value;
 
     use super::*;
+    use crate::mcp_client::MockTransport;
     const TEST_BIN_OUT_DIR: &str = "target/debug";
     const TEST_SERVER_NAME: &str = "test_mcp_server";
 
@@ -738,6 +865,7 @@ mod tests {
                 map.insert("ENV_TWO".to_owned(), "2".to_owned());
                 Some(map)
             },
+            log_path: None,
         };
         let client_info_two = serde_json::json!({
           "name": "TestClientTwo",
@@ -755,6 +883,7 @@ mod tests {
                 map.insert("ENV_TWO".to_owned(), "2".to_owned());
                 Some(map)
             },
+            log_path: None,
         };
         let mut client_one = Client::<StdioTransport>::from_config(client_config_one).expect("Failed to create client");
         let mut client_two = Client::<StdioTransport>::from_config(client_config_two).expect("Failed to create client");
@@ -777,13 +906,61 @@ mod tests {
         assert!(res_two.is_ok());
     }
 
+    fn flaky_client(failures: u64) -> Client<MockTransport> {
+        let transport = MockTransport::new()
+            .with_result("initialize", serde_json::json!({ "capabilities": {} }))
+            .with_init_failures(failures);
+        Client::from_transport("flaky".to_string(), transport, 1000)
+    }
+
+    fn init_attempts(client: &Client<MockTransport>) -> usize {
+        let requests = client.transport.requests();
+        requests.iter().filter(|req| req.method == "initialize").count()
+    }
+
+    #[tokio::test]
+    async fn test_init_with_retries() {
+        let client = flaky_client(1);
+        assert!(client.init_with_retries(3).await.is_ok());
+        assert_eq!(init_attempts(&client), 2);
+
+        let client = flaky_client(1);
+        assert!(client.init_with_retries(0).await.is_err());
+        assert_eq!(init_attempts(&client), 1);
+
+        let client = flaky_client(3);
+        assert!(client.init_with_retries(2).await.is_err());
+        assert_eq!(init_attempts(&client), 3);
+    }
+
+    #[test]
+    fn test_init_retry_delay() {
+        assert_eq!(init_retry_delay_ms(0), INIT_RETRY_BASE_DELAY_MS);
+        assert_eq!(init_retry_delay_ms(1), INIT_RETRY_BASE_DELAY_MS * 2);
+        assert_eq!(init_retry_delay_ms(40), INIT_RETRY_MAX_DELAY_MS);
+        assert_eq!(init_retry_delay_ms(u32::MAX), INIT_RETRY_MAX_DELAY_MS);
+    }
+
+    #[tokio::test]
+    async fn test_request_times_out() {
+        // The transport never answers anything but initialize requests.
+        let client = flaky_client(0);
+        let res = time::timeout(
+            time::Duration::from_secs(5),
+            client.request_with_timeout("tools/call", None, 100),
+        )
+        .await
+        .expect("the request should time out by itself");
+        assert!(res.is_err_and(|e| e.is_timeout()));
+    }
+
     #[allow(clippy::await_holding_lock)]
     async fn test_client_routine<T: Transport>(
         client: &mut Client<T>,
         cap_sent: serde_json::Value,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // Test init
-        let _ = client.init().await.expect("Client init failed");
+        let _ = client.init_with_retries(0).await.expect("Client init failed");
         tokio::time::sleep(time::Duration::from_millis(1500)).await;
         let client_capabilities_sent = client
             .request("verify_init_ack_sent", None)
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use super::base_protocol::{
+    JsonRpcError,
+    JsonRpcMessage,
+    JsonRpcRequest,
+    JsonRpcResponse,
+    JsonRpcVersion,
+};
+use super::stdio::{
+    StdioListener,
+    StdioLogListener,
+};
+use super::{
+    Listener,
+    LogListener,
+    Transport,
+    TransportError,
+};
+
+/// A transport that answers requests with canned responses, for testing code that talks to MCP
+/// servers without spawning a server process.
+///
+/// Requests for methods without a canned response are never answered.
+#[derive(Debug)]
+pub struct MockTransport {
+    /// Responses keyed by method. The id is replaced with the id of the request being answered.
+    responses: HashMap<String, JsonRpcResponse>,
+    /// Every request sent so far, in order.
+    requests: Mutex<Vec<JsonRpcRequest>>,
+    /// How many of the next initialize requests fail to send.
+    init_failures: AtomicU64,
+    sender: broadcast::Sender<Result<JsonRpcMessage, TransportError>>,
+    log_sender: broadcast::Sender<String>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self {
+            responses: HashMap::new(),
+            requests: Mutex::new(Vec::new()),
+            init_failures: AtomicU64::new(0),
+            sender: broadcast::channel(16).0,
+            log_sender: broadcast::channel(16).0,
+        }
+    }
+
+    /// Answers requests for `method` with `result`.
+    pub fn with_result(self, method: &str, result: serde_json::Value) -> Self {
+        self.with_response(method, Some(result), None)
+    }
+
+    /// Answers requests for `method` with `error`.
+    pub fn with_error(self, method: &str, error: JsonRpcError) -> Self {
+        self.with_response(method, None, Some(error))
+    }
+
+    /// Fails to send the first `failures` initialize requests, as if the server wasn't ready yet.
+    pub fn with_init_failures(self, failures: u64) -> Self {
+        self.init_failures.store(failures, Ordering::SeqCst);
+        self
+    }
+
+    fn with_response(mut self, method: &str, result: Option<serde_json::Value>, error: Option<JsonRpcError>) -> Self {
+        self.responses.insert(method.to_string(), JsonRpcResponse {
+            jsonrpc: JsonRpcVersion::default(),
+            id: 0,
+            result,
+            error,
+        });
+        self
+    }
+
+    /// Returns the requests sent through this transport.
+    pub fn requests(&self) -> Vec<JsonRpcRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for MockTransport {
+    async fn send(&self, msg: &JsonRpcMessage) -> Result<(), TransportError> {
+        let JsonRpcMessage::Request(req) = msg else {
+            return Ok(());
+        };
+        self.requests.lock().unwrap().push(req.clone());
+        if req.method == "initialize"
+            && self
+                .init_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        {
+            return Err(TransportError::Custom("server is not ready".to_string()));
+        }
+        let Some(mut resp) = self.responses.get(&req.method).cloned() else {
+            return Ok(());
+        };
+        resp.id = req.id;
+
+        // The client only starts listening for the response once the request is sent.
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let _ = sender.send(Ok(JsonRpcMessage::Response(resp)));
+        });
+        Ok(())
+    }
+
+    fn get_listener(&self) -> impl Listener {
+        StdioListener {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    async fn shutdown(&self) -> Result<(), TransportError> {
+        Ok(())
+    }
+
+    fn get_log_listener(&self) -> impl LogListener {
+        StdioLogListener {
+            receiver: self.log_sender.subscribe(),
+        }
+    }
+}
@@ -1,9 +1,13 @@
 pub mod base_protocol;
+#[cfg(test)]
+pub mod mock;
 pub mod stdio;
 
 use std::fmt::Debug;
 
 pub use base_protocol::*;
+#[cfg(test)]
+pub use mock::*;
 pub use stdio::*;
 use thiserror::Error;
 
@@ -39,9 +39,12 @@ pub enum JsonRpcStdioTransport {
 }
 
 impl JsonRpcStdioTransport {
+    /// Reads messages from `reader`. Lines that are not messages are also sent to `log_tx` if
+    /// provided, as servers sometimes print their diagnostics to stdout.
     fn spawn_reader<R: AsyncRead + Unpin + Send + 'static>(
         reader: R,
         tx: broadcast::Sender<Result<JsonRpcMessage, TransportError>>,
+        log_tx: Option<broadcast::Sender<String>>,
     ) {
         tokio::spawn(async move {
             let mut buffer = Vec::<u8>::new();
@@ -57,6 +60,10 @@ impl JsonRpcStdioTransport {
                             let _ = tx.send(Ok(msg));
                         },
                         Err(e) => {
+                            let line = String::from_utf8_lossy(&buffer).trim_end().to_string();
+                            if let Some(log_tx) = log_tx.as_ref().filter(|_| !line.is_empty()) {
+                                let _ = log_tx.send(line);
+                            }
                             let _ = tx.send(Err(e.into()));
                         },
                     },
@@ -80,6 +87,7 @@ impl JsonRpcStdioTransport {
             return Err(TransportError::Custom("No stderr found on child process".to_owned()));
         };
         let (log_tx, log_receiver) = broadcast::channel::<String>(100);
+        let stdout_log_tx = log_tx.clone();
         tokio::task::spawn(async move {
             let stderr = tokio::io::BufReader::new(stderr);
             let mut lines = stderr.lines();
@@ -88,7 +96,7 @@ impl JsonRpcStdioTransport {
             }
         });
         let stdin = Arc::new(Mutex::new(stdin));
-        Self::spawn_reader(stdout, tx);
+        Self::spawn_reader(stdout, tx, Some(stdout_log_tx));
         Ok(JsonRpcStdioTransport::Client {
             stdin,
             receiver,
@@ -98,7 +106,7 @@ impl JsonRpcStdioTransport {
 
     pub fn server(stdin: Stdin, stdout: Stdout) -> Result<Self, TransportError> {
         let (tx, receiver) = broadcast::channel::<Result<JsonRpcMessage, TransportError>>(100);
-        Self::spawn_reader(stdin, tx);
+        Self::spawn_reader(stdin, tx, None);
         let stdout = Arc::new(Mutex::new(stdout));
         Ok(JsonRpcStdioTransport::Server { stdout, receiver })
     }
@@ -230,6 +230,16 @@ impl Fs {
         }
     }
 
+    /// Writes `contents` to `path` such that `path` holds either its old or its new contents, even
+    /// if the process is killed mid-write. See [write_atomic].
+    pub async fn write_atomic(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        match self {
+            Self::Real => write_atomic(path, contents, 0o666).await,
+            Self::Chroot(root) => write_atomic(append(root.path(), path), contents, 0o666).await,
+            Self::Fake(_) => self.write(path, contents).await,
+        }
+    }
+
     /// Removes a file from the filesystem.
     ///
     /// Note that there is no guarantee that the file is immediately deleted (e.g.
@@ -446,6 +456,47 @@ impl Fs {
     }
 }
 
+/// Writes `contents` to a temporary file in the same directory as `path` and then renames it over
+/// `path`, so that a process killed mid-write never leaves `path` partially written.
+///
+/// The permissions of an existing file at `path` are kept. Otherwise the file is created with
+/// `mode` (subject to the umask) on unix.
+pub async fn write_atomic(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+    #[cfg_attr(windows, allow(unused_variables))] mode: u32,
+) -> io::Result<()> {
+    use std::io::Write as _;
+
+    let path = path.as_ref().to_path_buf();
+    let contents = contents.as_ref().to_vec();
+    tokio::task::spawn_blocking(move || {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let prefix = format!(".{}.", path.file_name().unwrap_or_default().to_string_lossy());
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(&prefix).suffix(".tmp");
+
+        #[cfg(unix)]
+        let permissions = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.permissions(),
+            Err(_) => std::os::unix::fs::PermissionsExt::from_mode(mode),
+        };
+        #[cfg(unix)]
+        builder.permissions(permissions);
+
+        let mut file = builder.tempfile_in(dir)?;
+        file.write_all(&contents)?;
+        file.as_file().sync_all()?;
+        file.persist(&path).map_err(|err| err.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
 impl Default for Fs {
     fn default() -> Self {
         Self::new()
@@ -607,6 +658,53 @@ mod tests {
         assert!(fs.open("/rename_1").await.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_write_atomic() {
+        let fs = Fs::new();
+        fs.create_dir_all("/dir/config.json").await.unwrap();
+        fs.write("/dir/config.json/settings.json", "original").await.unwrap();
+
+        fs.write_atomic("/dir/new.json", "new").await.unwrap();
+        assert_eq!(fs.read_to_string("/dir/new.json").await.unwrap(), "new");
+        fs.write_atomic("/dir/new.json", "updated").await.unwrap();
+        assert_eq!(fs.read_to_string("/dir/new.json").await.unwrap(), "updated");
+
+        // The temporary file is written, but can't replace a directory. The failed write leaves
+        // the target as it was and cleans up after itself.
+        assert!(fs.write_atomic("/dir/config.json", "replaced").await.is_err());
+        assert!(fs.symlink_metadata("/dir/config.json").await.unwrap().is_dir());
+        assert_eq!(
+            fs.read_to_string("/dir/config.json/settings.json").await.unwrap(),
+            "original"
+        );
+
+        // Writing into a missing directory fails without touching anything else
+        assert!(fs.write_atomic("/dir/missing/new.json", "updated").await.is_err());
+
+        let mut entries = Vec::new();
+        let mut read_dir = fs.read_dir("/dir").await.unwrap();
+        while let Some(entry) = read_dir.next_entry().await.unwrap() {
+            entries.push(entry.file_name().to_string_lossy().to_string());
+        }
+        entries.sort();
+        assert_eq!(entries, vec!["config.json", "new.json"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("settings.json");
+        write_atomic(&path, "{}", 0o600).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        std::fs::set_permissions(&path, Permissions::from_mode(0o640)).unwrap();
+        write_atomic(&path, "{}", 0o600).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+
     #[tokio::test]
     async fn test_chroot_tempdir() {
         let fs = Fs::new();
@@ -7,7 +7,10 @@ mod sysinfo;
 
 pub use env::Env;
 use eyre::Result;
-pub use fs::Fs;
+pub use fs::{
+    Fs,
+    write_atomic,
+};
 pub use sysinfo::SysInfo;
 
 use crate::api_client::ApiClient;
@@ -0,0 +1,136 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use amzn_toolkit_telemetry_client::types::MetricDatum;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+use crate::database::Database;
+use crate::database::settings::Setting;
+
+/// Once the sink file grows past this size, it is moved to `<path>.1`, replacing the previous one,
+/// and a new file is started.
+const MAX_SINK_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Writes each metric as a line of JSON to the file set with "q settings telemetry.localSink
+/// <path>", so users can see exactly what telemetry is collected.
+///
+/// Metrics are written whether or not telemetry is enabled, since nothing leaves the machine.
+#[derive(Debug, Clone)]
+pub struct LocalSink {
+    path: PathBuf,
+    max_size: u64,
+}
+
+impl LocalSink {
+    /// Returns the sink configured with `telemetry.localSink`, or [None] if it is unset or empty.
+    pub fn from_settings(database: &Database) -> Option<Self> {
+        match database.settings.get_string(Setting::TelemetryLocalSink) {
+            Some(path) if !path.is_empty() => Some(Self::new(shellexpand::tilde(&path).as_ref())),
+            _ => None,
+        }
+    }
+
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            max_size: MAX_SINK_FILE_SIZE,
+        }
+    }
+
+    /// Appends `metric_datum` to the sink file, rotating the file first if it's over the size cap.
+    pub async fn write(&self, metric_datum: &MetricDatum) -> std::io::Result<()> {
+        if tokio::fs::metadata(&self.path)
+            .await
+            .is_ok_and(|metadata| metadata.len() >= self.max_size)
+        {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            tokio::fs::rename(&self.path, rotated).await?;
+        }
+
+        let mut line = serde_json::to_string(&metric_datum_to_json(metric_datum))?;
+        line.push('\n');
+
+        // The line is written with a single write to a file opened for appending, so lines from
+        // concurrent sessions are never interleaved.
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await
+    }
+}
+
+/// Converts `metric_datum` to the JSON sent to the toolkit telemetry service.
+fn metric_datum_to_json(metric_datum: &MetricDatum) -> serde_json::Value {
+    let metadata = metric_datum
+        .metadata()
+        .iter()
+        .filter_map(|entry| Some((entry.key()?.to_string(), entry.value()?.into())))
+        .collect::<serde_json::Map<_, _>>();
+
+    serde_json::json!({
+        "metricName": metric_datum.metric_name(),
+        "epochTimestamp": metric_datum.epoch_timestamp(),
+        "unit": metric_datum.unit().as_str(),
+        "value": metric_datum.value(),
+        "passive": metric_datum.passive(),
+        "metadata": metadata,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use amzn_toolkit_telemetry_client::types::{
+        MetadataEntry,
+        Unit,
+    };
+
+    use super::*;
+
+    fn metric_datum(name: &str) -> MetricDatum {
+        MetricDatum::builder()
+            .metric_name(name)
+            .epoch_timestamp(1754675008672)
+            .unit(Unit::None)
+            .value(1.0)
+            .metadata(MetadataEntry::builder().key("result").value("Succeeded").build())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_local_sink_writes_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = LocalSink::new(dir.path().join("telemetry.jsonl"));
+        sink.write(&metric_datum("first")).await.unwrap();
+        sink.write(&metric_datum("second")).await.unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join("telemetry.jsonl")).unwrap();
+        let lines = content
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["metricName"], "first");
+        assert_eq!(lines[0]["unit"], "None");
+        assert_eq!(lines[0]["metadata"]["result"], "Succeeded");
+        assert_eq!(lines[1]["metricName"], "second");
+    }
+
+    #[tokio::test]
+    async fn test_local_sink_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut sink = LocalSink::new(dir.path().join("telemetry.jsonl"));
+        sink.max_size = 1;
+        sink.write(&metric_datum("first")).await.unwrap();
+        sink.write(&metric_datum("second")).await.unwrap();
+
+        let rotated = std::fs::read_to_string(dir.path().join("telemetry.jsonl.1")).unwrap();
+        let current = std::fs::read_to_string(dir.path().join("telemetry.jsonl")).unwrap();
+        assert!(rotated.contains("\"first\""));
+        assert!(current.contains("\"second\""));
+        assert_eq!(current.lines().count(), 1);
+    }
+}
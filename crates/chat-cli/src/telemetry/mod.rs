@@ -3,6 +3,7 @@ pub mod core;
 pub mod definitions;
 pub mod endpoint;
 mod install_method;
+mod local_sink;
 
 use core::{
     AgentConfigInitArgs,
@@ -36,6 +37,7 @@ pub use install_method::{
     InstallMethod,
     get_install_method,
 };
+use local_sink::LocalSink;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio::time::error::Elapsed;
@@ -465,31 +467,38 @@ struct TelemetryClient {
     telemetry_enabled: bool,
     codewhisperer_client: Option<ApiClient>,
     toolkit_telemetry_client: Option<ToolkitTelemetryClient>,
+    local_sink: Option<LocalSink>,
+}
+
+/// Returns whether telemetry is enabled. Telemetry is opted out of with "q settings
+/// telemetry.enabled false" or by setting the `Q_DISABLE_TELEMETRY` environment variable.
+///
+/// This is checked once when the [TelemetryClient] is created, and [TelemetryClient::send_event]
+/// drops every event while it is disabled, so no metric leaves the machine.
+pub fn is_telemetry_enabled(env: &Env, database: &Database) -> bool {
+    env.get_os("Q_DISABLE_TELEMETRY").is_none() && database.settings.get_bool(Setting::TelemetryEnabled).unwrap_or(true)
+}
+
+fn toolkit_telemetry_client(stage: TelemetryStage) -> ToolkitTelemetryClient {
+    ToolkitTelemetryClient::from_conf(
+        Config::builder()
+            .http_client(crate::aws_common::http_client::client())
+            .behavior_version(BehaviorVersion::v2025_01_17())
+            .endpoint_resolver(StaticEndpoint(stage.endpoint))
+            .app_name(app_name())
+            .region(stage.region.clone())
+            .credentials_provider(SharedCredentialsProvider::new(CognitoProvider::new(stage)))
+            .build(),
+    )
 }
 
 impl TelemetryClient {
     async fn new(env: &Env, fs: &Fs, database: &mut Database) -> Result<Self, TelemetryError> {
-        let telemetry_enabled = !cfg!(test)
-            && env.get_os("Q_DISABLE_TELEMETRY").is_none()
-            && database.settings.get_bool(Setting::TelemetryEnabled).unwrap_or(true);
+        let telemetry_enabled = !cfg!(test) && is_telemetry_enabled(env, database);
 
         // If telemetry is disabled we do not emit using toolkit_telemetry
-        let toolkit_telemetry_client = if telemetry_enabled {
-            Some(ToolkitTelemetryClient::from_conf(
-                Config::builder()
-                    .http_client(crate::aws_common::http_client::client())
-                    .behavior_version(BehaviorVersion::v2025_01_17())
-                    .endpoint_resolver(StaticEndpoint(TelemetryStage::EXTERNAL_PROD.endpoint))
-                    .app_name(app_name())
-                    .region(TelemetryStage::EXTERNAL_PROD.region.clone())
-                    .credentials_provider(SharedCredentialsProvider::new(CognitoProvider::new(
-                        TelemetryStage::EXTERNAL_PROD,
-                    )))
-                    .build(),
-            ))
-        } else {
-            None
-        };
+        let toolkit_telemetry_client =
+            telemetry_enabled.then(|| toolkit_telemetry_client(TelemetryStage::EXTERNAL_PROD));
 
         fn client_id(env: &Env, database: &mut Database, telemetry_enabled: bool) -> Result<Uuid, TelemetryError> {
             if !telemetry_enabled {
@@ -532,18 +541,41 @@ impl TelemetryClient {
             telemetry_enabled,
             toolkit_telemetry_client,
             codewhisperer_client,
+            local_sink: LocalSink::from_settings(database),
         })
     }
 
     /// Sends a telemetry event to both the CW and toolkit API's. If the clients do not exist, then
     /// telemetry is not sent.
     ///
-    /// See [TelemetryClient::new] for which conditions the clients are created for.
+    /// See [TelemetryClient::new] for which conditions the clients are created for. Nothing is sent
+    /// when telemetry is disabled, see [is_telemetry_enabled].
     async fn send_event(&self, event: Event) {
+        self.write_local_sink(&event).await;
+
+        if !self.telemetry_enabled {
+            trace!(?event, "not sending telemetry event - telemetry is disabled");
+            return;
+        }
+
         self.send_cw_telemetry_event(&event).await;
         self.send_telemetry_toolkit_metric(event).await;
     }
 
+    /// Writes the metric of `event` to the [LocalSink], if one is configured.
+    async fn write_local_sink(&self, event: &Event) {
+        let Some(local_sink) = &self.local_sink else {
+            return;
+        };
+        let Some(metric_datum) = event.clone().into_metric_datum() else {
+            return;
+        };
+
+        if let Err(err) = local_sink.write(&metric_datum).await {
+            error!(%err, "Failed to write metric to the local telemetry sink");
+        }
+    }
+
     async fn send_cw_telemetry_event(&self, event: &Event) {
         let Some(codewhisperer_client) = self.codewhisperer_client.clone() else {
             trace!("not sending cw metric - client does not exist");
@@ -707,6 +739,48 @@ mod test {
         assert_eq!(context.ide_version.as_deref(), Some(PRODUCT_VERSION));
     }
 
+    #[tokio::test]
+    async fn test_is_telemetry_enabled() {
+        let mut database = Database::new().await.unwrap();
+        assert!(is_telemetry_enabled(&Env::from_slice(&[]), &database));
+        assert!(!is_telemetry_enabled(
+            &Env::from_slice(&[("Q_DISABLE_TELEMETRY", "1")]),
+            &database
+        ));
+
+        database.settings.set(Setting::TelemetryEnabled, false).await.unwrap();
+        assert!(!is_telemetry_enabled(&Env::from_slice(&[]), &database));
+    }
+
+    #[tokio::test]
+    async fn test_send_event_with_telemetry_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        // A scoped subscriber, as the global one may already be installed by another test
+        let log_path = dir.path().join("log");
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(std::fs::File::create(&log_path).unwrap())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let client = TelemetryClient {
+            client_id: Uuid::new_v4(),
+            telemetry_enabled: false,
+            codewhisperer_client: None,
+            toolkit_telemetry_client: Some(toolkit_telemetry_client(TelemetryStage::BETA)),
+            local_sink: Some(LocalSink::new(dir.path().join("telemetry.jsonl"))),
+        };
+        client.send_event(Event::new(EventType::UserLoggedIn {})).await;
+
+        let logs = std::fs::read_to_string(&log_path).unwrap();
+        assert!(logs.contains("telemetry is disabled"));
+        assert!(!logs.contains("Sending toolkit telemetry event"));
+        // The local sink still records what would have been sent
+        let content = std::fs::read_to_string(dir.path().join("telemetry.jsonl")).unwrap();
+        assert_eq!(content.lines().count(), 1);
+    }
+
     #[tracing_test::traced_test]
     #[tokio::test]
     #[ignore = "needs auth which is not in CI"]
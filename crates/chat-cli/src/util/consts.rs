@@ -65,6 +65,9 @@ pub mod env_var {
         /// Indicates the user is using zsh autosuggestions which disables Inline
         Q_USING_ZSH_AUTOSUGGESTIONS = "Q_USING_ZSH_AUTOSUGGESTIONS",
 
+        /// Overrides the directory that the database, settings, and chat history are stored in
+        Q_DATA_DIR = "Q_DATA_DIR",
+
         /// Overrides the path to the bundle metadata released with certain desktop builds.
         Q_BUNDLE_METADATA_PATH = "Q_BUNDLE_METADATA_PATH",
 
@@ -1,3 +1,4 @@
+use std::ffi::OsString;
 use std::path::{
     PathBuf,
     StripPrefixError,
@@ -6,6 +7,7 @@ use std::path::{
 use thiserror::Error;
 
 use crate::os::Os;
+use crate::util::consts::env_var::Q_DATA_DIR;
 
 #[derive(Debug, Error)]
 pub enum DirectoryError {
@@ -14,6 +16,8 @@ pub enum DirectoryError {
     #[cfg(unix)]
     #[error("runtime directory not found: neither XDG_RUNTIME_DIR nor TMPDIR were found")]
     NoRuntimeDirectory,
+    #[error("Q_DATA_DIR must be an absolute path, got: {}", .0.display())]
+    RelativeDataDir(PathBuf),
     #[error("IO Error: {0}")]
     Io(#[from] std::io::Error),
     #[error(transparent)]
@@ -33,7 +37,7 @@ pub enum DirectoryError {
 type Result<T, E = DirectoryError> = std::result::Result<T, E>;
 
 const WORKSPACE_AGENT_DIR_RELATIVE: &str = ".amazonq/cli-agents";
-const GLOBAL_AGENT_DIR_RELATIVE_TO_HOME: &str = ".aws/amazonq/cli-agents";
+const GLOBAL_CONFIG_DIR_RELATIVE_TO_HOME: &str = ".aws/amazonq";
 
 /// The directory of the users home
 ///
@@ -78,14 +82,58 @@ pub fn home_dir(#[cfg_attr(windows, allow(unused_variables))] os: &Os) -> Result
     }
 }
 
+/// The directory containing the global chat config, such as global agents
+///
+/// - Linux: `$XDG_CONFIG_HOME/amazonq` if `XDG_CONFIG_HOME` is set, unless only
+///   `$HOME/.aws/amazonq` exists, so that existing configs keep being used
+/// - Otherwise: `$HOME/.aws/amazonq`
+pub fn global_config_dir(os: &Os) -> Result<PathBuf> {
+    let home_config_dir = home_dir(os)?.join(GLOBAL_CONFIG_DIR_RELATIVE_TO_HOME);
+
+    #[cfg(target_os = "linux")]
+    if let Some(xdg_config_home) = os
+        .env
+        .get("XDG_CONFIG_HOME")
+        .ok()
+        .map(PathBuf::from)
+        // Relative paths are invalid per the XDG base directory spec
+        .filter(|path| path.is_absolute())
+    {
+        let xdg_config_dir = os.fs.chroot_path(xdg_config_home.join("amazonq"));
+        if os.fs.exists(&xdg_config_dir) || !os.fs.exists(&home_config_dir) {
+            return Ok(xdg_config_dir);
+        }
+    }
+
+    Ok(home_config_dir)
+}
+
 /// The q data directory
 ///
 /// - Linux: `$XDG_DATA_HOME/amazon-q` or `$HOME/.local/share/amazon-q`
 /// - MacOS: `$HOME/Library/Application Support/amazon-q`
+///
+/// Setting `Q_DATA_DIR` to an absolute path overrides this, e.g. to run isolated or portable
+/// instances of q. The directory is created when data is first written to it.
 pub fn fig_data_dir() -> Result<PathBuf> {
-    Ok(dirs::data_local_dir()
-        .ok_or(DirectoryError::NoHomeDirectory)?
-        .join("amazon-q"))
+    match data_dir_override(std::env::var_os(Q_DATA_DIR))? {
+        Some(dir) => Ok(dir),
+        None => Ok(dirs::data_local_dir()
+            .ok_or(DirectoryError::NoHomeDirectory)?
+            .join("amazon-q")),
+    }
+}
+
+/// Validates the value of `Q_DATA_DIR`. An unset or empty value means there is no override.
+fn data_dir_override(value: Option<OsString>) -> Result<Option<PathBuf>> {
+    let Some(dir) = value.filter(|value| !value.is_empty()).map(PathBuf::from) else {
+        return Ok(None);
+    };
+    if !dir.is_absolute() {
+        return Err(DirectoryError::RelativeDataDir(dir));
+    }
+
+    Ok(Some(dir))
 }
 
 /// Get the macos tempdir from the `confstr` function
@@ -137,6 +185,35 @@ pub fn logs_dir() -> Result<PathBuf> {
     }
 }
 
+/// The file that the stdout and stderr output of the MCP server `server_name` is appended to while
+/// it runs in a chat session
+///
+/// The file name is the server name with unsafe characters replaced, followed by a short hash of
+/// the server name so that e.g. `a.b` and `a_b` don't share a file.
+pub fn mcp_server_log_path(server_name: &str) -> Result<PathBuf> {
+    use sha2::{
+        Digest,
+        Sha256,
+    };
+
+    let sanitized = server_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let hash = Sha256::digest(server_name.as_bytes())
+        .iter()
+        .take(4)
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    Ok(logs_dir()?.join("mcp").join(format!("{sanitized}-{hash}.log")))
+}
+
 /// Example agent config path
 pub fn example_agent_config(os: &Os) -> Result<PathBuf> {
     let global_path = chat_global_agent_path(os)?;
@@ -145,7 +222,7 @@ pub fn example_agent_config(os: &Os) -> Result<PathBuf> {
 
 /// Legacy global MCP server config path
 pub fn chat_legacy_global_mcp_config(os: &Os) -> Result<PathBuf> {
-    Ok(home_dir(os)?.join(".aws").join("amazonq").join("mcp.json"))
+    Ok(global_config_dir(os)?.join("mcp.json"))
 }
 
 /// Legacy workspace MCP server config path
@@ -156,7 +233,7 @@ pub fn chat_legacy_workspace_mcp_config(os: &Os) -> Result<PathBuf> {
 
 /// The directory to the directory containing global agents
 pub fn chat_global_agent_path(os: &Os) -> Result<PathBuf> {
-    Ok(home_dir(os)?.join(GLOBAL_AGENT_DIR_RELATIVE_TO_HOME))
+    Ok(global_config_dir(os)?.join("cli-agents"))
 }
 
 /// The directory to the directory containing config for the `/context` feature in `q chat`.
@@ -176,13 +253,13 @@ pub fn agent_config_dir(workspace_dir: PathBuf) -> Result<PathBuf> {
 
 /// The directory to the directory containing config for the `/context` feature in `q chat`.
 pub fn chat_global_context_path(os: &Os) -> Result<PathBuf> {
-    Ok(home_dir(os)?.join(".aws").join("amazonq").join("global_context.json"))
+    Ok(global_config_dir(os)?.join("global_context.json"))
 }
 
 /// The directory to the directory containing config for the `/context` feature in `q chat`.
 #[allow(dead_code)]
 pub fn chat_profiles_dir(os: &Os) -> Result<PathBuf> {
-    Ok(home_dir(os)?.join(".aws").join("amazonq").join("profiles"))
+    Ok(global_config_dir(os)?.join("profiles"))
 }
 
 /// The path to the fig settings file
@@ -190,6 +267,16 @@ pub fn settings_path() -> Result<PathBuf> {
     Ok(fig_data_dir()?.join("settings.json"))
 }
 
+/// The default path to the file storing the prompt history of `q chat`
+pub fn chat_history_path() -> Result<PathBuf> {
+    Ok(fig_data_dir()?.join("chat_history.txt"))
+}
+
+/// The path to the cached list of models available to `q chat`
+pub fn chat_model_cache_path() -> Result<PathBuf> {
+    Ok(fig_data_dir()?.join("model_cache.json"))
+}
+
 /// The path to the local sqlite database
 pub fn database_path() -> Result<PathBuf> {
     Ok(fig_data_dir()?.join("data.sqlite3"))
@@ -204,6 +291,54 @@ mod linux_tests {
         assert!(logs_dir().is_ok());
         assert!(settings_path().is_ok());
     }
+
+    #[test]
+    fn test_data_dir_override() {
+        assert!(data_dir_override(None).unwrap().is_none());
+        assert!(data_dir_override(Some("".into())).unwrap().is_none());
+        assert!(matches!(
+            data_dir_override(Some("relative/dir".into())),
+            Err(DirectoryError::RelativeDataDir(_))
+        ));
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let data_dir = tempdir.path().join("nested").join("data");
+        assert_eq!(
+            data_dir_override(Some(data_dir.clone().into())).unwrap(),
+            Some(data_dir.clone())
+        );
+        assert!(
+            !data_dir.exists(),
+            "the data dir should not be created until it's written to"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_global_config_dir_xdg() {
+        let os = Os::new().await.unwrap();
+        let home_config_dir = home_dir(&os).unwrap().join(".aws").join("amazonq");
+        let xdg_config_dir = os.fs.chroot_path("/xdg/amazonq");
+
+        // Unset, empty or relative XDG_CONFIG_HOME
+        assert_eq!(global_config_dir(&os).unwrap(), home_config_dir);
+        for value in ["", "relative/dir"] {
+            unsafe { os.env.set_var("XDG_CONFIG_HOME", value) };
+            assert_eq!(global_config_dir(&os).unwrap(), home_config_dir);
+        }
+
+        unsafe { os.env.set_var("XDG_CONFIG_HOME", "/xdg") };
+        assert_eq!(global_config_dir(&os).unwrap(), xdg_config_dir);
+        assert_eq!(chat_global_agent_path(&os).unwrap(), xdg_config_dir.join("cli-agents"));
+
+        // An existing config under the home directory keeps being used...
+        os.fs.create_dir_all(&home_config_dir).await.unwrap();
+        assert_eq!(global_config_dir(&os).unwrap(), home_config_dir);
+
+        // ...unless there is one under XDG_CONFIG_HOME too
+        os.fs.create_dir_all(&xdg_config_dir).await.unwrap();
+        assert_eq!(global_config_dir(&os).unwrap(), xdg_config_dir);
+    }
 }
 
 // TODO(grant): Add back path tests on linux
@@ -281,6 +416,23 @@ mod tests {
         path
     }
 
+    #[test]
+    fn test_mcp_server_log_path() {
+        let file_name = |name: &str| {
+            mcp_server_log_path(name)
+                .unwrap()
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        };
+        assert!(file_name("git").starts_with("git-"));
+        assert!(file_name("a.b").starts_with("a_b-"));
+        assert_eq!(file_name("a.b"), file_name("a.b"));
+        assert_ne!(file_name("a.b"), file_name("a_b"));
+        assert_ne!(file_name("a.b"), file_name("a b"));
+    }
+
     #[test]
     fn snapshot_fig_data_dir() {
         linux!(fig_data_dir(), @"$HOME/.local/share/amazon-q");
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub use sysinfo::Pid;
 
 #[cfg(target_os = "windows")]
@@ -9,3 +11,38 @@ pub use windows::*;
 mod unix;
 #[cfg(not(windows))]
 pub use unix::*;
+
+/// Time a stopped subprocess is given to exit after SIGTERM, before it is sent SIGKILL.
+pub const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Stops a subprocess when dropped, unless [Self::disarm] was called first.
+///
+/// Tools hold one while they wait on a subprocess, so that cancelling the tool, e.g. with Ctrl+C,
+/// doesn't leave the subprocess running. See [terminate_gracefully].
+#[derive(Debug)]
+pub struct TerminateOnDrop {
+    pid: Option<Pid>,
+    grace: Duration,
+}
+
+impl TerminateOnDrop {
+    pub fn new(child: &tokio::process::Child) -> Self {
+        Self {
+            pid: child.id().map(Pid::from_u32),
+            grace: TERMINATE_GRACE_PERIOD,
+        }
+    }
+
+    /// Leaves the subprocess alone, e.g. because it has already exited.
+    pub fn disarm(mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for TerminateOnDrop {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid.take() {
+            terminate_gracefully(pid, false, self.grace);
+        }
+    }
+}
@@ -1,11 +1,44 @@
+use std::time::Duration;
+
 use nix::sys::signal::Signal;
 use sysinfo::Pid;
 
+// Only used by tests on unix, where processes are stopped with [terminate_gracefully].
+#[allow(dead_code)]
 pub fn terminate_process(pid: Pid) -> Result<(), String> {
     let nix_pid = nix::unistd::Pid::from_raw(pid.as_u32() as i32);
     nix::sys::signal::kill(nix_pid, Signal::SIGTERM).map_err(|e| format!("Failed to terminate process: {}", e))
 }
 
+/// Sends SIGTERM to the process, or to the process group it leads if `group` is set, then SIGKILL
+/// if it is still running after `grace`.
+pub fn terminate_gracefully(pid: Pid, group: bool, grace: Duration) {
+    let signal = move |signal: Signal| {
+        let nix_pid = nix::unistd::Pid::from_raw(pid.as_u32() as i32);
+        match group {
+            true => nix::sys::signal::killpg(nix_pid, signal),
+            false => nix::sys::signal::kill(nix_pid, signal),
+        }
+    };
+    if signal(Signal::SIGTERM).is_err() {
+        return;
+    }
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(async move {
+                tokio::time::sleep(grace).await;
+                let _ = signal(Signal::SIGKILL);
+            });
+        },
+        Err(_) => {
+            std::thread::spawn(move || {
+                std::thread::sleep(grace);
+                let _ = signal(Signal::SIGKILL);
+            });
+        },
+    }
+}
+
 #[cfg(test)]
 #[cfg(not(windows))]
 mod tests {
@@ -50,6 +83,33 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_terminate_on_drop() {
+        let mut child = tokio::process::Command::new("sleep").arg("30").spawn().unwrap();
+        drop(crate::util::process::TerminateOnDrop::new(&child));
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait()).await;
+        assert!(status.is_ok(), "process is still running after the guard was dropped");
+
+        // A process that ignores SIGTERM is killed once the grace period is over
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 30"])
+            .spawn()
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut guard = crate::util::process::TerminateOnDrop::new(&child);
+        guard.grace = Duration::from_millis(100);
+        drop(guard);
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait()).await;
+        assert!(status.is_ok(), "process is still running after the grace period");
+
+        // Nothing happens to a process whose guard is disarmed
+        let mut child = tokio::process::Command::new("sleep").arg("30").spawn().unwrap();
+        crate::util::process::TerminateOnDrop::new(&child).disarm();
+        let status = tokio::time::timeout(Duration::from_millis(300), child.wait()).await;
+        assert!(status.is_err());
+        child.kill().await.unwrap();
+    }
+
     #[test]
     fn test_terminate_nonexistent_process() {
         // Use a likely invalid PID
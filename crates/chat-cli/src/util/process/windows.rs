@@ -1,4 +1,5 @@
 use std::ops::Deref;
+use std::time::Duration;
 
 use sysinfo::Pid;
 use windows::Win32::Foundation::{
@@ -28,6 +29,11 @@ pub fn terminate_process(pid: Pid) -> Result<(), String> {
     }
 }
 
+/// Terminates the process. Windows has no equivalent of SIGTERM, so `group` and `grace` are unused.
+pub fn terminate_gracefully(pid: Pid, _group: bool, _grace: Duration) {
+    let _ = terminate_process(pid);
+}
+
 struct SafeHandle(HANDLE);
 
 impl SafeHandle {